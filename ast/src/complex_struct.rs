@@ -1,11 +1,23 @@
-use crate::{Expr, FunctionDeclare, Identifier, Type};
-use std::collections::BTreeMap;
-use std::sync::Arc;
+use crate::{
+    ConstDeclare, Expr, FunctionDeclare, Identifier, ModDeclare, StaticDeclare, Type, UseDeclare,
+    Visibility,
+};
+use std::collections::{BTreeMap, HashMap};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ModuleItem {
     FunctionDeclare(FunctionDeclare),
     StructDeclare(StructDeclare),
+    EnumDeclare(EnumDeclare),
+    Impl(ImplBlock),
+    TraitDeclare(TraitDeclare),
+    /// `mod name;`, the items of the file of the module are merged in by the resolver
+    Mod(ModDeclare),
+    Use(UseDeclare),
+    Const(ConstDeclare),
+    Static(StaticDeclare),
+    TypeAlias(TypeAlias),
+    Newtype(NewtypeDeclare),
 }
 //
 // impl ModuleItem {
@@ -37,21 +49,313 @@ pub enum ModuleItem {
 //     }
 // }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct StructDeclare {
+    pub visibility: Visibility,
     pub name: Identifier,
+    /// type parameters `<A, B>` the fields can refer to
+    pub generics: Vec<Identifier>,
     pub fields: BTreeMap<Identifier, Type>,
 }
 
 impl StructDeclare {
     pub fn new(name: String, items: Vec<(Identifier, Type)>) -> Self {
         Self {
+            visibility: Visibility::Private,
             name,
+            generics: vec![],
             fields: items.into_iter().collect(),
         }
     }
+
+    pub fn is_generic(&self) -> bool {
+        !self.generics.is_empty()
+    }
+
+    /// the struct with its type parameters replaced by `arguments`
+    pub fn instantiate(&self, arguments: &[Type]) -> StructDeclare {
+        let arguments: HashMap<Identifier, Type> = self
+            .generics
+            .iter()
+            .cloned()
+            .zip(arguments.iter().cloned())
+            .collect();
+        StructDeclare {
+            visibility: self.visibility,
+            name: self.name.clone(),
+            generics: vec![],
+            fields: self
+                .fields
+                .iter()
+                .map(|(name, ty)| (name.clone(), ty.substitute(&arguments)))
+                .collect(),
+        }
+    }
+}
+
+impl StructDeclare {
+    /// position of the field in the struct layout
+    pub fn field_index(&self, name: &str) -> Option<u32> {
+        self.fields.keys().position(|it| it == name).map(|it| it as u32)
+    }
+}
+
+/// the fields of an enum variant, also used for the patterns mentioning the variant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VariantFields<T> {
+    /// `Empty`
+    Unit,
+    /// `Circle(T)`
+    Tuple(Vec<T>),
+    /// `Rect { w: T, h: T }`
+    Struct(Vec<(Identifier, T)>),
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumVariant {
+    pub name: Identifier,
+    pub fields: VariantFields<Type>,
+}
+
+impl EnumVariant {
+    pub fn new(name: Identifier, fields: VariantFields<Type>) -> Self {
+        Self { name, fields }
+    }
+
+    /// field types in layout order
+    pub fn field_types(&self) -> Vec<&Type> {
+        match &self.fields {
+            VariantFields::Unit => vec![],
+            VariantFields::Tuple(types) => types.iter().collect(),
+            VariantFields::Struct(fields) => fields.iter().map(|(_, ty)| ty).collect(),
+        }
+    }
+
+    /// position of a named field of a struct-like variant
+    pub fn field_index(&self, name: &str) -> Option<u32> {
+        match &self.fields {
+            VariantFields::Struct(fields) => fields
+                .iter()
+                .position(|(field, _)| field == name)
+                .map(|it| it as u32),
+            _ => None,
+        }
+    }
+}
+
+/// `enum Shape { Circle(i32), Rect { w: i32, h: i32 }, Empty }`, lowered to a tag and a
+/// payload large enough for every variant
+#[derive(Debug, Clone)]
+pub struct EnumDeclare {
+    pub visibility: Visibility,
+    pub name: Identifier,
+    /// type parameters `<T>` the variants can refer to
+    pub generics: Vec<Identifier>,
+    pub variants: Vec<EnumVariant>,
+}
+
+impl EnumDeclare {
+    pub fn new(name: Identifier, variants: Vec<EnumVariant>) -> Self {
+        Self {
+            visibility: Visibility::Private,
+            name,
+            generics: vec![],
+            variants,
+        }
+    }
+
+    pub fn is_generic(&self) -> bool {
+        !self.generics.is_empty()
+    }
+
+    /// the enum with its type parameters replaced by `arguments`
+    pub fn instantiate(&self, arguments: &[Type]) -> EnumDeclare {
+        let arguments: HashMap<Identifier, Type> = self
+            .generics
+            .iter()
+            .cloned()
+            .zip(arguments.iter().cloned())
+            .collect();
+        let substitute = |fields: &VariantFields<Type>| match fields {
+            VariantFields::Unit => VariantFields::Unit,
+            VariantFields::Tuple(types) => {
+                VariantFields::Tuple(types.iter().map(|ty| ty.substitute(&arguments)).collect())
+            }
+            VariantFields::Struct(fields) => VariantFields::Struct(
+                fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), ty.substitute(&arguments)))
+                    .collect(),
+            ),
+        };
+        EnumDeclare {
+            visibility: self.visibility,
+            name: self.name.clone(),
+            generics: vec![],
+            variants: self
+                .variants
+                .iter()
+                .map(|variant| EnumVariant::new(variant.name.clone(), substitute(&variant.fields)))
+                .collect(),
+        }
+    }
+
+    /// the tag of the variant together with the variant
+    pub fn variant(&self, name: &str) -> Option<(u32, &EnumVariant)> {
+        self.variants
+            .iter()
+            .enumerate()
+            .find(|(_, variant)| variant.name == name)
+            .map(|(tag, variant)| (tag as u32, variant))
+    }
 }
 
-macro_rules! struct_decl {
-    ($name:tt, {  }) => {};
+/// `type Meters = i64;`, another name for a type, both names are interchangeable
+#[derive(Debug, Clone)]
+pub struct TypeAlias {
+    pub visibility: Visibility,
+    pub name: Identifier,
+    pub ty: Type,
+}
+
+/// `newtype UserId(i64);`, a distinct type wrapping a value of the inner type, built with
+/// `UserId(1)` and unwrapped with `.0`
+#[derive(Debug, Clone)]
+pub struct NewtypeDeclare {
+    pub visibility: Visibility,
+    pub name: Identifier,
+    pub inner: Type,
+}
+
+impl NewtypeDeclare {
+    /// the newtype as a struct with the single field `0`, which has the layout of the
+    /// inner type
+    pub fn as_struct(&self) -> StructDeclare {
+        StructDeclare {
+            visibility: self.visibility,
+            ..StructDeclare::new(self.name.clone(), vec![("0".to_string(), self.inner.clone())])
+        }
+    }
+}
+
+/// `impl<A, B> Name<A, B> { fn ... }`, the methods and associated functions of a type,
+/// or `impl Trait for Name { fn ... }` implementing a trait for it
+#[derive(Debug, Clone)]
+pub struct ImplBlock {
+    /// type parameters of the block, shared by every function in it
+    pub generics: Vec<Identifier>,
+    /// trait bounds `T: Trait` of the type parameters
+    pub bounds: Vec<(Identifier, Identifier)>,
+    pub trait_name: Option<Identifier>,
+    /// type arguments of a generic trait, `i64` in `impl Iterator<i64> for Range`
+    pub trait_arguments: Vec<Type>,
+    pub name: Identifier,
+    /// type arguments of `Name`, usually the type parameters themselves
+    pub arguments: Vec<Type>,
+    pub functions: Vec<FunctionDeclare>,
+}
+
+impl ImplBlock {
+    /// the type `Self` stands for
+    pub fn target(&self) -> Type {
+        if self.arguments.is_empty() {
+            Type::named(self.name.clone())
+        } else {
+            Type::generic(self.name.clone(), self.arguments.clone())
+        }
+    }
+
+    /// the functions as free functions named `Name::function`, with `Self` replaced by the
+    /// target type in their signatures
+    pub fn qualified_functions(&self) -> Vec<FunctionDeclare> {
+        let target = self.target();
+        let self_type: HashMap<Identifier, Type> =
+            [("Self".to_string(), target.clone())].into_iter().collect();
+        self.functions
+            .iter()
+            .map(|function| FunctionDeclare {
+                name: format!("{}::{}", self.name, function.name),
+                generics: self.generics.iter().chain(&function.generics).cloned().collect(),
+                bounds: self.bounds.iter().chain(&function.bounds).cloned().collect(),
+                args: function
+                    .args
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), ty.substitute(&self_type)))
+                    .collect(),
+                return_type: function.return_type.substitute(&self_type),
+                self_type: Some(target.clone()),
+                ..function.clone()
+            })
+            .collect()
+    }
+}
+
+/// `trait Name { fn required(&self) -> i32; fn provided(&self) -> i32 { .. } }`
+#[derive(Debug, Clone)]
+pub struct TraitDeclare {
+    pub visibility: Visibility,
+    pub name: Identifier,
+    /// type parameters `trait Iterator<Item>`, a type implements a generic trait for one
+    /// list of type arguments, so they follow from the type
+    pub generics: Vec<Identifier>,
+    pub functions: Vec<TraitFunction>,
+}
+
+/// function of a trait, implementations that leave out a function with a default body use
+/// the default
+#[derive(Debug, Clone)]
+pub struct TraitFunction {
+    pub name: Identifier,
+    pub generics: Vec<Identifier>,
+    pub bounds: Vec<(Identifier, Identifier)>,
+    pub args: Vec<(Identifier, Type)>,
+    pub return_type: Type,
+    pub default: Option<Box<Expr>>,
+}
+
+impl TraitDeclare {
+    pub fn new(name: Identifier, functions: Vec<TraitFunction>) -> Self {
+        Self {
+            visibility: Visibility::Private,
+            name,
+            generics: vec![],
+            functions,
+        }
+    }
+
+    pub fn function(&self, name: &str) -> Option<&TraitFunction> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+impl TraitFunction {
+    pub fn new(
+        name: Identifier,
+        args: Vec<(Identifier, Type)>,
+        return_type: Type,
+        default: Option<Box<Expr>>,
+    ) -> Self {
+        Self {
+            name,
+            generics: vec![],
+            bounds: vec![],
+            args,
+            return_type,
+            default,
+        }
+    }
+
+    /// the default implementation as a function of an `impl` block
+    pub fn default_function(&self) -> Option<FunctionDeclare> {
+        self.default.as_ref().map(|body| FunctionDeclare {
+            generics: self.generics.clone(),
+            bounds: self.bounds.clone(),
+            ..FunctionDeclare::new(
+                self.name.clone(),
+                self.args.clone(),
+                self.return_type.clone(),
+                body.clone(),
+            )
+        })
+    }
 }
@@ -1,6 +1,5 @@
-use crate::{ttype::Identifier, Statement, Type};
+use crate::{ttype::Identifier, Location, Pattern, Statement, Type};
 use std::fmt::{Display, Formatter};
-use std::sync::Arc;
 
 #[derive(Debug, PartialOrd, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Opcode {
@@ -18,6 +17,23 @@ pub enum Opcode {
 
     LShift,
     RShift,
+
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Opcode {
+    /// `==`, `!=`, `<`, `<=`, `>` and `>=`, which give 1 when they hold and 0 otherwise
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Opcode::Eq | Opcode::Ne | Opcode::Lt | Opcode::Le | Opcode::Gt | Opcode::Ge
+        )
+    }
 }
 
 impl Display for Opcode {
@@ -34,6 +50,37 @@ impl Display for Opcode {
             Opcode::Xor => write!(f, "^"),
             Opcode::LShift => write!(f, "<<"),
             Opcode::RShift => write!(f, ">>"),
+            Opcode::Eq => write!(f, "=="),
+            Opcode::Ne => write!(f, "!="),
+            Opcode::Lt => write!(f, "<"),
+            Opcode::Le => write!(f, "<="),
+            Opcode::Gt => write!(f, ">"),
+            Opcode::Ge => write!(f, ">="),
+        }
+    }
+}
+
+impl From<&str> for Opcode {
+    fn from(op: &str) -> Self {
+        match op {
+            "+" => Opcode::Add,
+            "-" => Opcode::Sub,
+            "*" => Opcode::Mul,
+            "/" => Opcode::Div,
+            "%" => Opcode::Mod,
+            "**" => Opcode::Pow,
+            "|" => Opcode::Or,
+            "&" => Opcode::And,
+            "^" => Opcode::Xor,
+            "<<" => Opcode::LShift,
+            ">>" => Opcode::RShift,
+            "==" => Opcode::Eq,
+            "!=" => Opcode::Ne,
+            "<" => Opcode::Lt,
+            "<=" => Opcode::Le,
+            ">" => Opcode::Gt,
+            ">=" => Opcode::Ge,
+            _ => unreachable!("unknown opcode {}", op),
         }
     }
 }
@@ -73,13 +120,19 @@ impl Display for Opcode {
 //     }
 // }
 // mathematical
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Expr {
     // StructAssign(Identifier, Vec<(Box<Expr>, Box<Expr>)>),
-    Identifier(Identifier),
-    Field(Box<Expr>, Box<Expr>),
+    /// a variable, a function or a constant, the location is reported by the move checker
+    Identifier(Identifier, Location),
+    Field(Box<Expr>, Identifier),
+    /// `tuple.0`
+    TupleField(Box<Expr>, u32),
+    /// `(a, b)`, `()` is the unit value
+    Tuple(Vec<Box<Expr>>),
     Number(Number),
-    BinOperation(Opcode, Box<Expr>, Box<Expr>),
+    /// `lhs op rhs`, the location of the operator is reported when the operation panics
+    BinOperation(Opcode, Box<Expr>, Box<Expr>, Location),
     If {
         condition: Box<Expr>,
         then_body: Box<Expr>,
@@ -91,10 +144,92 @@ pub enum Expr {
     Group(Box<Expr>),
     Negative(Box<Number>),
     String(String),
+    /// `&expr` and `&mut expr`
+    Reference {
+        mutable: bool,
+        expr: Box<Expr>,
+    },
+    /// `*expr`, the location is the one of the `*`
+    Deref(Box<Expr>, Location),
+    /// `box expr`, moves the value to the garbage collected heap
+    Box(Box<Expr>),
+    /// `[a, b, c]`
+    Array(Vec<Box<Expr>>),
+    /// `[value; len]`
+    ArrayRepeat(Box<Expr>, u64),
+    /// `expr[index]`, the location is reported when the index is out of bounds
+    Index {
+        expr: Box<Expr>,
+        index: Box<Expr>,
+        location: Location,
+    },
+    /// `simd[a, b, c, d]`, one value per lane
+    SimdVector(Vec<Box<Expr>>),
+    /// `simd[value; lanes]`, the value is splatted to every lane
+    SimdSplat(Box<Expr>, u64),
+    /// `Enum::Variant`, called like a function for tuple-like variants
+    Path(Identifier, Identifier),
+    /// `Point { x: 1, y: 2 }` or `Shape::Rect { w: 1, h: 2 }` when a variant is given
+    StructLiteral {
+        name: Identifier,
+        variant: Option<Identifier>,
+        fields: Vec<(Identifier, Box<Expr>)>,
+    },
+    /// `|a, b: i32| a + b` or `|x| -> i32 { x }`, parameters without a type take the one
+    /// expected by the variable or parameter the closure is assigned to
+    Closure {
+        params: Vec<(Identifier, Option<Type>)>,
+        return_type: Option<Type>,
+        body: Box<Expr>,
+    },
+    /// `expr as TYPE`, converts between integer types
+    Cast(Box<Expr>, Type),
+    /// `unsafe { ... }`, the block may use mutable statics
+    Unsafe(Box<Expr>),
+    /// `match expr { pattern => expr, ... }`, the location is reported when the arms do
+    /// not cover every value
+    Match {
+        scrutinee: Box<Expr>,
+        arms: Vec<MatchArm>,
+        location: Location,
+    },
+    /// `expr?`, the value of a `Some` or an `Ok`, a `None` or an `Err` is returned from the
+    /// function, the location is reported when the function cannot return it
+    Try(Box<Expr>, Location),
+    /// `start..end` or `start..=end` when `inclusive`
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+        inclusive: bool,
+    },
+    /// `for pattern in iterable { ... }`, runs the body for every item of the iterator
+    For {
+        pattern: Pattern,
+        iterable: Box<Expr>,
+        body: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Box<Expr>,
+    /// location of the pattern, reported when the arm is unreachable
+    pub location: Location,
+}
+
+impl MatchArm {
+    pub fn new(pattern: Pattern, body: Expr, location: Location) -> Self {
+        Self {
+            pattern,
+            body: Box::new(body),
+            location,
+        }
+    }
 }
 
 impl Display for Expr {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
         // match self {
         //     Expr::StructAssign(ident, _fields) => write!(f, "struct {} {{}}", ident),
         //     Expr::Identifier(ident) => write!(f, "{}", ident),
@@ -116,14 +251,17 @@ impl Display for Expr {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Number {
     Integer8(i8),
     Integer16(i16),
     Integer32(i32),
+    Integer64(i64),
     UnSignInteger8(u8),
     UnSignInteger16(u16),
     UnSignInteger32(u32),
+    /// `1.5`, a literal with a fractional part, `f64` unless an `f32` is expected
+    Float(f64),
 }
 // impl Display for Number {
 //     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -1,15 +1,25 @@
-use crate::{statement::Statement, Identifier, Expr, Type};
-
-use std::collections::HashMap;
-use std::sync::Arc;
+use crate::{Identifier, Expr, Type, Visibility};
 
 // stmt
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FunctionDeclare {
+    pub visibility: Visibility,
+    /// `extern` functions use the C calling convention and keep their source name as symbol
+    pub is_extern: bool,
     pub name: Identifier,
+    /// type parameters `<T, U>`, a generic function is instantiated for every list of type
+    /// arguments it is called with
+    pub generics: Vec<Identifier>,
+    /// trait bounds `T: Trait` the type arguments of an instance have to satisfy
+    pub bounds: Vec<(Identifier, Identifier)>,
     pub args: Vec<(Identifier, Type)>,
     pub return_type: Type,
     pub stats: Box<Expr>,
+    /// the type `Self` stands for in a function of an `impl` block
+    pub self_type: Option<Type>,
+    /// the type parameters of a generic trait with the type arguments of the `impl` block,
+    /// which the body of a default function may name
+    pub trait_arguments: Vec<(Identifier, Type)>,
     // pub context: FunctionContext,
 }
 
@@ -23,13 +33,33 @@ impl FunctionDeclare {
         stats: Box<Expr>,
     ) -> Self {
         Self {
+            visibility: Visibility::Private,
+            is_extern: false,
             name,
+            generics: vec![],
+            bounds: vec![],
             args,
             return_type,
             stats,
+            self_type: None,
+            trait_arguments: vec![],
             // context: HashMap::new(),
         }
     }
+
+    pub fn is_generic(&self) -> bool {
+        !self.generics.is_empty()
+    }
+
+    /// whether the first parameter is a `self`, `&self` or `&mut self` receiver
+    pub fn is_method(&self) -> bool {
+        matches!(self.args.first(), Some((name, _)) if name == "self")
+    }
+
+    /// whether the function is exported from a library as `pub extern fn`
+    pub fn is_exported(&self) -> bool {
+        self.is_extern && self.visibility == Visibility::Public
+    }
 }
 //
 // impl FunctionDeclare {
@@ -1,5 +1,3 @@
-#[macro_use]
-extern crate log;
 
 
 #[macro_export]
@@ -12,15 +10,19 @@ macro_rules! c_str {
 mod complex_struct;
 mod expresion;
 mod function;
+mod location;
 mod module;
+mod pattern;
 mod statement;
 mod ttype;
 
 
 
 pub use complex_struct::*;
-pub use expresion::{Expr, Number, Opcode};
+pub use expresion::{Expr, MatchArm, Number, Opcode};
 pub use function::{FunctionDeclare};
-pub use module::Module;
+pub use location::Location;
+pub use module::{ConstDeclare, ModDeclare, Module, StaticDeclare, UseDeclare, Visibility};
+pub use pattern::Pattern;
 pub use statement::Statement;
 pub use ttype::*;
@@ -0,0 +1,20 @@
+use std::fmt::{Display, Formatter};
+
+/// position of a node in the source file, both line and column start from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Location {
+    pub line: u32,
+    pub column: usize,
+}
+
+impl Location {
+    pub fn new(line: u32, column: usize) -> Self {
+        Location { line, column }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
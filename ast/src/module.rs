@@ -1,8 +1,49 @@
-use crate::{ModuleItem};
-use std::sync::Arc;
+use crate::{Expr, Identifier, ModuleItem, Type};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Visibility {
+    Private,
+    Public,
+}
+
+/// `mod name;` or `pub mod name;`
+#[derive(Debug, Clone)]
+pub struct ModDeclare {
+    pub visibility: Visibility,
+    pub name: Identifier,
+}
+
+/// `use a::b;`, `use a::b as c;` or `use a::{b, c};`, a `pub use` re-exports the names
+#[derive(Debug, Clone)]
+pub struct UseDeclare {
+    pub visibility: Visibility,
+    /// path of every imported item with the name it is imported as
+    pub imports: Vec<(Vec<Identifier>, Identifier)>,
+}
+
+/// `const NAME: TYPE = VALUE;`, the value is evaluated at compile time and inlined where
+/// the constant is used
+#[derive(Debug, Clone)]
+pub struct ConstDeclare {
+    pub visibility: Visibility,
+    pub name: Identifier,
+    pub ty: Type,
+    pub value: Box<Expr>,
+}
+
+/// `static NAME: TYPE = VALUE;` or `static mut NAME: TYPE = VALUE;`, a global variable
+/// initialized with a constant value, a mutable one is only used inside of `unsafe`
+#[derive(Debug, Clone)]
+pub struct StaticDeclare {
+    pub visibility: Visibility,
+    pub mutable: bool,
+    pub name: Identifier,
+    pub ty: Type,
+    pub value: Box<Expr>,
+}
 
 // stmt
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Module {
     pub items: Vec<Box<ModuleItem>>,
 }
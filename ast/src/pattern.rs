@@ -0,0 +1,81 @@
+use crate::{Identifier, VariantFields};
+use std::fmt::{Display, Formatter};
+
+/// left hand side of a destructuring `let` and of the arms of a `match`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// `_`, the value is dropped
+    Wildcard,
+    /// binds the value to a new variable
+    Identifier(Identifier),
+    /// integer literal, matches by equality
+    Integer(i64),
+    /// `1..=5`, matches integers between both bounds inclusively
+    Range(i64, i64),
+    /// `(a, b)`, binds every element of a tuple
+    Tuple(Vec<Pattern>),
+    /// `Point { x, y: 0, .. }`, fields that are not mentioned match anything
+    Struct(Identifier, Vec<(Identifier, Pattern)>),
+    /// `Shape::Circle(r)`, `Shape::Rect { w, .. }` or `Shape::Empty`
+    Variant {
+        enum_name: Identifier,
+        variant: Identifier,
+        fields: VariantFields<Pattern>,
+    },
+}
+
+fn write_list(f: &mut Formatter<'_>, patterns: &[Pattern]) -> std::fmt::Result {
+    let patterns: Vec<String> = patterns.iter().map(|it| it.to_string()).collect();
+    write!(f, "{}", patterns.join(", "))
+}
+
+fn write_fields(f: &mut Formatter<'_>, fields: &[(Identifier, Pattern)]) -> std::fmt::Result {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, pattern)| match pattern {
+            Pattern::Identifier(binding) if binding == name => name.clone(),
+            pattern => format!("{}: {}", name, pattern),
+        })
+        .collect();
+    write!(f, "{{ {} }}", fields.join(", "))
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Identifier(name) => write!(f, "{}", name),
+            Pattern::Integer(value) => write!(f, "{}", value),
+            Pattern::Range(start, end) => write!(f, "{}..={}", start, end),
+            Pattern::Tuple(elements) if elements.len() == 1 => write!(f, "({},)", elements[0]),
+            Pattern::Tuple(elements) => {
+                write!(f, "(")?;
+                write_list(f, elements)?;
+                write!(f, ")")
+            }
+            Pattern::Struct(name, fields) => {
+                write!(f, "{} ", name)?;
+                write_fields(f, fields)
+            }
+            Pattern::Variant {
+                enum_name,
+                variant,
+                fields,
+            } => {
+                write!(f, "{}::{}", enum_name, variant)?;
+                match fields {
+                    VariantFields::Unit => Ok(()),
+                    VariantFields::Tuple(patterns) => {
+                        write!(f, "(")?;
+                        write_list(f, patterns)?;
+                        write!(f, ")")
+                    }
+                    VariantFields::Struct(fields) => {
+                        write!(f, " ")?;
+                        write_fields(f, fields)
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,13 +1,16 @@
-use crate::{Expr, Identifier, Type};
+use crate::{Expr, Identifier, Location, Pattern, Type};
 use std::fmt::Debug;
-use std::sync::Arc;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Statement {
     Declare(Identifier, Type, Box<Expr>),
-    Assignment(Identifier, Box<Expr>),
+    /// `let (a, b): (i32, i8) = value;`, the type annotation is optional
+    Destructure(Pattern, Option<Type>, Box<Expr>),
+    /// `place = value;` where place is a variable, a dereference or a field
+    Assignment(Box<Expr>, Box<Expr>),
     Expr(Box<Expr>),
-    Return(Box<Expr>),
+    /// `return value;`, the location is the one of the `return`
+    Return(Box<Expr>, Location),
 }
 // impl Display for Statement {
 //     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
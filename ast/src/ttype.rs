@@ -1,253 +1,141 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use uuid::Uuid;
 
-// use crate::{Opcode, StructDetail};
-// use llvm_sys::prelude::{LLVMBuilderRef, LLVMContextRef, LLVMModuleRef, LLVMTypeRef, LLVMValueRef};
-// use llvm_wrapper::typ::{Typ};
-// use std::{
-//     collections::HashMap,
-//     sync::{Arc, RwLock},
-// };
-// use uuid::Uuid;
-//
 pub type Identifier = String;
-//
-// pub type TypeName = String;
 pub type TypeId = Uuid;
-//
-#[derive(Debug)]
-pub struct Type {
-    pub name: Identifier,
-    pub type_id: TypeId,
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Named(Identifier),
+    /// `&T` and `&mut T`
+    Reference { mutable: bool, inner: Box<Type> },
+    /// raw pointer `*T`
+    Pointer(Box<Type>),
+    /// `Box<T>`, a pointer to a value allocated on the garbage collected heap
+    Boxed(Box<Type>),
+    /// fixed-size array `[T; N]`
+    Array(Box<Type>, u64),
+    /// vector of `N` integer or float lanes `simd<T, N>`, lowered to an llvm vector
+    Simd(Box<Type>, u64),
+    /// `(A, B)`, the empty tuple is the unit type `()`
+    Tuple(Vec<Type>),
+    /// generic struct applied to type arguments `Pair<i32, i8>`
+    Generic(Identifier, Vec<Type>),
+    /// trait object `dyn Trait` or `dyn Trait<i64>` of a generic trait, a value of any type
+    /// implementing the trait, only used behind a reference or a pointer
+    Dyn(Identifier, Vec<Type>),
+    /// `fn(A, B) -> R`, a named function or a closure taking `A` and `B`
+    Function(Vec<Type>, Box<Type>),
 }
-//
+
 impl Type {
-    pub fn new(name: Identifier) -> Self {
-        Self {
-            name,
-            type_id: Uuid::new_v4(),
+    pub fn named(name: Identifier) -> Self {
+        Type::Named(name)
+    }
+    pub fn void() -> Self {
+        Type::Tuple(vec![])
+    }
+    pub fn is_void(&self) -> bool {
+        matches!(self, Type::Tuple(elements) if elements.is_empty())
+    }
+    pub fn tuple(elements: Vec<Type>) -> Self {
+        Type::Tuple(elements)
+    }
+    pub fn reference(inner: Type, mutable: bool) -> Self {
+        Type::Reference {
+            mutable,
+            inner: Box::new(inner),
+        }
+    }
+    pub fn pointer(inner: Type) -> Self {
+        Type::Pointer(Box::new(inner))
+    }
+
+    pub fn boxed(inner: Type) -> Self {
+        Type::Boxed(Box::new(inner))
+    }
+
+    pub fn array(element: Type, len: u64) -> Self {
+        Type::Array(Box::new(element), len)
+    }
+
+    pub fn simd(element: Type, lanes: u64) -> Self {
+        Type::Simd(Box::new(element), lanes)
+    }
+
+    pub fn generic(name: Identifier, arguments: Vec<Type>) -> Self {
+        Type::Generic(name, arguments)
+    }
+
+    pub fn function(parameters: Vec<Type>, return_type: Type) -> Self {
+        Type::Function(parameters, Box::new(return_type))
+    }
+
+    /// replace the type parameters named in `arguments`
+    pub fn substitute(&self, arguments: &HashMap<Identifier, Type>) -> Type {
+        match self {
+            Type::Named(name) => arguments.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Type::Reference { mutable, inner } => Type::reference(inner.substitute(arguments), *mutable),
+            Type::Pointer(inner) => Type::pointer(inner.substitute(arguments)),
+            Type::Boxed(inner) => Type::boxed(inner.substitute(arguments)),
+            Type::Array(element, len) => Type::array(element.substitute(arguments), *len),
+            Type::Simd(element, lanes) => Type::simd(element.substitute(arguments), *lanes),
+            Type::Tuple(elements) => {
+                Type::tuple(elements.iter().map(|it| it.substitute(arguments)).collect())
+            }
+            Type::Generic(name, elements) => Type::generic(
+                name.clone(),
+                elements.iter().map(|it| it.substitute(arguments)).collect(),
+            ),
+            Type::Dyn(trait_name, elements) => Type::Dyn(
+                trait_name.clone(),
+                elements.iter().map(|it| it.substitute(arguments)).collect(),
+            ),
+            Type::Function(parameters, return_type) => Type::function(
+                parameters.iter().map(|it| it.substitute(arguments)).collect(),
+                return_type.substitute(arguments),
+            ),
         }
     }
-    pub fn void() ->Self {
-        Type::new("".to_owned())
+
+    /// the type behind a reference, a raw pointer or a box
+    pub fn pointee(&self) -> Option<&Type> {
+        match self {
+            Type::Reference { inner, .. } | Type::Pointer(inner) | Type::Boxed(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Reference { mutable: true, inner } => write!(f, "&mut {}", inner),
+            Type::Reference { mutable: false, inner } => write!(f, "&{}", inner),
+            Type::Pointer(inner) => write!(f, "*{}", inner),
+            Type::Boxed(inner) => write!(f, "Box<{}>", inner),
+            Type::Array(element, len) => write!(f, "[{}; {}]", element, len),
+            Type::Simd(element, lanes) => write!(f, "simd<{}, {}>", element, lanes),
+            Type::Tuple(elements) if elements.len() == 1 => write!(f, "({},)", elements[0]),
+            Type::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|it| it.to_string()).collect();
+                write!(f, "({})", elements.join(", "))
+            }
+            Type::Generic(name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(|it| it.to_string()).collect();
+                write!(f, "{}<{}>", name, arguments.join(", "))
+            }
+            Type::Dyn(trait_name, arguments) if arguments.is_empty() => write!(f, "dyn {}", trait_name),
+            Type::Dyn(trait_name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(|it| it.to_string()).collect();
+                write!(f, "dyn {}<{}>", trait_name, arguments.join(", "))
+            }
+            Type::Function(parameters, return_type) => {
+                let parameters: Vec<String> = parameters.iter().map(|it| it.to_string()).collect();
+                write!(f, "fn({}) -> {}", parameters.join(", "), return_type)
+            }
+        }
     }
 }
-//
-//     pub fn new_struct(struct_detail: &StructDetail, llvm_type: LLVMTypeRef) -> Self {
-//         Self {
-//             name: struct_detail.name.clone(),
-//             type_id: Uuid::new_v4(),
-//             operands: Default::default(),
-//             llvm_type_ref: Some((struct_detail.clone(), llvm_type)),
-//         }
-//     }
-//
-//     pub fn generate_type(&self, context: Arc<TyphoonContext>) -> LLVMTypeRef {
-//         // todo support all primitive type
-//         if self.name.eq("i8") {
-//             Typ::int8(context.llvm_context)
-//         } else if self.name.eq("i32") {
-//             Typ::int32(context.llvm_context)
-//         } else if let Some((_, b)) = self.llvm_type_ref.as_ref() {
-//             b.clone()
-//         } else {
-//             Typ::int32(context.llvm_context)
-//         }
-//     }
-//
-//     pub fn is_primitive(&self) -> bool {
-//         self.llvm_type_ref.is_none()
-//     }
-//
-//     pub fn get_field_type(
-//         &self,
-//         context: Arc<TyphoonContext>,
-//         field: &Identifier,
-//     ) -> Option<Arc<Type>> {
-//         self.llvm_type_ref
-//             .as_ref()
-//             .map(|t_ref| &t_ref.0)
-//             .and_then(|struct_detail| {
-//                 struct_detail
-//                     .fields
-//                     .iter()
-//                     .filter(|(k, _v)| k.eq(&field))
-//                     .map(|(_k, v)| v)
-//                     .next()
-//             })
-//             .and_then(|v| context.get_type_from_name(v.clone()))
-//     }
-//
-//     pub fn get_type_field_idx(&self, ident: &Identifier) -> Option<u32> {
-//         self.llvm_type_ref
-//             .as_ref()
-//             .map(|t_ref| &t_ref.0)
-//             .and_then(|struct_detail| {
-//                 struct_detail
-//                     .fields
-//                     .iter()
-//                     .enumerate()
-//                     .filter(|(_idx, (k, _v))| k.eq(&ident))
-//                     .map(|(idx, _)| idx as u32)
-//                     .next()
-//             })
-//     }
-//
-//     pub fn get_operand_type(&self, opcode: Opcode, rhs: Arc<Type>) -> Option<TypeId> {
-//         let key = (opcode, rhs.type_id);
-//         self.operands.get(&key).map(|v| v.clone())
-//     }
-//
-//     pub fn can_be_operand(&self, opcode: Opcode, rhs: Arc<Type>) -> bool {
-//         let key = (opcode, rhs.type_id);
-//         self.operands.contains_key(&key)
-//     }
-//
-//     pub fn equals(&self, other: Arc<Type>) -> bool {
-//         self.name.eq(&other.name)
-//     }
-// }
-//
-// #[derive(Debug)]
-// pub struct TyphoonContext {
-//     pub llvm_context: LLVMContextRef,
-//     pub builder: LLVMBuilderRef,
-//     pub module: LLVMModuleRef,
-//     pub upper: Option<Arc<TyphoonContext>>,
-//     pub variables: RwLock<HashMap<Identifier, (LLVMValueRef, TypeId)>>,
-//     pub types: RwLock<HashMap<TypeName, Arc<Type>>>,
-//     pub types_id: RwLock<HashMap<TypeId, Arc<Type>>>,
-//     pub function: Option<LLVMValueRef>,
-// }
-//
-// impl TyphoonContext {
-//     pub fn new(
-//         llvm_context: LLVMContextRef,
-//         builder: LLVMBuilderRef,
-//         module: LLVMModuleRef,
-//     ) -> TyphoonContext {
-//         // todo
-//         let mut i8_type = Type::new("i8".to_string());
-//         let mut i32_type = Type::new("i32".to_string());
-//
-//         i8_type
-//             .operands
-//             .insert((Opcode::Add, i8_type.type_id), i8_type.type_id);
-//         i8_type
-//             .operands
-//             .insert((Opcode::Mul, i8_type.type_id), i8_type.type_id);
-//         i8_type
-//             .operands
-//             .insert((Opcode::Div, i8_type.type_id), i8_type.type_id);
-//         i8_type
-//             .operands
-//             .insert((Opcode::Sub, i8_type.type_id), i8_type.type_id);
-//         i32_type
-//             .operands
-//             .insert((Opcode::Add, i32_type.type_id), i32_type.type_id);
-//         i32_type
-//             .operands
-//             .insert((Opcode::Mul, i32_type.type_id), i32_type.type_id);
-//         i32_type
-//             .operands
-//             .insert((Opcode::Div, i32_type.type_id), i32_type.type_id);
-//         i32_type
-//             .operands
-//             .insert((Opcode::Sub, i32_type.type_id), i32_type.type_id);
-//
-//         let arc = Arc::new(i8_type);
-//         let arc1 = Arc::new(i32_type);
-//
-//         let mut type_map = HashMap::new();
-//         type_map.insert("i8".to_string(), arc.clone());
-//         type_map.insert("i32".to_string(), arc1.clone());
-//
-//         let mut type_id_map = HashMap::new();
-//         type_id_map.insert(arc.clone().type_id, arc.clone());
-//         type_id_map.insert(arc1.clone().type_id, arc1.clone());
-//
-//         TyphoonContext {
-//             llvm_context,
-//             builder,
-//             module,
-//             upper: None,
-//             variables: RwLock::new(HashMap::new()),
-//             types: RwLock::new(type_map),
-//             types_id: RwLock::new(type_id_map),
-//             function: None,
-//         }
-//     }
-//
-//     pub fn new_with_upper(upper: Arc<TyphoonContext>, function: LLVMValueRef) -> TyphoonContext {
-//         TyphoonContext {
-//             llvm_context: upper.llvm_context,
-//             builder: upper.builder,
-//             module: upper.module,
-//             upper: Some(upper.clone()),
-//             variables: RwLock::new(HashMap::new()),
-//             types: RwLock::new(HashMap::new()),
-//             types_id: RwLock::new(HashMap::default()),
-//             function: Some(function),
-//         }
-//     }
-//
-//     pub fn new_assign(&self, name: Identifier, value: LLVMValueRef, type_id: TypeId) {
-//         debug!("add assign to variable table {} {}", &name, &type_id);
-//         let mut guard = self.variables.write().unwrap();
-//         guard.insert(name, (value, type_id));
-//     }
-//
-//     pub fn define_struct(&self, struct_detail: &StructDetail, llvm_type: LLVMTypeRef) {
-//         // todo define struct duplicated check
-//         let named_struct = Arc::new(Type::new_struct(struct_detail, llvm_type));
-//         {
-//             let mut guard = self.types.write().unwrap();
-//             guard.insert(struct_detail.name.clone(), named_struct.clone());
-//         }
-//
-//         {
-//             let mut guard = self.types_id.write().unwrap();
-//             guard.insert(named_struct.type_id, named_struct.clone());
-//         }
-//     }
-//
-//     pub fn get_variable_type(&self, name: Identifier) -> Option<Arc<Type>> {
-//         debug!("get variable type {}", &name);
-//         let guard = self.variables.read().unwrap();
-//         guard
-//             .get(&name)
-//             .map(|d| d.1)
-//             .and_then(|t| self.get_type_from_id(t))
-//             .or_else(|| self.upper.as_ref().and_then(|f| f.get_variable_type(name)))
-//     }
-//
-//     #[inline]
-//     pub fn get_type_from_name(&self, name: Identifier) -> Option<Arc<Type>> {
-//         debug!("get type from name {}", &name);
-//         self.types
-//             .read()
-//             .expect("cannot get lock")
-//             .get(&name)
-//             .map(|d| d.clone())
-//             .or_else(|| self.upper.as_ref().and_then(|f| f.get_type_from_name(name)))
-//     }
-//
-//     pub fn get_type_from_id(&self, type_id: TypeId) -> Option<Arc<Type>> {
-//         debug!("get type from id {}", &type_id);
-//         self.types_id
-//             .read()
-//             .expect("cannot get lock")
-//             .get(&type_id)
-//             .map(|d| d.clone())
-//             .or_else(|| {
-//                 self.upper
-//                     .as_ref()
-//                     .and_then(|f| f.get_type_from_id(type_id))
-//             })
-//     }
-// }
-//
-// pub trait Storable {
-//     fn store();
-//
-//     fn load();
-// }
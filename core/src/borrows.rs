@@ -0,0 +1,516 @@
+use crate::cfg::{
+    in_place, Access, BlockId, ControlFlowGraph, Event, Items, LoanId, LocalId, Place, Source, Through, RETURN,
+};
+use ast::{FunctionDeclare, Identifier, Location, Module, ModuleItem};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+
+/// what an access does to the place it conflicts on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Use,
+    Move,
+    Assign,
+    Borrow { mutable: bool },
+}
+
+impl Action {
+    /// the action on `place` as written in a diagnostic
+    fn on(&self, place: &str) -> String {
+        match self {
+            Action::Use => format!("use `{}`", place),
+            Action::Move => format!("move out of `{}`", place),
+            Action::Assign => format!("assign to `{}`", place),
+            Action::Borrow { mutable: false } => format!("borrow `{}`", place),
+            Action::Borrow { mutable: true } => format!("borrow `{}` as mutable", place),
+        }
+    }
+
+    /// whether the action is allowed while the place is borrowed
+    fn conflicts(&self, mutable_borrow: bool) -> bool {
+        match self {
+            Action::Use => mutable_borrow,
+            Action::Move | Action::Assign => true,
+            Action::Borrow { mutable } => *mutable || mutable_borrow,
+        }
+    }
+}
+
+/// a use of a borrowed value or of a reference which the borrows of the function do not allow
+#[derive(Debug, Clone, PartialEq)]
+pub enum BorrowError {
+    /// `place` is accessed while a borrow of it is live, the borrow is created at
+    /// `borrowed` and keeps being live until it is used at `used`
+    Conflict {
+        function: Identifier,
+        place: String,
+        action: Action,
+        location: Location,
+        mutable: bool,
+        borrowed: Location,
+        used: Location,
+    },
+    /// `local` goes out of scope while a borrow of it is live
+    OutOfScope {
+        function: Identifier,
+        local: String,
+        borrowed: Location,
+        used: Location,
+    },
+    /// the function returns a reference to `local`, which does not live longer than the call
+    ReturnsLocal {
+        function: Identifier,
+        local: String,
+        borrowed: Location,
+    },
+    /// the value behind the shared reference `reference` is assigned or borrowed as mutable
+    ThroughShared {
+        function: Identifier,
+        reference: String,
+        action: Action,
+        location: Location,
+    },
+}
+
+impl BorrowError {
+    /// where the error is reported in the source
+    pub fn location(&self) -> Location {
+        match self {
+            BorrowError::Conflict { location, .. } | BorrowError::ThroughShared { location, .. } => *location,
+            BorrowError::OutOfScope { used, .. } => *used,
+            BorrowError::ReturnsLocal { borrowed, .. } => *borrowed,
+        }
+    }
+}
+
+impl Display for BorrowError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BorrowError::Conflict {
+                function,
+                place,
+                action,
+                location,
+                mutable,
+                borrowed,
+                used,
+            } => write!(
+                f,
+                "cannot {} in {} at {} while it is borrowed{}, the borrow is created at {} and used later at {}",
+                action.on(place),
+                function,
+                location,
+                if *mutable { " as mutable" } else { "" },
+                borrowed,
+                used
+            ),
+            BorrowError::OutOfScope {
+                function,
+                local,
+                borrowed,
+                used,
+            } => write!(
+                f,
+                "`{}` in {} goes out of scope while it is borrowed, the borrow is created at {} and used later at {}",
+                local, function, borrowed, used
+            ),
+            BorrowError::ReturnsLocal {
+                function,
+                local,
+                borrowed,
+            } => write!(
+                f,
+                "{} returns a reference to its local `{}`, which is borrowed at {}",
+                function, local, borrowed
+            ),
+            BorrowError::ThroughShared {
+                function,
+                reference,
+                action,
+                location,
+            } => write!(
+                f,
+                "cannot {} through the shared reference `{}` in {} at {}",
+                match action {
+                    Action::Assign => "assign",
+                    _ => "borrow as mutable",
+                },
+                reference,
+                function,
+                location
+            ),
+        }
+    }
+}
+
+/// a borrow of a place, as created by the event `Access::Borrow`
+struct Loan {
+    place: Place,
+    mutable: bool,
+    location: Location,
+    through: Option<Through>,
+}
+
+/// the loans created but not yet written to a local, and the loans the references held by
+/// each local may come from
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Loans {
+    pending: BTreeSet<LoanId>,
+    held: BTreeMap<LocalId, BTreeSet<LoanId>>,
+}
+
+impl Loans {
+    /// add the loans of `other`, whether any is new
+    fn join(&mut self, other: &Loans) -> bool {
+        let before = self.clone();
+        self.pending.extend(other.pending.iter().copied());
+        for (local, loans) in other.held.iter() {
+            self.held.entry(*local).or_default().extend(loans.iter().copied());
+        }
+        *self != before
+    }
+
+    /// the loans a value made of `sources` may hold
+    fn of(&self, sources: &[Source]) -> BTreeSet<LoanId> {
+        sources
+            .iter()
+            .flat_map(|source| match source {
+                Source::Loan(loan) => vec![*loan],
+                Source::Local(local) => self.held.get(local).into_iter().flatten().copied().collect(),
+            })
+            .collect()
+    }
+
+    /// the loans live where `live` are the locals used later, with the locals holding them
+    fn live(&self, live: &HashSet<LocalId>) -> BTreeMap<LoanId, HashSet<LocalId>> {
+        let mut loans: BTreeMap<LoanId, HashSet<LocalId>> =
+            self.pending.iter().map(|loan| (*loan, HashSet::new())).collect();
+        for (local, held) in self.held.iter().filter(|(local, _)| live.contains(local)) {
+            for loan in held {
+                loans.entry(*loan).or_default().insert(*local);
+            }
+        }
+        loans
+    }
+}
+
+/// the local the event gives a new value or ends the scope of
+fn defines(event: &Event) -> Option<LocalId> {
+    let whole = event.through.is_none() && event.place.fields.is_empty();
+    match event.access {
+        Access::Write if whole => Some(event.place.local),
+        Access::Dead => Some(event.place.local),
+        _ => None,
+    }
+}
+
+/// the local whose value the event reads, the sources of a write are read by events of
+/// their own before it
+fn uses(event: &Event) -> Option<LocalId> {
+    match event.access {
+        Access::Dead => None,
+        Access::Write if in_place(event.through) => None,
+        _ => Some(event.place.local),
+    }
+}
+
+/// the locals used later at the end of each block, a borrow is live while a local holding
+/// it is used later, not to the end of the scope of the local
+fn liveness(graph: &ControlFlowGraph) -> Vec<HashSet<LocalId>> {
+    let mut live_out: Vec<HashSet<LocalId>> = vec![HashSet::new(); graph.blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in (0..graph.blocks.len()).rev() {
+            let mut live: HashSet<LocalId> = graph.blocks[block]
+                .successors
+                .iter()
+                .flat_map(|successor| live_in(graph, *successor, &live_out[*successor]))
+                .collect();
+            if live.len() != live_out[block].len() {
+                std::mem::swap(&mut live, &mut live_out[block]);
+                changed = true;
+            }
+        }
+    }
+    live_out
+}
+
+fn live_in(graph: &ControlFlowGraph, block: BlockId, live_out: &HashSet<LocalId>) -> HashSet<LocalId> {
+    let mut live = live_out.clone();
+    for event in graph.blocks[block].events.iter().rev() {
+        step_back(event, &mut live);
+    }
+    live
+}
+
+/// the locals used later before `event` from the ones used later after it
+fn step_back(event: &Event, live: &mut HashSet<LocalId>) {
+    if let Some(local) = defines(event) {
+        live.remove(&local);
+    }
+    live.extend(uses(event));
+}
+
+/// checks that borrows are used the way rust allows: a place borrowed as mutable is not
+/// used while the borrow is live and a borrowed place is not assigned, moved or borrowed as
+/// mutable, references do not outlive the locals they borrow and values behind shared
+/// references are not mutated
+///
+/// a borrow is live from where it is created up to the last use of a local holding it,
+/// references passed to a call live until the call
+pub struct BorrowChecker {
+    items: Items,
+}
+
+impl BorrowChecker {
+    pub fn new(module: &Module) -> Self {
+        BorrowChecker {
+            items: Items::new(module),
+        }
+    }
+
+    /// the errors of the functions of the module and of their closures
+    pub fn check_module(&self, module: &Module) -> Vec<BorrowError> {
+        module
+            .items
+            .iter()
+            .flat_map(|item| match item.as_ref() {
+                ModuleItem::FunctionDeclare(function) => vec![function.clone()],
+                ModuleItem::Impl(impl_block) => impl_block.qualified_functions(),
+                _ => vec![],
+            })
+            .flat_map(|function| self.check_function(&function))
+            .collect()
+    }
+
+    /// the errors of `function` and of its closures, ordered by location
+    pub fn check_function(&self, function: &FunctionDeclare) -> Vec<BorrowError> {
+        let graph = ControlFlowGraph::new(function, &self.items);
+        let mut errors = vec![];
+        self.check_graph(&graph, &mut errors);
+        errors.sort_by_key(|error| {
+            let location = error.location();
+            (location.line, location.column)
+        });
+        errors.dedup();
+        errors
+    }
+
+    fn check_graph(&self, graph: &ControlFlowGraph, errors: &mut Vec<BorrowError>) {
+        let loans: BTreeMap<LoanId, Loan> = graph
+            .blocks
+            .iter()
+            .flat_map(|block| block.events.iter())
+            .filter_map(|event| match event.access {
+                Access::Borrow { mutable, loan } => Some((
+                    loan,
+                    Loan {
+                        place: event.place.clone(),
+                        mutable,
+                        location: event.location,
+                        through: event.through,
+                    },
+                )),
+                _ => None,
+            })
+            .collect();
+        // the loans at the start of each block, until nothing changes
+        let mut entries: Vec<Option<Loans>> = vec![None; graph.blocks.len()];
+        entries[0] = Some(Loans::default());
+        let mut pending = vec![0];
+        while let Some(block) = pending.pop() {
+            let mut state = entries[block].clone().unwrap();
+            for event in graph.blocks[block].events.iter() {
+                self.transfer(graph, event, &mut state);
+            }
+            for successor in graph.blocks[block].successors.iter() {
+                let first_visit = entries[*successor].is_none();
+                let changed = entries[*successor].get_or_insert_with(Loans::default).join(&state);
+                if (first_visit || changed) && !pending.contains(successor) {
+                    pending.push(*successor);
+                }
+            }
+        }
+        let live_out = liveness(graph);
+        for (block, entry) in entries.into_iter().enumerate() {
+            let mut state = match entry {
+                Some(state) => state,
+                None => continue,
+            };
+            let events = &graph.blocks[block].events;
+            // the locals used later after each event of the block
+            let mut live_after = vec![HashSet::new(); events.len()];
+            let mut live = live_out[block].clone();
+            for (index, event) in events.iter().enumerate().rev() {
+                live_after[index] = live.clone();
+                step_back(event, &mut live);
+            }
+            for (index, event) in events.iter().enumerate() {
+                let live = state.live(&live_after[index]);
+                self.check_event(graph, &loans, (block, index), &live, errors);
+                self.transfer(graph, event, &mut state);
+                if event.access == Access::Write && event.place.local == RETURN {
+                    for loan in state.held.get(&RETURN).into_iter().flatten() {
+                        let loan = &loans[loan];
+                        if in_place(loan.through) {
+                            errors.push(BorrowError::ReturnsLocal {
+                                function: graph.function.clone(),
+                                local: graph.describe(&Place::new(loan.place.local)),
+                                borrowed: loan.location,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        for closure in graph.closures.iter() {
+            self.check_graph(closure, errors);
+        }
+    }
+
+    /// whether the place written by `event` may hold references
+    fn holds_references(&self, graph: &ControlFlowGraph, event: &Event) -> bool {
+        let ty = match event.through {
+            None => graph.place_type(&event.place, &self.items),
+            Some(_) => None,
+        };
+        ty.is_none_or(|ty| self.items.holds_references(&ty))
+    }
+
+    fn transfer(&self, graph: &ControlFlowGraph, event: &Event, state: &mut Loans) {
+        match event.access {
+            Access::Borrow { loan, .. } => {
+                state.pending.insert(loan);
+            }
+            Access::Write => {
+                let mut loans = state.of(&event.sources);
+                for source in event.sources.iter() {
+                    if let Source::Loan(loan) = source {
+                        state.pending.remove(loan);
+                    }
+                }
+                if !self.holds_references(graph, event) {
+                    loans.clear();
+                }
+                match defines(event) {
+                    Some(local) => {
+                        state.held.insert(local, loans);
+                    }
+                    None => state.held.entry(event.place.local).or_default().extend(loans),
+                }
+            }
+            Access::Dead => {
+                state.held.remove(&event.place.local);
+            }
+            Access::Read | Access::Move => {}
+        }
+    }
+
+    /// report the borrows the event at `position` conflicts with, `live` are the loans
+    /// live after the event with the locals holding them
+    fn check_event(
+        &self,
+        graph: &ControlFlowGraph,
+        loans: &BTreeMap<LoanId, Loan>,
+        position: (BlockId, usize),
+        live: &BTreeMap<LoanId, HashSet<LocalId>>,
+        errors: &mut Vec<BorrowError>,
+    ) {
+        let event = &graph.blocks[position.0].events[position.1];
+        let function = graph.function.clone();
+        let mutates = match event.access {
+            Access::Write => Some(Action::Assign),
+            Access::Borrow { mutable: true, .. } => Some(Action::Borrow { mutable: true }),
+            _ => None,
+        };
+        if let (Some(action), Some(Through::SharedReference)) = (mutates, event.through) {
+            errors.push(BorrowError::ThroughShared {
+                function: function.clone(),
+                reference: graph.describe(&event.place),
+                action,
+                location: event.location,
+            });
+        }
+        // an access through a pointer only reads the local holding it
+        let action = match (event.access, in_place(event.through)) {
+            (Access::Dead, _) => None,
+            (Access::Move, _) => Some(Action::Move),
+            (Access::Write, true) => Some(Action::Assign),
+            (Access::Borrow { mutable, .. }, true) => Some(Action::Borrow { mutable }),
+            _ => Some(Action::Use),
+        };
+        let own = match event.access {
+            Access::Borrow { loan, .. } => Some(loan),
+            _ => None,
+        };
+        let conflict = live
+            .iter()
+            .filter(|(loan, _)| Some(**loan) != own)
+            .map(|(id, holders)| (*id, &loans[id], holders))
+            .filter(|(_, loan, _)| in_place(loan.through))
+            .find(|(_, loan, _)| match action {
+                Some(action) => loan.place.overlaps(&event.place) && action.conflicts(loan.mutable),
+                None => loan.place.local == event.place.local,
+            });
+        let (id, loan, holders) = match conflict {
+            Some(conflict) => conflict,
+            None => return,
+        };
+        let used = self.later_use(graph, position, id, holders.clone()).unwrap_or(event.location);
+        errors.push(match action {
+            Some(action) => BorrowError::Conflict {
+                function,
+                place: graph.describe(&event.place),
+                action,
+                location: event.location,
+                mutable: loan.mutable,
+                borrowed: loan.location,
+                used,
+            },
+            None => BorrowError::OutOfScope {
+                function,
+                local: graph.describe(&event.place),
+                borrowed: loan.location,
+                used,
+            },
+        });
+    }
+
+    /// the first use after `position` of a local holding `loan`, following the locals the loan
+    /// is written to, events without a location are passed over
+    fn later_use(
+        &self,
+        graph: &ControlFlowGraph,
+        position: (BlockId, usize),
+        loan: LoanId,
+        mut holders: HashSet<LocalId>,
+    ) -> Option<Location> {
+        let mut visited = HashSet::new();
+        let mut pending = VecDeque::from([(position.0, position.1 + 1)]);
+        while let Some((block, start)) = pending.pop_front() {
+            for event in graph.blocks[block].events[start..].iter() {
+                let carries = event.sources.iter().any(|source| match source {
+                    Source::Loan(it) => *it == loan,
+                    Source::Local(local) => holders.contains(local),
+                });
+                let used = carries || uses(event).is_some_and(|local| holders.contains(&local));
+                if used && event.location != Location::default() {
+                    return Some(event.location);
+                }
+                if let Some(local) = defines(event) {
+                    holders.remove(&local);
+                }
+                if carries && event.access == Access::Write && self.holds_references(graph, event) {
+                    holders.insert(event.place.local);
+                }
+            }
+            for successor in graph.blocks[block].successors.iter() {
+                if visited.insert(*successor) {
+                    pending.push_back((*successor, 0));
+                }
+            }
+        }
+        None
+    }
+}
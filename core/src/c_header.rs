@@ -0,0 +1,264 @@
+use crate::error::TyphoonError;
+use ast::{FunctionDeclare, Module, ModuleItem, StructDeclare, Type, TypeAlias};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// C header generator for libraries built from typhoon source
+///
+/// the header contains a prototype for every `pub extern fn` and the layout of the structs
+/// their signatures refer to, so that C (or Rust via bindgen) can link against the
+/// `.a`/`.so` produced by `Program::as_library`.
+pub struct CHeader<'a> {
+    name: &'a str,
+    module: &'a Module,
+}
+
+impl<'a> CHeader<'a> {
+    pub fn new(name: &'a str, module: &'a Module) -> Self {
+        CHeader { name, module }
+    }
+
+    pub fn generate(&self) -> Result<String, TyphoonError> {
+        let guard = format!("TYPHOON_{}_H", self.name.to_uppercase().replace('-', "_"));
+        let mut output = String::new();
+        writeln!(output, "/* generated by typhoon from {}.ty, do not edit */", self.name).unwrap();
+        writeln!(output, "#ifndef {}", guard).unwrap();
+        writeln!(output, "#define {}", guard).unwrap();
+        writeln!(output).unwrap();
+        writeln!(output, "#include <stdint.h>").unwrap();
+        writeln!(output).unwrap();
+        writeln!(output, "#ifdef __cplusplus").unwrap();
+        writeln!(output, "extern \"C\" {{").unwrap();
+        writeln!(output, "#endif").unwrap();
+        writeln!(output).unwrap();
+
+        let mut emitted = HashSet::new();
+        for item in self.exported_structs() {
+            self.write_struct(&mut output, item, &mut emitted, &mut vec![])?;
+        }
+
+        for function in self.exported_functions() {
+            writeln!(output, "{};", self.prototype(function)?).unwrap();
+        }
+        if self.exported_functions().next().is_some() {
+            writeln!(output).unwrap();
+        }
+
+        writeln!(output, "#ifdef __cplusplus").unwrap();
+        writeln!(output, "}}").unwrap();
+        writeln!(output, "#endif").unwrap();
+        writeln!(output).unwrap();
+        writeln!(output, "#endif /* {} */", guard).unwrap();
+        Ok(output)
+    }
+
+    fn structs(&self) -> impl Iterator<Item = &'a StructDeclare> {
+        self.module.items.iter().filter_map(|item| match item.as_ref() {
+            // generic structs only have a layout once instantiated
+            ModuleItem::StructDeclare(s) if !s.is_generic() => Some(s),
+            _ => None,
+        })
+    }
+
+    /// the structs the signatures of the exported functions refer to and the structs of
+    /// their fields, in the order they are declared, the other items of the module, the
+    /// standard library among them, stay out of the header
+    fn exported_structs(&self) -> Vec<&'a StructDeclare> {
+        let mut reachable = HashSet::new();
+        for function in self.exported_functions() {
+            let types = function.args.iter().map(|(_, ty)| ty);
+            for ty in types.chain(std::iter::once(&function.return_type)) {
+                self.reach(ty, &mut reachable);
+            }
+        }
+        self.structs()
+            .filter(|item| reachable.contains(item.name.as_str()))
+            .collect()
+    }
+
+    fn reach(&self, ty: &Type, reachable: &mut HashSet<&'a str>) {
+        match ty {
+            Type::Reference { inner, .. } | Type::Pointer(inner) | Type::Array(inner, _) => {
+                self.reach(inner, reachable)
+            }
+            Type::Named(name) => {
+                if let Some(alias) = self.find_alias(name) {
+                    self.reach(&alias.ty, reachable);
+                } else if let Some(item) = self.find_struct(name) {
+                    if reachable.insert(item.name.as_str()) {
+                        for field in item.fields.values() {
+                            self.reach(field, reachable);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn exported_functions(&self) -> impl Iterator<Item = &'a FunctionDeclare> {
+        self.module.items.iter().filter_map(|item| match item.as_ref() {
+            ModuleItem::FunctionDeclare(f) if f.is_exported() => Some(f),
+            _ => None,
+        })
+    }
+
+    fn find_struct(&self, name: &str) -> Option<&'a StructDeclare> {
+        self.structs().find(|s| s.name.eq(name))
+    }
+
+    fn find_alias(&self, name: &str) -> Option<&'a TypeAlias> {
+        self.module.items.iter().find_map(|item| match item.as_ref() {
+            ModuleItem::TypeAlias(alias) if alias.name == name => Some(alias),
+            _ => None,
+        })
+    }
+
+    /// the struct a type refers to by value
+    fn struct_of(&self, ty: &Type) -> Option<&'a StructDeclare> {
+        match ty {
+            Type::Named(name) => match self.find_alias(name) {
+                Some(alias) => self.struct_of(&alias.ty),
+                None => self.find_struct(name),
+            },
+            Type::Array(element, _) => self.struct_of(element),
+            _ => None,
+        }
+    }
+
+    /// C requires a struct to be complete before it is embedded by value, so the structs
+    /// used as fields are written out first
+    fn write_struct(
+        &self,
+        output: &mut String,
+        item: &'a StructDeclare,
+        emitted: &mut HashSet<&'a str>,
+        visiting: &mut Vec<&'a str>,
+    ) -> Result<(), TyphoonError> {
+        if emitted.contains(item.name.as_str()) {
+            return Ok(());
+        }
+        if visiting.contains(&item.name.as_str()) {
+            return Err(TyphoonError::CompileError(format!(
+                "struct {} contains itself by value",
+                &item.name
+            )));
+        }
+        visiting.push(&item.name);
+        for ty in item.fields.values() {
+            if let Some(dependency) = self.struct_of(ty) {
+                self.write_struct(output, dependency, emitted, visiting)?;
+            }
+        }
+        visiting.pop();
+
+        writeln!(output, "struct {} {{", &item.name).unwrap();
+        for (name, ty) in item.fields.iter() {
+            writeln!(output, "    {};", self.field_declaration(ty, name)?).unwrap();
+        }
+        writeln!(output, "}};").unwrap();
+        writeln!(output).unwrap();
+        emitted.insert(&item.name);
+        Ok(())
+    }
+
+    fn prototype(&self, function: &FunctionDeclare) -> Result<String, TyphoonError> {
+        if function.is_generic() {
+            return Err(TyphoonError::CompileError(format!(
+                "generic function {} cannot be exported",
+                &function.name
+            )));
+        }
+        let params = if function.args.is_empty() {
+            "void".to_string()
+        } else {
+            function
+                .args
+                .iter()
+                .map(|(name, ty)| Ok(format!("{} {}", self.abi_type(function, ty)?, name)))
+                .collect::<Result<Vec<String>, TyphoonError>>()?
+                .join(", ")
+        };
+        Ok(format!(
+            "{} {}({})",
+            self.abi_type(function, &function.return_type)?,
+            &function.name,
+            params
+        ))
+    }
+
+    /// LLVM does not lower aggregates to the platform C ABI on its own, so structs are
+    /// rejected in exported signatures instead of silently mismatching the caller
+    fn abi_type(&self, function: &FunctionDeclare, ty: &Type) -> Result<String, TyphoonError> {
+        if let Some(struct_decl) = self.struct_of(ty) {
+            return Err(TyphoonError::CompileError(format!(
+                "struct {} cannot be passed by value in exported function {}, pass it by reference instead",
+                &struct_decl.name, &function.name
+            )));
+        }
+        self.c_type(ty)
+    }
+
+    /// arrays are only representable as struct fields, where C puts the length after the name
+    fn field_declaration(&self, ty: &Type, name: &str) -> Result<String, TyphoonError> {
+        match ty {
+            Type::Array(element, len) => {
+                self.field_declaration(element, &format!("{}[{}]", name, len))
+            }
+            Type::Named(alias) if self.find_alias(alias).is_some() => {
+                self.field_declaration(&self.find_alias(alias).unwrap().ty, name)
+            }
+            ty => Ok(format!("{} {}", self.c_type(ty)?, name)),
+        }
+    }
+
+    fn c_type(&self, ty: &Type) -> Result<String, TyphoonError> {
+        let c_type = match ty {
+            Type::Named(name) => match name.as_str() {
+                "i8" => "int8_t".to_string(),
+                "i16" => "int16_t".to_string(),
+                "i32" => "int32_t".to_string(),
+                "i64" => "int64_t".to_string(),
+                "f32" => "float".to_string(),
+                "f64" => "double".to_string(),
+                name if self.find_struct(name).is_some() => format!("struct {}", name),
+                // aliases are expanded, C code sees the aliased type
+                name if self.find_alias(name).is_some() => self.c_type(&self.find_alias(name).unwrap().ty)?,
+                name => {
+                    return Err(TyphoonError::CompileError(format!(
+                        "type {} has no C representation",
+                        name
+                    )))
+                }
+            },
+            Type::Reference {
+                mutable: false,
+                inner,
+            } => format!("const {} *", self.c_type(inner)?),
+            Type::Reference {
+                mutable: true,
+                inner,
+            }
+            | Type::Pointer(inner) => format!("{} *", self.c_type(inner)?),
+            Type::Tuple(elements) if elements.is_empty() => "void".to_string(),
+            Type::Simd(..)
+            | Type::Boxed(..)
+            | Type::Tuple(..)
+            | Type::Generic(..)
+            | Type::Dyn(..)
+            | Type::Function(..) => {
+                return Err(TyphoonError::CompileError(format!(
+                    "{} has no C representation",
+                    ty
+                )))
+            }
+            Type::Array(..) => {
+                return Err(TyphoonError::CompileError(format!(
+                    "array {} has no C representation outside of a struct",
+                    ty
+                )))
+            }
+        };
+        Ok(c_type)
+    }
+}
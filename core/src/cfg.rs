@@ -0,0 +1,965 @@
+use crate::closures::captures;
+use crate::traits::{type_name, COPY, INDEX, NEXT};
+use ast::{
+    EnumDeclare, Expr, FunctionDeclare, Identifier, Location, MatchArm, Module, ModuleItem,
+    Pattern, Statement, StructDeclare, Type, VariantFields,
+};
+use std::collections::{HashMap, HashSet};
+
+pub type BlockId = usize;
+pub type LocalId = usize;
+
+/// the declarations of a module the analyses of function bodies look types up in
+pub struct Items {
+    /// structs and newtypes, the field of a newtype is named `0`
+    structs: HashMap<Identifier, StructDeclare>,
+    enums: HashMap<Identifier, EnumDeclare>,
+    aliases: HashMap<Identifier, Type>,
+    /// functions of `impl` blocks by their qualified name `Type::function`
+    methods: HashMap<Identifier, FunctionDeclare>,
+    /// types with an `impl Copy` block
+    copy_types: HashSet<Identifier>,
+}
+
+impl Items {
+    pub fn new(module: &Module) -> Self {
+        let mut items = Items {
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            aliases: HashMap::new(),
+            methods: HashMap::new(),
+            copy_types: HashSet::new(),
+        };
+        for item in module.items.iter() {
+            match item.as_ref() {
+                ModuleItem::StructDeclare(struct_decl) => {
+                    items.structs.insert(struct_decl.name.clone(), struct_decl.clone());
+                }
+                ModuleItem::Newtype(newtype) => {
+                    items.structs.insert(newtype.name.clone(), newtype.as_struct());
+                }
+                ModuleItem::EnumDeclare(enum_decl) => {
+                    items.enums.insert(enum_decl.name.clone(), enum_decl.clone());
+                }
+                ModuleItem::TypeAlias(alias) => {
+                    items.aliases.insert(alias.name.clone(), alias.ty.clone());
+                }
+                ModuleItem::Impl(impl_block) => {
+                    if impl_block.trait_name.as_deref() == Some(COPY) {
+                        items.copy_types.insert(impl_block.name.clone());
+                    }
+                    for function in impl_block.qualified_functions() {
+                        items.methods.insert(function.name.clone(), function);
+                    }
+                }
+                _ => {}
+            }
+        }
+        items
+    }
+
+    /// `ty` with the aliases it names replaced by their types
+    pub fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Named(name) => match self.aliases.get(name) {
+                Some(aliased) => self.resolve(aliased),
+                None => ty.clone(),
+            },
+            _ => ty.clone(),
+        }
+    }
+
+    /// whether values of `ty` are copied rather than moved, `copy_parameters` are the type
+    /// parameters bound by `Copy`
+    ///
+    /// references are reborrowed where they are passed, so that they are never moved
+    /// either, boxes and trait objects are never copied
+    pub fn is_copy(&self, ty: &Type, copy_parameters: &HashSet<Identifier>) -> bool {
+        match self.resolve(ty) {
+            Type::Named(name) => {
+                matches!(name.as_str(), "i8" | "i16" | "i32" | "i64" | "f32" | "f64")
+                    || copy_parameters.contains(&name)
+                    || self.copy_types.contains(&name)
+            }
+            Type::Generic(name, _) => self.copy_types.contains(&name),
+            Type::Reference { .. } | Type::Pointer(_) | Type::Simd(..) | Type::Function(..) => true,
+            Type::Boxed(_) | Type::Dyn(..) => false,
+            Type::Array(element, _) => self.is_copy(&element, copy_parameters),
+            Type::Tuple(elements) => elements.iter().all(|it| self.is_copy(it, copy_parameters)),
+        }
+    }
+
+    /// fields of the struct, instance of a generic struct or newtype `ty`, and the elements
+    /// of a tuple, named by their position
+    pub fn fields(&self, ty: &Type) -> Option<Vec<(Identifier, Type)>> {
+        let struct_decl = match self.resolve(ty) {
+            Type::Named(name) => self.structs.get(&name)?.clone(),
+            Type::Generic(name, arguments) => self.structs.get(&name)?.instantiate(&arguments),
+            Type::Tuple(elements) => {
+                return Some(
+                    elements
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, ty)| (index.to_string(), ty))
+                        .collect(),
+                )
+            }
+            _ => return None,
+        };
+        Some(struct_decl.fields.into_iter().collect())
+    }
+
+    pub fn field_type(&self, ty: &Type, field: &str) -> Option<Type> {
+        self.fields(ty)?
+            .into_iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, ty)| ty)
+    }
+
+    pub fn get_enum(&self, name: &str) -> Option<&EnumDeclare> {
+        self.enums.get(name)
+    }
+
+    /// the function `method` of the `impl` blocks of `ty`
+    pub fn method(&self, ty: &Type, method: &str) -> Option<&FunctionDeclare> {
+        let ty = self.resolve(ty);
+        let name = type_name(&ty)?;
+        self.methods.get(&format!("{}::{}", name, method))
+    }
+
+    /// the variables bound by `pattern` matching a value of type `ty`, with their types as
+    /// far as they are known
+    ///
+    /// a pattern matching a reference binds references to the parts of the value
+    pub fn bindings(&self, pattern: &Pattern, ty: Option<&Type>) -> Vec<(Identifier, Option<Type>)> {
+        let mut bindings = vec![];
+        self.bind(pattern, ty.map(|it| self.resolve(it)), None, &mut bindings);
+        bindings
+    }
+
+    /// `reference` is the mutability of the reference the matched value is behind
+    fn bind(
+        &self,
+        pattern: &Pattern,
+        ty: Option<Type>,
+        reference: Option<bool>,
+        bindings: &mut Vec<(Identifier, Option<Type>)>,
+    ) {
+        let (ty, reference) = match ty {
+            Some(Type::Reference { mutable, inner }) => (Some(self.resolve(&inner)), Some(mutable)),
+            ty => (ty, reference),
+        };
+        let part = |ty: Option<Type>| ty.map(|it| self.resolve(&it));
+        match pattern {
+            Pattern::Identifier(name) => {
+                let ty = match reference {
+                    Some(mutable) => ty.map(|it| Type::reference(it, mutable)),
+                    None => ty,
+                };
+                bindings.push((name.clone(), ty));
+            }
+            Pattern::Tuple(elements) => {
+                let types = match &ty {
+                    Some(Type::Tuple(types)) if types.len() == elements.len() => {
+                        types.iter().cloned().map(Some).collect()
+                    }
+                    _ => vec![None; elements.len()],
+                };
+                for (element, ty) in elements.iter().zip(types) {
+                    self.bind(element, part(ty), reference, bindings);
+                }
+            }
+            Pattern::Struct(_, fields) => {
+                for (field, pattern) in fields {
+                    let ty = ty.as_ref().and_then(|it| self.field_type(it, field));
+                    self.bind(pattern, part(ty), reference, bindings);
+                }
+            }
+            Pattern::Variant {
+                enum_name,
+                variant,
+                fields,
+            } => {
+                // the variants of an instance of a generic enum have its type arguments
+                let enum_decl = self.get_enum(enum_name).map(|enum_decl| match &ty {
+                    Some(Type::Generic(_, arguments)) => enum_decl.instantiate(arguments),
+                    _ => enum_decl.clone(),
+                });
+                let declared = enum_decl
+                    .as_ref()
+                    .and_then(|enum_decl| enum_decl.variant(variant))
+                    .map(|(_, variant)| variant.fields.clone());
+                match (fields, declared) {
+                    (VariantFields::Tuple(patterns), Some(VariantFields::Tuple(types))) => {
+                        for (pattern, ty) in patterns.iter().zip(types) {
+                            self.bind(pattern, part(Some(ty)), reference, bindings);
+                        }
+                    }
+                    (VariantFields::Struct(patterns), Some(VariantFields::Struct(types))) => {
+                        for (field, pattern) in patterns {
+                            let ty = types.iter().find(|(name, _)| name == field).map(|(_, ty)| ty.clone());
+                            self.bind(pattern, part(ty), reference, bindings);
+                        }
+                    }
+                    (VariantFields::Tuple(patterns), _) => {
+                        patterns.iter().for_each(|it| self.bind(it, None, reference, bindings))
+                    }
+                    (VariantFields::Struct(patterns), _) => {
+                        patterns.iter().for_each(|(_, it)| self.bind(it, None, reference, bindings))
+                    }
+                    (VariantFields::Unit, _) => {}
+                }
+            }
+            Pattern::Wildcard | Pattern::Integer(_) | Pattern::Range(..) => {}
+        }
+    }
+
+    /// whether a value of `ty` may hold a reference, type parameters and closures may
+    pub fn holds_references(&self, ty: &Type) -> bool {
+        self.holds(ty, &mut HashSet::new())
+    }
+
+    /// `visited` are the structs and enums already looked into
+    fn holds(&self, ty: &Type, visited: &mut HashSet<Identifier>) -> bool {
+        match self.resolve(ty) {
+            Type::Reference { .. } | Type::Function(..) => true,
+            Type::Pointer(_) | Type::Simd(..) | Type::Dyn(..) => false,
+            Type::Boxed(inner) | Type::Array(inner, _) => self.holds(&inner, visited),
+            Type::Tuple(elements) => elements.iter().any(|it| self.holds(it, visited)),
+            Type::Named(name) if matches!(name.as_str(), "i8" | "i16" | "i32" | "i64" | "f32" | "f64") => false,
+            ty @ (Type::Named(_) | Type::Generic(..)) => {
+                let name = type_name(&ty).cloned().unwrap_or_default();
+                if !visited.insert(name.clone()) {
+                    return false;
+                }
+                if let Some(fields) = self.fields(&ty) {
+                    return fields.iter().any(|(_, it)| self.holds(it, visited));
+                }
+                let enum_decl = self.get_enum(&name).map(|enum_decl| match &ty {
+                    Type::Generic(_, arguments) => enum_decl.instantiate(arguments),
+                    _ => enum_decl.clone(),
+                });
+                match enum_decl {
+                    Some(enum_decl) => enum_decl
+                        .variants
+                        .iter()
+                        .any(|variant| variant.field_types().into_iter().any(|it| self.holds(it, visited))),
+                    None => true,
+                }
+            }
+        }
+    }
+}
+
+/// a variable of a function, a binding shadowing another one is a different local
+#[derive(Debug, Clone)]
+pub struct Local {
+    pub name: Identifier,
+    /// `None` when the type is not written and cannot be told from the value, such a
+    /// local is treated as `Copy`
+    pub ty: Option<Type>,
+}
+
+/// the local the value of the function is written to, by its `return` statements and by
+/// the value of its body
+pub const RETURN: LocalId = 0;
+
+/// a local or a field of it, `p.a.b` is the local `p` with the fields `[a, b]`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Place {
+    pub local: LocalId,
+    pub fields: Vec<Identifier>,
+}
+
+impl Place {
+    pub fn new(local: LocalId) -> Self {
+        Place { local, fields: vec![] }
+    }
+
+    /// whether `other` is this place or a field of it
+    pub fn contains(&self, other: &Place) -> bool {
+        self.local == other.local && other.fields.starts_with(&self.fields)
+    }
+
+    /// whether a part of the value is in both places
+    pub fn overlaps(&self, other: &Place) -> bool {
+        self.contains(other) || other.contains(self)
+    }
+}
+
+/// how an access reaches the value it uses from its place, an access through a pointer
+/// only reads the local holding the pointer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Through {
+    SharedReference,
+    MutableReference,
+    /// a box, a raw pointer or a value of a type which is not known
+    Pointer,
+    /// an element `a[i]` of an array held by the place
+    Index,
+}
+
+impl Through {
+    /// the kind of the pointer `ty` and the type of the value it points to, as far as it is
+    /// known
+    fn pointer(ty: Option<Type>, items: &Items) -> (Through, Option<Type>) {
+        match ty.map(|it| items.resolve(&it)) {
+            Some(Type::Reference { mutable: true, inner }) => (Through::MutableReference, Some(*inner)),
+            Some(Type::Reference { mutable: false, inner }) => (Through::SharedReference, Some(*inner)),
+            Some(Type::Boxed(inner)) | Some(Type::Pointer(inner)) => (Through::Pointer, Some(*inner)),
+            _ => (Through::Pointer, None),
+        }
+    }
+}
+
+/// whether an access reaching its value `through` uses the storage of its place itself
+pub fn in_place(through: Option<Through>) -> bool {
+    matches!(through, None | Some(Through::Index))
+}
+
+pub type LoanId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// the value is read and stays usable, either because it is copied or because only a
+    /// part of it behind a reference or a box is used
+    Read,
+    /// the value is moved out, the place is unusable until it is written again
+    Move,
+    /// `&place` or `&mut place`, the loans of a graph are numbered in the order of their
+    /// borrows
+    Borrow { mutable: bool, loan: LoanId },
+    /// the place is initialized or assigned
+    Write,
+    /// the local goes out of scope at the end of the block declaring it
+    Dead,
+}
+
+/// a part of a written value, the reference created by a borrow or a value read from a
+/// local, which holds the references the local holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source {
+    Loan(LoanId),
+    Local(LocalId),
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub place: Place,
+    pub access: Access,
+    pub location: Location,
+    /// the pointer or the index the accessed value is reached through
+    pub through: Option<Through>,
+    /// what the value of a write is made of
+    pub sources: Vec<Source>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BasicBlock {
+    pub events: Vec<Event>,
+    pub successors: Vec<BlockId>,
+}
+
+/// the accesses to the locals of a function in the order they happen, the branches of a
+/// `match` or an `if` are blocks of their own, a `return` ends the block it is in
+///
+/// the arguments of a call are written to a temporary local which the call reads, so that
+/// the references created for a call live until it is made
+#[derive(Debug)]
+pub struct ControlFlowGraph {
+    pub function: Identifier,
+    pub locals: Vec<Local>,
+    /// the entry is the first block
+    pub blocks: Vec<BasicBlock>,
+    /// the number of borrows
+    pub loans: usize,
+    /// the graphs of the closures created by the function, a closure moves the variables it
+    /// captures when it is created
+    pub closures: Vec<ControlFlowGraph>,
+}
+
+impl ControlFlowGraph {
+    pub fn new(function: &FunctionDeclare, items: &Items) -> Self {
+        let copy_parameters = function
+            .bounds
+            .iter()
+            .filter(|(_, bound)| bound == COPY)
+            .map(|(parameter, _)| parameter.clone())
+            .collect();
+        let mut builder = Builder::new(
+            function.name.clone(),
+            items,
+            copy_parameters,
+            Some(function.return_type.clone()),
+        );
+        for (name, ty) in function.args.iter() {
+            builder.declare(name, Some(ty.clone()), vec![]);
+        }
+        let sources = builder.collect(|builder| builder.operand(&function.stats));
+        builder.write_return(sources, tail_location(&function.stats));
+        builder.graph
+    }
+
+    /// the place as written in the source, `p.a.b`
+    pub fn describe(&self, place: &Place) -> String {
+        std::iter::once(self.locals[place.local].name.as_str())
+            .chain(place.fields.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    pub fn place_type(&self, place: &Place, items: &Items) -> Option<Type> {
+        let mut ty = self.locals[place.local].ty.clone()?;
+        for field in place.fields.iter() {
+            ty = items.field_type(&ty, field)?;
+        }
+        Some(ty)
+    }
+}
+
+/// an expression naming a place, with the pointer or the index the value is reached
+/// through and the type of the value
+struct PlaceExpr {
+    place: Place,
+    location: Location,
+    through: Option<Through>,
+    ty: Option<Type>,
+}
+
+/// where a call is written, the location of the function or of the receiver of the method
+fn call_location(callee: &Expr) -> Location {
+    match callee {
+        Expr::Identifier(_, location) => *location,
+        Expr::Field(inner, _) | Expr::Group(inner) | Expr::Deref(inner, _) => call_location(inner),
+        _ => Location::default(),
+    }
+}
+
+/// where the value of a block is written, the location of the first place or operator
+/// the tail expression is made of
+fn tail_location(expr: &Expr) -> Location {
+    match expr {
+        Expr::Identifier(_, location)
+        | Expr::BinOperation(_, _, _, location)
+        | Expr::Deref(_, location)
+        | Expr::Index { location, .. }
+        | Expr::Match { location, .. }
+        | Expr::Try(_, location) => *location,
+        Expr::Block(_, Some(tail)) => tail_location(tail),
+        Expr::Field(inner, _)
+        | Expr::TupleField(inner, _)
+        | Expr::Group(inner)
+        | Expr::Reference { expr: inner, .. }
+        | Expr::Box(inner)
+        | Expr::Cast(inner, _)
+        | Expr::Unsafe(inner) => tail_location(inner),
+        Expr::Call(callee, _) => call_location(callee),
+        _ => Location::default(),
+    }
+}
+
+struct Builder<'a> {
+    items: &'a Items,
+    copy_parameters: HashSet<Identifier>,
+    graph: ControlFlowGraph,
+    current: BlockId,
+    /// the locals of the blocks the current one is in, in the order they are declared
+    scopes: Vec<Vec<(Identifier, LocalId)>>,
+    /// the sources of the values being evaluated, innermost last
+    collectors: Vec<Vec<Source>>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(
+        function: Identifier,
+        items: &'a Items,
+        copy_parameters: HashSet<Identifier>,
+        return_type: Option<Type>,
+    ) -> Self {
+        let result = Local {
+            name: "return".to_string(),
+            ty: return_type,
+        };
+        Builder {
+            items,
+            copy_parameters,
+            graph: ControlFlowGraph {
+                function,
+                locals: vec![result],
+                blocks: vec![BasicBlock::default()],
+                loans: 0,
+                closures: vec![],
+            },
+            current: 0,
+            scopes: vec![vec![]],
+            collectors: vec![],
+        }
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.graph.blocks.push(BasicBlock::default());
+        self.graph.blocks.len() - 1
+    }
+
+    fn goto(&mut self, target: BlockId) {
+        self.graph.blocks[self.current].successors.push(target);
+    }
+
+    /// an access to `place`, a read or a borrow is a source of the value being evaluated
+    fn event(&mut self, place: Place, access: Access, location: Location, through: Option<Through>) {
+        let mut sources = match access {
+            Access::Read | Access::Move => vec![Source::Local(place.local)],
+            Access::Borrow { loan, .. } if in_place(through) => vec![Source::Loan(loan)],
+            // a reborrow holds the references the pointer it goes through holds
+            Access::Borrow { loan, .. } => vec![Source::Loan(loan), Source::Local(place.local)],
+            Access::Write | Access::Dead => vec![],
+        };
+        if let Some(collector) = self.collectors.last_mut() {
+            collector.append(&mut sources);
+        }
+        self.graph.blocks[self.current].events.push(Event {
+            place,
+            access,
+            location,
+            through,
+            sources: vec![],
+        });
+    }
+
+    fn write(&mut self, place: Place, location: Location, through: Option<Through>, sources: Vec<Source>) {
+        self.graph.blocks[self.current].events.push(Event {
+            place,
+            access: Access::Write,
+            location,
+            through,
+            sources,
+        });
+    }
+
+    /// the sources of the value `evaluate` lowers, which are sources of the enclosing value
+    /// as well
+    fn collect(&mut self, evaluate: impl FnOnce(&mut Self)) -> Vec<Source> {
+        self.collectors.push(vec![]);
+        evaluate(self);
+        let sources = self.collectors.pop().unwrap();
+        if let Some(parent) = self.collectors.last_mut() {
+            parent.extend(sources.iter().copied());
+        }
+        sources
+    }
+
+    /// a local holding a value made of `sources` which is not named in the source
+    fn temporary(&mut self, sources: Vec<Source>, location: Location) -> LocalId {
+        let local = self.graph.locals.len();
+        self.graph.locals.push(Local {
+            name: "temporary".to_string(),
+            ty: None,
+        });
+        self.write(Place::new(local), location, None, sources);
+        local
+    }
+
+    fn write_return(&mut self, sources: Vec<Source>, location: Location) {
+        self.write(Place::new(RETURN), location, None, sources);
+    }
+
+    fn lookup(&self, name: &str) -> Option<LocalId> {
+        self.scopes.iter().rev().find_map(|scope| {
+            scope
+                .iter()
+                .rev()
+                .find(|(declared, _)| declared == name)
+                .map(|(_, local)| *local)
+        })
+    }
+
+    /// a new local initialized here with a value made of `sources`
+    fn declare(&mut self, name: &str, ty: Option<Type>, sources: Vec<Source>) -> LocalId {
+        let local = self.graph.locals.len();
+        self.graph.locals.push(Local {
+            name: name.to_string(),
+            ty,
+        });
+        self.scopes.last_mut().unwrap().push((name.to_string(), local));
+        self.write(Place::new(local), Location::default(), None, sources);
+        local
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern, ty: Option<&Type>, sources: Vec<Source>) {
+        for (name, ty) in self.items.bindings(pattern, ty) {
+            self.declare(&name, ty, sources.clone());
+        }
+    }
+
+    /// the locals of the innermost block go out of scope, the last declared first
+    fn pop_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+        for (_, local) in scope.into_iter().rev() {
+            self.event(Place::new(local), Access::Dead, Location::default(), None);
+        }
+    }
+
+    fn is_copy(&self, ty: &Type) -> bool {
+        self.items.is_copy(ty, &self.copy_parameters)
+    }
+
+    /// the place `expr` names, `None` when it is no place, then it is evaluated as an
+    /// operand, the indexes of the place are evaluated as well
+    fn place(&mut self, expr: &Expr) -> Option<PlaceExpr> {
+        match expr {
+            Expr::Identifier(name, location) => self.lookup(name).map(|local| PlaceExpr {
+                place: Place::new(local),
+                location: *location,
+                through: None,
+                ty: self.graph.locals[local].ty.clone(),
+            }),
+            Expr::Field(inner, field) => self.field(inner, field.clone()),
+            Expr::TupleField(inner, index) => self.field(inner, index.to_string()),
+            Expr::Group(inner) => self.place(inner),
+            Expr::Deref(inner, _) => {
+                let base = self.place(inner)?;
+                let (through, ty) = Through::pointer(base.ty.clone(), self.items);
+                Some(PlaceExpr {
+                    through: base.through.or(Some(through)),
+                    ty,
+                    ..base
+                })
+            }
+            Expr::Index { expr, index, .. } => {
+                let base = self.place(expr);
+                self.operand(index);
+                let base = base?;
+                let (through, array) = match base.ty.clone().map(|it| self.items.resolve(&it)) {
+                    Some(array @ Type::Array(..)) => (Through::Index, Some(array)),
+                    // `v[i]` of a type with an `index` method borrows the whole value
+                    Some(ty) if self.items.method(&ty, INDEX).is_some() => (Through::Index, None),
+                    ty => Through::pointer(ty, self.items),
+                };
+                let ty = match array.map(|it| self.items.resolve(&it)) {
+                    Some(Type::Array(element, _)) => Some(*element),
+                    _ => None,
+                };
+                Some(PlaceExpr {
+                    through: base.through.or(Some(through)),
+                    ty,
+                    ..base
+                })
+            }
+            expr => {
+                self.operand(expr);
+                None
+            }
+        }
+    }
+
+    /// fields are places of their own while the value they are in is owned, a field
+    /// reached through a pointer is only a use of the local holding the pointer
+    fn field(&mut self, inner: &Expr, field: Identifier) -> Option<PlaceExpr> {
+        let mut base = self.place(inner)?;
+        match base.ty.as_ref().and_then(|ty| self.items.field_type(ty, &field)) {
+            Some(ty) => {
+                if base.through.is_none() {
+                    base.place.fields.push(field);
+                }
+                base.ty = Some(ty);
+            }
+            None => {
+                let (through, pointee) = Through::pointer(base.ty.clone(), self.items);
+                base.through = base.through.or(Some(through));
+                base.ty = pointee.and_then(|it| self.items.field_type(&it, &field));
+            }
+        }
+        Some(base)
+    }
+
+    /// the value of `expr` is used, a place of a type which is not `Copy` is moved
+    fn operand(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Identifier(..)
+            | Expr::Field(..)
+            | Expr::TupleField(..)
+            | Expr::Group(_)
+            | Expr::Deref(..)
+            | Expr::Index { .. } => {
+                if let Some(place) = self.place(expr) {
+                    self.consume(place);
+                }
+            }
+            Expr::Reference { mutable, expr } => {
+                if let Some(place) = self.place(expr) {
+                    self.borrow(place, *mutable);
+                }
+            }
+            Expr::Tuple(elements) | Expr::Array(elements) | Expr::SimdVector(elements) => {
+                elements.iter().for_each(|it| self.operand(it))
+            }
+            Expr::ArrayRepeat(expr, _)
+            | Expr::SimdSplat(expr, _)
+            | Expr::Cast(expr, _)
+            | Expr::Box(expr)
+            | Expr::Unsafe(expr) => self.operand(expr),
+            Expr::BinOperation(_, lhs, rhs, _)
+            | Expr::Range {
+                start: lhs,
+                end: rhs,
+                ..
+            } => {
+                self.operand(lhs);
+                self.operand(rhs);
+            }
+            Expr::StructLiteral { fields, .. } => fields.iter().for_each(|(_, it)| self.operand(it)),
+            Expr::Call(callee, args) => {
+                let sources = self.collect(|builder| {
+                    if !matches!(&**callee, Expr::Field(..) | Expr::Path(..)) {
+                        builder.operand(callee);
+                    }
+                    args.iter().for_each(|it| builder.operand(it));
+                    // the receiver is borrowed once the arguments are evaluated, so that
+                    // they may read it
+                    if let Expr::Field(receiver, method) = &**callee {
+                        builder.receiver(receiver, method);
+                    }
+                });
+                let location = call_location(callee);
+                let arguments = self.temporary(sources, location);
+                self.event(Place::new(arguments), Access::Read, location, None);
+            }
+            Expr::Block(statements, value) => {
+                self.scopes.push(vec![]);
+                statements.iter().for_each(|it| self.statement(it));
+                if let Some(value) = value {
+                    self.operand(value);
+                }
+                self.pop_scope();
+            }
+            Expr::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.operand(condition);
+                let start = self.current;
+                let join = self.new_block();
+                for body in [then_body, else_body] {
+                    self.current = start;
+                    let block = self.new_block();
+                    self.goto(block);
+                    self.current = block;
+                    self.operand(body);
+                    self.goto(join);
+                }
+                self.current = join;
+            }
+            Expr::Match { scrutinee, arms, .. } => self.matching(scrutinee, arms),
+            Expr::Try(expr, location) => {
+                // a `None` or an `Err` is returned from a block of its own
+                let sources = self.collect(|builder| builder.operand(expr));
+                let value = self.temporary(sources, *location);
+                let failure = self.new_block();
+                let rest = self.new_block();
+                self.goto(failure);
+                self.goto(rest);
+                self.current = failure;
+                self.write_return(vec![Source::Local(value)], *location);
+                self.current = rest;
+            }
+            Expr::For {
+                pattern,
+                iterable,
+                body,
+            } => self.looping(pattern, iterable, body),
+            Expr::Closure {
+                params,
+                return_type,
+                body,
+            } => self.closure(params, return_type.clone(), body),
+            Expr::Number(_) | Expr::Negative(_) | Expr::String(_) | Expr::Path(..) => {}
+        }
+    }
+
+    fn borrow(&mut self, place: PlaceExpr, mutable: bool) {
+        let loan = self.graph.loans;
+        self.graph.loans += 1;
+        let access = Access::Borrow { mutable, loan };
+        self.event(place.place, access, place.location, place.through);
+    }
+
+    fn consume(&mut self, place: PlaceExpr) {
+        let moved = place.through.is_none() && place.ty.as_ref().is_some_and(|ty| !self.is_copy(ty));
+        let access = if moved { Access::Move } else { Access::Read };
+        self.event(place.place, access, place.location, place.through);
+    }
+
+    /// the receiver of `receiver.method()` is borrowed or moved like the `self` parameter of
+    /// the method, which is looked up through a reference, a method which cannot be found
+    /// only reads it
+    fn receiver(&mut self, receiver: &Expr, method: &str) {
+        let place = match self.place(receiver) {
+            Some(place) => place,
+            None => return,
+        };
+        let place = match place.ty.clone().map(|it| self.items.resolve(&it)) {
+            Some(reference @ Type::Reference { .. }) => {
+                let (through, ty) = Through::pointer(Some(reference), self.items);
+                PlaceExpr {
+                    through: place.through.or(Some(through)),
+                    ty,
+                    ..place
+                }
+            }
+            _ => place,
+        };
+        let self_type = place
+            .ty
+            .as_ref()
+            .and_then(|ty| self.items.method(ty, method))
+            .filter(|function| function.is_method())
+            .map(|function| function.args[0].1.clone());
+        match self_type {
+            Some(Type::Reference { mutable, .. }) => self.borrow(place, mutable),
+            Some(_) => self.consume(place),
+            None => self.event(place.place, Access::Read, place.location, place.through),
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Declare(name, ty, value) => {
+                let sources = self.collect(|builder| builder.operand(value));
+                self.declare(name, Some(ty.clone()), sources);
+            }
+            Statement::Destructure(pattern, ty, value) => {
+                let mut value_type = None;
+                let sources = self.collect(|builder| {
+                    if let Some(place) = builder.place(value) {
+                        value_type = place.ty.clone();
+                        builder.consume(place);
+                    }
+                });
+                let ty = ty.clone().or(value_type);
+                self.declare_pattern(pattern, ty.as_ref(), sources);
+            }
+            Statement::Assignment(target, value) => {
+                let sources = self.collect(|builder| builder.operand(value));
+                if let Some(place) = self.place(target) {
+                    self.write(place.place, place.location, place.through, sources);
+                }
+            }
+            Statement::Expr(expr) => {
+                let sources = self.collect(|builder| builder.operand(expr));
+                self.temporary(sources, Location::default());
+            }
+            Statement::Return(expr, location) => {
+                let sources = self.collect(|builder| builder.operand(expr));
+                self.write_return(sources, *location);
+                // the rest of the block is unreachable
+                self.current = self.new_block();
+            }
+        }
+    }
+
+    /// the scrutinee is read by the patterns, an arm binding a part of it which is not
+    /// `Copy` moves it
+    fn matching(&mut self, scrutinee: &Expr, arms: &[MatchArm]) {
+        let mut place = None;
+        let sources = self.collect(|builder| {
+            place = builder.place(scrutinee);
+            if let Some(place) = &place {
+                builder.event(place.place.clone(), Access::Read, place.location, place.through);
+            }
+        });
+        let ty = place.as_ref().and_then(|it| it.ty.clone());
+        let value = self.temporary(sources, Location::default());
+        let start = self.current;
+        let join = self.new_block();
+        for arm in arms {
+            self.current = start;
+            let block = self.new_block();
+            self.goto(block);
+            self.current = block;
+            self.scopes.push(vec![]);
+            if let Some(place) = &place {
+                let moves = place.through.is_none()
+                    && self
+                        .items
+                        .bindings(&arm.pattern, ty.as_ref())
+                        .iter()
+                        .any(|(_, ty)| ty.as_ref().is_some_and(|it| !self.is_copy(it)));
+                if moves {
+                    self.event(place.place.clone(), Access::Move, place.location, None);
+                }
+            }
+            self.declare_pattern(&arm.pattern, ty.as_ref(), vec![Source::Local(value)]);
+            self.operand(&arm.body);
+            self.pop_scope();
+            self.goto(join);
+        }
+        self.current = join;
+    }
+
+    /// the iterable is moved into a temporary the items are read from, a value without a
+    /// `next` method is only borrowed by its `iter` method, the body runs any number of
+    /// times between the header and the exit of the loop
+    fn looping(&mut self, pattern: &Pattern, iterable: &Expr, body: &Expr) {
+        let sources = self.collect(|builder| {
+            if let Some(place) = builder.place(iterable) {
+                let ty = place.ty.clone().map(|it| builder.items.resolve(&it));
+                let moved = match &ty {
+                    Some(Type::Array(..)) | None => true,
+                    Some(ty) => builder.items.method(ty, NEXT).is_some(),
+                };
+                if moved {
+                    builder.consume(place);
+                } else {
+                    builder.borrow(place, false);
+                }
+            }
+        });
+        let iterator = self.temporary(sources, Location::default());
+        let header = self.new_block();
+        self.goto(header);
+        self.current = header;
+        self.event(Place::new(iterator), Access::Read, Location::default(), None);
+        let exit = self.new_block();
+        let block = self.new_block();
+        self.goto(block);
+        self.goto(exit);
+        self.current = block;
+        self.scopes.push(vec![]);
+        self.declare_pattern(pattern, None, vec![Source::Local(iterator)]);
+        self.operand(body);
+        self.pop_scope();
+        self.goto(header);
+        self.current = exit;
+    }
+
+    /// the captured variables are moved into the environment of the closure, its body is a
+    /// graph of its own
+    fn closure(&mut self, params: &[(Identifier, Option<Type>)], return_type: Option<Type>, body: &Expr) {
+        let names: Vec<Identifier> = params.iter().map(|(name, _)| name.clone()).collect();
+        let mut builder = Builder::new(
+            format!("{}::{{closure}}", self.graph.function),
+            self.items,
+            self.copy_parameters.clone(),
+            return_type,
+        );
+        for (name, location) in captures(&names, body) {
+            if let Some(local) = self.lookup(&name) {
+                let ty = self.graph.locals[local].ty.clone();
+                self.consume(PlaceExpr {
+                    place: Place::new(local),
+                    location,
+                    through: None,
+                    ty: ty.clone(),
+                });
+                builder.declare(&name, ty, vec![]);
+            }
+        }
+        for (name, ty) in params {
+            builder.declare(name, ty.clone(), vec![]);
+        }
+        let sources = builder.collect(|builder| builder.operand(body));
+        builder.write_return(sources, tail_location(body));
+        self.graph.closures.push(builder.graph);
+    }
+}
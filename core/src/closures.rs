@@ -0,0 +1,153 @@
+use ast::{Expr, Identifier, Location, Pattern, Statement, VariantFields};
+use std::collections::HashSet;
+
+/// variables of the enclosing function used by the body of a closure, in the order of
+/// their first use and with its location, these are copied into the environment of the
+/// closure
+///
+/// names are free when neither a parameter nor a variable declared inside of the closure
+/// binds them, a free name that is no variable, like a function used as a value, is
+/// filtered out by the caller
+pub(crate) fn captures(params: &[Identifier], body: &Expr) -> Vec<(Identifier, Location)> {
+    let mut walker = Captures {
+        bound: vec![params.iter().cloned().collect()],
+        free: vec![],
+    };
+    walker.expr(body);
+    walker.free
+}
+
+struct Captures {
+    bound: Vec<HashSet<Identifier>>,
+    free: Vec<(Identifier, Location)>,
+}
+
+impl Captures {
+    fn is_bound(&self, name: &str) -> bool {
+        self.bound.iter().any(|scope| scope.contains(name))
+    }
+
+    fn bind(&mut self, name: &str) {
+        self.bound.last_mut().unwrap().insert(name.to_string());
+    }
+
+    fn bind_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Identifier(name) => self.bind(name),
+            Pattern::Tuple(elements) => elements.iter().for_each(|it| self.bind_pattern(it)),
+            Pattern::Struct(_, fields) => fields.iter().for_each(|(_, it)| self.bind_pattern(it)),
+            Pattern::Variant { fields, .. } => match fields {
+                VariantFields::Unit => {}
+                VariantFields::Tuple(elements) => elements.iter().for_each(|it| self.bind_pattern(it)),
+                VariantFields::Struct(fields) => {
+                    fields.iter().for_each(|(_, it)| self.bind_pattern(it))
+                }
+            },
+            Pattern::Wildcard | Pattern::Integer(_) | Pattern::Range(..) => {}
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Declare(name, _, value) => {
+                self.expr(value);
+                self.bind(name);
+            }
+            Statement::Destructure(pattern, _, value) => {
+                self.expr(value);
+                self.bind_pattern(pattern);
+            }
+            Statement::Assignment(place, value) => {
+                self.expr(place);
+                self.expr(value);
+            }
+            Statement::Expr(expr) | Statement::Return(expr, _) => self.expr(expr),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Identifier(name, location) => {
+                if !self.is_bound(name) && !self.free.iter().any(|(it, _)| it == name) {
+                    self.free.push((name.clone(), *location));
+                }
+            }
+            Expr::Block(statements, value) => {
+                self.bound.push(HashSet::new());
+                statements.iter().for_each(|it| self.statement(it));
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+                self.bound.pop();
+            }
+            Expr::Closure { params, body, .. } => {
+                // the captures of a nested closure are captured by this one as well
+                self.bound.push(params.iter().map(|(name, _)| name.clone()).collect());
+                self.expr(body);
+                self.bound.pop();
+            }
+            Expr::Match { scrutinee, arms, .. } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    self.bound.push(HashSet::new());
+                    self.bind_pattern(&arm.pattern);
+                    self.expr(&arm.body);
+                    self.bound.pop();
+                }
+            }
+            Expr::For {
+                pattern,
+                iterable,
+                body,
+            } => {
+                self.expr(iterable);
+                self.bound.push(HashSet::new());
+                self.bind_pattern(pattern);
+                self.expr(body);
+                self.bound.pop();
+            }
+            Expr::Call(callee, args) => {
+                self.expr(callee);
+                args.iter().for_each(|it| self.expr(it));
+            }
+            Expr::Field(expr, _)
+            | Expr::TupleField(expr, _)
+            | Expr::Group(expr)
+            | Expr::Reference { expr, .. }
+            | Expr::Deref(expr, _)
+            | Expr::Box(expr)
+            | Expr::ArrayRepeat(expr, _)
+            | Expr::SimdSplat(expr, _)
+            | Expr::Cast(expr, _)
+            | Expr::Try(expr, _)
+            | Expr::Unsafe(expr) => self.expr(expr),
+            Expr::Tuple(elements) | Expr::Array(elements) | Expr::SimdVector(elements) => {
+                elements.iter().for_each(|it| self.expr(it))
+            }
+            Expr::BinOperation(_, lhs, rhs, _)
+            | Expr::Range {
+                start: lhs,
+                end: rhs,
+                ..
+            } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expr::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.expr(condition);
+                self.expr(then_body);
+                self.expr(else_body);
+            }
+            Expr::Index { expr, index, .. } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            Expr::StructLiteral { fields, .. } => fields.iter().for_each(|(_, it)| self.expr(it)),
+            Expr::Number(_) | Expr::Negative(_) | Expr::String(_) | Expr::Path(..) => {}
+        }
+    }
+}
@@ -1,14 +1,42 @@
 use crate::llvm_wrapper::builder::TyphoonBuilder;
 use crate::llvm_wrapper::context::TyphoonContext;
 use crate::llvm_wrapper::module::TyphoonModule;
-use crate::llvm_wrapper::types::void_type::VoidType;
-use crate::llvm_wrapper::types::BasicType;
+use crate::llvm_wrapper::types::{AddressSpace, BasicType};
+use crate::llvm_wrapper::values::function_value::FunctionValue;
+use crate::llvm_wrapper::values::pointer_value::PointerValue;
 use crate::llvm_wrapper::values::BasicValue;
-use ast::{Expr, FunctionDeclare, Module, ModuleItem, Number, Statement, StructDeclare, Type};
-use llvm_sys::core::{LLVMBuildRet, LLVMBuildRetVoid};
+use crate::closures::captures;
+use crate::error::{fail, TyphoonError};
+use crate::consts::{ConstEvaluator, ConstValue};
+use crate::decision_tree::match_codegen;
+use crate::loops::{for_codegen, range_codegen};
+use crate::gc;
+use crate::intrinsics::{build_intrinsic, build_string_literal, builtin_method, INTRINSICS};
+use crate::mangle::{closure_symbol, function_symbol, mangle, module_prefix, shim_symbol};
+use crate::panics::{
+    build_bounds_check, build_checked_arithmetic, build_division_check, panic_codegen, PANIC_BUILTIN,
+};
+use crate::program::Overflow;
+use crate::resolver::STD;
+use crate::llvm_wrapper::target_data::TargetData;
+use crate::scope::{Environment, Scope};
+use crate::traits::{
+    builtin_implements, builtin_traits, check_object_safety, impl_functions, implements,
+    operator_trait, type_name, INDEX, INDEX_MUT, OPTION, RESULT,
+};
+use ast::{
+    EnumDeclare, EnumVariant, Expr, FunctionDeclare, Identifier, Location, Module, ModuleItem,
+    NewtypeDeclare, Number, Opcode, Pattern, Statement, StructDeclare, TraitDeclare, Type, VariantFields,
+};
+use llvm_sys::{LLVMIntPredicate, LLVMLinkage, LLVMOpcode, LLVMRealPredicate};
+use std::collections::HashMap;
 
 pub trait Codegen {
-    fn codegen(self, context: &TyphoonContext) -> (TyphoonModule, TyphoonBuilder);
+    fn codegen(
+        self,
+        context: &TyphoonContext,
+        environment: &Environment,
+    ) -> (TyphoonModule, TyphoonBuilder);
 }
 
 pub trait ModuleCodegen {
@@ -17,6 +45,17 @@ pub trait ModuleCodegen {
         context: &TyphoonContext,
         builder: &TyphoonBuilder,
         module: &TyphoonModule,
+        environment: &Environment,
+    );
+}
+
+pub trait StatementCodegen {
+    fn statement_codegen(
+        self,
+        context: &TyphoonContext,
+        builder: &TyphoonBuilder,
+        module: &TyphoonModule,
+        scope: &Scope,
     );
 }
 
@@ -26,63 +65,1589 @@ pub trait ExprCodegen {
         context: &TyphoonContext,
         builder: &TyphoonBuilder,
         module: &TyphoonModule,
+        scope: &Scope,
     ) -> BasicValue;
 }
 
+/// codegen of expressions that denote a memory location, i.e. the left hand side of an
+/// assignment and the operand of `&`
+pub trait PlaceCodegen {
+    fn place_codegen(
+        self,
+        context: &TyphoonContext,
+        builder: &TyphoonBuilder,
+        module: &TyphoonModule,
+        scope: &Scope,
+    ) -> PointerValue;
+}
+
 impl Codegen for Module {
-    fn codegen(self, context: &TyphoonContext) -> (TyphoonModule, TyphoonBuilder) {
+    fn codegen(
+        self,
+        context: &TyphoonContext,
+        environment: &Environment,
+    ) -> (TyphoonModule, TyphoonBuilder) {
         debug!("module codegen");
         let module = context.create_module("typhoon");
         let builder = context.create_builder();
+        for trait_decl in builtin_traits() {
+            environment.declare_trait(trait_decl);
+        }
+        // declare every struct, enum and trait up front so that fields and signatures can refer
+        // to types defined later in the file
+        for item in self.items.iter() {
+            match item.as_ref() {
+                ModuleItem::StructDeclare(struct_decl) => {
+                    // generic structs get a type for every instance instead
+                    if !struct_decl.is_generic() {
+                        context.opaque_struct_type(&struct_decl.name);
+                    }
+                    environment.declare_struct(struct_decl.clone());
+                }
+                ModuleItem::EnumDeclare(enum_decl) => {
+                    if !enum_decl.is_generic() {
+                        context.opaque_struct_type(&enum_decl.name);
+                    }
+                    environment.declare_enum(enum_decl.clone());
+                }
+                ModuleItem::TraitDeclare(trait_decl) => {
+                    environment.declare_trait(trait_decl.clone());
+                }
+                ModuleItem::TypeAlias(alias) => environment.declare_alias(alias.clone()),
+                // a newtype is a struct with a single field, it has the layout of the inner
+                // type but is a different type
+                ModuleItem::Newtype(newtype) => {
+                    context.opaque_struct_type(&newtype.name);
+                    environment.declare_newtype(newtype.clone());
+                }
+                ModuleItem::FunctionDeclare(_)
+                | ModuleItem::Impl(_)
+                | ModuleItem::Const(_)
+                | ModuleItem::Static(_) => {}
+                ModuleItem::Mod(_) | ModuleItem::Use(_) => {
+                    unreachable!("modules are flattened by the resolver")
+                }
+            }
+        }
+        for item in self.items.iter() {
+            if let ModuleItem::TypeAlias(alias) = item.as_ref() {
+                check_alias_cycle(&Type::named(alias.name.clone()), environment, &mut vec![]);
+            }
+        }
+        for item in self.items.iter() {
+            match item.as_ref() {
+                ModuleItem::StructDeclare(struct_decl) if !struct_decl.is_generic() => {
+                    define_struct_body(struct_decl, &struct_decl.name, context, environment)
+                }
+                ModuleItem::Newtype(newtype) => {
+                    define_struct_body(&newtype.as_struct(), &newtype.name, context, environment)
+                }
+                _ => {}
+            }
+        }
+        // the payload of an enum is sized by its largest variant, so an enum can only be
+        // laid out once the enums it contains are
+        let mut pending: Vec<&EnumDeclare> = self
+            .items
+            .iter()
+            .filter_map(|item| match item.as_ref() {
+                ModuleItem::EnumDeclare(enum_decl) if !enum_decl.is_generic() => Some(enum_decl),
+                _ => None,
+            })
+            .collect();
+        while !pending.is_empty() {
+            let before = pending.len();
+            pending.retain(|enum_decl| !define_enum_body(enum_decl, context, environment));
+            if pending.len() == before {
+                panic!("enum {} contains itself by value", &pending[0].name);
+            }
+        }
+        // constants are folded at compile time, statics become globals initialized with
+        // the folded value of their initializer
+        let mut evaluator = ConstEvaluator::new(&self);
+        for item in self.items.iter() {
+            match item.as_ref() {
+                ModuleItem::Const(const_decl) => {
+                    let value = evaluator
+                        .constant(&const_decl.name)
+                        .unwrap_or_else(|message| fail(TyphoonError::ConstError(message)))
+                        .unwrap();
+                    environment.add_constant(const_decl.name.clone(), const_decl.ty.clone(), value);
+                }
+                ModuleItem::Static(static_decl) => {
+                    let value = evaluator
+                        .evaluate(&static_decl.value, &static_decl.ty)
+                        .unwrap_or_else(|message| {
+                            fail(TyphoonError::ConstError(format!(
+                                "initializer of static {}: {}",
+                                static_decl.name, message
+                            )))
+                        });
+                    let ty = to_basic_type(&static_decl.ty, context, environment);
+                    let value = const_value_codegen(&value, &ty, context);
+                    let symbol = mangle(&static_decl.name, &[]);
+                    let global = if static_decl.mutable {
+                        module.add_global(&symbol, &value)
+                    } else {
+                        module.add_global_constant(&symbol, &value)
+                    };
+                    environment.add_static(static_decl.name.clone(), global, static_decl.mutable);
+                }
+                _ => {}
+            }
+        }
+        // and every function, so that calls do not depend on the declaration order,
+        // generic functions are declared once they are called with concrete types
+        for item in self.items.iter() {
+            match item.as_ref() {
+                ModuleItem::FunctionDeclare(func_decl) if func_decl.is_generic() => {
+                    environment.declare_generic_function(func_decl.clone());
+                }
+                ModuleItem::FunctionDeclare(func_decl) => {
+                    // a `main` returning a `Result` is called by the entry point built below
+                    let symbol = if returns_result(func_decl, context, environment) {
+                        mangle(&func_decl.name, &[])
+                    } else {
+                        function_symbol(func_decl, &[])
+                    };
+                    let function = declare_function(func_decl, &symbol, context, &module, environment);
+                    environment.add_function(func_decl.name.clone(), function);
+                    let parameters = func_decl.args.iter().map(|(_, ty)| ty.clone()).collect();
+                    environment.declare_parameters(func_decl.name.clone(), parameters);
+                }
+                ModuleItem::Impl(impl_block) => {
+                    let name = &impl_block.name;
+                    if environment.get_struct(name).is_none()
+                        && environment.get_enum(name).is_none()
+                        && !matches!(name.as_str(), "i8" | "i16" | "i32" | "i64" | "f32" | "f64")
+                    {
+                        panic!("impl of undefined type {}", name);
+                    }
+                    if let Some(trait_name) = &impl_block.trait_name {
+                        environment.add_trait_impl(name.clone(), trait_name.clone());
+                    }
+                    for func_decl in impl_functions(impl_block, environment) {
+                        if environment.get_function(&func_decl.name).is_some()
+                            || environment.get_generic_function(&func_decl.name).is_some()
+                        {
+                            panic!("duplicate definitions of {}", &func_decl.name);
+                        }
+                        if func_decl.is_generic() {
+                            environment.declare_generic_function(func_decl.clone());
+                        } else {
+                            let symbol = function_symbol(&func_decl, &[]);
+                            let function =
+                                declare_function(&func_decl, &symbol, context, &module, environment);
+                            environment.add_function(func_decl.name.clone(), function);
+                            let parameters = func_decl.args.iter().map(|(_, ty)| ty.clone()).collect();
+                            environment.declare_parameters(func_decl.name.clone(), parameters);
+                        }
+                        environment.declare_method(func_decl);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let entry = self.items.iter().find_map(|item| match item.as_ref() {
+            ModuleItem::FunctionDeclare(func_decl) if returns_result(func_decl, context, environment) => {
+                environment.get_function(&func_decl.name)
+            }
+            _ => None,
+        });
         for item in self.items {
-            item.module_codegen(&context, &builder, &module);
+            item.module_codegen(context, &builder, &module, environment);
+        }
+        if let Some(function) = entry {
+            build_result_entry_point(function, context, &builder, &module, environment);
         }
+        // the functions of the standard library the program does not use are removed, so
+        // that a program which neither allocates nor panics is not linked with the runtime
+        remove_unused_functions(&module, &module_prefix(STD));
         (module, builder)
     }
 }
 
+/// whether `func_decl` is a `main` returning a `Result`, which exits with 1 on an `Err`
+fn returns_result(func_decl: &FunctionDeclare, context: &TyphoonContext, environment: &Environment) -> bool {
+    func_decl.name == "main"
+        && !func_decl.return_type.is_void()
+        && enum_instance(
+            &to_basic_type(&func_decl.return_type, context, environment),
+            RESULT,
+            environment,
+        )
+        .is_some()
+}
+
+/// the `main` of the program calling `function`, the `main` of the source returning a
+/// `Result`, and exiting with 0 on `Ok` and with 1 on `Err`
+fn build_result_entry_point(
+    function: FunctionValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) {
+    let result = enum_instance(&function.return_type(), RESULT, environment).unwrap();
+    let (tag, _) = result.variant("Ok").unwrap();
+    let i32_type = context.i32_type();
+    let entry = module.add_function("main", i32_type.as_basic_type().fn_type(&[], false));
+    let block = context.append_basic_block(entry, "entry");
+    builder.position_at_end(&block);
+    let value = builder.build_call(&function, &[]);
+    let is_err = builder.build_int_compare(
+        LLVMIntPredicate::LLVMIntNE,
+        &builder.build_extract_value(&value, 0, "tag"),
+        &i32_type.const_int(tag as u64, false).into_basic_value(),
+        "is_err",
+    );
+    let code = builder.build_int_cast(&is_err, &i32_type.as_basic_type(), false);
+    builder.build_return(code);
+}
+
+/// remove the functions whose symbols start with `prefix` and which nothing else refers to,
+/// the functions only they called are removed in the next round
+fn remove_unused_functions(module: &TyphoonModule, prefix: &str) {
+    loop {
+        let unused: Vec<FunctionValue> = module
+            .functions()
+            .into_iter()
+            .filter(|function| function.name().starts_with(prefix) && !function.is_used())
+            .collect();
+        if unused.is_empty() {
+            break;
+        }
+        unused.into_iter().for_each(FunctionValue::delete);
+    }
+}
+
 impl ModuleCodegen for ModuleItem {
     fn module_codegen(
         self,
         context: &TyphoonContext,
         builder: &TyphoonBuilder,
         module: &TyphoonModule,
+        environment: &Environment,
     ) {
         match self {
+            ModuleItem::FunctionDeclare(func_decl) if func_decl.is_generic() => {
+                // instances are generated at their first call
+            }
             ModuleItem::FunctionDeclare(func_decl) => {
-                func_decl.module_codegen(context, builder, module);
+                func_decl.module_codegen(context, builder, module, environment);
+            }
+            ModuleItem::Impl(impl_block) => {
+                for func_decl in impl_functions(&impl_block, environment) {
+                    if !func_decl.is_generic() {
+                        func_decl.module_codegen(context, builder, module, environment);
+                    }
+                }
             }
-            ModuleItem::StructDeclare(struct_decl) => {
-                struct_decl.module_codegen(context, builder, module);
+            ModuleItem::StructDeclare(_) | ModuleItem::EnumDeclare(_) | ModuleItem::TraitDeclare(_) => {
+                // type bodies are defined before any function
+            }
+            ModuleItem::Const(_) | ModuleItem::Static(_) => {
+                // folded and emitted before any function
+            }
+            ModuleItem::TypeAlias(_) | ModuleItem::Newtype(_) => {}
+            ModuleItem::Mod(_) | ModuleItem::Use(_) => {
+                unreachable!("modules are flattened by the resolver")
             }
         }
     }
 }
 
-impl ModuleCodegen for StructDeclare {
-    fn module_codegen(
-        self,
-        context: &TyphoonContext,
-        builder: &TyphoonBuilder,
-        module: &TyphoonModule,
-    ) {
-        // let struct_type = context.opaque_struct_type(&self.name);
-        for it in self.fields {}
-        // struct_type.set_body()
+/// lay out the struct named `llvm_name`, which is the struct itself or an instance of it
+fn define_struct_body(
+    struct_decl: &StructDeclare,
+    llvm_name: &str,
+    context: &TyphoonContext,
+    environment: &Environment,
+) {
+    debug!("struct {} codegen", llvm_name);
+    let struct_type = context
+        .get_struct_type(llvm_name)
+        .expect("struct should be declared before codegen");
+    // fields are laid out in the order of `StructDeclare::fields`, which the C header
+    // generator relies on as well
+    let fields: Vec<BasicType> = struct_decl
+        .fields
+        .values()
+        .map(|ty| to_basic_type(ty, context, environment))
+        .collect();
+    struct_type.set_body(&fields, false);
+}
+
+/// the struct type of `name<arguments>`, laid out at its first use
+fn instantiate_struct(
+    name: &str,
+    arguments: &[Type],
+    context: &TyphoonContext,
+    environment: &Environment,
+) -> BasicType {
+    let llvm_name = Type::generic(name.to_string(), arguments.to_vec()).to_string();
+    if let Some(struct_type) = context.get_struct_type(&llvm_name) {
+        return struct_type.as_basic_type();
+    }
+    let struct_decl = environment
+        .get_struct(name)
+        .unwrap_or_else(|| panic!("struct {} is undefined", name));
+    if struct_decl.generics.len() != arguments.len() {
+        panic!(
+            "struct {} takes {} type arguments but {} were supplied",
+            name,
+            struct_decl.generics.len(),
+            arguments.len()
+        );
+    }
+    // declared before its fields are lowered, so that it can refer to itself by reference
+    let struct_type = context.opaque_struct_type(&llvm_name);
+    environment.add_struct_instance(llvm_name.clone(), name.to_string(), arguments.to_vec());
+    define_struct_body(&struct_decl.instantiate(arguments), &llvm_name, context, environment);
+    struct_type.as_basic_type()
+}
+
+/// the enum type of `name<arguments>`, laid out at its first use like an instance of a
+/// generic struct, the instance is declared as an enum under the name of its llvm type
+fn instantiate_enum(
+    name: &str,
+    arguments: &[Type],
+    context: &TyphoonContext,
+    environment: &Environment,
+) -> BasicType {
+    let llvm_name = Type::generic(name.to_string(), arguments.to_vec()).to_string();
+    if let Some(enum_type) = context.get_struct_type(&llvm_name) {
+        return enum_type.as_basic_type();
+    }
+    let enum_decl = environment.get_enum(name).unwrap();
+    if enum_decl.generics.len() != arguments.len() {
+        panic!(
+            "enum {} takes {} type arguments but {} were supplied",
+            name,
+            enum_decl.generics.len(),
+            arguments.len()
+        );
+    }
+    let enum_type = context.opaque_struct_type(&llvm_name);
+    environment.add_struct_instance(llvm_name.clone(), name.to_string(), arguments.to_vec());
+    let instance = EnumDeclare {
+        name: llvm_name,
+        ..enum_decl.instantiate(arguments)
+    };
+    if !define_enum_body(&instance, context, environment) {
+        panic!("enum {} contains itself by value", &instance.name);
+    }
+    environment.declare_enum(instance);
+    enum_type.as_basic_type()
+}
+
+/// the enum `name` a variant is built of, an instance of a generic enum takes its type
+/// arguments from the type the value is `expected` to have or else from the `values` of
+/// the fields of `variant`
+fn variant_enum(
+    name: &str,
+    variant: &str,
+    values: &[BasicValue],
+    expected: Option<&BasicType>,
+    context: &TyphoonContext,
+    environment: &Environment,
+) -> EnumDeclare {
+    let enum_decl = environment
+        .get_enum(name)
+        .unwrap_or_else(|| panic!("enum {} is undefined", name));
+    if !enum_decl.is_generic() {
+        return enum_decl;
+    }
+    if let Some(enum_decl) = expected.and_then(|ty| enum_instance(ty, name, environment)) {
+        return enum_decl;
+    }
+    let parameters = enum_decl
+        .variant(variant)
+        .map(|(_, variant)| variant.field_types())
+        .unwrap_or_default();
+    let arguments = infer_type_arguments(name, &enum_decl.generics, &parameters, values, None, environment);
+    let enum_type = instantiate_enum(name, &arguments, context, environment);
+    environment
+        .get_enum(&enum_type.into_struct_type().unwrap().name().unwrap())
+        .unwrap()
+}
+
+/// declaration of the instance of the generic enum `name` which `ty` is, if it is one
+pub(crate) fn enum_instance(ty: &BasicType, name: &str, environment: &Environment) -> Option<EnumDeclare> {
+    let llvm_name = ty.into_struct_type()?.name()?;
+    match environment.get_struct_instance(&llvm_name) {
+        Some((instance_of, _)) if instance_of == name => environment.get_enum(&llvm_name),
+        _ => None,
+    }
+}
+
+/// declaration of the struct `ty` is an instance of or is itself, with the type arguments
+/// of an instance substituted
+pub(crate) fn struct_declaration(ty: &BasicType, environment: &Environment) -> Option<StructDeclare> {
+    let llvm_name = ty.into_struct_type()?.name()?;
+    match environment.get_struct_instance(&llvm_name) {
+        Some((name, arguments)) => environment
+            .get_struct(&name)
+            .map(|struct_decl| struct_decl.instantiate(&arguments)),
+        None => environment.get_struct(&llvm_name),
+    }
+}
+
+/// anonymous struct holding the fields of an enum variant
+pub(crate) fn variant_payload_type(
+    variant: &EnumVariant,
+    context: &TyphoonContext,
+    environment: &Environment,
+) -> BasicType {
+    let fields: Vec<BasicType> = variant
+        .field_types()
+        .into_iter()
+        .map(|ty| to_basic_type(ty, context, environment))
+        .collect();
+    context.struct_type(&fields, false).as_basic_type()
+}
+
+/// `{ i32 tag, [N x iA] payload }` where the payload is as large and as aligned as the
+/// largest variant, returns false when a variant contains an enum which is not laid out yet
+fn define_enum_body(
+    enum_decl: &EnumDeclare,
+    context: &TyphoonContext,
+    environment: &Environment,
+) -> bool {
+    let payloads: Vec<BasicType> = enum_decl
+        .variants
+        .iter()
+        .map(|variant| variant_payload_type(variant, context, environment))
+        .collect();
+    if !payloads.iter().all(|payload| payload.is_sized()) {
+        return false;
+    }
+    debug!("enum {} codegen", &enum_decl.name);
+    let target_data = TargetData::x86_64();
+    let size = payloads
+        .iter()
+        .map(|payload| target_data.abi_size(payload))
+        .max()
+        .unwrap_or(0);
+    let alignment = payloads
+        .iter()
+        .map(|payload| target_data.abi_alignment(payload))
+        .max()
+        .unwrap_or(1);
+    let mut fields = vec![context.i32_type().as_basic_type()];
+    if size > 0 {
+        let unit = context.int_type(alignment * 8).as_basic_type();
+        let len = size.div_ceil(alignment as u64);
+        fields.push(unit.array_type(len as u32).as_basic_type());
+    }
+    context
+        .get_struct_type(&enum_decl.name)
+        .expect("enum should be declared before codegen")
+        .set_body(&fields, false);
+    true
+}
+
+/// pointer to a field of the tuple, struct or enum behind `ptr`
+pub(crate) fn struct_field_ptr(
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    ptr: &PointerValue,
+    index: u32,
+) -> PointerValue {
+    let i32_type = context.i32_type();
+    let indices = [
+        i32_type.const_int(0, false).into_basic_value(),
+        i32_type.const_int(index as u64, false).into_basic_value(),
+    ];
+    builder.build_in_bounds_gep(ptr, &indices, "field_ptr")
+}
+
+/// follow references until `ptr` points at a value which is not a pointer itself
+pub(crate) fn auto_deref(builder: &TyphoonBuilder, mut ptr: PointerValue) -> PointerValue {
+    while ptr.pointee_type().into_pointer_type().is_some() {
+        ptr = builder.build_load(&ptr, "auto_deref").into_pointer_value();
+    }
+    ptr
+}
+
+/// store the tag and the fields of the variant into a temporary and load the enum value
+fn build_enum_value(
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    environment: &Environment,
+    enum_decl: &EnumDeclare,
+    variant_name: &str,
+    values: Vec<BasicValue>,
+) -> BasicValue {
+    let (tag, variant) = enum_decl
+        .variant(variant_name)
+        .unwrap_or_else(|| panic!("enum {} has no variant {}", &enum_decl.name, variant_name));
+    let field_count = variant.field_types().len();
+    if field_count != values.len() {
+        panic!(
+            "variant {}::{} has {} fields but {} were supplied",
+            &enum_decl.name,
+            variant_name,
+            field_count,
+            values.len()
+        );
+    }
+    let enum_type = context
+        .get_struct_type(&enum_decl.name)
+        .expect("enum should be declared before codegen")
+        .as_basic_type();
+    let ptr = builder.build_entry_alloca(&enum_type, "enum_tmp");
+    let tag = context.i32_type().const_int(tag as u64, false).into_basic_value();
+    builder.build_store(&struct_field_ptr(context, builder, &ptr, 0), &tag);
+    if !values.is_empty() {
+        let payload_type = variant_payload_type(variant, context, environment);
+        let payload = builder.build_pointer_cast(
+            &struct_field_ptr(context, builder, &ptr, 1),
+            &payload_type.ptr_type(AddressSpace::Generic).as_basic_type(),
+            "payload",
+        );
+        for (index, value) in values.into_iter().enumerate() {
+            let field = struct_field_ptr(context, builder, &payload, index as u32);
+            builder.build_store(&field, &coerce(builder, value, &field.pointee_type()));
+        }
+    }
+    builder.build_load(&ptr, "enum")
+}
+
+pub(crate) fn to_basic_type(ty: &Type, context: &TyphoonContext, environment: &Environment) -> BasicType {
+    match ty {
+        Type::Named(name) => match name.as_str() {
+            "i8" => context.i8_type().as_basic_type(),
+            "i16" => context.i16_type().as_basic_type(),
+            "i32" => context.i32_type().as_basic_type(),
+            "i64" => context.i64_type().as_basic_type(),
+            "f32" => context.f32_type(),
+            "f64" => context.f64_type(),
+            name if environment.get_alias(name).is_some() => {
+                to_basic_type(&environment.get_alias(name).unwrap().ty, context, environment)
+            }
+            name => context
+                .get_struct_type(name)
+                .map(|struct_type| struct_type.as_basic_type())
+                .unwrap_or_else(|| match environment.get_struct(name) {
+                    Some(struct_decl) if struct_decl.is_generic() => {
+                        panic!("generic struct {} is used without type arguments", name)
+                    }
+                    _ if environment.get_enum(name).is_some() => {
+                        panic!("generic enum {} is used without type arguments", name)
+                    }
+                    _ => unimplemented!("type {} is not supported", name),
+                }),
+        },
+        Type::Generic(name, arguments) if environment.get_enum(name).is_some() => {
+            instantiate_enum(name, arguments, context, environment)
+        }
+        Type::Generic(name, arguments) => instantiate_struct(name, arguments, context, environment),
+        Type::Reference { inner, .. } | Type::Pointer(inner) if matches!(**inner, Type::Dyn(..)) => {
+            match &**inner {
+                Type::Dyn(trait_name, arguments) => trait_object_type(trait_name, arguments, context, environment),
+                _ => unreachable!(),
+            }
+        }
+        Type::Dyn(..) => {
+            panic!("{} has no size, use it behind a reference", ty)
+        }
+        Type::Boxed(inner) if matches!(**inner, Type::Dyn(..)) => {
+            unimplemented!("{} is not supported, box a value of a type implementing the trait", ty)
+        }
+        Type::Boxed(inner) => {
+            let pointee = to_basic_type(inner, context, environment);
+            let pointee = if pointee.is_void() {
+                context.i8_type().as_basic_type()
+            } else {
+                pointee
+            };
+            gc::box_type(&pointee)
+        }
+        Type::Reference { inner, .. } | Type::Pointer(inner) => {
+            let pointee = to_basic_type(inner, context, environment);
+            // llvm has no pointer to void, `*()` is lowered as `i8*` like C does
+            let pointee = if pointee.is_void() {
+                context.i8_type().as_basic_type()
+            } else {
+                pointee
+            };
+            pointee.ptr_type(AddressSpace::Generic).as_basic_type()
+        }
+        Type::Array(element, len) => to_basic_type(element, context, environment)
+            .array_type(*len as u32)
+            .as_basic_type(),
+        Type::Tuple(elements) if elements.is_empty() => context.void_type().as_basic_type(),
+        Type::Tuple(elements) => {
+            let fields: Vec<BasicType> = elements
+                .iter()
+                .map(|element| {
+                    let field = to_basic_type(element, context, environment);
+                    if field.is_void() {
+                        unimplemented!("unit inside of tuple {} is not supported", ty);
+                    }
+                    field
+                })
+                .collect();
+            context.struct_type(&fields, false).as_basic_type()
+        }
+        Type::Simd(element, lanes) => {
+            let element_type = to_basic_type(element, context, environment);
+            if element_type.int_width().is_none() && element_type.float_width().is_none() {
+                unimplemented!("simd lanes of type {} are not supported", element);
+            }
+            element_type.vec_type(*lanes as u32).as_basic_type()
+        }
+        Type::Function(parameters, return_type) => {
+            let params: Vec<BasicType> = parameters
+                .iter()
+                .map(|parameter| {
+                    let param = to_basic_type(parameter, context, environment);
+                    if param.is_void() {
+                        unimplemented!("unit parameter of {} is not supported", ty);
+                    }
+                    param
+                })
+                .collect();
+            let return_type = to_basic_type(return_type, context, environment);
+            function_value_type(&params, &return_type, context)
+        }
+    }
+}
+
+/// a function value is the pair `{ R (i8 addrspace(1)*, A...)*, i8 addrspace(1)* }` of the
+/// code and of the environment it is called with, which holds the captured variables of a
+/// closure and is null for named functions
+fn function_value_type(params: &[BasicType], return_type: &BasicType, context: &TyphoonContext) -> BasicType {
+    let env_type = environment_type(context);
+    let params: Vec<BasicType> = std::iter::once(env_type).chain(params.iter().cloned()).collect();
+    let code_type = return_type
+        .fn_type(&params, false)
+        .ptr_type(AddressSpace::Generic)
+        .as_basic_type();
+    context.struct_type(&[code_type, env_type], false).as_basic_type()
+}
+
+/// the environment of a closure is allocated on the heap, so that it is freed once the
+/// closure is unreachable and the boxes it captured are kept alive while it is not
+fn environment_type(context: &TyphoonContext) -> BasicType {
+    gc::box_type(&context.i8_type().as_basic_type())
+}
+
+/// parameter and return types of a function value type, without the environment
+fn function_value_signature(ty: &BasicType) -> Option<(Vec<BasicType>, BasicType)> {
+    let struct_type = ty.into_struct_type()?;
+    let fields = struct_type.field_types();
+    if struct_type.name().is_some() || fields.len() != 2 {
+        return None;
     }
+    let function_type = fields[0].into_pointer_type()?.element_type().into_function_type()?;
+    let params = function_type.param_types();
+    Some((params[1..].to_vec(), function_type.return_type()))
 }
 
-fn to_basic_type(ty: &Type, context: &TyphoonContext) -> BasicType {
-    match ty.name.as_str() {
-        "i8" => context.i8_type().as_basic_type(),
-        "i16" => context.i16_type().as_basic_type(),
-        "i32" => context.i32_type().as_basic_type(),
-        "i64" => context.i64_type().as_basic_type(),
-        "" => context.void_type().as_basic_type(),
+fn build_function_value(
+    code: FunctionValue,
+    env: BasicValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+) -> BasicValue {
+    let ty = function_value_type(&code.param_types()[1..], &code.return_type(), context);
+    let value = builder.build_insert_value(&ty.get_undef(), &code.as_pointer_value().into_basic_value(), 0, "fn");
+    builder.build_insert_value(&value, &env, 1, "fn")
+}
+
+/// the function `name` as a function value, a generic function is instantiated for the
+/// function value type `expected` it is used as
+fn named_function_value(
+    name: &str,
+    expected: Option<&BasicType>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> BasicValue {
+    let function = match environment.get_function(name) {
+        Some(function) => function,
+        None => {
+            let func_decl = environment
+                .get_generic_function(name)
+                .unwrap_or_else(|| panic!("variable {} is undefined", name));
+            let expected = expected
+                .filter(|it| function_value_signature(it).is_some())
+                .unwrap_or_else(|| panic!("cannot infer the type arguments of {} used as a value", name));
+            let parameter = Type::function(
+                func_decl.args.iter().map(|(_, ty)| ty.clone()).collect(),
+                func_decl.return_type.clone(),
+            );
+            let mut bindings = HashMap::new();
+            unify(&parameter, &source_type(*expected, environment), &func_decl.generics, &mut bindings, name);
+            let arguments = func_decl
+                .generics
+                .iter()
+                .map(|parameter| {
+                    bindings.remove(parameter).unwrap_or_else(|| {
+                        panic!("cannot infer type parameter {} of {}", parameter, name)
+                    })
+                })
+                .collect();
+            instantiate_function(&func_decl, arguments, context, builder, module, environment)
+        }
+    };
+    let env = environment_type(context).const_zero();
+    build_function_value(function_shim(function, context, builder, module), env, context, builder)
+}
+
+/// a function with the unused environment as first parameter that calls `function`, emitted
+/// once per function
+fn function_shim(
+    function: FunctionValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+) -> FunctionValue {
+    let symbol = shim_symbol(&function.name());
+    if let Some(shim) = module.get_function(&symbol) {
+        return shim;
+    }
+    let params: Vec<BasicType> = std::iter::once(environment_type(context))
+        .chain(function.param_types())
+        .collect();
+    let shim = module.add_function(&symbol, function.return_type().fn_type(&params, false));
+    shim.set_linkage(LLVMLinkage::LLVMInternalLinkage);
+
+    let position = builder.insert_block();
+    let block = context.append_basic_block(shim, "entry");
+    builder.position_at_end(&block);
+    let args: Vec<BasicValue> = (1..params.len() as u32).map(|index| shim.get_param(index)).collect();
+    let value = builder.build_call(&function, &args);
+    build_implicit_return(builder, shim, value);
+    builder.position_at_end(&position);
+    shim
+}
+
+/// the parts of a closure expression `|params| -> return_type body`
+struct Closure {
+    params: Vec<(Identifier, Option<Type>)>,
+    return_type: Option<Type>,
+    body: Expr,
+}
+
+/// a closure as a function value, the body becomes a function taking the environment with
+/// copies of the captured variables as first parameter, parameter and return types that are
+/// not written are the ones `expected` of the function value
+fn closure_codegen(
+    closure: Closure,
+    expected_params: Option<Vec<Option<BasicType>>>,
+    expected_return: Option<BasicType>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    trace!("build closure");
+    let environment = scope.environment();
+    if let Some(expected) = expected_params.as_ref().filter(|it| it.len() != closure.params.len()) {
+        panic!(
+            "closure takes {} parameters but a function of {} parameters is expected",
+            closure.params.len(),
+            expected.len()
+        );
+    }
+    let param_types: Vec<BasicType> = closure
+        .params
+        .iter()
+        .enumerate()
+        .map(|(index, (name, ty))| match ty {
+            Some(ty) => to_basic_type(&scope.resolve_type(ty), context, environment),
+            None => expected_params
+                .as_ref()
+                .and_then(|expected| expected[index])
+                .unwrap_or_else(|| panic!("cannot infer the type of closure parameter {}", name)),
+        })
+        .collect();
+    let names: Vec<Identifier> = closure.params.iter().map(|(name, _)| name.clone()).collect();
+    let captured: Vec<(Identifier, BasicValue)> = captures(&names, &closure.body)
+        .into_iter()
+        .filter_map(|(name, _)| scope.get(&name).map(|ptr| (name, ptr)))
+        .map(|(name, ptr)| {
+            let value = builder.build_load(&ptr, &name);
+            (name, value)
+        })
+        .collect();
+    let env = build_closure_environment(&captured, context, builder, module, environment);
+    let code_params: Vec<BasicType> = std::iter::once(environment_type(context)).chain(param_types).collect();
+
+    let return_type = match closure
+        .return_type
+        .as_ref()
+        .map(|ty| to_basic_type(&scope.resolve_type(ty), context, environment))
+        .or(expected_return)
+    {
+        Some(return_type) => return_type,
+        None => {
+            // the body is generated once in a throwaway function to find the type of its value
+            let trial = module.add_function("", context.void_type().as_basic_type().fn_type(&code_params, false));
+            let ty = define_closure_body(trial, &captured, &closure, context, builder, module, scope);
+            trial.delete();
+            ty.unwrap_or_else(|| context.void_type().as_basic_type())
+        }
+    };
+    let enclosing = builder.current_function().name();
+    let symbol = closure_symbol(&enclosing, environment.next_closure(&enclosing));
+    let function = module.add_function(&symbol, return_type.fn_type(&code_params, false));
+    function.set_linkage(LLVMLinkage::LLVMInternalLinkage);
+    define_closure_body(function, &captured, &closure, context, builder, module, scope);
+    build_function_value(function, env, context, builder)
+}
+
+/// copy the captured values into an environment allocated on the heap, as the closure may
+/// outlive the stack frame of the function creating it, null when nothing is captured
+fn build_closure_environment(
+    captured: &[(Identifier, BasicValue)],
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> BasicValue {
+    if captured.is_empty() {
+        return environment_type(context).const_zero();
+    }
+    let fields: Vec<BasicType> = captured.iter().map(|(_, value)| value.get_type().unwrap()).collect();
+    let struct_type = context.struct_type(&fields, false).as_basic_type();
+    let ptr = gc::build_allocation(&struct_type, context, builder, module, environment);
+    for (index, (_, value)) in captured.iter().enumerate() {
+        builder.build_store(&struct_field_ptr(context, builder, &ptr, index as u32), value);
+    }
+    builder
+        .build_pointer_cast(&ptr, &environment_type(context), "env")
+        .into_basic_value()
+}
+
+/// generate the body of a closure into `function`, the captured variables are loaded from
+/// the environment, returns the type of the value of the body
+fn define_closure_body(
+    function: FunctionValue,
+    captured: &[(Identifier, BasicValue)],
+    closure: &Closure,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> Option<BasicType> {
+    let position = builder.insert_block();
+    let block = context.append_basic_block(function, "entry");
+    builder.position_at_end(&block);
+    let scope = Scope::new_closure(scope);
+
+    if !captured.is_empty() {
+        let fields: Vec<BasicType> = captured.iter().map(|(_, value)| value.get_type().unwrap()).collect();
+        let env_type = context.struct_type(&fields, false).as_basic_type();
+        let env = builder.build_pointer_cast(
+            &function.get_param(0).into_pointer_value(),
+            &gc::box_type(&env_type),
+            "env",
+        );
+        for (index, ((name, _), ty)) in captured.iter().zip(fields).enumerate() {
+            let value = builder.build_load(&struct_field_ptr(context, builder, &env, index as u32), name);
+            let ptr = gc::build_slot(&ty, name, context, builder, module, scope.environment());
+            builder.build_store(&ptr, &value);
+            scope.declare(name.clone(), ptr);
+        }
+    }
+    let param_types = function.param_types();
+    for (index, (name, _)) in closure.params.iter().enumerate() {
+        let ptr = gc::build_slot(&param_types[index + 1], name, context, builder, module, scope.environment());
+        builder.build_store(&ptr, &function.get_param(index as u32 + 1));
+        scope.declare(name.clone(), ptr);
+    }
+
+    let return_type = function.return_type();
+    let body = closure.body.clone();
+    let value = if return_type.is_void() {
+        body.expr_codegen(context, builder, module, &scope)
+    } else {
+        expr_codegen_as(body, &return_type, context, builder, module, &scope)
+    };
+    let ty = value.get_type();
+    build_implicit_return(builder, function, value);
+    builder.position_at_end(&position);
+    ty
+}
+
+/// call of a function value, the code is called with the environment as first argument
+fn function_value_call(
+    callee: BasicValue,
+    args: Vec<Expr>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    let callee_type = callee.get_type().expect("void value is called");
+    let (param_types, _) = function_value_signature(&callee_type).unwrap_or_else(|| {
+        panic!("{} is not a function", source_type(callee_type, scope.environment()))
+    });
+    if param_types.len() != args.len() {
+        panic!(
+            "function value takes {} arguments but {} were supplied",
+            param_types.len(),
+            args.len()
+        );
+    }
+    let code = builder.build_extract_value(&callee, 0, "code");
+    let env = builder.build_extract_value(&callee, 1, "env");
+    let args: Vec<BasicValue> = std::iter::once(env)
+        .chain(
+            args.into_iter()
+                .zip(param_types)
+                .map(|(arg, ty)| expr_codegen_as(arg, &ty, context, builder, module, scope)),
+        )
+        .collect();
+    let value = builder.build_call(&FunctionValue::new(code.as_llvm_ref().unwrap()), &args);
+    gc::build_root(value, context, builder, module, scope.environment());
+    value
+}
+
+/// a trait object is a fat pointer `{ i8*, vtable* }` to the data and to the functions of
+/// the trait implemented for its type, one `dyn Trait` struct type per trait and list of
+/// type arguments of a generic trait
+fn trait_object_type(
+    trait_name: &str,
+    arguments: &[Type],
+    context: &TyphoonContext,
+    environment: &Environment,
+) -> BasicType {
+    let name = Type::Dyn(trait_name.to_string(), arguments.to_vec()).to_string();
+    if let Some(struct_type) = context.get_struct_type(&name) {
+        return struct_type.as_basic_type();
+    }
+    let trait_decl = environment
+        .get_trait(trait_name)
+        .unwrap_or_else(|| panic!("trait {} is undefined", trait_name));
+    check_object_safety(&trait_decl).unwrap_or_else(|message| type_error(message));
+    let data_type = context.i8_type().as_basic_type().ptr_type(AddressSpace::Generic);
+    // a slot for every function of the trait in declaration order, taking the data pointer
+    // in place of the receiver
+    let slots: Vec<BasicType> = trait_function_types(&trait_decl, arguments, context, environment)
+        .into_iter()
+        .map(|(params, return_type)| {
+            let args: Vec<BasicType> = std::iter::once(data_type.as_basic_type()).chain(params).collect();
+            return_type.fn_type(&args, false).ptr_type(AddressSpace::Generic).as_basic_type()
+        })
+        .collect();
+    let vtable_type = context.opaque_struct_type(&format!("vtable {}", &name["dyn ".len()..]));
+    vtable_type.set_body(&slots, false);
+    let struct_type = context.opaque_struct_type(&name);
+    struct_type.set_body(
+        &[
+            data_type.as_basic_type(),
+            vtable_type.as_basic_type().ptr_type(AddressSpace::Generic).as_basic_type(),
+        ],
+        false,
+    );
+    environment.add_trait_object(name, trait_name.to_string(), arguments.to_vec());
+    struct_type.as_basic_type()
+}
+
+/// parameter types after the receiver and return type of every function of a trait, with
+/// the type parameters of a generic trait replaced by `arguments`
+fn trait_function_types(
+    trait_decl: &TraitDeclare,
+    arguments: &[Type],
+    context: &TyphoonContext,
+    environment: &Environment,
+) -> Vec<(Vec<BasicType>, BasicType)> {
+    if trait_decl.generics.len() != arguments.len() {
+        type_error(format!(
+            "trait {} takes {} type arguments but {} were supplied",
+            trait_decl.name,
+            trait_decl.generics.len(),
+            arguments.len()
+        ));
+    }
+    let bindings: HashMap<Identifier, Type> =
+        trait_decl.generics.iter().cloned().zip(arguments.iter().cloned()).collect();
+    let basic_type = |ty: &Type| to_basic_type(&ty.substitute(&bindings), context, environment);
+    trait_decl
+        .functions
+        .iter()
+        .map(|function| {
+            let params = function.args[1..].iter().map(|(_, ty)| basic_type(ty)).collect();
+            (params, basic_type(&function.return_type))
+        })
+        .collect()
+}
+
+/// trait of a trait object type with the type arguments of a generic trait
+fn trait_object_trait(ty: &BasicType, environment: &Environment) -> Option<(Identifier, Vec<Type>)> {
+    let name = ty.into_struct_type()?.name()?;
+    environment.get_trait_object(&name)
+}
+
+/// the vtable of `ty` as `dyn Trait`, a constant global emitted once per type and trait,
+/// the functions of the impl have to take and return the types the trait object expects
+fn vtable(
+    ty: &Type,
+    (trait_name, arguments): (&str, &[Type]),
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> PointerValue {
+    let object = Type::Dyn(trait_name.to_string(), arguments.to_vec());
+    // `Trait` or `Trait<A, B>`
+    let trait_path = &object.to_string()["dyn ".len()..];
+    let name = format!("vtable {} as {}", ty, trait_path);
+    if let Some(global) = module.get_global(&name) {
+        return global;
+    }
+    if !implements(ty, trait_name, environment) {
+        panic!("{} does not implement {}, so it cannot be used as dyn {}", ty, trait_name, trait_name);
+    }
+    let trait_decl = environment.get_trait(trait_name).unwrap();
+    let vtable_type = context
+        .get_struct_type(&format!("vtable {}", trait_path))
+        .expect("vtable type is defined with the trait object type");
+    let function_types = trait_function_types(&trait_decl, arguments, context, environment);
+    let entries: Vec<BasicValue> = trait_decl
+        .functions
+        .iter()
+        .zip(function_types)
+        .zip(vtable_type.field_types())
+        .map(|((function, (params, return_type)), slot_type)| {
+            let name = format!("{}::{}", type_name(ty).unwrap(), function.name);
+            let function = environment.get_function(&name).unwrap_or_else(|| {
+                // the type arguments of a generic impl follow from the receiver
+                let func_decl = environment.get_generic_function(&name).unwrap();
+                let mut bindings = HashMap::new();
+                let receiver = Type::reference(ty.clone(), false);
+                unify(&func_decl.args[0].1, &receiver, &func_decl.generics, &mut bindings, &name);
+                let arguments = func_decl
+                    .generics
+                    .iter()
+                    .map(|parameter| {
+                        bindings.remove(parameter).unwrap_or_else(|| {
+                            panic!("cannot infer type parameter {} of {}", parameter, name)
+                        })
+                    })
+                    .collect();
+                instantiate_function(&func_decl, arguments, context, builder, module, environment)
+            });
+            let same = |a: &BasicType, b: &BasicType| a.as_llvm_type_ref() == b.as_llvm_type_ref();
+            let implemented = function.param_types();
+            if implemented.len() != params.len() + 1
+                || !implemented[1..].iter().zip(&params).all(|(a, b)| same(a, b))
+                || !same(&function.return_type(), &return_type)
+            {
+                type_error(format!(
+                    "{} implements {} with other types than {}, so it cannot be used as one",
+                    ty, trait_name, object
+                ));
+            }
+            function
+                .as_pointer_value()
+                .const_pointer_cast(&slot_type)
+                .into_basic_value()
+        })
+        .collect();
+    module.add_global_constant(&name, &vtable_type.const_named_struct(&entries))
+}
+
+/// `coerce` that also turns a reference into a trait object when `ty` is one
+fn coerce_unsized(
+    value: BasicValue,
+    ty: &BasicType,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> BasicValue {
+    let pointee = value
+        .get_type()
+        .and_then(|it| it.into_pointer_type())
+        .map(|it| it.element_type());
+    match (trait_object_trait(ty, environment), pointee) {
+        (Some((trait_name, arguments)), Some(pointee)) => {
+            let pointee = source_type(pointee, environment);
+            let vtable = vtable(&pointee, (&trait_name, &arguments), context, builder, module, environment);
+            let fields = ty.into_struct_type().unwrap().field_types();
+            let data = builder.build_pointer_cast(&value.into_pointer_value(), &fields[0], "data");
+            let object = builder.build_insert_value(&ty.get_undef(), &data.into_basic_value(), 0, "dyn");
+            builder.build_insert_value(&object, &vtable.into_basic_value(), 1, "dyn")
+        }
+        _ => coerce(builder, value, ty),
+    }
+}
+
+/// evaluate `expr` as a value of type `ty`, array literals are built element by element so
+/// that references of different types can become trait objects of the same trait
+pub(crate) fn expr_codegen_as(
+    expr: Expr,
+    ty: &BasicType,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    // closures and functions used as values take the types of the expected function value
+    let expr = match (expr, function_value_signature(ty)) {
+        (
+            Expr::Closure {
+                params,
+                return_type,
+                body,
+            },
+            Some((param_types, expected_return)),
+        ) => {
+            let expected_params = param_types.into_iter().map(Some).collect();
+            let closure = Closure { params, return_type, body: *body };
+            let value = closure_codegen(
+                closure,
+                Some(expected_params),
+                Some(expected_return),
+                context,
+                builder,
+                module,
+                scope,
+            );
+            return expect_function_value(value, ty, scope.environment());
+        }
+        (Expr::Identifier(name, _), Some(_)) if scope.get(&name).is_none() => {
+            let value = named_function_value(&name, Some(ty), context, builder, module, scope.environment());
+            return expect_function_value(value, ty, scope.environment());
+        }
+        // the boxed value takes the type the expected box points to
+        (Expr::Box(value), _) if gc::is_box_type(ty) => {
+            let pointee = ty.into_pointer_type().unwrap().element_type();
+            let value = expr_codegen_as(*value, &pointee, context, builder, module, scope);
+            return gc::build_box(value, context, builder, module, scope.environment());
+        }
+        (Expr::Call(callee, args), _) => {
+            let args = args.into_iter().map(|arg| *arg).collect();
+            let value = call_codegen(*callee, args, Some(ty), context, builder, module, scope);
+            return coerce_unsized(value, ty, context, builder, module, scope.environment());
+        }
+        // every arm is evaluated as a value of the expected type
+        (
+            Expr::Match {
+                scrutinee,
+                arms,
+                location,
+            },
+            _,
+        ) => {
+            trace!("build match");
+            let value = match_codegen((*scrutinee, arms), location, Some(ty), context, builder, module, scope);
+            return coerce_unsized(value, ty, context, builder, module, scope.environment());
+        }
+        // a variant without fields takes the type arguments of the expected enum
+        (Expr::Path(enum_name, variant), _) if scope.environment().get_enum(&enum_name).is_some() => {
+            let environment = scope.environment();
+            let enum_decl = variant_enum(&enum_name, &variant, &[], Some(ty), context, environment);
+            return build_enum_value(context, builder, environment, &enum_decl, &variant, vec![]);
+        }
+        // the value of a block is the value of its last expression
+        (Expr::Block(statements, Some(value)), _) => {
+            let scope = Scope::new_with_upper(scope);
+            for statement in statements {
+                statement.statement_codegen(context, builder, module, &scope);
+            }
+            return expr_codegen_as(*value, ty, context, builder, module, &scope);
+        }
+        (expr, _) => expr,
+    };
+    match (expr, ty.into_array_type()) {
+        (Expr::Array(items), Some(array_type)) if items.len() == array_type.len() as usize => {
+            let element_type = array_type.element_type();
+            items
+                .into_iter()
+                .enumerate()
+                .fold(ty.get_undef(), |aggregate, (index, item)| {
+                    let value = expr_codegen_as(*item, &element_type, context, builder, module, scope);
+                    builder.build_insert_value(&aggregate, &value, index as u32, "array")
+                })
+        }
+        (expr, _) => {
+            let value = expr.expr_codegen(context, builder, module, scope);
+            coerce_unsized(value, ty, context, builder, module, scope.environment())
+        }
+    }
+}
+
+/// panics unless `value` has the llvm type of the declared type `ty`, which is printed as
+/// written, so that an alias keeps its name
+fn expect_type(value: &BasicValue, expected: &BasicType, ty: &Type, environment: &Environment) {
+    if let Some(value_type) = value.get_type() {
+        if value_type.as_llvm_type_ref() != expected.as_llvm_type_ref() {
+            panic!(
+                "mismatched types: expected {}, found {}",
+                ty,
+                source_type(value_type, environment)
+            );
+        }
+    }
+}
+
+/// panics when a type alias in `ty` expands to a type containing the alias itself
+fn check_alias_cycle(ty: &Type, environment: &Environment, expanding: &mut Vec<Identifier>) {
+    match ty {
+        Type::Named(name) => {
+            if let Some(alias) = environment.get_alias(name) {
+                if let Some(start) = expanding.iter().position(|it| it == name) {
+                    let mut cycle = expanding[start..].to_vec();
+                    cycle.push(name.clone());
+                    type_error(format!(
+                        "cycle detected when expanding type alias {}: {}",
+                        name,
+                        cycle.join(" -> ")
+                    ));
+                }
+                expanding.push(name.clone());
+                check_alias_cycle(&alias.ty, environment, expanding);
+                expanding.pop();
+            }
+        }
+        Type::Reference { inner, .. }
+        | Type::Pointer(inner)
+        | Type::Boxed(inner)
+        | Type::Array(inner, _)
+        | Type::Simd(inner, _) => check_alias_cycle(inner, environment, expanding),
+        Type::Tuple(elements) | Type::Generic(_, elements) => elements
+            .iter()
+            .for_each(|it| check_alias_cycle(it, environment, expanding)),
+        Type::Function(parameters, return_type) => {
+            parameters
+                .iter()
+                .for_each(|it| check_alias_cycle(it, environment, expanding));
+            check_alias_cycle(return_type, environment, expanding);
+        }
+        Type::Dyn(_, arguments) => arguments
+            .iter()
+            .for_each(|it| check_alias_cycle(it, environment, expanding)),
+    }
+}
+
+/// `Name(value)`, a newtype wrapping the value
+fn newtype_codegen(
+    newtype: &NewtypeDeclare,
+    args: Vec<Expr>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    trace!("build newtype {}", &newtype.name);
+    let environment = scope.environment();
+    let value = match <[Expr; 1]>::try_from(args) {
+        Ok([value]) => value,
+        Err(args) => panic!("newtype {} wraps 1 value but {} were supplied", newtype.name, args.len()),
+    };
+    let inner_type = to_basic_type(&newtype.inner, context, environment);
+    let value = expr_codegen_as(value, &inner_type, context, builder, module, scope);
+    expect_type(&value, &inner_type, &newtype.inner, environment);
+    let newtype_type = to_basic_type(&Type::named(newtype.name.clone()), context, environment);
+    builder.build_insert_value(&newtype_type.get_undef(), &value, 0, "newtype")
+}
+
+/// function values are not coerced, their parameter and return types must match exactly
+fn expect_function_value(value: BasicValue, ty: &BasicType, environment: &Environment) -> BasicValue {
+    let value_type = value.get_type().unwrap();
+    if value_type.as_llvm_type_ref() != ty.as_llvm_type_ref() {
+        panic!(
+            "expected {}, found {}",
+            source_type(*ty, environment),
+            source_type(value_type, environment)
+        );
+    }
+    value
+}
+
+pub(crate) fn coerce(builder: &TyphoonBuilder, value: BasicValue, ty: &BasicType) -> BasicValue {
+    let value_type = match value.get_type() {
+        Some(value_type) => value_type,
+        None => return value,
+    };
+    if let (Some(from), Some(to)) = (value_type.int_width(), ty.int_width()) {
+        return if from != to {
+            builder.build_int_cast(&value, ty, true)
+        } else {
+            value
+        };
+    }
+    // floats are widened and narrowed, integer literals become floats where one is expected
+    if lane_type(ty).float_width().is_some()
+        && (lane_type(&value_type).float_width().is_some() || value.is_constant())
+    {
+        if let Some(value) = build_numeric_cast(builder, &value, ty) {
+            return value;
+        }
+    }
+    // a box is borrowed where a reference to its value is expected
+    if let (Some(from), Some(to)) = (value_type.into_pointer_type(), ty.into_pointer_type()) {
+        if gc::is_box_type(&value_type)
+            && !gc::is_box_type(ty)
+            && from.element_type().as_llvm_type_ref() == to.element_type().as_llvm_type_ref()
+        {
+            return builder
+                .build_pointer_cast(&value.into_pointer_value(), ty, "borrow")
+                .into_basic_value();
+        }
+    }
+    match (value_type.into_vector_type(), ty.into_vector_type()) {
+        // integer casts work lane-wise on vectors
+        (Some(from), Some(to))
+            if from.len() == to.len()
+                && from.element_type().int_width() != to.element_type().int_width() =>
+        {
+            return builder.build_int_cast(&value, ty, true);
+        }
+        // a scalar operand of a vector operation is splatted to every lane
+        (None, Some(to)) if value_type.int_width().is_some() || value_type.float_width().is_some() => {
+            let value = coerce(builder, value, &to.element_type());
+            return builder.build_vector_splat(&value, to.len(), "splat");
+        }
+        _ => {}
+    }
+    // array and tuple literals are coerced element by element
+    match (aggregate_element_types(&value_type), aggregate_element_types(ty)) {
+        (Some(from), Some(to))
+            if from.len() == to.len()
+                && value_type.as_llvm_type_ref() != ty.as_llvm_type_ref() =>
+        {
+            to.iter()
+                .enumerate()
+                .fold(ty.get_undef(), |aggregate, (index, element_type)| {
+                    let element = builder.build_extract_value(&value, index as u32, "element");
+                    let element = coerce(builder, element, element_type);
+                    builder.build_insert_value(&aggregate, &element, index as u32, "aggregate")
+                })
+        }
+        _ => value,
+    }
+}
+
+/// the type of the lanes of a vector, any other type is its own lane
+fn lane_type(ty: &BasicType) -> BasicType {
+    ty.into_vector_type().map_or(*ty, |vector_type| vector_type.element_type())
+}
+
+/// `value` converted between integers and floats, lane by lane for vectors of the same
+/// length, `None` when either type is no number
+fn build_numeric_cast(builder: &TyphoonBuilder, value: &BasicValue, ty: &BasicType) -> Option<BasicValue> {
+    let value_type = value.get_type()?;
+    let lanes = |ty: &BasicType| ty.into_vector_type().map(|vector_type| vector_type.len());
+    if lanes(&value_type) != lanes(ty) {
+        return None;
+    }
+    let (from, to) = (lane_type(&value_type), lane_type(ty));
+    let opcode = match (from.int_width(), from.float_width(), to.int_width(), to.float_width()) {
+        (Some(_), _, Some(_), _) => return Some(builder.build_int_cast(value, ty, true)),
+        (Some(_), _, _, Some(_)) => LLVMOpcode::LLVMSIToFP,
+        (_, Some(_), Some(_), _) => LLVMOpcode::LLVMFPToSI,
+        (_, Some(from), _, Some(to)) if from < to => LLVMOpcode::LLVMFPExt,
+        (_, Some(from), _, Some(to)) if from > to => LLVMOpcode::LLVMFPTrunc,
+        (_, Some(_), _, Some(_)) => return Some(*value),
+        _ => return None,
+    };
+    Some(builder.build_cast(opcode, value, ty, "numeric_cast"))
+}
+
+fn aggregate_element_types(ty: &BasicType) -> Option<Vec<BasicType>> {
+    if let Some(array_type) = ty.into_array_type() {
+        return Some(vec![array_type.element_type(); array_type.len() as usize]);
+    }
+    ty.into_struct_type().map(|struct_type| struct_type.field_types())
+}
+
+/// what `v[i]` indexes
+enum Indexed {
+    /// an array or a simd vector and the index into it
+    Aggregate(PointerValue, BasicValue),
+    /// the element the `index` or `index_mut` method of another type returned
+    Element(PointerValue),
+}
+
+/// `expr[index]`, any type other than arrays and simd vectors is indexed through its
+/// methods, `index` returns a reference to the element and `index_mut`, called for
+/// `mutable` places, a mutable one
+fn index_codegen(
+    (expr, index): (Expr, Expr),
+    location: Location,
+    mutable: bool,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> Indexed {
+    trace!("build index");
+    // indexing through a reference to an array indexes the array itself
+    let ptr = auto_deref(builder, expr.place_codegen(context, builder, module, scope));
+    let pointee_type = ptr.pointee_type();
+    let len = match (pointee_type.into_array_type(), pointee_type.into_vector_type()) {
+        (Some(array_type), _) => array_type.len(),
+        (_, Some(vector_type)) => vector_type.len(),
         _ => {
-            unimplemented!()
+            let environment = scope.environment();
+            let method = if mutable { INDEX_MUT } else { INDEX };
+            let receiver_type = source_type(pointee_type, environment);
+            let func_decl = type_name(&receiver_type)
+                .and_then(|type_name| environment.get_method(type_name, method))
+                .unwrap_or_else(|| {
+                    panic!("{} cannot be indexed, it has no method {}", receiver_type, method)
+                });
+            let args = call_arguments(
+                &func_decl.name,
+                vec![ptr.into_basic_value()],
+                vec![index],
+                context,
+                builder,
+                module,
+                scope,
+            );
+            let name = &func_decl.name;
+            let function = function_for_call(name, &args, None, context, builder, module, environment);
+            let element = build_function_call(name, &function, args, context, builder, module, environment);
+            return Indexed::Element(element.into_pointer_value());
         }
+    };
+
+    let index = index.expr_codegen(context, builder, module, scope);
+    let index = coerce(builder, index, &context.i64_type().as_basic_type());
+    if scope.environment().options.bounds_checks {
+        let len = context.i64_type().const_int(len as u64, false).into_basic_value();
+        build_bounds_check(&index, &len, Some(location), context, builder, module, scope.environment());
     }
+    Indexed::Aggregate(ptr, index)
+}
+
+/// stop compiling with `message`, an expression or a declaration the types do not allow
+pub(crate) fn type_error(message: String) -> ! {
+    fail(TyphoonError::TypeError(message))
+}
+
+/// `value?` unwraps the `Some` or `Ok` of an `Option` or a `Result` and returns `None` or
+/// the `Err` from the current function otherwise, which has to return the same kind of enum
+/// with the same error type
+fn try_codegen(
+    expr: Expr,
+    location: Location,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    let environment = scope.environment();
+    let value = expr.expr_codegen(context, builder, module, scope);
+    let ty = value
+        .get_type()
+        .unwrap_or_else(|| {
+            type_error(format!(
+                "the `?` operator needs a value at {}:{}",
+                &environment.source_name, location
+            ))
+        });
+    let (kind, success, failure, enum_decl) = enum_instance(&ty, OPTION, environment)
+        .map(|enum_decl| (OPTION, "Some", "None", enum_decl))
+        .or_else(|| enum_instance(&ty, RESULT, environment).map(|enum_decl| (RESULT, "Ok", "Err", enum_decl)))
+        .unwrap_or_else(|| {
+            type_error(format!(
+                "the `?` operator can only be applied to an Option or a Result, found {} at {}:{}",
+                source_type(ty, environment),
+                &environment.source_name,
+                location
+            ))
+        });
+    let return_type = builder.current_function().return_type();
+    let return_enum = enum_instance(&return_type, kind, environment).unwrap_or_else(|| {
+        let found = if return_type.is_void() {
+            Type::void()
+        } else {
+            source_type(return_type, environment)
+        };
+        type_error(format!(
+            "the `?` operator can only be used in a function returning {}, not {} at {}:{}",
+            kind, found, &environment.source_name, location
+        ))
+    });
+    let (_, error) = enum_decl.variant(failure).unwrap();
+    let (_, return_error) = return_enum.variant(failure).unwrap();
+    if error.field_types() != return_error.field_types() {
+        type_error(format!(
+            "mismatched error types: `?` returns {} from a function returning {} at {}:{}",
+            error.field_types()[0],
+            return_error.field_types()[0],
+            &environment.source_name,
+            location
+        ));
+    }
+
+    let (tag, success) = enum_decl.variant(success).unwrap();
+    let place = temporary_place(value, context, builder, module, scope);
+    let is_success = builder.build_int_compare(
+        LLVMIntPredicate::LLVMIntEQ,
+        &builder.build_load(&struct_field_ptr(context, builder, &place, 0), "tag"),
+        &context.i32_type().const_int(tag as u64, false).into_basic_value(),
+        "is_success",
+    );
+    let function = builder.current_function();
+    let failure_block = context.append_basic_block(function, "try_failure");
+    let success_block = context.append_basic_block(function, "try_success");
+    builder.build_conditional_branch(&is_success, &success_block, &failure_block);
+
+    builder.position_at_end(&failure_block);
+    let values = if error.field_types().is_empty() {
+        vec![]
+    } else {
+        vec![payload_field(error, &place, 0, context, builder, environment)]
+    };
+    let returned = build_enum_value(context, builder, environment, &return_enum, failure, values);
+    builder.build_return(returned);
+
+    builder.position_at_end(&success_block);
+    payload_field(success, &place, 0, context, builder, environment)
+}
+
+/// field `index` of the payload of the enum value at `ptr`, which holds `variant`
+fn payload_field(
+    variant: &EnumVariant,
+    ptr: &PointerValue,
+    index: u32,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    environment: &Environment,
+) -> BasicValue {
+    let payload_type = variant_payload_type(variant, context, environment);
+    let payload = builder.build_pointer_cast(
+        &struct_field_ptr(context, builder, ptr, 1),
+        &payload_type.ptr_type(AddressSpace::Generic).as_basic_type(),
+        "payload",
+    );
+    builder.build_load(&struct_field_ptr(context, builder, &payload, index), "field")
+}
+
+/// declare the function under the symbol `symbol`, functions that are not generic are
+/// looked up by their source name afterwards
+fn declare_function(
+    func_decl: &FunctionDeclare,
+    symbol: &str,
+    context: &TyphoonContext,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> FunctionValue {
+    let return_type = to_basic_type(&func_decl.return_type, context, environment);
+    let args: Vec<BasicType> = func_decl
+        .args
+        .iter()
+        .map(|(_, ty)| to_basic_type(ty, context, environment))
+        .collect();
+    let function_type = return_type.fn_type(&args, false);
+    let function_value = module.add_function(symbol, function_type);
+    // only `pub extern fn` and the entry point are visible outside of the object file
+    if !func_decl.is_exported() && symbol != "main" {
+        function_value.set_linkage(LLVMLinkage::LLVMInternalLinkage);
+    }
+    function_value
 }
 
 impl ModuleCodegen for FunctionDeclare {
@@ -91,58 +1656,1214 @@ impl ModuleCodegen for FunctionDeclare {
         context: &TyphoonContext,
         builder: &TyphoonBuilder,
         module: &TyphoonModule,
+        environment: &Environment,
     ) {
         debug!("function {} codegen", &self.name);
 
-        let return_type = to_basic_type(&self.return_type, &context);
-        let args: Vec<BasicType> = self
-            .args
+        let function_value = environment
+            .get_function(&self.name)
+            .expect("function should be declared before codegen");
+        let type_arguments = self_type_argument(&self, &HashMap::new());
+        let scope = Scope::new_generic(environment, type_arguments);
+        define_function_body(self, function_value, &scope, context, builder, module);
+    }
+}
+
+/// the type arguments of a function together with `Self` in a function of an `impl` block
+/// and the type parameters of the trait in a default function of a generic trait
+fn self_type_argument(
+    func_decl: &FunctionDeclare,
+    type_arguments: &HashMap<Identifier, Type>,
+) -> HashMap<Identifier, Type> {
+    let mut type_arguments = type_arguments.clone();
+    if let Some(self_type) = &func_decl.self_type {
+        type_arguments.insert("Self".to_string(), self_type.substitute(&type_arguments));
+    }
+    for (parameter, argument) in func_decl.trait_arguments.iter() {
+        type_arguments.insert(parameter.clone(), argument.substitute(&type_arguments));
+    }
+    type_arguments
+}
+
+/// generate the body of a declared function, `scope` is empty apart from the type arguments
+fn define_function_body(
+    func_decl: FunctionDeclare,
+    function_value: FunctionValue,
+    scope: &Scope,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+) {
+    let block = context.append_basic_block(function_value, "entry");
+    builder.position_at_end(&block);
+    for (index, (name, ty)) in func_decl.args.iter().enumerate() {
+        let ty = to_basic_type(&scope.resolve_type(ty), context, scope.environment());
+        let ptr = gc::build_slot(&ty, name, context, builder, module, scope.environment());
+        builder.build_store(&ptr, &function_value.get_param(index as u32));
+        scope.declare(name.clone(), ptr);
+    }
+
+    let return_type = function_value.return_type();
+    let x = if return_type.is_void() {
+        func_decl.stats.expr_codegen(context, builder, module, scope)
+    } else {
+        expr_codegen_as(*func_decl.stats, &return_type, context, builder, module, scope)
+    };
+    build_implicit_return(builder, function_value, x);
+}
+
+/// return the value of the body of a function, unless the body returned already
+fn build_implicit_return(builder: &TyphoonBuilder, function_value: FunctionValue, value: BasicValue) {
+    if !builder.is_terminated() {
+        let return_type = function_value.return_type();
+        if return_type.is_void() {
+            builder.build_return(None);
+        } else if value.get_type().is_some() {
+            builder.build_return(coerce(builder, value, &return_type));
+        } else {
+            // the body ends without producing the declared return value
+            builder.build_unreachable();
+        }
+    }
+}
+
+/// bind the type parameters appearing in `parameter` to the matching parts of `actual`
+fn unify(
+    parameter: &Type,
+    actual: &Type,
+    generics: &[Identifier],
+    bindings: &mut HashMap<Identifier, Type>,
+    function: &str,
+) {
+    match (parameter, actual) {
+        (Type::Named(name), _) if generics.contains(name) => match bindings.get(name) {
+            Some(bound) if bound != actual => panic!(
+                "type parameter {} of {} is inferred as both {} and {}",
+                name, function, bound, actual
+            ),
+            Some(_) => {}
+            None => {
+                bindings.insert(name.clone(), actual.clone());
+            }
+        },
+        (Type::Reference { inner, .. }, Type::Reference { inner: actual, .. })
+        | (Type::Pointer(inner), Type::Reference { inner: actual, .. })
+        | (Type::Reference { inner, .. }, Type::Boxed(actual))
+        | (Type::Boxed(inner), Type::Boxed(actual)) => unify(inner, actual, generics, bindings, function),
+        (Type::Array(element, _), Type::Array(actual, _))
+        | (Type::Simd(element, _), Type::Simd(actual, _)) => {
+            unify(element, actual, generics, bindings, function)
+        }
+        (Type::Tuple(elements), Type::Tuple(actuals))
+        | (Type::Generic(_, elements), Type::Generic(_, actuals))
+            if elements.len() == actuals.len() =>
+        {
+            for (element, actual) in elements.iter().zip(actuals) {
+                unify(element, actual, generics, bindings, function);
+            }
+        }
+        (Type::Function(parameters, return_type), Type::Function(actuals, actual_return))
+            if parameters.len() == actuals.len() =>
+        {
+            for (parameter, actual) in parameters.iter().zip(actuals) {
+                unify(parameter, actual, generics, bindings, function);
+            }
+            unify(return_type, actual_return, generics, bindings, function);
+        }
+        // mismatches are left to the coercions at the call
+        _ => {}
+    }
+}
+
+/// type arguments of `function` inferred from the values passed as `parameters`, integer
+/// constants only decide parameters no other value binds, so that `max(x, 1)` follows `x`,
+/// and then as `i32` like an untyped literal
+///
+/// the parameters no argument binds are taken from the `returned` type, the declared return
+/// type and the type the result is expected to have
+fn infer_type_arguments(
+    function: &str,
+    generics: &[Identifier],
+    parameters: &[&Type],
+    values: &[BasicValue],
+    returned: Option<(&Type, Type)>,
+    environment: &Environment,
+) -> Vec<Type> {
+    let mut bindings = bind_type_parameters(function, generics, parameters, values, environment);
+    if let Some((declared, expected)) = returned {
+        let mut candidates = HashMap::new();
+        unify(declared, &expected, generics, &mut candidates, function);
+        for (name, ty) in candidates {
+            bindings.entry(name).or_insert(ty);
+        }
+    }
+    generics
+        .iter()
+        .map(|name| {
+            bindings
+                .remove(name)
+                .unwrap_or_else(|| panic!("cannot infer type parameter {} of {}", name, function))
+        })
+        .collect()
+}
+
+/// the type parameters of `function` bound by the values passed as `parameters`, as
+/// inferred by `infer_type_arguments`
+fn bind_type_parameters(
+    function: &str,
+    generics: &[Identifier],
+    parameters: &[&Type],
+    values: &[BasicValue],
+    environment: &Environment,
+) -> HashMap<Identifier, Type> {
+    let mut bindings = HashMap::new();
+    for constants in [false, true] {
+        for (parameter, value) in parameters.iter().zip(values) {
+            if value.is_constant() != constants {
+                continue;
+            }
+            if let Some(ty) = value.get_type() {
+                let actual = match ty.int_width() {
+                    Some(_) if constants => Type::named("i32".to_string()),
+                    _ => source_type(ty, environment),
+                };
+                if constants {
+                    let mut candidates = HashMap::new();
+                    unify(parameter, &actual, generics, &mut candidates, function);
+                    for (name, ty) in candidates {
+                        bindings.entry(name).or_insert(ty);
+                    }
+                } else {
+                    unify(parameter, &actual, generics, &mut bindings, function);
+                }
+            }
+        }
+    }
+    bindings
+}
+
+/// whether `ty` refers to one of the type parameters `generics`
+fn mentions_type_parameter(ty: &Type, generics: &[Identifier]) -> bool {
+    match ty {
+        Type::Named(name) => generics.contains(name),
+        Type::Generic(_, elements) | Type::Tuple(elements) => {
+            elements.iter().any(|it| mentions_type_parameter(it, generics))
+        }
+        Type::Reference { inner, .. }
+        | Type::Pointer(inner)
+        | Type::Boxed(inner)
+        | Type::Array(inner, _)
+        | Type::Simd(inner, _) => mentions_type_parameter(inner, generics),
+        Type::Function(parameters, return_type) => {
+            parameters.iter().any(|it| mentions_type_parameter(it, generics))
+                || mentions_type_parameter(return_type, generics)
+        }
+        Type::Dyn(_, arguments) => arguments.iter().any(|it| mentions_type_parameter(it, generics)),
+    }
+}
+
+/// values of the arguments of a call of `name` after the `leading` ones, a closure or a
+/// function passed as value takes the type of its parameter, for a generic function once the
+/// other arguments have bound the type parameters in it
+fn call_arguments(
+    name: &str,
+    leading: Vec<BasicValue>,
+    args: Vec<Expr>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> Vec<BasicValue> {
+    let environment = scope.environment();
+    let offset = leading.len();
+    let param_types = environment
+        .get_function(name)
+        .map(|function| function.param_types())
+        .filter(|param_types| param_types.len() == offset + args.len());
+    if let Some(param_types) = param_types {
+        let declared = environment
+            .get_parameters(name)
+            .filter(|declared| declared.len() == param_types.len());
+        let values = args
             .into_iter()
-            .map(|(name, ty)| to_basic_type(&ty, &context))
-            .collect();
-        let function_type = return_type.fn_type(&args, false);
-        let function_value = module.add_function(&self.name, function_type);
-        let block = context.append_basic_block(function_value, "entry");
-        builder.position_at_end(&block);
-        let x = self.stats.expr_codegen(context, builder, module);
-        dbg!(x);
+            .enumerate()
+            .map(|(index, arg)| {
+                let ty = &param_types[offset + index];
+                let value = expr_codegen_as(arg, ty, context, builder, module, scope);
+                if let Some(declared) = &declared {
+                    expect_type(&value, ty, &declared[offset + index], environment);
+                }
+                value
+            })
+            .collect::<Vec<_>>();
+        return leading.into_iter().chain(values).collect();
+    }
+    let func_decl = match environment.get_generic_function(name) {
+        Some(func_decl) if func_decl.args.len() == offset + args.len() => func_decl,
+        // arity errors are reported by the call
+        _ => {
+            let values = args
+                .into_iter()
+                .map(|arg| arg.expr_codegen(context, builder, module, scope));
+            return leading.into_iter().chain(values).collect();
+        }
+    };
+    let mut values: Vec<Option<BasicValue>> = leading.into_iter().map(Some).collect();
+    let mut deferred = vec![];
+    for (index, arg) in args.into_iter().enumerate() {
+        let is_function = match &arg {
+            Expr::Closure { .. } => true,
+            Expr::Identifier(name, _) => {
+                scope.get(name).is_none() && environment.get_generic_function(name).is_some()
+            }
+            _ => false,
+        };
+        if is_function {
+            deferred.push((offset + index, arg));
+            values.push(None);
+        } else {
+            values.push(Some(arg.expr_codegen(context, builder, module, scope)));
+        }
+    }
+    for (index, arg) in deferred {
+        let (parameters, evaluated): (Vec<&Type>, Vec<BasicValue>) = func_decl
+            .args
+            .iter()
+            .zip(&values)
+            .filter_map(|((_, ty), value)| value.map(|value| (ty, value)))
+            .unzip();
+        let bindings = bind_type_parameters(name, &func_decl.generics, &parameters, &evaluated, environment);
+        let parameter = func_decl.args[index].1.substitute(&bindings);
+        let known = |ty: &Type| {
+            (!mentions_type_parameter(ty, &func_decl.generics))
+                .then(|| to_basic_type(&scope.resolve_type(ty), context, environment))
+        };
+        let value = match (arg, &parameter) {
+            (
+                Expr::Closure {
+                    params,
+                    return_type,
+                    body,
+                },
+                Type::Function(parameters, expected_return),
+            ) => {
+                let expected_params = parameters.iter().map(known).collect();
+                let expected_return = known(expected_return);
+                let closure = Closure { params, return_type, body: *body };
+                closure_codegen(
+                    closure,
+                    Some(expected_params),
+                    expected_return,
+                    context,
+                    builder,
+                    module,
+                    scope,
+                )
+            }
+            (Expr::Identifier(function, _), parameter) => {
+                let expected = known(parameter);
+                named_function_value(&function, expected.as_ref(), context, builder, module, environment)
+            }
+            (arg, _) => arg.expr_codegen(context, builder, module, scope),
+        };
+        values[index] = Some(value);
     }
+    values.into_iter().map(Option::unwrap).collect()
 }
 
-impl ModuleCodegen for Statement {
-    fn module_codegen(
+/// the instance of a generic function for the type arguments, generated at its first use
+fn instantiate_function(
+    func_decl: &FunctionDeclare,
+    arguments: Vec<Type>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> FunctionValue {
+    if let Some(function) = environment.get_function_instance(&func_decl.name, &arguments) {
+        return function;
+    }
+    let type_arguments: HashMap<Identifier, Type> = func_decl
+        .generics
+        .iter()
+        .cloned()
+        .zip(arguments.iter().cloned())
+        .collect();
+    for (parameter, trait_name) in func_decl.bounds.iter() {
+        let ty = type_arguments.get(parameter).unwrap_or_else(|| {
+            panic!("bound {}: {} of {} names no type parameter", parameter, trait_name, func_decl.name)
+        });
+        if !implements(ty, trait_name, environment) {
+            panic!(
+                "{} does not implement {}, required by the bound {}: {} of {}",
+                ty, trait_name, parameter, trait_name, func_decl.name
+            );
+        }
+    }
+    let instance = FunctionDeclare {
+        name: format!(
+            "{}<{}>",
+            func_decl.name,
+            arguments.iter().map(|it| it.to_string()).collect::<Vec<_>>().join(", ")
+        ),
+        args: func_decl
+            .args
+            .iter()
+            .map(|(name, ty)| (name.clone(), ty.substitute(&type_arguments)))
+            .collect(),
+        return_type: func_decl.return_type.substitute(&type_arguments),
+        generics: vec![],
+        ..func_decl.clone()
+    };
+    debug!("instantiate {}", &instance.name);
+    let symbol = function_symbol(func_decl, &arguments);
+    let function = declare_function(&instance, &symbol, context, module, environment);
+    // cached before the body is generated, so that recursive calls find the instance
+    environment.add_function_instance(func_decl.name.clone(), arguments, function);
+
+    let position = builder.insert_block();
+    let scope = Scope::new_generic(environment, self_type_argument(func_decl, &type_arguments));
+    define_function_body(instance, function, &scope, context, builder, module);
+    builder.position_at_end(&position);
+    function
+}
+
+/// the function `name` refers to, generic functions are instantiated for the arguments and
+/// the type the result is `expected` to have
+pub(crate) fn function_for_call(
+    name: &str,
+    args: &[BasicValue],
+    expected: Option<&BasicType>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> FunctionValue {
+    if let Some(function) = environment.get_function(name) {
+        return function;
+    }
+    let func_decl = environment
+        .get_generic_function(name)
+        .unwrap_or_else(|| panic!("function {} is undefined", name));
+    let parameters: Vec<&Type> = func_decl.args.iter().map(|(_, ty)| ty).collect();
+    let returned = expected.map(|ty| (&func_decl.return_type, source_type(*ty, environment)));
+    let type_arguments =
+        infer_type_arguments(name, &func_decl.generics, &parameters, args, returned, environment);
+    instantiate_function(&func_decl, type_arguments, context, builder, module, environment)
+}
+
+/// the name of a type as written before `::` or in a struct literal, `Self` is replaced by
+/// the type of the `impl` block
+fn resolve_type_name(name: &str, scope: &Scope) -> Identifier {
+    match scope.resolve_type(&Type::named(name.to_string())) {
+        Type::Named(name) | Type::Generic(name, _) => name,
+        ty => panic!("{} is not a named type", ty),
+    }
+}
+
+/// `callee(args)`, the type arguments of a generic function which its arguments do not bind
+/// follow from the type the result is `expected` to have, as in `let v: Vec<i32> = Vec::new()`
+fn call_codegen(
+    callee: Expr,
+    args: Vec<Expr>,
+    expected: Option<&BasicType>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    let name = match callee {
+        Expr::Identifier(name, location)
+            if name == PANIC_BUILTIN
+                && scope.get(&name).is_none()
+                && scope.environment().get_function(&name).is_none() =>
+        {
+            return panic_codegen(args, location, context, builder, module, scope);
+        }
+        Expr::Identifier(name, _) if scope.get(&name).is_none() => name,
+        Expr::Path(prefix, name) if prefix == INTRINSICS => {
+            return build_intrinsic(&name, args, expected, context, builder, module, scope);
+        }
+        Expr::Field(receiver, method) => {
+            return method_call(*receiver, method, args, context, builder, module, scope);
+        }
+        Expr::Path(type_name, function)
+            if scope
+                .environment()
+                .get_enum(&type_name)
+                .is_none_or(|enum_decl| enum_decl.variant(&function).is_none()) =>
+        {
+            // associated function `Type::function`
+            format!("{}::{}", resolve_type_name(&type_name, scope), function)
+        }
+        Expr::Path(enum_name, variant) => {
+            trace!("build variant {}::{}", &enum_name, &variant);
+            let environment = scope.environment();
+            let enum_decl = environment
+                .get_enum(&enum_name)
+                .unwrap_or_else(|| panic!("enum {} is undefined", enum_name));
+            if let Some((_, EnumVariant { fields: VariantFields::Struct(_), .. })) =
+                enum_decl.variant(&variant)
+            {
+                panic!("variant {}::{} has named fields", enum_name, variant);
+            }
+            let values: Vec<BasicValue> = args
+                .into_iter()
+                .map(|arg| arg.expr_codegen(context, builder, module, scope))
+                .collect();
+            let enum_decl = variant_enum(&enum_name, &variant, &values, expected, context, environment);
+            return build_enum_value(context, builder, environment, &enum_decl, &variant, values);
+        }
+        callee => {
+            trace!("build call of function value");
+            let callee = callee.expr_codegen(context, builder, module, scope);
+            return function_value_call(callee, args, context, builder, module, scope);
+        }
+    };
+    trace!("build call {}", &name);
+    if let Some(newtype) = scope.environment().get_newtype(&name) {
+        return newtype_codegen(&newtype, args, context, builder, module, scope);
+    }
+    let args = call_arguments(&name, vec![], args, context, builder, module, scope);
+    if scope.environment().get_function(&name).is_none()
+        && simd_reduction(&name).is_some()
+        && args.len() == 1
+    {
+        return build_simd_reduction(builder, module, &name, &args[0]);
+    }
+    let function = function_for_call(&name, &args, expected, context, builder, module, scope.environment());
+    build_function_call(&name, &function, args, context, builder, module, scope.environment())
+}
+
+/// `receiver.method(args)` as a call of `Type::method` with the receiver as first argument,
+/// which is borrowed or loaded as the receiver of the method is declared
+fn method_call(
+    receiver: Expr,
+    method: Identifier,
+    args: Vec<Expr>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    trace!("build method call {}", &method);
+    let environment = scope.environment();
+    // methods are called through references as well
+    let ptr = auto_deref(builder, receiver.place_codegen(context, builder, module, scope));
+    if let Some((trait_name, _)) = trait_object_trait(&ptr.pointee_type(), environment) {
+        return dyn_method_call((ptr, &trait_name), method, args, context, builder, module, scope);
+    }
+    let receiver_type = source_type(ptr.pointee_type(), environment);
+    let func_decl = match type_name(&receiver_type).and_then(|type_name| environment.get_method(type_name, &method)) {
+        Some(func_decl) => func_decl,
+        None => {
+            return builtin_method(&ptr, &method, args, context, builder, module, scope)
+                .unwrap_or_else(|| panic!("no method {} on type {}", method, receiver_type))
+        }
+    };
+    if !func_decl.is_method() {
+        panic!(
+            "{} is an associated function without self, call it as {}(..)",
+            func_decl.name, func_decl.name
+        );
+    }
+    let receiver = match &func_decl.args[0].1 {
+        Type::Reference { .. } => ptr.into_basic_value(),
+        _ => builder.build_load(&ptr, "self"),
+    };
+    let args = call_arguments(&func_decl.name, vec![receiver], args, context, builder, module, scope);
+    let function = function_for_call(&func_decl.name, &args, None, context, builder, module, environment);
+    build_function_call(&func_decl.name, &function, args, context, builder, module, environment)
+}
+
+/// `object.method(args)` on a trait object, an indirect call through the slot of the method
+/// in the vtable with the data pointer as receiver
+fn dyn_method_call(
+    (object, trait_name): (PointerValue, &str),
+    method: Identifier,
+    args: Vec<Expr>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    let environment = scope.environment();
+    let trait_decl = environment.get_trait(trait_name).unwrap();
+    let slot = trait_decl
+        .functions
+        .iter()
+        .position(|function| function.name == method)
+        .unwrap_or_else(|| panic!("no method {} on type dyn {}", method, trait_name));
+    let data = builder.build_load(&struct_field_ptr(context, builder, &object, 0), "data");
+    let vtable = builder
+        .build_load(&struct_field_ptr(context, builder, &object, 1), "vtable")
+        .into_pointer_value();
+    let function = builder.build_load(&struct_field_ptr(context, builder, &vtable, slot as u32), &method);
+    let function = FunctionValue::new(function.as_llvm_ref().unwrap());
+    let args: Vec<BasicValue> = std::iter::once(data)
+        .chain(
+            args.into_iter()
+                .map(|arg| arg.expr_codegen(context, builder, module, scope)),
+        )
+        .collect();
+    let name = format!("dyn {}::{}", trait_name, method);
+    build_function_call(&name, &function, args, context, builder, module, environment)
+}
+
+/// call `function` with the argument values coerced to its parameter types
+pub(crate) fn build_function_call(
+    name: &str,
+    function: &FunctionValue,
+    args: Vec<BasicValue>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> BasicValue {
+    let param_types = function.param_types();
+    if param_types.len() != args.len() {
+        panic!(
+            "function {} takes {} arguments but {} were supplied",
+            name,
+            param_types.len(),
+            args.len()
+        );
+    }
+    let args: Vec<BasicValue> = args
+        .into_iter()
+        .zip(param_types)
+        .map(|(value, param_type)| {
+            coerce_unsized(value, &param_type, context, builder, module, environment)
+        })
+        .collect();
+    let value = builder.build_call(function, &args);
+    // the returned boxes are only held in a register until they are stored
+    gc::build_root(value, context, builder, module, environment);
+    value
+}
+
+/// type of a value as written in the source, recovered from its llvm type
+pub(crate) fn source_type(ty: BasicType, environment: &Environment) -> Type {
+    if let Some(width) = ty.int_width() {
+        return Type::named(format!("i{}", width));
+    }
+    if let Some(width) = ty.float_width() {
+        return Type::named(format!("f{}", width));
+    }
+    if let Some(pointer_type) = ty.into_pointer_type() {
+        let pointee = source_type(pointer_type.element_type(), environment);
+        return if gc::is_box_type(&ty) {
+            Type::boxed(pointee)
+        } else {
+            Type::reference(pointee, false)
+        };
+    }
+    if let Some(array_type) = ty.into_array_type() {
+        let element = source_type(array_type.element_type(), environment);
+        return Type::array(element, array_type.len() as u64);
+    }
+    if let Some(vector_type) = ty.into_vector_type() {
+        let element = source_type(vector_type.element_type(), environment);
+        return Type::simd(element, vector_type.len() as u64);
+    }
+    if let Some((trait_name, arguments)) = trait_object_trait(&ty, environment) {
+        return Type::reference(Type::Dyn(trait_name, arguments), false);
+    }
+    if let Some((params, return_type)) = function_value_signature(&ty) {
+        return Type::function(
+            params.into_iter().map(|it| source_type(it, environment)).collect(),
+            source_type(return_type, environment),
+        );
+    }
+    match ty.into_struct_type() {
+        Some(struct_type) => match struct_type.name() {
+            Some(name) => match environment.get_struct_instance(&name) {
+                Some((name, arguments)) => Type::generic(name, arguments),
+                None => Type::named(name),
+            },
+            None => Type::tuple(
+                struct_type
+                    .field_types()
+                    .into_iter()
+                    .map(|it| source_type(it, environment))
+                    .collect(),
+            ),
+        },
+        None => Type::void(),
+    }
+}
+
+impl StatementCodegen for Statement {
+    fn statement_codegen(
         self,
         context: &TyphoonContext,
         builder: &TyphoonBuilder,
         module: &TyphoonModule,
+        scope: &Scope,
     ) {
         match self {
-            Statement::Declare(_, _, _) => {}
-            Statement::Assignment(_, _) => {}
-            Statement::Expr(_) => {}
-            Statement::Return(expr) => {
+            Statement::Declare(name, ty, expr) => {
+                trace!("build declare {}", &name);
+                let llvm_type = to_basic_type(&scope.resolve_type(&ty), context, scope.environment());
+                let value = expr_codegen_as(*expr, &llvm_type, context, builder, module, scope);
+                expect_type(&value, &llvm_type, &ty, scope.environment());
+                let ptr = gc::build_slot(&llvm_type, &name, context, builder, module, scope.environment());
+                builder.build_store(&ptr, &value);
+                scope.declare(name, ptr);
+            }
+            Statement::Assignment(place, expr) => {
+                trace!("build assignment");
+                if let Expr::Index {
+                    expr: vector,
+                    index,
+                    location,
+                } = *place
+                {
+                    let indexed =
+                        index_codegen((*vector, *index), location, true, context, builder, module, scope);
+                    let value = expr.expr_codegen(context, builder, module, scope);
+                    match indexed {
+                        Indexed::Aggregate(ptr, index) if ptr.pointee_type().into_vector_type().is_some() => {
+                            // a lane is replaced with `insertelement` on the whole vector
+                            let vector_type = ptr.pointee_type().into_vector_type().unwrap();
+                            let value = coerce(builder, value, &vector_type.element_type());
+                            let vector = builder.build_load(&ptr, "vector");
+                            let vector = builder.build_insert_element(&vector, &value, &index, "vector");
+                            builder.build_store(&ptr, &vector);
+                        }
+                        Indexed::Aggregate(ptr, index) => {
+                            let zero = context.i64_type().const_int(0, false).into_basic_value();
+                            let ptr = builder.build_in_bounds_gep(&ptr, &[zero, index], "element_ptr");
+                            builder.build_store(&ptr, &coerce(builder, value, &ptr.pointee_type()));
+                        }
+                        Indexed::Element(ptr) => {
+                            builder.build_store(&ptr, &coerce(builder, value, &ptr.pointee_type()));
+                        }
+                    }
+                    return;
+                }
+                if let Expr::Identifier(name, _) = &*place {
+                    let environment = scope.environment();
+                    if scope.get(name).is_none() {
+                        if environment.get_constant(name).is_some() {
+                            panic!("cannot assign to constant {}", name);
+                        }
+                        if let Some((_, false)) = environment.get_static(name) {
+                            panic!("cannot assign to immutable static {}", name);
+                        }
+                    }
+                }
+                let ptr = place.place_codegen(context, builder, module, scope);
+                let value = expr_codegen_as(*expr, &ptr.pointee_type(), context, builder, module, scope);
+                builder.build_store(&ptr, &value);
+            }
+            Statement::Destructure(pattern, ty, expr) => {
+                trace!("build destructure");
+                let value = expr.expr_codegen(context, builder, module, scope);
+                let value = match ty {
+                    Some(ty) => {
+                        let ty = to_basic_type(&scope.resolve_type(&ty), context, scope.environment());
+                        coerce(builder, value, &ty)
+                    }
+                    None => value,
+                };
+                bind_pattern(pattern, value, context, builder, module, scope);
+            }
+            Statement::Expr(expr) => {
+                expr.expr_codegen(context, builder, module, scope);
+            }
+            Statement::Return(expr, _) => {
                 trace!("build return");
-                let value = expr.expr_codegen(&context, &builder, &module);
-                dbg!(&value);
-                builder.build_return(value);
+                let return_type = builder.current_function().return_type();
+                if return_type.is_void() {
+                    expr.expr_codegen(context, builder, module, scope);
+                    builder.build_return(None);
+                } else {
+                    let value = expr_codegen_as(*expr, &return_type, context, builder, module, scope);
+                    builder.build_return(value);
+                }
+                // statements after `return` are unreachable, but they still need a block
+                let block = context.append_basic_block(builder.current_function(), "after_return");
+                builder.position_at_end(&block);
             }
         }
     }
 }
 
+/// the llvm reduction intrinsic behind a built-in `reduce_*` function
+pub(crate) fn simd_reduction(name: &str) -> Option<&'static str> {
+    match name {
+        "reduce_add" => Some("add"),
+        "reduce_mul" => Some("mul"),
+        "reduce_and" => Some("and"),
+        "reduce_or" => Some("or"),
+        "reduce_xor" => Some("xor"),
+        "reduce_min" => Some("smin"),
+        "reduce_max" => Some("smax"),
+        _ => None,
+    }
+}
+
+/// horizontal reduction of every lane of a simd vector into a scalar, the lanes of floats
+/// are added and multiplied in order starting from the identity of the operation
+fn build_simd_reduction(
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    name: &str,
+    vector: &BasicValue,
+) -> BasicValue {
+    let vector_type = vector
+        .get_type()
+        .and_then(|it| it.into_vector_type())
+        .unwrap_or_else(|| panic!("{} expects a simd vector", name));
+    let element_type = vector_type.element_type();
+    let operation = simd_reduction(name).unwrap();
+    let (intrinsic, args) = match element_type.float_width() {
+        None => {
+            let width = element_type.int_width().unwrap();
+            let intrinsic = format!("llvm.vector.reduce.{}.v{}i{}", operation, vector_type.len(), width);
+            (intrinsic, vec![*vector])
+        }
+        Some(width) => {
+            let (operation, args) = match operation {
+                "add" => ("fadd", vec![element_type.const_float(-0.0), *vector]),
+                "mul" => ("fmul", vec![element_type.const_float(1.0), *vector]),
+                "smin" => ("fmin", vec![*vector]),
+                "smax" => ("fmax", vec![*vector]),
+                _ => panic!("{} expects a simd vector of integers", name),
+            };
+            let intrinsic = format!("llvm.vector.reduce.{}.v{}f{}", operation, vector_type.len(), width);
+            (intrinsic, args)
+        }
+    };
+    let params: Vec<BasicType> = args.iter().map(|it| it.get_type().unwrap()).collect();
+    let function = module.get_or_add_function(&intrinsic, element_type.fn_type(&params, false));
+    builder.build_call(&function, &args)
+}
+
+/// values of the fields of a struct literal in the order of `field_names`
+fn named_field_values(
+    name: &str,
+    field_names: &[Identifier],
+    fields: Vec<(Identifier, Box<Expr>)>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> Vec<BasicValue> {
+    if let Some((field, _)) = fields.iter().find(|(field, _)| !field_names.contains(field)) {
+        panic!("{} has no field {}", name, field);
+    }
+    // fields are evaluated in the order they are written
+    let mut values: Vec<(Identifier, BasicValue)> = fields
+        .into_iter()
+        .map(|(field, value)| (field, value.expr_codegen(context, builder, module, scope)))
+        .collect();
+    field_names
+        .iter()
+        .map(|field_name| {
+            let position = values
+                .iter()
+                .position(|(field, _)| field == field_name)
+                .unwrap_or_else(|| panic!("field {} of {} is missing", field_name, name));
+            values.remove(position).1
+        })
+        .collect()
+}
+
+/// declare a variable for every identifier in the pattern, holding the matching part of
+/// the value
+pub(crate) fn bind_pattern(
+    pattern: Pattern,
+    value: BasicValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) {
+    match pattern {
+        Pattern::Wildcard => {}
+        Pattern::Identifier(name) => {
+            let ty = value.get_type().expect("cannot bind void to a variable");
+            let ptr = gc::build_slot(&ty, &name, context, builder, module, scope.environment());
+            builder.build_store(&ptr, &value);
+            scope.declare(name, ptr);
+        }
+        Pattern::Tuple(patterns) => {
+            let fields = value
+                .get_type()
+                .and_then(|it| it.into_struct_type())
+                .map(|it| it.field_types().len())
+                .expect("only tuples can be destructured");
+            if fields != patterns.len() {
+                panic!(
+                    "tuple pattern has {} elements but the value has {}",
+                    patterns.len(),
+                    fields
+                );
+            }
+            for (index, pattern) in patterns.into_iter().enumerate() {
+                let element = builder.build_extract_value(&value, index as u32, "element");
+                bind_pattern(pattern, element, context, builder, module, scope);
+            }
+        }
+        Pattern::Struct(name, fields) => {
+            let struct_decl = scope
+                .environment()
+                .get_struct(&name)
+                .unwrap_or_else(|| panic!("struct {} is undefined", name));
+            for (field, pattern) in fields {
+                let index = struct_decl
+                    .field_index(&field)
+                    .unwrap_or_else(|| panic!("struct {} has no field {}", name, field));
+                let element = builder.build_extract_value(&value, index, &field);
+                bind_pattern(pattern, element, context, builder, module, scope);
+            }
+        }
+        Pattern::Integer(_) | Pattern::Range(..) | Pattern::Variant { .. } => {
+            panic!("refutable pattern in let, use match instead")
+        }
+    }
+}
+
+fn opcode_codegen(opcode: Opcode) -> LLVMOpcode {
+    match opcode {
+        Opcode::Add => LLVMOpcode::LLVMAdd,
+        Opcode::Sub => LLVMOpcode::LLVMSub,
+        Opcode::Mul => LLVMOpcode::LLVMMul,
+        Opcode::Div => LLVMOpcode::LLVMSDiv,
+        Opcode::Mod => LLVMOpcode::LLVMSRem,
+        Opcode::Or => LLVMOpcode::LLVMOr,
+        Opcode::And => LLVMOpcode::LLVMAnd,
+        Opcode::Xor => LLVMOpcode::LLVMXor,
+        Opcode::LShift => LLVMOpcode::LLVMShl,
+        Opcode::RShift => LLVMOpcode::LLVMAShr,
+        Opcode::Pow => unimplemented!("pow is not supported yet"),
+        _ => unreachable!("{} is a comparison", opcode),
+    }
+}
+
+/// `lhs op rhs` of two integers or two floats, 1 when the comparison holds and 0 otherwise,
+/// integers compare signed and any comparison with NaN other than `!=` is 0
+fn build_comparison(
+    opcode: Opcode,
+    lhs: &BasicValue,
+    rhs: &BasicValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+) -> BasicValue {
+    let holds = if lhs.get_type().unwrap().float_width().is_some() {
+        let predicate = match opcode {
+            Opcode::Eq => LLVMRealPredicate::LLVMRealOEQ,
+            Opcode::Ne => LLVMRealPredicate::LLVMRealUNE,
+            Opcode::Lt => LLVMRealPredicate::LLVMRealOLT,
+            Opcode::Le => LLVMRealPredicate::LLVMRealOLE,
+            Opcode::Gt => LLVMRealPredicate::LLVMRealOGT,
+            _ => LLVMRealPredicate::LLVMRealOGE,
+        };
+        builder.build_float_compare(predicate, lhs, rhs, "holds")
+    } else {
+        let predicate = match opcode {
+            Opcode::Eq => LLVMIntPredicate::LLVMIntEQ,
+            Opcode::Ne => LLVMIntPredicate::LLVMIntNE,
+            Opcode::Lt => LLVMIntPredicate::LLVMIntSLT,
+            Opcode::Le => LLVMIntPredicate::LLVMIntSLE,
+            Opcode::Gt => LLVMIntPredicate::LLVMIntSGT,
+            _ => LLVMIntPredicate::LLVMIntSGE,
+        };
+        builder.build_int_compare(predicate, lhs, rhs, "holds")
+    };
+    builder.build_int_cast(&holds, &context.i64_type().as_basic_type(), false)
+}
+
+/// the arithmetic operators of floats, `builtin_implements` rules out the others
+fn float_opcode_codegen(opcode: Opcode) -> LLVMOpcode {
+    match opcode {
+        Opcode::Add => LLVMOpcode::LLVMFAdd,
+        Opcode::Sub => LLVMOpcode::LLVMFSub,
+        Opcode::Mul => LLVMOpcode::LLVMFMul,
+        Opcode::Div => LLVMOpcode::LLVMFDiv,
+        Opcode::Mod => LLVMOpcode::LLVMFRem,
+        _ => unreachable!("{} of floats", opcode),
+    }
+}
+
+/// `lhs op rhs` for `+`, `-` and `*` of integers through the `with.overflow` intrinsics of
+/// llvm, the result wrapped around together with whether it overflowed, `None` for other
+/// operations and types
+pub(crate) fn build_with_overflow(
+    opcode: Opcode,
+    lhs: &BasicValue,
+    rhs: &BasicValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+) -> Option<(BasicValue, BasicValue)> {
+    let operation = match opcode {
+        Opcode::Add => "sadd",
+        Opcode::Sub => "ssub",
+        Opcode::Mul => "smul",
+        _ => return None,
+    };
+    let ty = lhs.get_type()?;
+    let width = ty.int_width()?;
+    let bool_type = context.int_type(1).as_basic_type();
+    let result_type = context.struct_type(&[ty, bool_type], false).as_basic_type();
+    let intrinsic = module.get_or_add_function(
+        &format!("llvm.{}.with.overflow.i{}", operation, width),
+        result_type.fn_type(&[ty, ty], false),
+    );
+    let result = builder.build_call(&intrinsic, &[*lhs, *rhs]);
+    Some((
+        builder.build_extract_value(&result, 0, "value"),
+        builder.build_extract_value(&result, 1, "overflows"),
+    ))
+}
+
+/// the methods of integers taking another integer of their type, `wrapping_op` wraps around
+/// on overflow, `checked_op` returns an `Option` which is `None` on overflow and
+/// `saturating_op` stops at the bounds of the type, for `op` one of `add`, `sub` and `mul`
+pub(crate) fn integer_method(
+    lhs: &BasicValue,
+    method: &str,
+    rhs: Expr,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> Option<BasicValue> {
+    let (mode, operation) = method.split_once('_')?;
+    let opcode = match operation {
+        "add" => Opcode::Add,
+        "sub" => Opcode::Sub,
+        "mul" => Opcode::Mul,
+        _ => return None,
+    };
+    if !matches!(mode, "wrapping" | "checked" | "saturating") {
+        return None;
+    }
+    let ty = lhs.get_type()?;
+    let width = ty.int_width()?;
+    let rhs = coerce(builder, rhs.expr_codegen(context, builder, module, scope), &ty);
+    if rhs.get_type().is_none_or(|it| it.as_llvm_type_ref() != ty.as_llvm_type_ref()) {
+        panic!("the argument of {} is not an integer", method);
+    }
+    match (mode, opcode) {
+        ("wrapping", _) => Some(builder.build_binop(opcode_codegen(opcode), lhs, &rhs, method)),
+        ("saturating", Opcode::Add | Opcode::Sub) => {
+            let operation = if opcode == Opcode::Add { "sadd" } else { "ssub" };
+            let intrinsic = module.get_or_add_function(
+                &format!("llvm.{}.sat.i{}", operation, width),
+                ty.fn_type(&[ty, ty], false),
+            );
+            Some(builder.build_call(&intrinsic, &[*lhs, rhs]))
+        }
+        ("saturating", _) => {
+            // a product overflows towards the minimum when the signs of its factors differ
+            let (value, overflows) = build_with_overflow(opcode, lhs, &rhs, context, builder, module)?;
+            let signs = builder.build_binop(LLVMOpcode::LLVMXor, lhs, &rhs, "signs");
+            let negative =
+                builder.build_int_compare(LLVMIntPredicate::LLVMIntSLT, &signs, &ty.const_zero(), "negative");
+            let min = ty.const_int(1 << (width - 1), false);
+            let max = ty.const_int((1 << (width - 1)) - 1, false);
+            let bound = builder.build_select(&negative, &min, &max, "bound");
+            Some(builder.build_select(&overflows, &bound, &value, method))
+        }
+        _ => {
+            let (value, overflows) = build_with_overflow(opcode, lhs, &rhs, context, builder, module)?;
+            let environment = scope.environment();
+            let enum_decl = variant_enum(OPTION, "Some", &[value], None, context, environment);
+            let some = build_enum_value(context, builder, environment, &enum_decl, "Some", vec![value]);
+            let none = build_enum_value(context, builder, environment, &enum_decl, "None", vec![]);
+            Some(builder.build_select(&overflows, &none, &some, method))
+        }
+    }
+}
+
+/// the pointer `*expr` reads or writes through, `expr` is a reference, a raw pointer or a box
+fn deref_codegen(
+    expr: Expr,
+    location: Location,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> PointerValue {
+    let value = expr.expr_codegen(context, builder, module, scope);
+    match value.get_type() {
+        Some(ty) if ty.into_pointer_type().is_some() => value.into_pointer_value(),
+        ty => {
+            let environment = scope.environment();
+            let ty = ty.map_or_else(|| "()".to_string(), |ty| source_type(ty, environment).to_string());
+            panic!("cannot dereference `{}` at {}:{}", ty, &environment.source_name, location)
+        }
+    }
+}
+
+impl PlaceCodegen for Expr {
+    fn place_codegen(
+        self,
+        context: &TyphoonContext,
+        builder: &TyphoonBuilder,
+        module: &TyphoonModule,
+        scope: &Scope,
+    ) -> PointerValue {
+        match self {
+            Expr::Identifier(name, location) => match scope.get(&name) {
+                Some(ptr) => ptr,
+                None if scope.environment().get_static(&name).is_some() => static_place(&name, location, scope),
+                // a constant has no address, `&MAX` points at a copy
+                None if scope.environment().get_constant(&name).is_some() => {
+                    let value = Expr::Identifier(name, location).expr_codegen(context, builder, module, scope);
+                    temporary_place(value, context, builder, module, scope)
+                }
+                None => panic!("variable {} is undefined", name),
+            },
+            Expr::Deref(expr, location) => deref_codegen(*expr, location, context, builder, module, scope),
+            Expr::Group(expr) => expr.place_codegen(context, builder, module, scope),
+            Expr::Field(expr, field) => {
+                trace!("build field {}", &field);
+                // fields are accessed through references as well
+                let ptr = auto_deref(builder, expr.place_codegen(context, builder, module, scope));
+                let struct_decl = struct_declaration(&ptr.pointee_type(), scope.environment())
+                    .unwrap_or_else(|| panic!("only structs have field {}", field));
+                let index = struct_decl
+                    .field_index(&field)
+                    .unwrap_or_else(|| panic!("struct {} has no field {}", struct_decl.name, field));
+                struct_field_ptr(context, builder, &ptr, index)
+            }
+            Expr::TupleField(expr, position) => {
+                trace!("build tuple field {}", position);
+                // fields are accessed through references as well
+                let ptr = auto_deref(builder, expr.place_codegen(context, builder, module, scope));
+                let fields = ptr
+                    .pointee_type()
+                    .into_struct_type()
+                    .map(|it| it.field_types().len())
+                    .expect("only tuples have positional fields");
+                if position as usize >= fields {
+                    panic!("tuple has {} fields but field {} is accessed", fields, position);
+                }
+                struct_field_ptr(context, builder, &ptr, position)
+            }
+            Expr::Index {
+                expr,
+                index,
+                location,
+            } => match index_codegen((*expr, *index), location, true, context, builder, module, scope) {
+                Indexed::Aggregate(ptr, index) => {
+                    let zero = context.i64_type().const_int(0, false).into_basic_value();
+                    builder.build_in_bounds_gep(&ptr, &[zero, index], "element_ptr")
+                }
+                Indexed::Element(ptr) => ptr,
+            },
+            other => {
+                let value = other.expr_codegen(context, builder, module, scope);
+                temporary_place(value, context, builder, module, scope)
+            }
+        }
+    }
+}
+
+/// temporaries are spilled to the stack, so that `&1` is still a pointer
+pub(crate) fn temporary_place(
+    value: BasicValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> PointerValue {
+    let ty = value.get_type().expect("cannot take the address of void");
+    let ptr = gc::build_slot(&ty, "tmp", context, builder, module, scope.environment());
+    builder.build_store(&ptr, &value);
+    ptr
+}
+
+/// the global of the static `name`, a mutable static is only accessible in unsafe blocks
+fn static_place(name: &str, location: Location, scope: &Scope) -> PointerValue {
+    let environment = scope.environment();
+    let (global, mutable) = environment.get_static(name).unwrap();
+    if mutable && !scope.is_unsafe() {
+        fail(TyphoonError::UnsafeError(format!(
+            "use of mutable static {} is unsafe and requires an unsafe block at {}:{}",
+            name, &environment.source_name, location
+        )));
+    }
+    global
+}
+
+/// llvm constant of type `ty` holding a value folded by the constant evaluator
+fn const_value_codegen(value: &ConstValue, ty: &BasicType, context: &TyphoonContext) -> BasicValue {
+    match value {
+        ConstValue::Integer(n) => ty.const_int(*n as u64, true),
+        ConstValue::Array(values) => {
+            let element_type = ty.into_array_type().expect("array constant of a non array type").element_type();
+            let values: Vec<BasicValue> = values
+                .iter()
+                .map(|it| const_value_codegen(it, &element_type, context))
+                .collect();
+            context.const_array(&element_type, &values)
+        }
+        ConstValue::Tuple(values) if values.is_empty() => context.void_type().const_value().into_basic_value(),
+        ConstValue::Tuple(values) => {
+            let tuple_type = ty.into_struct_type().expect("tuple constant of a non tuple type");
+            let values: Vec<BasicValue> = values
+                .iter()
+                .zip(tuple_type.field_types())
+                .map(|(it, ty)| const_value_codegen(it, &ty, context))
+                .collect();
+            tuple_type.const_named_struct(&values)
+        }
+    }
+}
+
 impl ExprCodegen for Expr {
     fn expr_codegen(
         self,
         context: &TyphoonContext,
         builder: &TyphoonBuilder,
         module: &TyphoonModule,
+        scope: &Scope,
     ) -> BasicValue {
         match self {
-            Expr::Identifier(_) => {
-                unimplemented!()
+            Expr::Identifier(name, location) => match scope.get(&name) {
+                Some(ptr) => {
+                    trace!("build load {}", &name);
+                    builder.build_load(&ptr, &name)
+                }
+                None => {
+                    let environment = scope.environment();
+                    if let Some((ty, value)) = environment.get_constant(&name) {
+                        const_value_codegen(&value, &to_basic_type(&ty, context, environment), context)
+                    } else if environment.get_static(&name).is_some() {
+                        builder.build_load(&static_place(&name, location, scope), &name)
+                    } else {
+                        named_function_value(&name, None, context, builder, module, environment)
+                    }
+                }
+            },
+            field @ (Expr::Field(..) | Expr::TupleField(..)) => {
+                let ptr = field.place_codegen(context, builder, module, scope);
+                builder.build_load(&ptr, "field")
             }
-            Expr::Field(_, _) => {
-                unimplemented!()
+            Expr::Tuple(items) if items.is_empty() => context.void_type().const_value().into_basic_value(),
+            Expr::Tuple(items) => {
+                trace!("build tuple");
+                let values: Vec<BasicValue> = items
+                    .into_iter()
+                    .map(|item| item.expr_codegen(context, builder, module, scope))
+                    .collect();
+                let fields: Vec<BasicType> = values
+                    .iter()
+                    .map(|it| it.get_type().expect("unit inside of tuple is not supported"))
+                    .collect();
+                let tuple_type = context.struct_type(&fields, false).as_basic_type();
+                values
+                    .iter()
+                    .enumerate()
+                    .fold(tuple_type.get_undef(), |tuple, (index, value)| {
+                        builder.build_insert_value(&tuple, value, index as u32, "tuple")
+                    })
             }
             Expr::Number(n) => {
                 trace!("build number");
@@ -150,47 +2871,339 @@ impl ExprCodegen for Expr {
                     Number::Integer8(inner) => context.i8_type().const_int(inner as u64, true),
                     Number::Integer16(inner) => context.i16_type().const_int(inner as u64, true),
                     Number::Integer32(inner) => context.i32_type().const_int(inner as u64, true),
-                    Number::UnSignInteger8(inner) => {
+                    Number::Integer64(inner) => context.i64_type().const_int(inner as u64, true),
+                    Number::UnSignInteger8(_) => {
                         unimplemented!()
                     }
-                    Number::UnSignInteger16(inner) => {
+                    Number::UnSignInteger16(_) => {
                         unimplemented!()
                     }
-                    Number::UnSignInteger32(inner) => {
+                    Number::UnSignInteger32(_) => {
                         unimplemented!()
                     }
+                    Number::Float(inner) => return context.f64_type().const_float(inner),
                 };
                 number_int_value.into_basic_value()
             }
-            Expr::BinOperation(_, _, _) => {
-                unimplemented!()
+            Expr::BinOperation(opcode, lhs, rhs, location) => {
+                trace!("build binary operation {}", opcode);
+                let lhs = lhs.expr_codegen(context, builder, module, scope);
+                let rhs = rhs.expr_codegen(context, builder, module, scope);
+                let lhs_type = lhs.get_type().expect("void value as operand");
+                let rhs_type = rhs.get_type().expect("void value as operand");
+                let environment = scope.environment();
+                let lhs_source = source_type(lhs_type, environment);
+                let rhs_source = source_type(rhs_type, environment);
+                if opcode.is_comparison() {
+                    let comparable = |ty: &BasicType| ty.int_width().or(ty.float_width()).is_some();
+                    if !comparable(&lhs_type) || !comparable(&rhs_type) {
+                        type_error(format!(
+                            "cannot apply `{}` to {} and {} at {}:{}",
+                            opcode, lhs_source, rhs_source, environment.source_name, location
+                        ));
+                    }
+                } else {
+                    let (trait_name, function) = operator_trait(opcode);
+                    if !builtin_implements(&lhs_source, trait_name) {
+                        // overloaded by `impl Trait for Type`
+                        let name = type_name(&lhs_source)
+                            .filter(|name| environment.has_trait_impl(name, trait_name))
+                            .map(|name| format!("{}::{}", name, function))
+                            .unwrap_or_else(|| {
+                                panic!(
+                                    "cannot apply `{}` to {}, it does not implement {}",
+                                    opcode, lhs_source, trait_name
+                                )
+                            });
+                        let args = vec![lhs, rhs];
+                        let function = function_for_call(&name, &args, None, context, builder, module, environment);
+                        return build_function_call(&name, &function, args, context, builder, module, environment);
+                    }
+                    if !builtin_implements(&rhs_source, trait_name) {
+                        panic!("cannot apply `{}` to {} and {}", opcode, lhs_source, rhs_source);
+                    }
+                }
+                // an integer literal on the left of a float is a float as well
+                let (lhs, rhs) = if (lhs_type.into_vector_type().is_none()
+                    && rhs_type.into_vector_type().is_some())
+                    || (lhs.is_constant() && lhs_type.int_width().is_some() && rhs_type.float_width().is_some())
+                {
+                    (coerce(builder, lhs, &rhs_type), rhs)
+                } else {
+                    (lhs, coerce(builder, rhs, &lhs_type))
+                };
+                let operand_type = lhs.get_type().unwrap();
+                if operand_type.as_llvm_type_ref() != rhs.get_type().unwrap().as_llvm_type_ref() {
+                    panic!("cannot apply `{}` to {} and {}", opcode, lhs_source, rhs_source);
+                }
+                if opcode.is_comparison() {
+                    return build_comparison(opcode, &lhs, &rhs, context, builder);
+                }
+                if matches!(opcode, Opcode::Div | Opcode::Mod) {
+                    build_division_check(opcode, (&lhs, &rhs), location, context, builder, module, environment);
+                }
+                if environment.options.overflow == Overflow::Checked {
+                    let checked = build_checked_arithmetic(
+                        opcode, (&lhs, &rhs), location, context, builder, module, environment,
+                    );
+                    if let Some(value) = checked {
+                        return value;
+                    }
+                }
+                let operation = if lane_type(&operand_type).float_width().is_some() {
+                    float_opcode_codegen(opcode)
+                } else {
+                    opcode_codegen(opcode)
+                };
+                builder.build_binop(operation, &lhs, &rhs, "binop_tmp")
             }
             Expr::If { .. } => {
                 unimplemented!()
             }
-            Expr::Call(_, _) => {
-                unimplemented!()
+            Expr::Call(callee, args) => {
+                let args = args.into_iter().map(|arg| *arg).collect();
+                call_codegen(*callee, args, None, context, builder, module, scope)
             }
             Expr::Block(stats, ret) => {
+                let scope = Scope::new_with_upper(scope);
                 for statement in stats {
-                    statement.module_codegen(&context, &builder, &module);
+                    statement.statement_codegen(context, builder, module, &scope);
                 }
 
                 if let Some(ret_expr) = ret {
-                    ret_expr.expr_codegen(&context, &builder, &module)
+                    ret_expr.expr_codegen(context, builder, module, &scope)
                 } else {
                     let value = context.void_type().const_value();
                     value.into_basic_value()
                 }
             }
-            Expr::Group(_) => {
-                unimplemented!()
-            }
+            Expr::Group(expr) => expr.expr_codegen(context, builder, module, scope),
             Expr::Negative(_) => {
                 unimplemented!()
             }
-            Expr::String(_) => {
-                unimplemented!()
+            Expr::String(value) => build_string_literal(&value, context, builder, module, scope),
+            Expr::Reference { expr, .. } => {
+                trace!("build reference");
+                let ptr = expr.place_codegen(context, builder, module, scope);
+                // a reference into a box does not keep the box alive
+                if gc::is_box_type(&ptr.into_basic_value().get_type().unwrap()) {
+                    let ty = ptr.pointee_type().ptr_type(AddressSpace::Generic).as_basic_type();
+                    builder.build_pointer_cast(&ptr, &ty, "borrow").into_basic_value()
+                } else {
+                    ptr.into_basic_value()
+                }
+            }
+            Expr::Box(expr) => {
+                trace!("build box");
+                let value = expr.expr_codegen(context, builder, module, scope);
+                gc::build_box(value, context, builder, module, scope.environment())
+            }
+            Expr::Deref(expr, location) => {
+                trace!("build dereference");
+                let ptr = deref_codegen(*expr, location, context, builder, module, scope);
+                builder.build_load(&ptr, "deref")
+            }
+            Expr::Cast(expr, ty) => {
+                trace!("build cast");
+                let value = expr.expr_codegen(context, builder, module, scope);
+                let ty = scope.resolve_type(&ty);
+                let target = to_basic_type(&ty, context, scope.environment());
+                let value_type = value.get_type().expect("cannot cast ()");
+                if let Some(value) = build_numeric_cast(builder, &value, &target) {
+                    value
+                } else if value_type.into_pointer_type().is_some() && target.into_pointer_type().is_some() {
+                    builder
+                        .build_pointer_cast(&value.into_pointer_value(), &target, "cast")
+                        .into_basic_value()
+                } else {
+                    panic!(
+                        "cannot cast {} as {}",
+                        source_type(value_type, scope.environment()),
+                        ty
+                    );
+                }
+            }
+            Expr::Unsafe(body) => body.expr_codegen(context, builder, module, &Scope::new_unsafe(scope)),
+            Expr::Array(items) => {
+                trace!("build array");
+                let values: Vec<BasicValue> = items
+                    .into_iter()
+                    .map(|item| item.expr_codegen(context, builder, module, scope))
+                    .collect();
+                let element_type = values
+                    .first()
+                    .and_then(|it| it.get_type())
+                    .expect("cannot infer the element type of an empty array");
+                let array_type = element_type.array_type(values.len() as u32).as_basic_type();
+                values
+                    .iter()
+                    .enumerate()
+                    .fold(array_type.get_undef(), |aggregate, (index, value)| {
+                        let value = coerce(builder, *value, &element_type);
+                        builder.build_insert_value(&aggregate, &value, index as u32, "array")
+                    })
+            }
+            Expr::ArrayRepeat(value, len) => {
+                trace!("build array repeat");
+                let value = value.expr_codegen(context, builder, module, scope);
+                let element_type = value.get_type().expect("array element cannot be void");
+                if value.is_constant() {
+                    context.const_array(&element_type, &vec![value; len as usize])
+                } else {
+                    let array_type = element_type.array_type(len as u32).as_basic_type();
+                    (0..len as u32).fold(array_type.get_undef(), |aggregate, index| {
+                        builder.build_insert_value(&aggregate, &value, index, "array")
+                    })
+                }
+            }
+            Expr::Index {
+                expr,
+                index,
+                location,
+            } => match index_codegen((*expr, *index), location, false, context, builder, module, scope) {
+                Indexed::Aggregate(ptr, index) if ptr.pointee_type().into_vector_type().is_some() => {
+                    let vector = builder.build_load(&ptr, "vector");
+                    builder.build_extract_element(&vector, &index, "lane")
+                }
+                Indexed::Aggregate(ptr, index) => {
+                    let zero = context.i64_type().const_int(0, false).into_basic_value();
+                    let ptr = builder.build_in_bounds_gep(&ptr, &[zero, index], "element_ptr");
+                    builder.build_load(&ptr, "element")
+                }
+                Indexed::Element(ptr) => builder.build_load(&ptr, "element"),
+            },
+            Expr::SimdVector(items) => {
+                trace!("build simd vector");
+                let values: Vec<BasicValue> = items
+                    .into_iter()
+                    .map(|item| item.expr_codegen(context, builder, module, scope))
+                    .collect();
+                let element_type = values
+                    .first()
+                    .and_then(|it| it.get_type())
+                    .expect("cannot infer the lane type of an empty simd vector");
+                let values: Vec<BasicValue> = values
+                    .into_iter()
+                    .map(|value| coerce(builder, value, &element_type))
+                    .collect();
+                if values.iter().all(|it| it.is_constant()) {
+                    context.const_vector(&values)
+                } else {
+                    let vector_type = element_type.vec_type(values.len() as u32).as_basic_type();
+                    let i32_type = context.i32_type();
+                    values
+                        .iter()
+                        .enumerate()
+                        .fold(vector_type.get_undef(), |vector, (index, value)| {
+                            let index = i32_type.const_int(index as u64, false).into_basic_value();
+                            builder.build_insert_element(&vector, value, &index, "vector")
+                        })
+                }
+            }
+            Expr::Path(enum_name, variant) => {
+                trace!("build variant {}::{}", &enum_name, &variant);
+                let environment = scope.environment();
+                let enum_decl = variant_enum(&enum_name, &variant, &[], None, context, environment);
+                build_enum_value(context, builder, environment, &enum_decl, &variant, vec![])
+            }
+            Expr::StructLiteral {
+                name,
+                variant: Some(variant),
+                fields,
+            } => {
+                trace!("build variant {}::{}", &name, &variant);
+                let enum_decl = scope
+                    .environment()
+                    .get_enum(&name)
+                    .unwrap_or_else(|| panic!("enum {} is undefined", name));
+                let field_names: Vec<Identifier> = match enum_decl.variant(&variant) {
+                    Some((_, EnumVariant { fields: VariantFields::Struct(fields), .. })) => {
+                        fields.iter().map(|(field, _)| field.clone()).collect()
+                    }
+                    _ => panic!("variant {}::{} has no named fields", name, variant),
+                };
+                let values =
+                    named_field_values(&name, &field_names, fields, context, builder, module, scope);
+                let environment = scope.environment();
+                let enum_decl = variant_enum(&name, &variant, &values, None, context, environment);
+                build_enum_value(context, builder, environment, &enum_decl, &variant, values)
+            }
+            Expr::StructLiteral {
+                name,
+                variant: None,
+                fields,
+            } => {
+                trace!("build struct {}", &name);
+                let environment = scope.environment();
+                let arguments = match scope.resolve_type(&Type::named(name.clone())) {
+                    Type::Generic(_, arguments) => Some(arguments),
+                    _ => None,
+                };
+                let name = resolve_type_name(&name, scope);
+                let struct_decl = environment
+                    .get_struct(&name)
+                    .unwrap_or_else(|| panic!("struct {} is undefined", name));
+                let field_names: Vec<Identifier> = struct_decl.fields.keys().cloned().collect();
+                let values =
+                    named_field_values(&name, &field_names, fields, context, builder, module, scope);
+                // the type arguments of a generic struct follow from its field values
+                let ty = if let Some(arguments) = arguments {
+                    Type::generic(name, arguments)
+                } else if struct_decl.is_generic() {
+                    let parameters: Vec<&Type> = struct_decl.fields.values().collect();
+                    let arguments =
+                        infer_type_arguments(&name, &struct_decl.generics, &parameters, &values, None, environment);
+                    Type::generic(name, arguments)
+                } else {
+                    Type::named(name)
+                };
+                let struct_type = to_basic_type(&ty, context, environment);
+                let field_types = struct_type.into_struct_type().unwrap().field_types();
+                values
+                    .into_iter()
+                    .zip(field_types)
+                    .enumerate()
+                    .fold(struct_type.get_undef(), |aggregate, (index, (value, ty))| {
+                        let value = coerce_unsized(value, &ty, context, builder, module, environment);
+                        builder.build_insert_value(&aggregate, &value, index as u32, "struct")
+                    })
+            }
+            Expr::Closure {
+                params,
+                return_type,
+                body,
+            } => {
+                let closure = Closure { params, return_type, body: *body };
+                closure_codegen(closure, None, None, context, builder, module, scope)
+            }
+            Expr::Match {
+                scrutinee,
+                arms,
+                location,
+            } => {
+                trace!("build match");
+                match_codegen((*scrutinee, arms), location, None, context, builder, module, scope)
+            }
+            Expr::Range { start, end, inclusive } => {
+                trace!("build range");
+                range_codegen(*start, *end, inclusive, context, builder, module, scope)
+            }
+            Expr::For {
+                pattern,
+                iterable,
+                body,
+            } => {
+                trace!("build for");
+                for_codegen(pattern, *iterable, *body, context, builder, module, scope)
+            }
+            Expr::Try(expr, location) => {
+                trace!("build try");
+                try_codegen(*expr, location, context, builder, module, scope)
+            }
+            Expr::SimdSplat(value, lanes) => {
+                trace!("build simd splat");
+                let value = value.expr_codegen(context, builder, module, scope);
+                builder.build_vector_splat(&value, lanes as u32, "splat")
             }
         }
     }
@@ -0,0 +1,262 @@
+use ast::{ConstDeclare, Expr, Identifier, Module, ModuleItem, Number, Opcode, Type};
+use std::collections::HashMap;
+
+/// value of a constant expression, integers are kept sign extended from the width of
+/// their type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstValue {
+    Integer(i64),
+    Array(Vec<ConstValue>),
+    /// the elements of a tuple, the unit value is the empty tuple
+    Tuple(Vec<ConstValue>),
+}
+
+/// folds the initializers of the constants of a module, and of statics, at compile time
+///
+/// a constant may use the constants declared anywhere in the module, the values are
+/// computed on first use and cycles between constants are rejected
+pub struct ConstEvaluator {
+    declarations: HashMap<Identifier, ConstDeclare>,
+    values: HashMap<Identifier, ConstValue>,
+    /// constants being evaluated, innermost last
+    evaluating: Vec<Identifier>,
+}
+
+impl ConstEvaluator {
+    pub fn new(module: &Module) -> Self {
+        let declarations = module
+            .items
+            .iter()
+            .filter_map(|item| match item.as_ref() {
+                ModuleItem::Const(const_decl) => Some((const_decl.name.clone(), const_decl.clone())),
+                _ => None,
+            })
+            .collect();
+        ConstEvaluator {
+            declarations,
+            values: HashMap::new(),
+            evaluating: vec![],
+        }
+    }
+
+    /// the value of the constant `name`, `None` when there is no such constant
+    pub fn constant(&mut self, name: &str) -> Result<Option<ConstValue>, String> {
+        if let Some(value) = self.values.get(name) {
+            return Ok(Some(value.clone()));
+        }
+        let const_decl = match self.declarations.get(name) {
+            Some(const_decl) => const_decl.clone(),
+            None => return Ok(None),
+        };
+        if let Some(start) = self.evaluating.iter().position(|it| it == name) {
+            let mut cycle = self.evaluating[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(format!(
+                "cycle detected when evaluating constant {}: {}",
+                name,
+                cycle.join(" -> ")
+            ));
+        }
+        self.evaluating.push(name.to_string());
+        let value = self.evaluate(&const_decl.value, &const_decl.ty);
+        self.evaluating.pop();
+        let value = value?;
+        self.values.insert(name.to_string(), value.clone());
+        Ok(Some(value))
+    }
+
+    /// the value of `expr` converted to `ty`, like the value stored into a variable
+    pub fn evaluate(&mut self, expr: &Expr, ty: &Type) -> Result<ConstValue, String> {
+        let (value, value_type) = self.value(expr)?;
+        convert(value, &value_type, ty)
+    }
+
+    /// the value of `expr` and the type it has without an expected type
+    fn value(&mut self, expr: &Expr) -> Result<(ConstValue, Type), String> {
+        match expr {
+            Expr::Number(number) => Ok(match number {
+                Number::Integer8(n) => (ConstValue::Integer(*n as i64), Type::named("i8".into())),
+                Number::Integer16(n) => (ConstValue::Integer(*n as i64), Type::named("i16".into())),
+                Number::Integer32(n) => (ConstValue::Integer(*n as i64), Type::named("i32".into())),
+                Number::Integer64(n) => (ConstValue::Integer(*n), Type::named("i64".into())),
+                Number::Float(_) => return Err("floats are not supported in constants".to_string()),
+                _ => return Err("unsigned integers are not supported in constants".to_string()),
+            }),
+            Expr::Identifier(name, _) => {
+                let ty = match self.declarations.get(name) {
+                    Some(const_decl) => const_decl.ty.clone(),
+                    None => return Err(format!("{} is not a constant", name)),
+                };
+                Ok((self.constant(name)?.unwrap(), ty))
+            }
+            Expr::Group(expr) => self.value(expr),
+            Expr::Block(statements, Some(value)) if statements.is_empty() => self.value(value),
+            Expr::BinOperation(op, lhs, rhs, _) => {
+                // like at runtime, the right operand is converted to the type of the left one
+                let (lhs, ty) = self.value(lhs)?;
+                let rhs = self.evaluate(rhs, &ty)?;
+                match (lhs, rhs) {
+                    (ConstValue::Integer(lhs), ConstValue::Integer(rhs)) if op.is_comparison() => {
+                        Ok((ConstValue::Integer(compare(op, lhs, rhs)), Type::named("i64".into())))
+                    }
+                    (ConstValue::Integer(lhs), ConstValue::Integer(rhs)) => {
+                        let value = binary(op, lhs, rhs, &ty)?;
+                        Ok((ConstValue::Integer(wrap(value, &ty)?), ty))
+                    }
+                    _ => Err(format!("cannot apply {} to values of type {}", op, ty)),
+                }
+            }
+            Expr::Cast(expr, ty) => {
+                let (value, value_type) = self.value(expr)?;
+                match value {
+                    ConstValue::Integer(n) if integer_width(&value_type).is_some() => {
+                        Ok((ConstValue::Integer(wrap(n, ty)?), ty.clone()))
+                    }
+                    _ => Err(format!("cannot cast {} as {}", value_type, ty)),
+                }
+            }
+            Expr::Tuple(elements) => {
+                let (values, types) = elements
+                    .iter()
+                    .map(|it| self.value(it))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into_iter()
+                    .unzip();
+                Ok((ConstValue::Tuple(values), Type::Tuple(types)))
+            }
+            Expr::Array(elements) => {
+                let (first, element_type) = match elements.first() {
+                    Some(first) => self.value(first)?,
+                    None => return Err("the type of an empty array constant is unknown".to_string()),
+                };
+                let mut values = vec![first];
+                for element in &elements[1..] {
+                    values.push(self.evaluate(element, &element_type)?);
+                }
+                let len = values.len() as u64;
+                Ok((ConstValue::Array(values), Type::Array(Box::new(element_type), len)))
+            }
+            Expr::ArrayRepeat(element, len) => {
+                let (value, element_type) = self.value(element)?;
+                Ok((
+                    ConstValue::Array(vec![value; *len as usize]),
+                    Type::Array(Box::new(element_type), *len),
+                ))
+            }
+            Expr::Index { expr, index, .. } => {
+                let (array, array_type) = self.value(expr)?;
+                let index = match self.evaluate(index, &Type::named("i64".into()))? {
+                    ConstValue::Integer(index) => index,
+                    _ => unreachable!(),
+                };
+                match (array, array_type) {
+                    (ConstValue::Array(mut values), Type::Array(element_type, len)) => {
+                        if index < 0 || index as u64 >= len {
+                            return Err(format!(
+                                "index out of bounds: the len is {} but the index is {}",
+                                len, index
+                            ));
+                        }
+                        Ok((values.swap_remove(index as usize), *element_type))
+                    }
+                    (_, ty) => Err(format!("cannot index into a value of type {}", ty)),
+                }
+            }
+            Expr::TupleField(expr, field) => match self.value(expr)? {
+                (ConstValue::Tuple(mut values), Type::Tuple(mut types))
+                    if (*field as usize) < values.len() =>
+                {
+                    Ok((values.swap_remove(*field as usize), types.swap_remove(*field as usize)))
+                }
+                (_, ty) => Err(format!("no field {} on type {}", field, ty)),
+            },
+            _ => Err("only integers, arithmetic, casts, arrays, tuples and other constants \
+                      can be evaluated at compile time"
+                .to_string()),
+        }
+    }
+}
+
+fn binary(op: &Opcode, lhs: i64, rhs: i64, ty: &Type) -> Result<i64, String> {
+    let width = integer_width(ty).unwrap();
+    Ok(match op {
+        Opcode::Add => lhs.wrapping_add(rhs),
+        Opcode::Sub => lhs.wrapping_sub(rhs),
+        Opcode::Mul => lhs.wrapping_mul(rhs),
+        Opcode::Div | Opcode::Mod if rhs == 0 => {
+            return Err("attempt to divide by zero in a constant".to_string())
+        }
+        Opcode::Div => lhs.wrapping_div(rhs),
+        Opcode::Mod => lhs.wrapping_rem(rhs),
+        Opcode::And => lhs & rhs,
+        Opcode::Or => lhs | rhs,
+        Opcode::Xor => lhs ^ rhs,
+        Opcode::LShift | Opcode::RShift if rhs < 0 || rhs >= width as i64 => {
+            return Err(format!("attempt to shift {} by {} in a constant", ty, rhs))
+        }
+        Opcode::LShift => lhs << rhs,
+        Opcode::RShift => lhs >> rhs,
+        Opcode::Pow => return Err(format!("{} is not supported in constants", op)),
+        _ => unreachable!("{} is a comparison", op),
+    })
+}
+
+/// 1 when `lhs op rhs` holds and 0 otherwise
+fn compare(op: &Opcode, lhs: i64, rhs: i64) -> i64 {
+    let holds = match op {
+        Opcode::Eq => lhs == rhs,
+        Opcode::Ne => lhs != rhs,
+        Opcode::Lt => lhs < rhs,
+        Opcode::Le => lhs <= rhs,
+        Opcode::Gt => lhs > rhs,
+        _ => lhs >= rhs,
+    };
+    holds as i64
+}
+
+/// `value` of type `from` as a value of type `to`, integers are truncated or sign extended
+fn convert(value: ConstValue, from: &Type, to: &Type) -> Result<ConstValue, String> {
+    match (value, from, to) {
+        (ConstValue::Integer(n), _, _) if integer_width(from).is_some() && integer_width(to).is_some() => {
+            Ok(ConstValue::Integer(wrap(n, to)?))
+        }
+        (ConstValue::Array(values), Type::Array(from, len), Type::Array(to, expected)) if len == expected => {
+            Ok(ConstValue::Array(
+                values
+                    .into_iter()
+                    .map(|it| convert(it, from, to))
+                    .collect::<Result<_, _>>()?,
+            ))
+        }
+        (ConstValue::Tuple(values), Type::Tuple(from), Type::Tuple(to)) if from.len() == to.len() => {
+            Ok(ConstValue::Tuple(
+                values
+                    .into_iter()
+                    .zip(from.iter().zip(to))
+                    .map(|(it, (from, to))| convert(it, from, to))
+                    .collect::<Result<_, _>>()?,
+            ))
+        }
+        _ => Err(format!("mismatched types: expected {}, found {}", to, from)),
+    }
+}
+
+/// bits of the integer type `ty`
+fn integer_width(ty: &Type) -> Option<u32> {
+    match ty {
+        Type::Named(name) => match name.as_str() {
+            "i8" => Some(8),
+            "i16" => Some(16),
+            "i32" => Some(32),
+            "i64" => Some(64),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `value` truncated to the width of `ty` and sign extended back
+fn wrap(value: i64, ty: &Type) -> Result<i64, String> {
+    let shift = 64 - integer_width(ty).ok_or_else(|| format!("{} is not an integer type", ty))?;
+    Ok((value << shift) >> shift)
+}
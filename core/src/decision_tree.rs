@@ -0,0 +1,560 @@
+use crate::codegen::{
+    auto_deref, coerce, expr_codegen_as, source_type, struct_field_ptr, variant_payload_type, ExprCodegen,
+    PlaceCodegen,
+};
+use crate::error::{fail, TyphoonError};
+use crate::exhaustiveness::{check_match, integer_bounds, truncate_literal};
+use crate::gc;
+use crate::llvm_wrapper::basic_block::BasicBlock;
+use crate::llvm_wrapper::builder::TyphoonBuilder;
+use crate::llvm_wrapper::context::TyphoonContext;
+use crate::llvm_wrapper::module::TyphoonModule;
+use crate::llvm_wrapper::types::AddressSpace;
+use crate::llvm_wrapper::values::pointer_value::PointerValue;
+use crate::llvm_wrapper::values::BasicValue;
+use crate::scope::Scope;
+use crate::llvm_wrapper::types::BasicType;
+use ast::{Expr, Identifier, Location, MatchArm, Pattern, VariantFields};
+use llvm_sys::{LLVMIntPredicate, LLVMOpcode};
+use std::collections::{HashMap, VecDeque};
+
+/// step from the matched value to one of its parts
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum PathStep {
+    /// field of a tuple or a struct
+    Field(u32),
+    /// field of the payload of an enum variant, only known once the tag is tested
+    VariantField(u32, u32),
+}
+
+/// a part of the matched value, the empty path is the value itself
+type Occurrence = Vec<PathStep>;
+
+/// a row of the clause matrix: the tests left before an arm is taken and the variables
+/// the arm binds
+#[derive(Clone)]
+struct Row {
+    tests: Vec<(Occurrence, Pattern)>,
+    bindings: Vec<(Identifier, Occurrence)>,
+    arm: usize,
+}
+
+impl Row {
+    fn test_at(&self, occurrence: &Occurrence) -> Option<&Pattern> {
+        self.tests
+            .iter()
+            .find(|(it, _)| it == occurrence)
+            .map(|(_, pattern)| pattern)
+    }
+
+    fn without_test_at(mut self, occurrence: &Occurrence) -> Row {
+        self.tests.retain(|(it, _)| it != occurrence);
+        self
+    }
+}
+
+/// compile `match` into a decision tree: the first row picks a refutable test, the value
+/// is `switch`ed on and the rows are specialized for every case, so that no part of the
+/// value is tested twice on a path
+struct MatchCompiler<'a, 'b> {
+    context: &'a TyphoonContext,
+    builder: &'a TyphoonBuilder,
+    module: &'a TyphoonModule,
+    scope: &'a Scope<'b>,
+    /// block and variables of every arm, created once a leaf of the tree reaches it
+    arms: Vec<Option<(BasicBlock, HashMap<Identifier, PointerValue>)>>,
+}
+
+/// the arms are evaluated as values of the `expected` type when it is known
+pub(crate) fn match_codegen(
+    (scrutinee, arms): (Expr, Vec<MatchArm>),
+    location: Location,
+    expected: Option<&BasicType>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    let place = scrutinee.place_codegen(context, builder, module, scope);
+    let environment = scope.environment();
+    let patterns: Vec<&Pattern> = arms.iter().map(|arm| &arm.pattern).collect();
+    let check = check_match(environment, &source_type(place.pointee_type(), environment), &patterns)
+        .unwrap_or_else(|message| {
+            fail(TyphoonError::MatchError(format!(
+                "{} at {}:{}",
+                message, &environment.source_name, location
+            )))
+        });
+    if !check.is_exhaustive() {
+        fail(TyphoonError::MatchError(format!(
+            "non-exhaustive patterns at {}:{}: {}",
+            &environment.source_name,
+            location,
+            check.missing_message()
+        )));
+    }
+    for index in check.unreachable {
+        environment.warn(format!(
+            "unreachable pattern `{}` at {}:{}",
+            &arms[index].pattern, &environment.source_name, arms[index].location
+        ));
+    }
+    let rows = arms
+        .iter()
+        .enumerate()
+        .map(|(arm, match_arm)| Row {
+            tests: vec![(vec![], match_arm.pattern.clone())],
+            bindings: vec![],
+            arm,
+        })
+        .collect();
+    let mut compiler = MatchCompiler {
+        context,
+        builder,
+        module,
+        scope,
+        arms: vec![None; arms.len()],
+    };
+    let mut occurrences = HashMap::new();
+    occurrences.insert(vec![], place);
+    compiler.compile(rows, occurrences);
+
+    let mut results: Vec<(BasicValue, BasicBlock)> = vec![];
+    for (arm, reached) in arms.into_iter().zip(compiler.arms) {
+        // arms no leaf reaches are never generated
+        if let Some((block, variables)) = reached {
+            builder.position_at_end(&block);
+            let arm_scope = Scope::new_with_upper(scope);
+            for (name, ptr) in variables {
+                arm_scope.declare(name, ptr);
+            }
+            let value = match expected {
+                Some(ty) => expr_codegen_as(*arm.body, ty, context, builder, module, &arm_scope),
+                None => arm.body.expr_codegen(context, builder, module, &arm_scope),
+            };
+            results.push((value, builder.insert_block()));
+        }
+    }
+
+    // integer literals are `i64`, so the type of a computed arm wins over a literal one
+    let result_type = results
+        .iter()
+        .find(|(value, _)| value.get_type().is_some() && !value.is_constant())
+        .or_else(|| results.iter().find(|(value, _)| value.get_type().is_some()))
+        .and_then(|(value, _)| value.get_type());
+    let end_block = context.append_basic_block(builder.current_function(), "match_end");
+    let mut incoming = vec![];
+    for (value, block) in results {
+        builder.position_at_end(&block);
+        if builder.is_terminated() {
+            continue;
+        }
+        if let Some(result_type) = &result_type {
+            let value = match value.get_type() {
+                Some(_) => coerce(builder, value, result_type),
+                None => result_type.get_undef(),
+            };
+            incoming.push((value, builder.insert_block()));
+        }
+        builder.build_branch(&end_block);
+    }
+    builder.position_at_end(&end_block);
+    match result_type {
+        Some(result_type) if !incoming.is_empty() => {
+            builder.build_phi(&result_type, &incoming, "match_value")
+        }
+        Some(result_type) => result_type.get_undef(),
+        None => context.void_type().const_value().into_basic_value(),
+    }
+}
+
+impl<'a, 'b> MatchCompiler<'a, 'b> {
+    fn compile(&mut self, rows: Vec<Row>, mut occurrences: HashMap<Occurrence, PointerValue>) {
+        let rows: Vec<Row> = rows.into_iter().map(|row| self.expand(row)).collect();
+        let first = match rows.first() {
+            Some(first) => first,
+            None => return self.build_no_match(),
+        };
+        let (occurrence, pattern) = match first.tests.first() {
+            Some(test) => test.clone(),
+            None => return self.build_leaf(first, &mut occurrences),
+        };
+        match pattern {
+            Pattern::Integer(_) | Pattern::Range(..) => {
+                self.switch_integer(occurrence, rows, occurrences)
+            }
+            Pattern::Variant { enum_name, .. } => {
+                self.switch_variant(occurrence, &enum_name, rows, occurrences)
+            }
+            _ => unreachable!("irrefutable patterns are expanded"),
+        }
+    }
+
+    /// split tuple and struct patterns into tests of their fields and turn identifiers
+    /// into bindings, so that only integer and variant tests are left
+    fn expand(&self, row: Row) -> Row {
+        let mut tests = vec![];
+        let mut bindings = row.bindings;
+        let mut pending: VecDeque<(Occurrence, Pattern)> = row.tests.into_iter().collect();
+        while let Some((occurrence, pattern)) = pending.pop_front() {
+            let field = |index: u32| {
+                let mut field = occurrence.clone();
+                field.push(PathStep::Field(index));
+                field
+            };
+            match pattern {
+                Pattern::Wildcard => {}
+                Pattern::Identifier(name) => bindings.push((name, occurrence)),
+                Pattern::Tuple(patterns) => {
+                    for (index, pattern) in patterns.into_iter().enumerate().rev() {
+                        pending.push_front((field(index as u32), pattern));
+                    }
+                }
+                Pattern::Struct(name, fields) => {
+                    let struct_decl = self
+                        .scope
+                        .environment()
+                        .get_struct(&name)
+                        .unwrap_or_else(|| panic!("struct {} is undefined", name));
+                    for (field_name, pattern) in fields.into_iter().rev() {
+                        let index = struct_decl.field_index(&field_name).unwrap_or_else(|| {
+                            panic!("struct {} has no field {}", name, field_name)
+                        });
+                        pending.push_front((field(index), pattern));
+                    }
+                }
+                pattern @ Pattern::Integer(_)
+                | pattern @ Pattern::Range(..)
+                | pattern @ Pattern::Variant { .. } => tests.push((occurrence, pattern)),
+            }
+        }
+        Row {
+            tests,
+            bindings,
+            arm: row.arm,
+        }
+    }
+
+    /// pointer to a part of the matched value, fields are reached through references
+    fn occurrence(
+        &self,
+        occurrence: &[PathStep],
+        occurrences: &mut HashMap<Occurrence, PointerValue>,
+    ) -> PointerValue {
+        if let Some(ptr) = occurrences.get(occurrence) {
+            return *ptr;
+        }
+        let (last, parent) = occurrence
+            .split_last()
+            .expect("the matched value is always known");
+        let ptr = match last {
+            PathStep::Field(index) => {
+                let base = auto_deref(self.builder, self.occurrence(parent, occurrences));
+                let fields = base
+                    .pointee_type()
+                    .into_struct_type()
+                    .map(|it| it.field_types().len())
+                    .expect("only tuples and structs can be destructured");
+                if *index as usize >= fields {
+                    panic!("pattern expects more than the {} fields of the value", fields);
+                }
+                struct_field_ptr(self.context, self.builder, &base, *index)
+            }
+            PathStep::VariantField(..) => {
+                unreachable!("variant fields are known once the tag is tested")
+            }
+        };
+        occurrences.insert(occurrence.to_vec(), ptr);
+        ptr
+    }
+
+    /// literals become the cases of a `switch`, ranges are split into disjoint pieces and
+    /// the pieces holding more than one value are compared against in its default block
+    fn switch_integer(
+        &mut self,
+        occurrence: Occurrence,
+        rows: Vec<Row>,
+        mut occurrences: HashMap<Occurrence, PointerValue>,
+    ) {
+        let ptr = auto_deref(self.builder, self.occurrence(&occurrence, &mut occurrences));
+        let value = self.builder.build_load(&ptr, "match_value");
+        let value_type = value.get_type().expect("cannot match on void");
+        let width = value_type
+            .int_width()
+            .expect("integer patterns only match integers");
+        let (min, max) = integer_bounds(width);
+        let interval = |pattern: &Pattern| match pattern {
+            Pattern::Integer(literal) => {
+                let literal = truncate_literal(*literal, width);
+                Some((literal, literal))
+            }
+            Pattern::Range(start, end) => Some(((*start as i128).max(min), (*end as i128).min(max))),
+            _ => None,
+        };
+        let intervals: Vec<(i128, i128)> = rows
+            .iter()
+            .filter_map(|row| row.test_at(&occurrence).and_then(interval))
+            .collect();
+        let mut bounds: Vec<i128> = intervals
+            .iter()
+            .flat_map(|(start, end)| [*start, end + 1])
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+        let pieces: Vec<(i128, i128)> = bounds
+            .windows(2)
+            .map(|pair| (pair[0], pair[1] - 1))
+            .filter(|(start, end)| {
+                intervals
+                    .iter()
+                    .any(|(other_start, other_end)| other_start <= start && end <= other_end)
+            })
+            .collect();
+
+        let function = self.builder.current_function();
+        let piece_blocks: Vec<BasicBlock> = pieces
+            .iter()
+            .map(|_| self.context.append_basic_block(function, "match_case"))
+            .collect();
+        let constant = |value: i128| value_type.const_int(value as i64 as u64, true);
+        let mut default_block = self.context.append_basic_block(function, "match_default");
+        let switch_cases: Vec<(BasicValue, BasicBlock)> = pieces
+            .iter()
+            .zip(piece_blocks.iter())
+            .filter(|((start, end), _)| start == end)
+            .map(|((start, _), block)| (constant(*start), *block))
+            .collect();
+        self.builder
+            .build_switch(&value, &default_block, &switch_cases);
+        for ((start, end), block) in pieces.iter().zip(piece_blocks.iter()) {
+            if start == end {
+                continue;
+            }
+            self.builder.position_at_end(&default_block);
+            let above = self.builder.build_int_compare(
+                LLVMIntPredicate::LLVMIntSGE,
+                &value,
+                &constant(*start),
+                "range_start",
+            );
+            let below = self.builder.build_int_compare(
+                LLVMIntPredicate::LLVMIntSLE,
+                &value,
+                &constant(*end),
+                "range_end",
+            );
+            let within = self
+                .builder
+                .build_binop(LLVMOpcode::LLVMAnd, &above, &below, "in_range");
+            default_block = self.context.append_basic_block(function, "match_default");
+            self.builder
+                .build_conditional_branch(&within, block, &default_block);
+        }
+
+        for ((start, end), block) in pieces.iter().zip(piece_blocks) {
+            self.builder.position_at_end(&block);
+            let specialized = rows
+                .iter()
+                .filter(|row| match row.test_at(&occurrence).and_then(interval) {
+                    Some((other_start, other_end)) => other_start <= *start && end <= &other_end,
+                    None => true,
+                })
+                .map(|row| row.clone().without_test_at(&occurrence))
+                .collect();
+            self.compile(specialized, occurrences.clone());
+        }
+
+        self.builder.position_at_end(&default_block);
+        let default = rows
+            .into_iter()
+            .filter(|row| row.test_at(&occurrence).is_none())
+            .collect();
+        self.compile(default, occurrences);
+    }
+
+    fn switch_variant(
+        &mut self,
+        occurrence: Occurrence,
+        enum_name: &str,
+        rows: Vec<Row>,
+        mut occurrences: HashMap<Occurrence, PointerValue>,
+    ) {
+        let environment = self.scope.environment();
+        if environment.get_enum(enum_name).is_none() {
+            panic!("enum {} is undefined", enum_name);
+        }
+        let ptr = auto_deref(self.builder, self.occurrence(&occurrence, &mut occurrences));
+        // the value is the enum itself or an instance of it, which is declared under the
+        // name of its llvm type
+        let enum_decl = ptr
+            .pointee_type()
+            .into_struct_type()
+            .and_then(|struct_type| struct_type.name())
+            .filter(|llvm_name| {
+                llvm_name == enum_name
+                    || environment
+                        .get_struct_instance(llvm_name)
+                        .is_some_and(|(instance_of, _)| instance_of == enum_name)
+            })
+            .and_then(|llvm_name| environment.get_enum(&llvm_name))
+            .unwrap_or_else(|| panic!("pattern of enum {} does not match the type of the value", enum_name));
+        let tag_of = |variant: &str| -> u32 {
+            enum_decl
+                .variant(variant)
+                .map(|(tag, _)| tag)
+                .unwrap_or_else(|| panic!("enum {} has no variant {}", enum_name, variant))
+        };
+        let mut cases: Vec<u32> = vec![];
+        for row in rows.iter() {
+            if let Some(Pattern::Variant { variant, .. }) = row.test_at(&occurrence) {
+                let tag = tag_of(variant);
+                if !cases.contains(&tag) {
+                    cases.push(tag);
+                }
+            }
+        }
+
+        let tag = self.builder.build_load(
+            &struct_field_ptr(self.context, self.builder, &ptr, 0),
+            "tag",
+        );
+        let function = self.builder.current_function();
+        let default_block = self.context.append_basic_block(function, "match_default");
+        let case_blocks: Vec<BasicBlock> = cases
+            .iter()
+            .map(|_| self.context.append_basic_block(function, "match_variant"))
+            .collect();
+        let i32_type = self.context.i32_type();
+        let switch_cases: Vec<(BasicValue, BasicBlock)> = cases
+            .iter()
+            .zip(case_blocks.iter())
+            .map(|(tag, block)| (i32_type.const_int(*tag as u64, false).into_basic_value(), *block))
+            .collect();
+        self.builder.build_switch(&tag, &default_block, &switch_cases);
+
+        for (tag, block) in cases.iter().zip(case_blocks) {
+            self.builder.position_at_end(&block);
+            let variant = &enum_decl.variants[*tag as usize];
+            let mut occurrences = occurrences.clone();
+            let field_count = variant.field_types().len();
+            if field_count > 0 {
+                let payload_type = variant_payload_type(variant, self.context, self.scope.environment());
+                let payload = self.builder.build_pointer_cast(
+                    &struct_field_ptr(self.context, self.builder, &ptr, 1),
+                    &payload_type.ptr_type(AddressSpace::Generic).as_basic_type(),
+                    "payload",
+                );
+                for index in 0..field_count as u32 {
+                    let mut field = occurrence.clone();
+                    field.push(PathStep::VariantField(*tag, index));
+                    let field_ptr = struct_field_ptr(self.context, self.builder, &payload, index);
+                    occurrences.insert(field, field_ptr);
+                }
+            }
+
+            let field = |index: u32| {
+                let mut field = occurrence.clone();
+                field.push(PathStep::VariantField(*tag, index));
+                field
+            };
+            let specialized = rows
+                .iter()
+                .filter_map(|row| {
+                    let fields = match row.test_at(&occurrence) {
+                        Some(Pattern::Variant {
+                            variant: other,
+                            fields,
+                            ..
+                        }) => {
+                            if tag_of(other) != *tag {
+                                return None;
+                            }
+                            fields.clone()
+                        }
+                        _ => return Some(row.clone()),
+                    };
+                    let mut row = row.clone().without_test_at(&occurrence);
+                    let subtests: Vec<(Occurrence, Pattern)> = match fields {
+                        VariantFields::Unit if field_count == 0 => vec![],
+                        VariantFields::Tuple(patterns) if patterns.len() == field_count => patterns
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, pattern)| (field(index as u32), pattern))
+                            .collect(),
+                        VariantFields::Struct(patterns) => patterns
+                            .into_iter()
+                            .map(|(name, pattern)| {
+                                let index = variant.field_index(&name).unwrap_or_else(|| {
+                                    panic!(
+                                        "variant {}::{} has no field {}",
+                                        enum_name, &variant.name, name
+                                    )
+                                });
+                                (field(index), pattern)
+                            })
+                            .collect(),
+                        _ => panic!(
+                            "pattern does not match the fields of variant {}::{}",
+                            enum_name, &variant.name
+                        ),
+                    };
+                    row.tests.splice(0..0, subtests);
+                    Some(row)
+                })
+                .collect();
+            self.compile(specialized, occurrences);
+        }
+
+        self.builder.position_at_end(&default_block);
+        if cases.len() == enum_decl.variants.len() {
+            // every tag has a case
+            self.builder.build_unreachable();
+        } else {
+            let default = rows
+                .into_iter()
+                .filter(|row| row.test_at(&occurrence).is_none())
+                .collect();
+            self.compile(default, occurrences);
+        }
+    }
+
+    /// copy the bound parts of the value into the variables of the arm and jump to it
+    fn build_leaf(&mut self, row: &Row, occurrences: &mut HashMap<Occurrence, PointerValue>) {
+        let values: Vec<(Identifier, BasicValue)> = row
+            .bindings
+            .iter()
+            .map(|(name, occurrence)| {
+                let ptr = self.occurrence(occurrence, occurrences);
+                (name.clone(), self.builder.build_load(&ptr, name))
+            })
+            .collect();
+        let context = self.context;
+        let builder = self.builder;
+        let module = self.module;
+        let environment = self.scope.environment();
+        let (block, variables) = self.arms[row.arm].get_or_insert_with(|| {
+            (
+                context.append_basic_block(builder.current_function(), "match_arm"),
+                HashMap::new(),
+            )
+        });
+        for (name, value) in values {
+            let ptr = *variables.entry(name).or_insert_with_key(|name| {
+                let ty = value.get_type().expect("cannot bind void to a variable");
+                gc::build_slot(&ty, name, context, builder, module, environment)
+            });
+            builder.build_store(&ptr, &value);
+        }
+        builder.build_branch(block);
+    }
+
+    /// no arm matches the value
+    fn build_no_match(&self) {
+        let abort = self.module.get_or_add_function(
+            "abort",
+            self.context.void_type().as_basic_type().fn_type(&[], false),
+        );
+        self.builder.build_call(&abort, &[]);
+        self.builder.build_unreachable();
+    }
+}
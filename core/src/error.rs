@@ -1,3 +1,4 @@
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
 use std::process::ExitStatus;
 use thiserror::Error;
 
@@ -9,6 +10,35 @@ pub enum TyphoonError {
     ParserError(String),
     #[error("Error on opening file {}", .0)]
     CompileError(String),
+    #[error("Resolve error: {}", .0)]
+    ResolveError(String),
+    #[error("Type error: {}", .0)]
+    TypeError(String),
+    #[error("Const error: {}", .0)]
+    ConstError(String),
+    #[error("Unsafe error: {}", .0)]
+    UnsafeError(String),
+    #[error("Match error: {}", .0)]
+    MatchError(String),
+    #[error("Move error: {}", .0)]
+    MoveError(String),
+    #[error("Borrow error: {}", .0)]
+    BorrowError(String),
     #[error("Error on linking output file as binary ({}) \nSTDOUT: {}\nSTDERR: {}", .0, .1, .2)]
     LinkError(ExitStatus, String, String),
 }
+
+/// stop compiling with `error`, which `catch` returns from the running stage, the unwind
+/// skips the panic hook so that no backtrace of the compiler is printed
+pub(crate) fn fail(error: TyphoonError) -> ! {
+    resume_unwind(Box::new(error))
+}
+
+/// the value of `stage`, or the error it stopped with through `fail`, other panics are
+/// bugs of the compiler and keep unwinding
+pub(crate) fn catch<T>(stage: impl FnOnce() -> T) -> Result<T, TyphoonError> {
+    catch_unwind(AssertUnwindSafe(stage)).map_err(|payload| match payload.downcast::<TyphoonError>() {
+        Ok(error) => *error,
+        Err(payload) => resume_unwind(payload),
+    })
+}
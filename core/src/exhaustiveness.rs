@@ -0,0 +1,493 @@
+use crate::scope::Environment;
+use ast::{EnumDeclare, Module, ModuleItem, Pattern, StructDeclare, Type, VariantFields};
+
+/// struct and enum declarations patterns are checked against
+pub trait Declarations {
+    fn get_struct(&self, name: &str) -> Option<StructDeclare>;
+    fn get_enum(&self, name: &str) -> Option<EnumDeclare>;
+}
+
+impl Declarations for Environment {
+    fn get_struct(&self, name: &str) -> Option<StructDeclare> {
+        Environment::get_struct(self, name)
+    }
+
+    fn get_enum(&self, name: &str) -> Option<EnumDeclare> {
+        Environment::get_enum(self, name)
+    }
+}
+
+impl Declarations for Module {
+    fn get_struct(&self, name: &str) -> Option<StructDeclare> {
+        self.items.iter().find_map(|item| match item.as_ref() {
+            ModuleItem::StructDeclare(struct_decl) if struct_decl.name == name => {
+                Some(struct_decl.clone())
+            }
+            _ => None,
+        })
+    }
+
+    fn get_enum(&self, name: &str) -> Option<EnumDeclare> {
+        self.items.iter().find_map(|item| match item.as_ref() {
+            ModuleItem::EnumDeclare(enum_decl) if enum_decl.name == name => {
+                Some(enum_decl.clone())
+            }
+            _ => None,
+        })
+    }
+}
+
+/// result of checking the arms of a `match`
+#[derive(Debug, Default)]
+pub struct MatchCheck {
+    /// values no arm matches, as patterns
+    pub missing: Vec<Pattern>,
+    /// indexes of the arms matching only values an earlier arm matches already
+    pub unreachable: Vec<usize>,
+}
+
+impl MatchCheck {
+    pub fn is_exhaustive(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    /// "`A` not covered", "`A`, `B` and `C` not covered" or "`A`, `B`, `C` and 2 more not
+    /// covered"
+    pub fn missing_message(&self) -> String {
+        let mut shown: Vec<String> = self
+            .missing
+            .iter()
+            .take(3)
+            .map(|pattern| format!("`{}`", pattern))
+            .collect();
+        let listed = if self.missing.len() > 3 {
+            format!("{} and {} more", shown.join(", "), self.missing.len() - 3)
+        } else {
+            let last = shown.pop().unwrap_or_default();
+            match shown.is_empty() {
+                true => last,
+                false => format!("{} and {}", shown.join(", "), last),
+            }
+        };
+        format!("{} not covered", listed)
+    }
+}
+
+/// check that the patterns cover every value of `ty` and that every pattern matches a value
+/// the patterns before it do not
+///
+/// returns an error when a pattern cannot match a value of `ty`
+pub fn check_match(
+    declarations: &impl Declarations,
+    ty: &Type,
+    patterns: &[&Pattern],
+) -> Result<MatchCheck, String> {
+    let checker = Checker { declarations };
+    let types = vec![ty.clone()];
+    let mut rows: Vec<Vec<Deconstructed>> = vec![];
+    let mut check = MatchCheck::default();
+    for (index, pattern) in patterns.iter().enumerate() {
+        let row = vec![checker.deconstruct(pattern, ty)?];
+        if checker.useful(&rows, &row, &types, false).is_empty() {
+            check.unreachable.push(index);
+        }
+        rows.push(row);
+    }
+    check.missing = checker
+        .useful(&rows, &[Deconstructed::wildcard()], &types, true)
+        .into_iter()
+        .map(|mut witness| witness.remove(0))
+        .collect();
+    Ok(check)
+}
+
+/// values of a type, as far as patterns can tell them apart
+enum Space {
+    /// signed integers of the given number of bits
+    Integer(u32),
+    Tuple(Vec<Type>),
+    Struct(StructDeclare),
+    Enum(EnumDeclare),
+    /// values patterns cannot look into, only wildcards and bindings match them
+    Opaque,
+}
+
+/// the outermost part of a pattern
+#[derive(Debug, Clone, PartialEq)]
+enum Constructor {
+    /// the only constructor of tuples and structs
+    Single,
+    Variant(u32),
+    /// integers between both bounds inclusively
+    Range(i128, i128),
+    Wildcard,
+}
+
+impl Constructor {
+    /// `other` only contains values matched by `self`, ranges are split so that they are
+    /// either contained or disjoint
+    fn covers(&self, other: &Constructor) -> bool {
+        match (self, other) {
+            (Constructor::Range(start, end), Constructor::Range(other_start, other_end)) => {
+                start <= other_start && other_end <= end
+            }
+            (this, other) => this == other,
+        }
+    }
+}
+
+/// pattern with its fields in declaration order
+#[derive(Debug, Clone)]
+struct Deconstructed {
+    constructor: Constructor,
+    fields: Vec<Deconstructed>,
+}
+
+impl Deconstructed {
+    fn wildcard() -> Self {
+        Deconstructed {
+            constructor: Constructor::Wildcard,
+            fields: vec![],
+        }
+    }
+}
+
+/// smallest and largest value of a signed integer of `bits` bits
+pub(crate) fn integer_bounds(bits: u32) -> (i128, i128) {
+    let max = (1i128 << (bits - 1)) - 1;
+    (-max - 1, max)
+}
+
+/// integer literals are `i64`, they match the value of the matched type they are
+/// truncated to
+pub(crate) fn truncate_literal(literal: i64, bits: u32) -> i128 {
+    let shift = 64 - bits.min(64);
+    ((literal << shift) >> shift) as i128
+}
+
+struct Checker<'a, D: Declarations> {
+    declarations: &'a D,
+}
+
+impl<'a, D: Declarations> Checker<'a, D> {
+    fn space(&self, ty: &Type) -> Space {
+        match ty {
+            Type::Named(name) => match name.as_str() {
+                "i8" | "i16" | "i32" | "i64" => Space::Integer(name[1..].parse().unwrap()),
+                name => self
+                    .declarations
+                    .get_enum(name)
+                    .map(Space::Enum)
+                    .or_else(|| self.declarations.get_struct(name).map(Space::Struct))
+                    .unwrap_or(Space::Opaque),
+            },
+            Type::Generic(name, arguments) => self
+                .declarations
+                .get_enum(name)
+                .map(|enum_decl| Space::Enum(enum_decl.instantiate(arguments)))
+                .or_else(|| {
+                    self.declarations
+                        .get_struct(name)
+                        .map(|struct_decl| Space::Struct(struct_decl.instantiate(arguments)))
+                })
+                .unwrap_or(Space::Opaque),
+            // patterns match through references
+            Type::Reference { inner, .. } => self.space(inner),
+            Type::Tuple(elements) if !elements.is_empty() => Space::Tuple(elements.clone()),
+            _ => Space::Opaque,
+        }
+    }
+
+    fn field_types(&self, space: &Space, constructor: &Constructor) -> Vec<Type> {
+        match (space, constructor) {
+            (Space::Tuple(elements), Constructor::Single) => elements.clone(),
+            (Space::Struct(struct_decl), Constructor::Single) => {
+                struct_decl.fields.values().cloned().collect()
+            }
+            (Space::Enum(enum_decl), Constructor::Variant(tag)) => enum_decl.variants
+                [*tag as usize]
+                .field_types()
+                .into_iter()
+                .cloned()
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    fn deconstruct(&self, pattern: &Pattern, ty: &Type) -> Result<Deconstructed, String> {
+        let space = self.space(ty);
+        let mismatch = || format!("pattern `{}` cannot match a value of type {}", pattern, ty);
+        let constructor = match (pattern, &space) {
+            (Pattern::Wildcard, _) | (Pattern::Identifier(_), _) => {
+                return Ok(Deconstructed::wildcard())
+            }
+            (Pattern::Integer(literal), Space::Integer(bits)) => {
+                let value = truncate_literal(*literal, *bits);
+                Constructor::Range(value, value)
+            }
+            (Pattern::Range(start, end), Space::Integer(bits)) => {
+                if start > end {
+                    return Err(format!("range pattern `{}` is empty", pattern));
+                }
+                let (min, max) = integer_bounds(*bits);
+                let (start, end) = ((*start as i128).max(min), (*end as i128).min(max));
+                if start > end {
+                    return Err(format!("range pattern `{}` is out of the range of {}", pattern, ty));
+                }
+                Constructor::Range(start, end)
+            }
+            (Pattern::Tuple(elements), Space::Tuple(types)) if elements.len() == types.len() => {
+                Constructor::Single
+            }
+            (Pattern::Struct(name, _), Space::Struct(struct_decl)) if name == &struct_decl.name => {
+                Constructor::Single
+            }
+            (Pattern::Variant { enum_name, variant, .. }, Space::Enum(enum_decl))
+                if enum_name == &enum_decl.name =>
+            {
+                let (tag, _) = enum_decl
+                    .variant(variant)
+                    .ok_or_else(|| format!("enum {} has no variant {}", enum_name, variant))?;
+                Constructor::Variant(tag)
+            }
+            _ => return Err(mismatch()),
+        };
+
+        let types = self.field_types(&space, &constructor);
+        let mut fields = vec![Deconstructed::wildcard(); types.len()];
+        let mut set_field = |index: usize, pattern: &Pattern| -> Result<(), String> {
+            fields[index] = self.deconstruct(pattern, &types[index])?;
+            Ok(())
+        };
+        match (pattern, &space) {
+            (Pattern::Tuple(elements), _) => {
+                for (index, element) in elements.iter().enumerate() {
+                    set_field(index, element)?;
+                }
+            }
+            (Pattern::Struct(name, field_patterns), Space::Struct(struct_decl)) => {
+                for (field, pattern) in field_patterns {
+                    let index = struct_decl
+                        .field_index(field)
+                        .ok_or_else(|| format!("struct {} has no field {}", name, field))?;
+                    set_field(index as usize, pattern)?;
+                }
+            }
+            (Pattern::Variant { variant, fields: variant_fields, .. }, Space::Enum(enum_decl)) => {
+                let (_, declared) = enum_decl.variant(variant).unwrap();
+                match (variant_fields, &declared.fields) {
+                    (VariantFields::Unit, VariantFields::Unit) => {}
+                    (VariantFields::Tuple(patterns), VariantFields::Tuple(declared))
+                        if patterns.len() == declared.len() =>
+                    {
+                        for (index, pattern) in patterns.iter().enumerate() {
+                            set_field(index, pattern)?;
+                        }
+                    }
+                    (VariantFields::Struct(patterns), VariantFields::Struct(_)) => {
+                        for (field, pattern) in patterns {
+                            let index = declared.field_index(field).ok_or_else(|| {
+                                format!("variant {} has no field {}", variant, field)
+                            })?;
+                            set_field(index as usize, pattern)?;
+                        }
+                    }
+                    _ => return Err(mismatch()),
+                }
+            }
+            _ => {}
+        }
+        Ok(Deconstructed {
+            constructor,
+            fields,
+        })
+    }
+
+    /// split `range` at the bounds of the ranges in `column`, every piece is then either
+    /// contained in or disjoint from each of them
+    fn split_range(&self, (start, end): (i128, i128), column: &[&Constructor]) -> Vec<Constructor> {
+        let mut bounds = vec![start, end + 1];
+        for constructor in column {
+            if let Constructor::Range(other_start, other_end) = constructor {
+                bounds.extend([*other_start, other_end + 1]);
+            }
+        }
+        bounds.retain(|bound| (start..=end + 1).contains(bound));
+        bounds.sort_unstable();
+        bounds.dedup();
+        bounds
+            .windows(2)
+            .map(|pair| Constructor::Range(pair[0], pair[1] - 1))
+            .collect()
+    }
+
+    /// constructors of `space` distinguished by `column`, `None` when they cannot be listed
+    fn constructors(&self, space: &Space, column: &[&Constructor]) -> Option<Vec<Constructor>> {
+        match space {
+            Space::Integer(bits) => Some(self.split_range(integer_bounds(*bits), column)),
+            Space::Tuple(_) | Space::Struct(_) => Some(vec![Constructor::Single]),
+            Space::Enum(enum_decl) => Some(
+                (0..enum_decl.variants.len() as u32)
+                    .map(Constructor::Variant)
+                    .collect(),
+            ),
+            Space::Opaque => None,
+        }
+    }
+
+    /// rows matching the values of `constructor`, with its fields in place of the first
+    /// column
+    fn specialize(
+        &self,
+        row: &[Deconstructed],
+        constructor: &Constructor,
+        arity: usize,
+    ) -> Option<Vec<Deconstructed>> {
+        let (head, tail) = row.split_first().unwrap();
+        let mut specialized = match &head.constructor {
+            Constructor::Wildcard => vec![Deconstructed::wildcard(); arity],
+            other if other.covers(constructor) => head.fields.clone(),
+            _ => return None,
+        };
+        specialized.extend_from_slice(tail);
+        Some(specialized)
+    }
+
+    /// pattern of a value built with `constructor` from the values of `fields`
+    fn rebuild(&self, space: &Space, constructor: &Constructor, fields: Vec<Pattern>) -> Pattern {
+        match (space, constructor) {
+            (_, Constructor::Range(start, end)) if start == end => Pattern::Integer(*start as i64),
+            (_, Constructor::Range(start, end)) => Pattern::Range(*start as i64, *end as i64),
+            (Space::Tuple(_), Constructor::Single) => Pattern::Tuple(fields),
+            (Space::Struct(struct_decl), Constructor::Single) => Pattern::Struct(
+                struct_decl.name.clone(),
+                struct_decl.fields.keys().cloned().zip(fields).collect(),
+            ),
+            (Space::Enum(enum_decl), Constructor::Variant(tag)) => {
+                let variant = &enum_decl.variants[*tag as usize];
+                Pattern::Variant {
+                    enum_name: enum_decl.name.clone(),
+                    variant: variant.name.clone(),
+                    fields: match &variant.fields {
+                        VariantFields::Unit => VariantFields::Unit,
+                        VariantFields::Tuple(_) => VariantFields::Tuple(fields),
+                        VariantFields::Struct(declared) => VariantFields::Struct(
+                            declared.iter().map(|(name, _)| name.clone()).zip(fields).collect(),
+                        ),
+                    },
+                }
+            }
+            _ => Pattern::Wildcard,
+        }
+    }
+
+    /// values matched by `row` but by none of `rows`, as one pattern per column
+    ///
+    /// only the first such value is searched for unless `all` is set
+    fn useful(
+        &self,
+        rows: &[Vec<Deconstructed>],
+        row: &[Deconstructed],
+        types: &[Type],
+        all: bool,
+    ) -> Vec<Vec<Pattern>> {
+        let head = match row.first() {
+            Some(head) => head,
+            None if rows.is_empty() => return vec![vec![]],
+            None => return vec![],
+        };
+        let space = self.space(&types[0]);
+        let column: Vec<&Constructor> = rows
+            .iter()
+            .map(|row| &row[0].constructor)
+            .filter(|constructor| **constructor != Constructor::Wildcard)
+            .collect();
+
+        let constructors = match &head.constructor {
+            Constructor::Range(start, end) => Some(self.split_range((*start, *end), &column)),
+            Constructor::Wildcard => self
+                .constructors(&space, &column)
+                .filter(|constructors| {
+                    // an incomplete column is checked against the rows starting with a wildcard
+                    constructors
+                        .iter()
+                        .all(|constructor| column.iter().any(|it| it.covers(constructor)))
+                }),
+            constructor => Some(vec![constructor.clone()]),
+        };
+
+        let mut witnesses = vec![];
+        if let Some(constructors) = constructors {
+            for constructor in constructors {
+                let field_types = self.field_types(&space, &constructor);
+                let arity = field_types.len();
+                let specialized: Vec<Vec<Deconstructed>> = rows
+                    .iter()
+                    .filter_map(|it| self.specialize(it, &constructor, arity))
+                    .collect();
+                let specialized_row = self.specialize(row, &constructor, arity).unwrap();
+                let types: Vec<Type> = field_types.into_iter().chain(types[1..].iter().cloned()).collect();
+                for mut witness in self.useful(&specialized, &specialized_row, &types, all) {
+                    let rest = witness.split_off(arity);
+                    let mut rebuilt = vec![self.rebuild(&space, &constructor, witness)];
+                    rebuilt.extend(rest);
+                    witnesses.push(rebuilt);
+                    if !all {
+                        return witnesses;
+                    }
+                }
+            }
+            return witnesses;
+        }
+
+        let defaults: Vec<Vec<Deconstructed>> = rows
+            .iter()
+            .filter(|row| row[0].constructor == Constructor::Wildcard)
+            .map(|row| row[1..].to_vec())
+            .collect();
+        let remaining = self.useful(&defaults, &row[1..], &types[1..], all);
+        if remaining.is_empty() {
+            return witnesses;
+        }
+        // report the values the column is missing, or `_` when it names none
+        let missing: Vec<Pattern> = match self.constructors(&space, &column) {
+            Some(constructors) if !column.is_empty() => {
+                merge_ranges(constructors.into_iter().filter(|constructor| {
+                    !column.iter().any(|it| it.covers(constructor))
+                }))
+                .into_iter()
+                .map(|constructor| {
+                    let wildcards = vec![Pattern::Wildcard; self.field_types(&space, &constructor).len()];
+                    self.rebuild(&space, &constructor, wildcards)
+                })
+                .collect()
+            }
+            _ => vec![Pattern::Wildcard],
+        };
+        for rest in remaining {
+            for pattern in missing.iter() {
+                let mut witness = vec![pattern.clone()];
+                witness.extend(rest.iter().cloned());
+                witnesses.push(witness);
+                if !all {
+                    return witnesses;
+                }
+            }
+        }
+        witnesses
+    }
+}
+
+/// join adjacent ranges, so that missing integers are reported as few ranges
+fn merge_ranges(constructors: impl Iterator<Item = Constructor>) -> Vec<Constructor> {
+    let mut merged: Vec<Constructor> = vec![];
+    for constructor in constructors {
+        match (merged.last_mut(), &constructor) {
+            (Some(Constructor::Range(_, end)), Constructor::Range(start, new_end)) if *end + 1 == *start => {
+                *end = *new_end;
+            }
+            _ => merged.push(constructor),
+        }
+    }
+    merged
+}
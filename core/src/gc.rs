@@ -0,0 +1,235 @@
+use crate::codegen::variant_payload_type;
+use crate::llvm_wrapper::builder::TyphoonBuilder;
+use crate::llvm_wrapper::context::TyphoonContext;
+use crate::llvm_wrapper::module::TyphoonModule;
+use crate::llvm_wrapper::target_data::TargetData;
+use crate::llvm_wrapper::types::{AddressSpace, BasicType};
+use crate::llvm_wrapper::values::pointer_value::PointerValue;
+use crate::llvm_wrapper::values::BasicValue;
+use crate::scope::Environment;
+
+/// the allocation function of the runtime, it returns a zeroed value of the type described
+/// by the type info it is passed, collecting the heap first when it has grown
+pub(crate) const ALLOC: &str = "typhoon_gc_alloc";
+
+/// the allocation function of arrays, it returns `count` zeroed values of the type described
+/// by the type info it is passed
+pub(crate) const ALLOC_ARRAY: &str = "typhoon_gc_alloc_array";
+
+/// the variable which has the runtime print the statistics of the heap when the program exits
+pub(crate) const STATS_VARIABLE: &str = "TYPHOON_GC_STATS";
+
+/// the collector strategy of functions with roots, llvm keeps a chain of the frames of
+/// these functions with the address and the type info of every root
+const STRATEGY: &str = "shadow-stack";
+
+extern "C" {
+    /// `llvm::linkAllBuiltinGCs`, llvm has no c api for it
+    #[link_name = "_ZN4llvm17linkAllBuiltinGCsEv"]
+    fn llvm_link_all_builtin_gcs();
+}
+
+/// make sure the builtin collector strategies of llvm are registered, a statically linked
+/// llvm leaves them out unless something refers to them and emitting code for a function
+/// using `STRATEGY` then aborts
+pub(crate) fn link_builtin_strategies() {
+    unsafe { llvm_link_all_builtin_gcs() }
+}
+
+/// the type of `Box<T>` for the llvm type of `T`, heap pointers live in their own address
+/// space, so that a box is never mistaken for a reference and only boxes are traced
+pub(crate) fn box_type(pointee: &BasicType) -> BasicType {
+    pointee.ptr_type(AddressSpace::Global).as_basic_type()
+}
+
+pub(crate) fn is_box_type(ty: &BasicType) -> bool {
+    ty.into_pointer_type()
+        .map(|pointer| pointer.address_space() == AddressSpace::Global as u32)
+        .unwrap_or(false)
+}
+
+/// offsets of the heap pointers in a value of type `ty` placed at `base`
+///
+/// the payload of an enum is an array of integers, when a variant of the enum holds heap
+/// pointers every word of the payload is taken as a possible pointer, the runtime only
+/// follows words which point into an object of the heap
+fn heap_pointer_offsets(
+    ty: &BasicType,
+    base: u64,
+    context: &TyphoonContext,
+    environment: &Environment,
+    target_data: &TargetData,
+    offsets: &mut Vec<u64>,
+) {
+    if is_box_type(ty) {
+        offsets.push(base);
+    } else if let Some(array_type) = ty.into_array_type() {
+        let element = array_type.element_type();
+        let size = target_data.abi_size(&element);
+        let mut element_offsets = vec![];
+        heap_pointer_offsets(&element, 0, context, environment, target_data, &mut element_offsets);
+        for index in 0..array_type.len() as u64 {
+            offsets.extend(element_offsets.iter().map(|it| base + index * size + it));
+        }
+    } else if let Some(struct_type) = ty.into_struct_type() {
+        let enum_decl = struct_type.name().and_then(|name| environment.get_enum(&name));
+        match enum_decl {
+            Some(enum_decl) => {
+                let holds_boxes = enum_decl.variants.iter().any(|variant| {
+                    has_heap_pointers(&variant_payload_type(variant, context, environment), context, environment)
+                });
+                let fields = struct_type.field_types();
+                if holds_boxes && fields.len() == 2 {
+                    let payload = base + target_data.offset_of_element(&struct_type, 1);
+                    let words = target_data.abi_size(&fields[1]) / 8;
+                    offsets.extend((0..words).map(|word| payload + word * 8));
+                }
+            }
+            None => {
+                for (index, field) in struct_type.field_types().iter().enumerate() {
+                    let offset = base + target_data.offset_of_element(&struct_type, index as u32);
+                    heap_pointer_offsets(field, offset, context, environment, target_data, offsets);
+                }
+            }
+        }
+    }
+}
+
+/// whether a value of type `ty` may hold pointers to the heap
+pub(crate) fn has_heap_pointers(ty: &BasicType, context: &TyphoonContext, environment: &Environment) -> bool {
+    let mut offsets = vec![];
+    heap_pointer_offsets(ty, 0, context, environment, &TargetData::x86_64(), &mut offsets);
+    !offsets.is_empty()
+}
+
+/// the constant describing `ty` to the runtime, `{ i64 size, i64 align, i64 count,
+/// [count x i64] offsets }` with the offsets of its heap pointers, emitted once per type
+fn type_info(
+    ty: &BasicType,
+    context: &TyphoonContext,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> BasicValue {
+    let i8_pointer = context.i8_type().as_basic_type().ptr_type(AddressSpace::Generic).as_basic_type();
+    if let Some(global) = environment.get_type_info(ty) {
+        return global.const_pointer_cast(&i8_pointer).into_basic_value();
+    }
+    let target_data = TargetData::x86_64();
+    let mut offsets = vec![];
+    heap_pointer_offsets(ty, 0, context, environment, &target_data, &mut offsets);
+    let i64_type = context.i64_type().as_basic_type();
+    let offsets: Vec<BasicValue> = offsets.iter().map(|it| i64_type.const_int(*it, false)).collect();
+    let value = context.const_struct(
+        &[
+            i64_type.const_int(target_data.abi_size(ty), false),
+            i64_type.const_int(target_data.abi_alignment(ty) as u64, false),
+            i64_type.const_int(offsets.len() as u64, false),
+            context.const_array(&i64_type, &offsets),
+        ],
+        false,
+    );
+    let global = module.add_global_constant("type_info", &value);
+    environment.add_type_info(ty, global);
+    global.const_pointer_cast(&i8_pointer).into_basic_value()
+}
+
+/// allocate a zeroed value of type `ty` on the heap, it is rooted until the function returns
+pub(crate) fn build_allocation(
+    ty: &BasicType,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> PointerValue {
+    let i8_type = context.i8_type().as_basic_type();
+    let info = type_info(ty, context, module, environment);
+    let alloc = module.get_or_add_function(
+        ALLOC,
+        box_type(&i8_type).fn_type(&[info.get_type().unwrap()], false),
+    );
+    let object = builder.build_call(&alloc, &[info]).into_pointer_value();
+    let ptr = builder.build_pointer_cast(&object, &box_type(ty), "box");
+    build_root(ptr.into_basic_value(), context, builder, module, environment);
+    ptr
+}
+
+/// allocate `count` zeroed values of type `ty` next to each other on the heap, the box points
+/// at the first one and is rooted until the function returns
+pub(crate) fn build_array_allocation(
+    ty: &BasicType,
+    count: BasicValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> PointerValue {
+    let i8_type = context.i8_type().as_basic_type();
+    let info = type_info(ty, context, module, environment);
+    let i64_type = context.i64_type().as_basic_type();
+    let alloc = module.get_or_add_function(
+        ALLOC_ARRAY,
+        box_type(&i8_type).fn_type(&[info.get_type().unwrap(), i64_type], false),
+    );
+    let object = builder.build_call(&alloc, &[info, count]).into_pointer_value();
+    let ptr = builder.build_pointer_cast(&object, &box_type(ty), "array");
+    build_root(ptr.into_basic_value(), context, builder, module, environment);
+    ptr
+}
+
+/// `box value`, the value moved to the heap, `box ()` allocates a byte
+pub(crate) fn build_box(
+    value: BasicValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> BasicValue {
+    let ty = value.get_type().unwrap_or_else(|| context.i8_type().as_basic_type());
+    let ptr = build_allocation(&ty, context, builder, module, environment);
+    builder.build_store(&ptr, &value);
+    ptr.into_basic_value()
+}
+
+/// an entry block slot for a value of type `ty`, a slot which may hold heap pointers is a
+/// root of the function, so that the collector keeps alive the objects it points to
+pub(crate) fn build_slot(
+    ty: &BasicType,
+    name: &str,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> PointerValue {
+    let slot = builder.build_entry_alloca(ty, name);
+    if has_heap_pointers(ty, context, environment) {
+        let i8_type = context.i8_type().as_basic_type();
+        let i8_pointer = i8_type.ptr_type(AddressSpace::Generic).as_basic_type();
+        let gcroot = module.get_or_add_function(
+            "llvm.gcroot",
+            context
+                .void_type()
+                .as_basic_type()
+                .fn_type(&[i8_pointer.ptr_type(AddressSpace::Generic).as_basic_type(), i8_pointer], false),
+        );
+        builder.build_gc_root(&slot, &type_info(ty, context, module, environment), &gcroot);
+        builder.current_function().set_gc(STRATEGY);
+    }
+    slot
+}
+
+/// store a value holding heap pointers, like the result of a call, into a root, so that the
+/// objects it points to are not collected while the value is only held in a register
+pub(crate) fn build_root(
+    value: BasicValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) {
+    if let Some(ty) = value.get_type() {
+        if has_heap_pointers(&ty, context, environment) {
+            let slot = build_slot(&ty, "root", context, builder, module, environment);
+            builder.build_store(&slot, &value);
+        }
+    }
+}
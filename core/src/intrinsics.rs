@@ -0,0 +1,487 @@
+use crate::codegen::{
+    auto_deref, build_function_call, coerce, function_for_call, source_type, struct_declaration,
+    integer_method, struct_field_ptr, ExprCodegen,
+};
+use crate::gc;
+use crate::llvm_wrapper::builder::TyphoonBuilder;
+use crate::llvm_wrapper::context::TyphoonContext;
+use crate::llvm_wrapper::module::TyphoonModule;
+use crate::llvm_wrapper::target_data::TargetData;
+use crate::llvm_wrapper::types::{AddressSpace, BasicType};
+use crate::llvm_wrapper::values::pointer_value::PointerValue;
+use crate::llvm_wrapper::values::BasicValue;
+use crate::panics::build_bounds_check;
+use crate::scope::{Environment, Scope};
+use crate::traits::{implements, type_name, EQ, HASH};
+use ast::{Expr, Type};
+use llvm_sys::{LLVMIntPredicate, LLVMOpcode, LLVMRealPredicate};
+
+/// the standard library calls the functions it cannot write in typhoon as
+/// `intrinsics::name(..)`, they are generated inline and no other module sees them
+pub(crate) const INTRINSICS: &str = "intrinsics";
+
+/// the function string literals are built with, from a box of bytes and their number
+pub(crate) const STRING_FROM_RAW_PARTS: &str = "std::String::from_raw_parts";
+
+/// every intrinsic with the number of its arguments
+///
+///  - `abort(data: Box<i8>, len)`, the `len` bytes at `data` written to the standard error
+///    before the program aborts
+///  - `array_alloc(count) -> Box<T>`, `count` zeroed values of the type the box is expected
+///    to point to
+///  - `array_element(data: Box<T>, index, len) -> &mut T`, the element `index` of the `len`
+///    values at `data`, panicking when it is out of bounds
+///  - `array_grow(data: Box<T>, len, cap) -> Box<T>`, an array of `cap` values starting with
+///    the `len` values at `data`
+///  - `eq_fields(a: &T, b: &T) -> i64`, one when each field of two structs is equal, the
+///    `eq` an empty `impl Eq for T {}` derives
+///  - `hash_fields(value: &T) -> i64`, the hashes of the fields of a struct combined, the
+///    `hash` an empty `impl Hash for T {}` derives
+///  - `write(data: Box<i8>, len)`, the `len` bytes at `data` written to the standard output
+const SIGNATURES: [(&str, usize); 7] = [
+    ("abort", 2),
+    ("array_alloc", 1),
+    ("array_element", 3),
+    ("array_grow", 3),
+    ("eq_fields", 2),
+    ("hash_fields", 1),
+    ("write", 2),
+];
+
+pub(crate) fn is_intrinsic(name: &str) -> bool {
+    SIGNATURES.iter().any(|(it, _)| *it == name)
+}
+
+/// the call of the intrinsic `name`, `expected` is the type the result is expected to have
+pub(crate) fn build_intrinsic(
+    name: &str,
+    args: Vec<Expr>,
+    expected: Option<&BasicType>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    trace!("build intrinsic {}", name);
+    let (_, arity) = SIGNATURES.iter().find(|(it, _)| *it == name).unwrap();
+    if args.len() != *arity {
+        panic!(
+            "intrinsic {} takes {} arguments but {} were supplied",
+            name,
+            arity,
+            args.len()
+        );
+    }
+    let environment = scope.environment();
+    let i64_type = context.i64_type().as_basic_type();
+    let args: Vec<BasicValue> = args
+        .into_iter()
+        .map(|arg| arg.expr_codegen(context, builder, module, scope))
+        .collect();
+    let integer = |index: usize| coerce(builder, args[index], &i64_type);
+    let array = |index: usize| {
+        let value = args[index];
+        if !value.get_type().is_some_and(|ty| gc::is_box_type(&ty)) {
+            panic!("argument {} of intrinsic {} is not a box", index + 1, name);
+        }
+        value.into_pointer_value()
+    };
+    match name {
+        "array_alloc" => {
+            let element_type = expected
+                .filter(|ty| gc::is_box_type(ty))
+                .map(|ty| ty.into_pointer_type().unwrap().element_type())
+                .unwrap_or_else(|| panic!("cannot infer the element type of intrinsic {}", name));
+            gc::build_array_allocation(
+                &element_type,
+                integer(0),
+                context,
+                builder,
+                module,
+                environment,
+            )
+            .into_basic_value()
+        }
+        "array_element" => {
+            let data = array(0);
+            let index = integer(1);
+            build_bounds_check(&index, &integer(2), None, context, builder, module, environment);
+            let element = builder.build_in_bounds_gep(&data, &[index], "element_ptr");
+            // the element is reached through a reference, which does not keep the array alive
+            let reference = element
+                .pointee_type()
+                .ptr_type(AddressSpace::Generic)
+                .as_basic_type();
+            builder
+                .build_pointer_cast(&element, &reference, "element")
+                .into_basic_value()
+        }
+        "array_grow" => {
+            let data = array(0);
+            let element_type = data.pointee_type();
+            let grown = gc::build_array_allocation(
+                &element_type,
+                integer(2),
+                context,
+                builder,
+                module,
+                environment,
+            );
+            build_copy(&data, &grown, integer(1), context, builder, module);
+            grown.into_basic_value()
+        }
+        "abort" => {
+            build_write(2, &array(0), &integer(1), context, builder, module);
+            let abort = module.get_or_add_function(
+                "abort",
+                context.void_type().as_basic_type().fn_type(&[], false),
+            );
+            builder.build_call(&abort, &[]);
+            context.void_type().const_value().into_basic_value()
+        }
+        "eq_fields" => {
+            let (lhs, rhs) = (args[0].into_pointer_value(), args[1].into_pointer_value());
+            derived_struct(&lhs, EQ, environment);
+            build_equal_elements(&lhs, &rhs, context, builder, module, environment)
+        }
+        "hash_fields" => {
+            let value = args[0].into_pointer_value();
+            derived_struct(&value, HASH, environment);
+            build_hash_elements(&value, context, builder, module, environment)
+        }
+        "write" => {
+            build_write(1, &array(0), &integer(1), context, builder, module);
+            context.void_type().const_value().into_basic_value()
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn byte_pointer(context: &TyphoonContext) -> BasicType {
+    context
+        .i8_type()
+        .as_basic_type()
+        .ptr_type(AddressSpace::Generic)
+        .as_basic_type()
+}
+
+/// write the `len` bytes at `data` to the file descriptor `fd`
+fn build_write(
+    fd: u64,
+    data: &PointerValue,
+    len: &BasicValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+) {
+    let i32_type = context.i32_type().as_basic_type();
+    let i64_type = context.i64_type().as_basic_type();
+    let write = module.get_or_add_function(
+        "write",
+        i64_type.fn_type(&[i32_type, byte_pointer(context), i64_type], false),
+    );
+    let data = builder.build_pointer_cast(data, &byte_pointer(context), "bytes");
+    builder.build_call(
+        &write,
+        &[i32_type.const_int(fd, false), data.into_basic_value(), *len],
+    );
+}
+
+/// only the fields of structs are hashed or compared by a derived implementation
+fn derived_struct(value: &PointerValue, trait_name: &str, environment: &Environment) {
+    if struct_declaration(&value.pointee_type(), environment).is_none() {
+        panic!(
+            "{} cannot derive {}, only structs do",
+            source_type(value.pointee_type(), environment),
+            trait_name
+        );
+    }
+}
+
+/// `value.hash()` and `value.eq(other)` of the built-in types, which have no `impl` blocks
+pub(crate) fn builtin_method(
+    value: &PointerValue,
+    method: &str,
+    args: Vec<Expr>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> Option<BasicValue> {
+    let environment = scope.environment();
+    let ty = source_type(value.pointee_type(), environment);
+    let implements = |trait_name: &str| {
+        environment.get_trait(trait_name).is_some() && implements(&ty, trait_name, environment)
+    };
+    match (method, &args[..]) {
+        ("hash", []) if implements(HASH) => {
+            Some(build_hash(value, context, builder, module, environment))
+        }
+        ("eq", [other]) if implements(EQ) => {
+            let other = other.clone().expr_codegen(context, builder, module, scope);
+            let other = other
+                .get_type()
+                .filter(|ty| ty.into_pointer_type().is_some())
+                .map(|_| auto_deref(builder, other.into_pointer_value()))
+                .unwrap_or_else(|| panic!("the argument of {}::eq is not a reference", ty));
+            Some(build_equal(
+                value,
+                &other,
+                context,
+                builder,
+                module,
+                environment,
+            ))
+        }
+        (method, [rhs]) if value.pointee_type().int_width().is_some() => {
+            let lhs = builder.build_load(value, "lhs");
+            integer_method(&lhs, method, rhs.clone(), context, builder, module, scope)
+        }
+        _ => None,
+    }
+}
+
+/// the hash of the value at `ptr`, integers are mixed, tuples and arrays combine the hashes
+/// of their elements, references and boxes hash the value they point to and other types
+/// call their `hash` method
+fn build_hash(
+    ptr: &PointerValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> BasicValue {
+    let i64_type = context.i64_type().as_basic_type();
+    if ptr.pointee_type().int_width().is_some() {
+        let value = builder.build_load(ptr, "value");
+        let value = builder.build_int_cast(&value, &i64_type, true);
+        // fibonacci hashing, the high bits are folded into the low ones maps use
+        let mixed = builder.build_binop(
+            LLVMOpcode::LLVMMul,
+            &value,
+            &i64_type.const_int(0x9e37_79b9_7f4a_7c15, false),
+            "mixed",
+        );
+        let high = builder.build_binop(
+            LLVMOpcode::LLVMLShr,
+            &mixed,
+            &i64_type.const_int(32, false),
+            "high",
+        );
+        return builder.build_binop(LLVMOpcode::LLVMXor, &mixed, &high, "hash");
+    }
+    match source_type(ptr.pointee_type(), environment) {
+        Type::Reference { .. } | Type::Boxed(_) => {
+            let pointee = builder.build_load(ptr, "pointee").into_pointer_value();
+            build_hash(&pointee, context, builder, module, environment)
+        }
+        Type::Tuple(_) | Type::Array(..) => {
+            build_hash_elements(ptr, context, builder, module, environment)
+        }
+        ty @ (Type::Named(_) | Type::Generic(..)) => {
+            let args = vec![ptr.into_basic_value()];
+            call_trait_method(
+                &ty,
+                (HASH, "hash"),
+                args,
+                context,
+                builder,
+                module,
+                environment,
+            )
+        }
+        ty => panic!("{} cannot be hashed", ty),
+    }
+}
+
+/// the hashes of the fields of a struct or tuple or of the elements of an array combined
+fn build_hash_elements(
+    ptr: &PointerValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> BasicValue {
+    let i64_type = context.i64_type().as_basic_type();
+    let factor = i64_type.const_int(31, false);
+    (0..element_count(&ptr.pointee_type())).fold(i64_type.const_int(0, false), |hash, index| {
+        let element = struct_field_ptr(context, builder, ptr, index);
+        let element = build_hash(&element, context, builder, module, environment);
+        let hash = builder.build_binop(LLVMOpcode::LLVMMul, &hash, &factor, "hash");
+        builder.build_binop(LLVMOpcode::LLVMAdd, &hash, &element, "hash")
+    })
+}
+
+/// one when the values at `lhs` and `rhs` are equal, zero otherwise, compared like
+/// `build_hash` hashes them
+fn build_equal(
+    lhs: &PointerValue,
+    rhs: &PointerValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> BasicValue {
+    let i64_type = context.i64_type().as_basic_type();
+    if lhs.pointee_type().int_width().is_some() {
+        let equal = builder.build_int_compare(
+            LLVMIntPredicate::LLVMIntEQ,
+            &builder.build_load(lhs, "lhs"),
+            &builder.build_load(rhs, "rhs"),
+            "equal",
+        );
+        return builder.build_int_cast(&equal, &i64_type, false);
+    }
+    if lhs.pointee_type().float_width().is_some() {
+        // `NaN` is not equal to itself
+        let equal = builder.build_float_compare(
+            LLVMRealPredicate::LLVMRealOEQ,
+            &builder.build_load(lhs, "lhs"),
+            &builder.build_load(rhs, "rhs"),
+            "equal",
+        );
+        return builder.build_int_cast(&equal, &i64_type, false);
+    }
+    match source_type(lhs.pointee_type(), environment) {
+        Type::Reference { .. } | Type::Boxed(_) => {
+            let lhs = builder.build_load(lhs, "lhs").into_pointer_value();
+            let rhs = builder.build_load(rhs, "rhs").into_pointer_value();
+            build_equal(&lhs, &rhs, context, builder, module, environment)
+        }
+        Type::Tuple(_) | Type::Array(..) => {
+            build_equal_elements(lhs, rhs, context, builder, module, environment)
+        }
+        ty @ (Type::Named(_) | Type::Generic(..)) => {
+            let args = vec![lhs.into_basic_value(), rhs.into_basic_value()];
+            call_trait_method(&ty, (EQ, "eq"), args, context, builder, module, environment)
+        }
+        ty => panic!("{} cannot be compared", ty),
+    }
+}
+
+/// one when each field or element of `lhs` equals the one of `rhs`
+fn build_equal_elements(
+    lhs: &PointerValue,
+    rhs: &PointerValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> BasicValue {
+    let i64_type = context.i64_type().as_basic_type();
+    (0..element_count(&lhs.pointee_type())).fold(i64_type.const_int(1, false), |equal, index| {
+        let lhs = struct_field_ptr(context, builder, lhs, index);
+        let rhs = struct_field_ptr(context, builder, rhs, index);
+        let element = build_equal(&lhs, &rhs, context, builder, module, environment);
+        builder.build_binop(LLVMOpcode::LLVMAnd, &equal, &element, "equal")
+    })
+}
+
+fn element_count(ty: &BasicType) -> u32 {
+    match ty.into_array_type() {
+        Some(array_type) => array_type.len(),
+        None => ty.into_struct_type().unwrap().field_types().len() as u32,
+    }
+}
+
+/// call `method` of the `impl trait_name for ty` block
+pub(crate) fn call_trait_method(
+    ty: &Type,
+    (trait_name, method): (&str, &str),
+    args: Vec<BasicValue>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> BasicValue {
+    let func_decl = type_name(ty)
+        .and_then(|name| environment.get_method(name, method))
+        .unwrap_or_else(|| panic!("{} does not implement {}", ty, trait_name));
+    let function = function_for_call(
+        &func_decl.name,
+        &args,
+        None,
+        context,
+        builder,
+        module,
+        environment,
+    );
+    build_function_call(
+        &func_decl.name,
+        &function,
+        args,
+        context,
+        builder,
+        module,
+        environment,
+    )
+}
+
+/// copy `count` values of the type `from` points to over to `to`
+fn build_copy(
+    from: &PointerValue,
+    to: &PointerValue,
+    count: BasicValue,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+) {
+    let i64_type = context.i64_type().as_basic_type();
+    let size = TargetData::x86_64().abi_size(&from.pointee_type());
+    let bytes = builder.build_binop(
+        LLVMOpcode::LLVMMul,
+        &count,
+        &i64_type.const_int(size, false),
+        "bytes",
+    );
+    let memcpy = module.get_or_add_function(
+        "llvm.memcpy.p0i8.p0i8.i64",
+        context.void_type().as_basic_type().fn_type(
+            &[
+                byte_pointer(context),
+                byte_pointer(context),
+                i64_type,
+                context.int_type(1).as_basic_type(),
+            ],
+            false,
+        ),
+    );
+    let from = builder.build_pointer_cast(from, &byte_pointer(context), "from");
+    let to = builder.build_pointer_cast(to, &byte_pointer(context), "to");
+    let volatile = context.int_type(1).const_int(0, false).into_basic_value();
+    builder.build_call(
+        &memcpy,
+        &[
+            to.into_basic_value(),
+            from.into_basic_value(),
+            bytes,
+            volatile,
+        ],
+    );
+}
+
+/// the string literal `value` as a `String` of the standard library, its bytes are copied
+/// from a constant to the heap
+pub(crate) fn build_string_literal(
+    value: &str,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    let environment = scope.environment();
+    let from_raw_parts = environment
+        .get_function(STRING_FROM_RAW_PARTS)
+        .unwrap_or_else(|| panic!("string literals need the standard library"));
+    let i8_type = context.i8_type().as_basic_type();
+    let len = context
+        .i64_type()
+        .const_int(value.len() as u64, false)
+        .into_basic_value();
+    let constant = builder.build_global_string_ptr(value, "string");
+    let data = gc::build_array_allocation(&i8_type, len, context, builder, module, environment);
+    build_copy(&constant, &data, len, context, builder, module);
+    let string = builder.build_call(&from_raw_parts, &[data.into_basic_value(), len]);
+    gc::build_root(string, context, builder, module, environment);
+    string
+}
@@ -8,9 +8,25 @@ macro_rules! c_str {
     };
 }
 
+pub mod borrows;
+pub mod c_header;
+pub mod cfg;
+pub(crate) mod closures;
 pub mod codegen;
+pub mod consts;
 pub mod context;
+pub(crate) mod decision_tree;
 pub mod error;
+pub mod exhaustiveness;
+pub(crate) mod gc;
+pub(crate) mod intrinsics;
+pub(crate) mod loops;
+pub mod mangle;
+pub mod moves;
+pub(crate) mod panics;
 pub mod program;
+pub mod resolver;
+pub(crate) mod scope;
+pub(crate) mod traits;
 
 pub(crate) mod llvm_wrapper;
\ No newline at end of file
@@ -1,6 +1,7 @@
 use llvm_sys::prelude::LLVMBasicBlockRef;
 use std::ops::Deref;
 
+#[derive(Clone, Copy)]
 pub struct BasicBlock {
     bb: LLVMBasicBlockRef,
 }
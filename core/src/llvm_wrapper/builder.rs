@@ -1,10 +1,25 @@
 use crate::llvm_wrapper::basic_block::BasicBlock;
+use crate::llvm_wrapper::types::BasicType;
+use crate::llvm_wrapper::values::function_value::FunctionValue;
+use crate::llvm_wrapper::values::pointer_value::PointerValue;
 use crate::llvm_wrapper::values::BasicValue;
 use llvm_sys::core::{
-    LLVMBuildRet, LLVMBuildRetVoid, LLVMDisposeBuilder, LLVMPositionBuilderAtEnd,
+    LLVMBuildAlloca, LLVMBuildBinOp, LLVMBuildBr, LLVMBuildCall2, LLVMBuildCondBr,
+    LLVMBuildExtractValue, LLVMBuildGlobalStringPtr, LLVMBuildICmp, LLVMBuildInBoundsGEP2,
+    LLVMBuildInsertValue, LLVMBuildIntCast2, LLVMBuildLoad2, LLVMBuildRet, LLVMBuildRetVoid,
+    LLVMBuildExtractElement, LLVMBuildInsertElement, LLVMBuildShuffleVector, LLVMBuildStore,
+    LLVMBuildUnreachable, LLVMConstInt, LLVMConstNull, LLVMGetUndef, LLVMInt32TypeInContext,
+    LLVMTypeOf, LLVMVectorType, LLVMAddCase, LLVMAddIncoming, LLVMBuildPhi, LLVMBuildPointerCast,
+    LLVMBuildSelect, LLVMBuildSwitch, LLVMBuildCast, LLVMBuildFCmp, LLVMGetElementType,
+    LLVMCreateBuilderInContext, LLVMDisposeBuilder, LLVMGetBasicBlockParent,
+    LLVMGetBasicBlockTerminator, LLVMGetEntryBasicBlock, LLVMGetFirstInstruction,
+    LLVMGetInsertBlock, LLVMGetTypeContext, LLVMPositionBuilderAtEnd, LLVMPositionBuilderBefore,
+    LLVMAddCallSiteAttribute, LLVMCreateEnumAttribute, LLVMGetEnumAttributeKindForName,
+    LLVMGetInstructionOpcode, LLVMGetInstructionParent, LLVMGetNextInstruction,
 };
-use llvm_sys::prelude::LLVMBuilderRef;
-use std::ops::Deref;
+use llvm_sys::prelude::{LLVMBasicBlockRef, LLVMBuilderRef, LLVMValueRef};
+use llvm_sys::{LLVMAttributeFunctionIndex, LLVMIntPredicate, LLVMOpcode, LLVMRealPredicate};
+use std::ffi::CString;
 
 pub struct TyphoonBuilder {
     b: LLVMBuilderRef,
@@ -23,12 +38,420 @@ impl TyphoonBuilder {
         unsafe { LLVMPositionBuilderAtEnd(self.b, block.as_llvm_ref()) }
     }
 
+    pub fn insert_block(&self) -> BasicBlock {
+        BasicBlock::new(unsafe { LLVMGetInsertBlock(self.b) })
+    }
+
+    /// the function the builder is currently generating
+    pub fn current_function(&self) -> FunctionValue {
+        FunctionValue::new(unsafe { LLVMGetBasicBlockParent(LLVMGetInsertBlock(self.b)) })
+    }
+
+    /// whether the current block already ends with `ret`, `br` or `unreachable`
+    pub fn is_terminated(&self) -> bool {
+        unsafe { !LLVMGetBasicBlockTerminator(LLVMGetInsertBlock(self.b)).is_null() }
+    }
+
     pub fn build_return(&self, value: impl Into<Option<BasicValue>>) {
         match dbg!(value.into().and_then(|v| v.as_llvm_ref())) {
             None => unsafe { LLVMBuildRetVoid(self.b) },
             Some(lvr) => unsafe { LLVMBuildRet(self.b, lvr) },
         };
     }
+
+    pub fn build_unreachable(&self) {
+        unsafe { LLVMBuildUnreachable(self.b) };
+    }
+
+    /// allocas are placed at the top of the entry block, so that a variable declared in a
+    /// loop body does not grow the stack on every iteration
+    pub fn build_entry_alloca(&self, ty: &BasicType, name: &str) -> PointerValue {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            let entry = LLVMGetEntryBasicBlock(LLVMGetBasicBlockParent(LLVMGetInsertBlock(self.b)));
+            let entry_builder =
+                TyphoonBuilder::new(LLVMCreateBuilderInContext(LLVMGetTypeContext(ty.as_llvm_type_ref())));
+            let first_instruction = LLVMGetFirstInstruction(entry);
+            if first_instruction.is_null() {
+                LLVMPositionBuilderAtEnd(entry_builder.b, entry);
+            } else {
+                LLVMPositionBuilderBefore(entry_builder.b, first_instruction);
+            }
+            PointerValue::new(LLVMBuildAlloca(
+                entry_builder.b,
+                ty.as_llvm_type_ref(),
+                name.as_ptr(),
+            ))
+        }
+    }
+
+    /// register the entry block slot `slot` as a root of the gc described by `metadata`
+    ///
+    /// the slot is zeroed right after its alloca, and `llvm.gcroot` is called after the
+    /// allocas and stores at the top of the entry block, the gc lowering takes the roots
+    /// stored to before that point as initialized
+    pub fn build_gc_root(&self, slot: &PointerValue, metadata: &BasicValue, gcroot: &FunctionValue) {
+        unsafe {
+            let alloca = slot.as_llvm_ref();
+            let entry = LLVMGetInstructionParent(alloca);
+            let entry_builder =
+                TyphoonBuilder::new(LLVMCreateBuilderInContext(LLVMGetTypeContext(LLVMTypeOf(alloca))));
+            entry_builder.position_before(entry, LLVMGetNextInstruction(alloca));
+            entry_builder.build_store(slot, &slot.pointee_type().const_zero());
+
+            let mut first = LLVMGetFirstInstruction(entry);
+            while !first.is_null()
+                && matches!(
+                    LLVMGetInstructionOpcode(first),
+                    LLVMOpcode::LLVMAlloca | LLVMOpcode::LLVMStore
+                )
+            {
+                first = LLVMGetNextInstruction(first);
+            }
+            entry_builder.position_before(entry, first);
+            let root = entry_builder.build_pointer_cast(slot, &gcroot.param_types()[0], "root");
+            entry_builder.build_call(gcroot, &[root.into_basic_value(), *metadata]);
+        }
+    }
+
+    /// position before `instruction`, at the end of `block` when it is null
+    unsafe fn position_before(&self, block: LLVMBasicBlockRef, instruction: LLVMValueRef) {
+        if instruction.is_null() {
+            LLVMPositionBuilderAtEnd(self.b, block);
+        } else {
+            LLVMPositionBuilderBefore(self.b, instruction);
+        }
+    }
+
+    pub fn build_load(&self, ptr: &PointerValue, name: &str) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        let ty = ptr.pointee_type().as_llvm_type_ref();
+        BasicValue::new(unsafe { LLVMBuildLoad2(self.b, ty, ptr.as_llvm_ref(), name.as_ptr()) })
+    }
+
+    pub fn build_store(&self, ptr: &PointerValue, value: &BasicValue) {
+        if let Some(value) = value.as_llvm_ref() {
+            unsafe { LLVMBuildStore(self.b, value, ptr.as_llvm_ref()) };
+        }
+    }
+
+    pub fn build_binop(
+        &self,
+        op: LLVMOpcode,
+        lhs: &BasicValue,
+        rhs: &BasicValue,
+        name: &str,
+    ) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        BasicValue::new(unsafe {
+            LLVMBuildBinOp(
+                self.b,
+                op,
+                lhs.as_llvm_ref().expect("void value as operand"),
+                rhs.as_llvm_ref().expect("void value as operand"),
+                name.as_ptr(),
+            )
+        })
+    }
+
+    /// truncate or extend an integer value to the given integer type
+    pub fn build_int_cast(&self, value: &BasicValue, ty: &BasicType, signed: bool) -> BasicValue {
+        BasicValue::new(unsafe {
+            LLVMBuildIntCast2(
+                self.b,
+                value.as_llvm_ref().expect("void value in cast"),
+                ty.as_llvm_type_ref(),
+                signed as i32,
+                c_str!("int_cast"),
+            )
+        })
+    }
+
+    /// conversion of `value` to `ty` with the cast instruction `op`, like `sitofp` or `fptrunc`
+    pub fn build_cast(&self, op: LLVMOpcode, value: &BasicValue, ty: &BasicType, name: &str) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        BasicValue::new(unsafe {
+            LLVMBuildCast(
+                self.b,
+                op,
+                value.as_llvm_ref().expect("void value in cast"),
+                ty.as_llvm_type_ref(),
+                name.as_ptr(),
+            )
+        })
+    }
+
+    pub fn build_float_compare(
+        &self,
+        predicate: LLVMRealPredicate,
+        lhs: &BasicValue,
+        rhs: &BasicValue,
+        name: &str,
+    ) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        BasicValue::new(unsafe {
+            LLVMBuildFCmp(
+                self.b,
+                predicate,
+                lhs.as_llvm_ref().expect("void value in comparison"),
+                rhs.as_llvm_ref().expect("void value in comparison"),
+                name.as_ptr(),
+            )
+        })
+    }
+
+    pub fn build_int_compare(
+        &self,
+        predicate: LLVMIntPredicate,
+        lhs: &BasicValue,
+        rhs: &BasicValue,
+        name: &str,
+    ) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        BasicValue::new(unsafe {
+            LLVMBuildICmp(
+                self.b,
+                predicate,
+                lhs.as_llvm_ref().expect("void value in comparison"),
+                rhs.as_llvm_ref().expect("void value in comparison"),
+                name.as_ptr(),
+            )
+        })
+    }
+
+    /// `then_value` when the `i1` condition is set and `else_value` otherwise
+    pub fn build_select(
+        &self,
+        condition: &BasicValue,
+        then_value: &BasicValue,
+        else_value: &BasicValue,
+        name: &str,
+    ) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        BasicValue::new(unsafe {
+            LLVMBuildSelect(
+                self.b,
+                condition.as_llvm_ref().expect("void value as condition"),
+                then_value.as_llvm_ref().expect("void value in select"),
+                else_value.as_llvm_ref().expect("void value in select"),
+                name.as_ptr(),
+            )
+        })
+    }
+
+    pub fn build_branch(&self, block: &BasicBlock) {
+        unsafe { LLVMBuildBr(self.b, block.as_llvm_ref()) };
+    }
+
+    pub fn build_conditional_branch(
+        &self,
+        condition: &BasicValue,
+        then_block: &BasicBlock,
+        else_block: &BasicBlock,
+    ) {
+        unsafe {
+            LLVMBuildCondBr(
+                self.b,
+                condition.as_llvm_ref().expect("void value as condition"),
+                then_block.as_llvm_ref(),
+                else_block.as_llvm_ref(),
+            )
+        };
+    }
+
+    pub fn build_in_bounds_gep(
+        &self,
+        ptr: &PointerValue,
+        indices: &[BasicValue],
+        name: &str,
+    ) -> PointerValue {
+        let name = CString::new(name).unwrap();
+        let mut indices: Vec<LLVMValueRef> =
+            indices.iter().filter_map(|it| it.as_llvm_ref()).collect();
+        PointerValue::new(unsafe {
+            LLVMBuildInBoundsGEP2(
+                self.b,
+                ptr.pointee_type().as_llvm_type_ref(),
+                ptr.as_llvm_ref(),
+                indices.as_mut_ptr(),
+                indices.len() as u32,
+                name.as_ptr(),
+            )
+        })
+    }
+
+    pub fn build_extract_value(&self, aggregate: &BasicValue, index: u32, name: &str) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        BasicValue::new(unsafe {
+            LLVMBuildExtractValue(
+                self.b,
+                aggregate.as_llvm_ref().expect("void value as aggregate"),
+                index,
+                name.as_ptr(),
+            )
+        })
+    }
+
+    pub fn build_insert_value(
+        &self,
+        aggregate: &BasicValue,
+        value: &BasicValue,
+        index: u32,
+        name: &str,
+    ) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        BasicValue::new(unsafe {
+            LLVMBuildInsertValue(
+                self.b,
+                aggregate.as_llvm_ref().expect("void value as aggregate"),
+                value.as_llvm_ref().expect("void value in aggregate"),
+                index,
+                name.as_ptr(),
+            )
+        })
+    }
+
+    pub fn build_extract_element(&self, vector: &BasicValue, index: &BasicValue, name: &str) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        BasicValue::new(unsafe {
+            LLVMBuildExtractElement(
+                self.b,
+                vector.as_llvm_ref().expect("void value as vector"),
+                index.as_llvm_ref().expect("void value as lane index"),
+                name.as_ptr(),
+            )
+        })
+    }
+
+    pub fn build_insert_element(
+        &self,
+        vector: &BasicValue,
+        value: &BasicValue,
+        index: &BasicValue,
+        name: &str,
+    ) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        BasicValue::new(unsafe {
+            LLVMBuildInsertElement(
+                self.b,
+                vector.as_llvm_ref().expect("void value as vector"),
+                value.as_llvm_ref().expect("void value in vector"),
+                index.as_llvm_ref().expect("void value as lane index"),
+                name.as_ptr(),
+            )
+        })
+    }
+
+    /// broadcast a scalar to every lane of a `lanes` wide vector, the usual
+    /// `insertelement` + `shufflevector` with a zero mask
+    pub fn build_vector_splat(&self, value: &BasicValue, lanes: u32, name: &str) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        let value = value.as_llvm_ref().expect("void value in vector");
+        BasicValue::new(unsafe {
+            let element_type = LLVMTypeOf(value);
+            let i32_type = LLVMInt32TypeInContext(LLVMGetTypeContext(element_type));
+            let single = LLVMBuildInsertElement(
+                self.b,
+                LLVMGetUndef(LLVMVectorType(element_type, 1)),
+                value,
+                LLVMConstInt(i32_type, 0, 0),
+                c_str!("splat_insert"),
+            );
+            LLVMBuildShuffleVector(
+                self.b,
+                single,
+                LLVMGetUndef(LLVMTypeOf(single)),
+                LLVMConstNull(LLVMVectorType(i32_type, lanes)),
+                name.as_ptr(),
+            )
+        })
+    }
+
+    pub fn build_pointer_cast(&self, ptr: &PointerValue, ty: &BasicType, name: &str) -> PointerValue {
+        let name = CString::new(name).unwrap();
+        PointerValue::new(unsafe {
+            LLVMBuildPointerCast(self.b, ptr.as_llvm_ref(), ty.as_llvm_type_ref(), name.as_ptr())
+        })
+    }
+
+    pub fn build_switch(
+        &self,
+        value: &BasicValue,
+        else_block: &BasicBlock,
+        cases: &[(BasicValue, BasicBlock)],
+    ) {
+        unsafe {
+            let switch = LLVMBuildSwitch(
+                self.b,
+                value.as_llvm_ref().expect("void value in switch"),
+                else_block.as_llvm_ref(),
+                cases.len() as u32,
+            );
+            for (case, block) in cases {
+                LLVMAddCase(
+                    switch,
+                    case.as_llvm_ref().expect("void value as case"),
+                    block.as_llvm_ref(),
+                );
+            }
+        }
+    }
+
+    pub fn build_phi(
+        &self,
+        ty: &BasicType,
+        incoming: &[(BasicValue, BasicBlock)],
+        name: &str,
+    ) -> BasicValue {
+        let name = CString::new(name).unwrap();
+        let mut values: Vec<LLVMValueRef> = incoming
+            .iter()
+            .map(|(value, _)| value.as_llvm_ref().expect("void value in phi"))
+            .collect();
+        let mut blocks: Vec<LLVMBasicBlockRef> =
+            incoming.iter().map(|(_, block)| block.as_llvm_ref()).collect();
+        BasicValue::new(unsafe {
+            let phi = LLVMBuildPhi(self.b, ty.as_llvm_type_ref(), name.as_ptr());
+            LLVMAddIncoming(phi, values.as_mut_ptr(), blocks.as_mut_ptr(), values.len() as u32);
+            phi
+        })
+    }
+
+    /// a private global holding the nul terminated string, returned as `i8*`
+    pub fn build_global_string_ptr(&self, value: &str, name: &str) -> PointerValue {
+        let value = CString::new(value).unwrap();
+        let name = CString::new(name).unwrap();
+        PointerValue::new(unsafe {
+            LLVMBuildGlobalStringPtr(self.b, value.as_ptr(), name.as_ptr())
+        })
+    }
+
+    pub fn build_call(&self, function: &FunctionValue, args: &[BasicValue]) -> BasicValue {
+        let mut args: Vec<LLVMValueRef> = args.iter().filter_map(|it| it.as_llvm_ref()).collect();
+        let value = unsafe {
+            LLVMBuildCall2(
+                self.b,
+                LLVMGetElementType(LLVMTypeOf(function.as_llvm_value_ref())),
+                function.as_llvm_value_ref(),
+                args.as_mut_ptr(),
+                args.len() as u32,
+                // void calls must not be named
+                c_str!(""),
+            )
+        };
+        // typhoon code never unwinds, so the shadow stack of the gc needs no cleanup for
+        // calls throwing an exception
+        unsafe {
+            let kind = LLVMGetEnumAttributeKindForName(c_str!("nounwind"), "nounwind".len());
+            let attribute = LLVMCreateEnumAttribute(LLVMGetTypeContext(LLVMTypeOf(value)), kind, 0);
+            LLVMAddCallSiteAttribute(value, LLVMAttributeFunctionIndex, attribute);
+        }
+        if function.return_type().is_void() {
+            BasicValue::new(None)
+        } else {
+            BasicValue::new(value)
+        }
+    }
 }
 
 impl Drop for TyphoonBuilder {
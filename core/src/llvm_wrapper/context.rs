@@ -3,10 +3,12 @@ use crate::llvm_wrapper::builder::TyphoonBuilder;
 use crate::llvm_wrapper::module::TyphoonModule;
 use crate::llvm_wrapper::types::int_type::IntType;
 use crate::llvm_wrapper::values::function_value::FunctionValue;
-use llvm_sys::core::{LLVMAppendBasicBlockInContext, LLVMContextCreate, LLVMCreateBuilderInContext, LLVMInt16TypeInContext, LLVMInt8Type, LLVMInt8TypeInContext, LLVMModuleCreateWithNameInContext, LLVMContextDispose, LLVMInt32TypeInContext, LLVMInt64TypeInContext, LLVMVoidTypeInContext};
-use llvm_sys::prelude::LLVMContextRef;
+use crate::llvm_wrapper::values::BasicValue;
+use llvm_sys::core::{LLVMAppendBasicBlockInContext, LLVMContextCreate, LLVMCreateBuilderInContext, LLVMInt16TypeInContext, LLVMInt8TypeInContext, LLVMModuleCreateWithNameInContext, LLVMContextDispose, LLVMInt32TypeInContext, LLVMInt64TypeInContext, LLVMVoidTypeInContext, LLVMStructCreateNamed, LLVMGetTypeByName2, LLVMConstArray, LLVMConstVector, LLVMStructTypeInContext, LLVMIntTypeInContext, LLVMConstStructInContext, LLVMFloatTypeInContext, LLVMDoubleTypeInContext};
+use llvm_sys::prelude::{LLVMContextRef, LLVMTypeRef, LLVMValueRef};
 use std::ffi::CString;
 use crate::llvm_wrapper::types::BasicType;
+use crate::llvm_wrapper::types::struct_type::StructType;
 use crate::llvm_wrapper::types::void_type::VoidType;
 
 pub struct TyphoonContext {
@@ -52,12 +54,74 @@ impl TyphoonContext {
     pub fn i32_type(&self) -> IntType {
         IntType::new(unsafe { LLVMInt32TypeInContext(self.ctx) })
     }
+    pub fn int_type(&self, bits: u32) -> IntType {
+        IntType::new(unsafe { LLVMIntTypeInContext(self.ctx, bits) })
+    }
+
     pub fn i64_type(&self) -> IntType {
         IntType::new(unsafe { LLVMInt64TypeInContext(self.ctx) })
     }
+
+    pub fn f32_type(&self) -> BasicType {
+        BasicType::new(unsafe { LLVMFloatTypeInContext(self.ctx) })
+    }
+
+    pub fn f64_type(&self) -> BasicType {
+        BasicType::new(unsafe { LLVMDoubleTypeInContext(self.ctx) })
+    }
+
+    pub fn const_array(&self, element_type: &BasicType, values: &[BasicValue]) -> BasicValue {
+        let mut values: Vec<LLVMValueRef> = values.iter().filter_map(|it| it.as_llvm_ref()).collect();
+        BasicValue::new(unsafe {
+            LLVMConstArray(element_type.as_llvm_type_ref(), values.as_mut_ptr(), values.len() as u32)
+        })
+    }
+
+    pub fn const_vector(&self, values: &[BasicValue]) -> BasicValue {
+        let mut values: Vec<LLVMValueRef> = values.iter().filter_map(|it| it.as_llvm_ref()).collect();
+        BasicValue::new(unsafe { LLVMConstVector(values.as_mut_ptr(), values.len() as u32) })
+    }
+
+    /// constant of the literal struct type of the values
+    pub fn const_struct(&self, values: &[BasicValue], packed: bool) -> BasicValue {
+        let mut values: Vec<LLVMValueRef> = values.iter().filter_map(|it| it.as_llvm_ref()).collect();
+        BasicValue::new(unsafe {
+            LLVMConstStructInContext(self.ctx, values.as_mut_ptr(), values.len() as u32, packed as i32)
+        })
+    }
+
     pub fn void_type(&self) -> VoidType {
         VoidType::new(unsafe { LLVMVoidTypeInContext(self.ctx) })
     }
+
+    /// literal struct type, structurally equal to every other one with the same fields
+    pub fn struct_type(&self, fields: &[BasicType], packed: bool) -> StructType {
+        let mut fields_ref: Vec<LLVMTypeRef> =
+            fields.iter().map(|it| it.as_llvm_type_ref()).collect();
+        StructType::new(unsafe {
+            LLVMStructTypeInContext(
+                self.ctx,
+                fields_ref.as_mut_ptr(),
+                fields_ref.len() as u32,
+                packed as i32,
+            )
+        })
+    }
+
+    pub fn opaque_struct_type(&self, name: &str) -> StructType {
+        let name = CString::new(name).unwrap();
+        StructType::new(unsafe { LLVMStructCreateNamed(self.ctx, name.as_ptr()) })
+    }
+
+    pub fn get_struct_type(&self, name: &str) -> Option<StructType> {
+        let name = CString::new(name).unwrap();
+        let ty = unsafe { LLVMGetTypeByName2(self.ctx, name.as_ptr()) };
+        if ty.is_null() {
+            None
+        } else {
+            Some(StructType::new(ty))
+        }
+    }
 }
 
 
@@ -9,5 +9,6 @@ pub mod values;
 pub mod context;
 pub mod module;
 pub mod builder;
+pub mod target_data;
 
 pub mod basic_block;
\ No newline at end of file
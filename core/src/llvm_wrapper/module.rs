@@ -1,6 +1,13 @@
 use crate::llvm_wrapper::types::function_type::FunctionType;
 use crate::llvm_wrapper::values::function_value::FunctionValue;
-use llvm_sys::core::{LLVMAddFunction, LLVMDisposeModule};
+use crate::llvm_wrapper::values::pointer_value::PointerValue;
+use crate::llvm_wrapper::values::BasicValue;
+use llvm_sys::core::{
+    LLVMAddFunction, LLVMAddGlobal, LLVMDisposeModule, LLVMGetFirstFunction, LLVMGetNamedFunction,
+    LLVMGetNamedGlobal, LLVMGetNextFunction, LLVMSetGlobalConstant, LLVMSetInitializer,
+    LLVMSetLinkage, LLVMTypeOf,
+};
+use llvm_sys::LLVMLinkage;
 use llvm_sys::prelude::LLVMModuleRef;
 use std::ffi::CString;
 
@@ -19,6 +26,62 @@ impl TyphoonModule {
         FunctionValue::new(llvm_value_ref)
     }
 
+    pub fn get_function(&self, name: &str) -> Option<FunctionValue> {
+        let name = CString::new(name).unwrap();
+        let llvm_value_ref = unsafe { LLVMGetNamedFunction(self.module, name.as_ptr()) };
+        if llvm_value_ref.is_null() {
+            None
+        } else {
+            Some(FunctionValue::new(llvm_value_ref))
+        }
+    }
+
+    /// declare an external function such as a libc routine, reusing an existing declaration
+    pub fn get_or_add_function(&self, name: &str, func: FunctionType) -> FunctionValue {
+        self.get_function(name)
+            .unwrap_or_else(|| self.add_function(name, func))
+    }
+
+    /// every function defined or declared in the module
+    pub fn functions(&self) -> Vec<FunctionValue> {
+        let mut functions = vec![];
+        let mut function = unsafe { LLVMGetFirstFunction(self.module) };
+        while !function.is_null() {
+            functions.push(FunctionValue::new(function));
+            function = unsafe { LLVMGetNextFunction(function) };
+        }
+        functions
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<PointerValue> {
+        let name = CString::new(name).unwrap();
+        let llvm_value_ref = unsafe { LLVMGetNamedGlobal(self.module, name.as_ptr()) };
+        if llvm_value_ref.is_null() {
+            None
+        } else {
+            Some(PointerValue::new(llvm_value_ref))
+        }
+    }
+
+    /// add an internal global initialized with `value`
+    pub fn add_global(&self, name: &str, value: &BasicValue) -> PointerValue {
+        let name = CString::new(name).unwrap();
+        let value = value.as_llvm_ref().expect("void value as global initializer");
+        unsafe {
+            let global = LLVMAddGlobal(self.module, LLVMTypeOf(value), name.as_ptr());
+            LLVMSetInitializer(global, value);
+            LLVMSetLinkage(global, LLVMLinkage::LLVMInternalLinkage);
+            PointerValue::new(global)
+        }
+    }
+
+    /// add an internal constant global holding `value`
+    pub fn add_global_constant(&self, name: &str, value: &BasicValue) -> PointerValue {
+        let global = self.add_global(name, value);
+        unsafe { LLVMSetGlobalConstant(global.as_llvm_ref(), 1) };
+        global
+    }
+
     pub fn to_llvm_module_ref(&self) -> LLVMModuleRef {
         self.module
     }
@@ -0,0 +1,45 @@
+use crate::llvm_wrapper::types::struct_type::StructType;
+use crate::llvm_wrapper::types::BasicType;
+use llvm_sys::target::{
+    LLVMABIAlignmentOfType, LLVMABISizeOfType, LLVMCreateTargetData, LLVMDisposeTargetData,
+    LLVMOffsetOfElement, LLVMTargetDataRef,
+};
+use std::ffi::CString;
+
+/// the data layout of the x86-64 target the object files are emitted for, used to size
+/// types before the target machine is created
+const X86_64_DATA_LAYOUT: &str =
+    "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128";
+
+pub struct TargetData {
+    td: LLVMTargetDataRef,
+}
+
+impl TargetData {
+    pub fn x86_64() -> Self {
+        let layout = CString::new(X86_64_DATA_LAYOUT).unwrap();
+        TargetData {
+            td: unsafe { LLVMCreateTargetData(layout.as_ptr()) },
+        }
+    }
+
+    /// size in bytes, including the padding up to the alignment
+    pub fn abi_size(&self, ty: &BasicType) -> u64 {
+        unsafe { LLVMABISizeOfType(self.td, ty.as_llvm_type_ref()) }
+    }
+
+    pub fn abi_alignment(&self, ty: &BasicType) -> u32 {
+        unsafe { LLVMABIAlignmentOfType(self.td, ty.as_llvm_type_ref()) }
+    }
+
+    /// offset in bytes of field `index` from the start of the struct
+    pub fn offset_of_element(&self, ty: &StructType, index: u32) -> u64 {
+        unsafe { LLVMOffsetOfElement(self.td, ty.as_basic_type().as_llvm_type_ref(), index) }
+    }
+}
+
+impl Drop for TargetData {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeTargetData(self.td) }
+    }
+}
@@ -1,4 +1,6 @@
 
+use crate::llvm_wrapper::types::BasicType;
+use llvm_sys::core::{LLVMGetArrayLength, LLVMGetElementType};
 use llvm_sys::prelude::LLVMTypeRef;
 
 pub struct ArrayType {
@@ -9,4 +11,16 @@ impl ArrayType {
     pub fn new(ty: LLVMTypeRef) -> Self {
         Self { ty }
     }
+
+    pub fn as_basic_type(&self) -> BasicType {
+        BasicType::new(self.ty)
+    }
+
+    pub fn len(&self) -> u32 {
+        unsafe { LLVMGetArrayLength(self.ty) }
+    }
+
+    pub fn element_type(&self) -> BasicType {
+        BasicType::new(unsafe { LLVMGetElementType(self.ty) })
+    }
 }
@@ -1,3 +1,7 @@
+use crate::llvm_wrapper::types::pointer_type::PointerType;
+use crate::llvm_wrapper::types::AddressSpace;
+use crate::llvm_wrapper::types::BasicType;
+use llvm_sys::core::{LLVMCountParamTypes, LLVMGetParamTypes, LLVMGetReturnType, LLVMPointerType};
 use llvm_sys::prelude::LLVMTypeRef;
 
 pub struct FunctionType {
@@ -11,4 +15,21 @@ impl FunctionType {
     pub fn as_llvm_type_ref(&self) -> LLVMTypeRef {
         self.ty
     }
+
+    pub fn param_types(&self) -> Vec<BasicType> {
+        unsafe {
+            let mut types = vec![std::ptr::null_mut(); LLVMCountParamTypes(self.ty) as usize];
+            LLVMGetParamTypes(self.ty, types.as_mut_ptr());
+            types.into_iter().map(BasicType::new).collect()
+        }
+    }
+
+    pub fn return_type(&self) -> BasicType {
+        BasicType::new(unsafe { LLVMGetReturnType(self.ty) })
+    }
+
+    /// type of a pointer to a function of this type
+    pub fn ptr_type(&self, address_space: AddressSpace) -> PointerType {
+        unsafe { PointerType::new(LLVMPointerType(self.ty, address_space as u32)) }
+    }
 }
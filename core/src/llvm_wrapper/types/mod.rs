@@ -1,13 +1,21 @@
 use crate::llvm_wrapper::types::array_type::ArrayType;
 use crate::llvm_wrapper::types::function_type::FunctionType;
 use crate::llvm_wrapper::types::pointer_type::PointerType;
+use crate::llvm_wrapper::types::struct_type::StructType;
 use crate::llvm_wrapper::types::vector_type::VectorType;
-use llvm_sys::core::{LLVMArrayType, LLVMFunctionType, LLVMPointerType, LLVMVectorType};
+use crate::llvm_wrapper::values::BasicValue;
+use llvm_sys::core::{
+    LLVMArrayType, LLVMConstInt, LLVMConstNull, LLVMConstReal, LLVMFunctionType, LLVMGetIntTypeWidth,
+    LLVMGetTypeKind, LLVMGetUndef,
+    LLVMPointerType, LLVMTypeIsSized, LLVMVectorType,
+};
 use llvm_sys::prelude::LLVMTypeRef;
+use llvm_sys::LLVMTypeKind;
 
 pub mod array_type;
 pub mod function_type;
 pub mod pointer_type;
+pub mod struct_type;
 pub mod vector_type;
 pub mod int_type;
 pub mod void_type;
@@ -22,6 +30,7 @@ pub enum AddressSpace {
 }
 
 
+#[derive(Clone, Copy)]
 pub struct BasicType {
     ty: LLVMTypeRef,
 }
@@ -35,6 +44,90 @@ impl BasicType {
         self.ty
     }
 
+    pub fn get_undef(&self) -> BasicValue {
+        BasicValue::new(unsafe { LLVMGetUndef(self.ty) })
+    }
+
+    /// the value of this type with every bit cleared
+    pub fn const_zero(&self) -> BasicValue {
+        BasicValue::new(unsafe { LLVMConstNull(self.ty) })
+    }
+
+    /// integer constant of this integer type
+    pub fn const_int(&self, value: u64, sign_extend: bool) -> BasicValue {
+        BasicValue::new(unsafe { LLVMConstInt(self.ty, value, sign_extend as i32) })
+    }
+
+    /// float constant of this float type
+    pub fn const_float(&self, value: f64) -> BasicValue {
+        BasicValue::new(unsafe { LLVMConstReal(self.ty, value) })
+    }
+
+    /// false for opaque structs and aggregates containing one
+    pub fn is_sized(&self) -> bool {
+        unsafe { LLVMTypeIsSized(self.ty) != 0 }
+    }
+
+    pub fn kind(&self) -> LLVMTypeKind {
+        unsafe { LLVMGetTypeKind(self.ty) }
+    }
+
+    pub fn is_void(&self) -> bool {
+        self.kind() == LLVMTypeKind::LLVMVoidTypeKind
+    }
+
+    /// bit width for integer types, `None` for every other type
+    pub fn int_width(&self) -> Option<u32> {
+        match self.kind() {
+            LLVMTypeKind::LLVMIntegerTypeKind => Some(unsafe { LLVMGetIntTypeWidth(self.ty) }),
+            _ => None,
+        }
+    }
+
+    /// bit width for `float` and `double`, `None` for every other type
+    pub fn float_width(&self) -> Option<u32> {
+        match self.kind() {
+            LLVMTypeKind::LLVMFloatTypeKind => Some(32),
+            LLVMTypeKind::LLVMDoubleTypeKind => Some(64),
+            _ => None,
+        }
+    }
+
+    pub fn into_array_type(self) -> Option<ArrayType> {
+        match self.kind() {
+            LLVMTypeKind::LLVMArrayTypeKind => Some(ArrayType::new(self.ty)),
+            _ => None,
+        }
+    }
+
+    pub fn into_struct_type(self) -> Option<StructType> {
+        match self.kind() {
+            LLVMTypeKind::LLVMStructTypeKind => Some(StructType::new(self.ty)),
+            _ => None,
+        }
+    }
+
+    pub fn into_vector_type(self) -> Option<VectorType> {
+        match self.kind() {
+            LLVMTypeKind::LLVMVectorTypeKind => Some(VectorType::new(self.ty)),
+            _ => None,
+        }
+    }
+
+    pub fn into_function_type(self) -> Option<FunctionType> {
+        match self.kind() {
+            LLVMTypeKind::LLVMFunctionTypeKind => Some(FunctionType::new(self.ty)),
+            _ => None,
+        }
+    }
+
+    pub fn into_pointer_type(self) -> Option<PointerType> {
+        match self.kind() {
+            LLVMTypeKind::LLVMPointerTypeKind => Some(PointerType::new(self.ty)),
+            _ => None,
+        }
+    }
+
     pub fn ptr_type(self, address_space: AddressSpace) -> PointerType {
         unsafe { PointerType::new(LLVMPointerType(self.ty, address_space as u32)) }
     }
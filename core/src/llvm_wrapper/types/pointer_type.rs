@@ -1,3 +1,6 @@
+use crate::llvm_wrapper::types::BasicType;
+use crate::llvm_wrapper::values::pointer_value::PointerValue;
+use llvm_sys::core::{LLVMConstNull, LLVMGetElementType, LLVMGetPointerAddressSpace};
 use llvm_sys::prelude::LLVMTypeRef;
 
 pub struct PointerType {
@@ -8,4 +11,21 @@ impl PointerType {
     pub fn new(ty: LLVMTypeRef) -> Self {
         Self { ty }
     }
+
+    pub fn as_basic_type(&self) -> BasicType {
+        BasicType::new(self.ty)
+    }
+
+    /// the type this pointer points to
+    pub fn element_type(&self) -> BasicType {
+        BasicType::new(unsafe { LLVMGetElementType(self.ty) })
+    }
+
+    pub fn address_space(&self) -> u32 {
+        unsafe { LLVMGetPointerAddressSpace(self.ty) }
+    }
+
+    pub fn const_null(&self) -> PointerValue {
+        PointerValue::new(unsafe { LLVMConstNull(self.ty) })
+    }
 }
@@ -0,0 +1,63 @@
+use crate::llvm_wrapper::types::BasicType;
+use crate::llvm_wrapper::values::BasicValue;
+use llvm_sys::core::{
+    LLVMConstNamedStruct, LLVMCountStructElementTypes, LLVMGetStructElementTypes,
+    LLVMGetStructName, LLVMStructSetBody,
+};
+use std::ffi::CStr;
+use llvm_sys::prelude::LLVMTypeRef;
+
+pub struct StructType {
+    ty: LLVMTypeRef,
+}
+
+impl StructType {
+    pub fn new(ty: LLVMTypeRef) -> Self {
+        Self { ty }
+    }
+
+    pub fn as_basic_type(&self) -> BasicType {
+        BasicType::new(self.ty)
+    }
+
+    /// name of a named struct, `None` for literal structs like tuples
+    pub fn name(&self) -> Option<String> {
+        unsafe {
+            let name = LLVMGetStructName(self.ty);
+            if name.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+            }
+        }
+    }
+
+    pub fn field_types(&self) -> Vec<BasicType> {
+        unsafe {
+            let mut fields = vec![std::ptr::null_mut(); LLVMCountStructElementTypes(self.ty) as usize];
+            LLVMGetStructElementTypes(self.ty, fields.as_mut_ptr());
+            fields.into_iter().map(BasicType::new).collect()
+        }
+    }
+
+    /// constant of this named struct type from constant field values
+    pub fn const_named_struct(&self, values: &[BasicValue]) -> BasicValue {
+        let mut values: Vec<_> = values.iter().filter_map(|it| it.as_llvm_ref()).collect();
+        BasicValue::new(unsafe {
+            LLVMConstNamedStruct(self.ty, values.as_mut_ptr(), values.len() as u32)
+        })
+    }
+
+    pub fn set_body(&self, fields: &[BasicType], packed: bool) {
+        let mut fields_ref: Vec<LLVMTypeRef> =
+            fields.iter().map(|it| it.as_llvm_type_ref()).collect();
+        unsafe {
+            LLVMStructSetBody(
+                self.ty,
+                fields_ref.as_mut_ptr(),
+                fields_ref.len() as u32,
+                packed as i32,
+            )
+        }
+    }
+}
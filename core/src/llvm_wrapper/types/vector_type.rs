@@ -1,3 +1,5 @@
+use crate::llvm_wrapper::types::BasicType;
+use llvm_sys::core::{LLVMGetElementType, LLVMGetVectorSize};
 use llvm_sys::prelude::LLVMTypeRef;
 
 pub struct VectorType {
@@ -8,4 +10,17 @@ impl VectorType {
     pub fn new(ty: LLVMTypeRef) -> Self {
         Self { ty }
     }
+
+    pub fn as_basic_type(&self) -> BasicType {
+        BasicType::new(self.ty)
+    }
+
+    /// number of lanes
+    pub fn len(&self) -> u32 {
+        unsafe { LLVMGetVectorSize(self.ty) }
+    }
+
+    pub fn element_type(&self) -> BasicType {
+        BasicType::new(unsafe { LLVMGetElementType(self.ty) })
+    }
 }
@@ -1,5 +1,17 @@
+use crate::llvm_wrapper::types::BasicType;
+use crate::llvm_wrapper::values::pointer_value::PointerValue;
+use crate::llvm_wrapper::values::BasicValue;
+use llvm_sys::core::{
+    LLVMCountParamTypes, LLVMCountParams, LLVMDeleteFunction, LLVMGetBasicBlockParent,
+    LLVMGetElementType, LLVMGetFirstUse, LLVMGetInstructionParent, LLVMGetNextUse, LLVMGetParam,
+    LLVMGetParamTypes, LLVMGetReturnType, LLVMGetUndef, LLVMGetUser, LLVMGetValueName2,
+    LLVMIsAConstant, LLVMIsAGlobalValue, LLVMIsAInstruction, LLVMReplaceAllUsesWith, LLVMSetGC,
+    LLVMSetLinkage, LLVMTypeOf,
+};
 use llvm_sys::prelude::LLVMValueRef;
+use llvm_sys::LLVMLinkage;
 
+#[derive(Debug, Clone, Copy)]
 pub struct FunctionValue {
     v: LLVMValueRef
 }
@@ -12,4 +24,86 @@ impl FunctionValue {
     pub fn as_llvm_value_ref(&self) -> LLVMValueRef {
         self.v
     }
-}
\ No newline at end of file
+
+    /// the function as a function pointer
+    pub fn as_pointer_value(&self) -> PointerValue {
+        PointerValue::new(self.v)
+    }
+
+    pub fn set_linkage(&self, linkage: LLVMLinkage) {
+        unsafe { LLVMSetLinkage(self.v, linkage) }
+    }
+
+    /// the garbage collector strategy llvm lowers the roots of the function for
+    pub fn set_gc(&self, name: &str) {
+        let name = std::ffi::CString::new(name).unwrap();
+        unsafe { LLVMSetGC(self.v, name.as_ptr()) }
+    }
+
+    /// the symbol of the function
+    pub fn name(&self) -> String {
+        unsafe {
+            let mut len = 0;
+            let name = LLVMGetValueName2(self.v, &mut len);
+            let bytes = std::slice::from_raw_parts(name as *const u8, len);
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+    }
+
+    /// whether anything other than the function itself refers to it, a function only calling
+    /// itself is not used, nor is one only held by constants nothing uses
+    pub fn is_used(&self) -> bool {
+        unsafe fn is_used_by(value: LLVMValueRef, function: LLVMValueRef) -> bool {
+            let mut use_ref = LLVMGetFirstUse(value);
+            while !use_ref.is_null() {
+                let user = LLVMGetUser(use_ref);
+                let used = if !LLVMIsAInstruction(user).is_null() {
+                    LLVMGetBasicBlockParent(LLVMGetInstructionParent(user)) != function
+                } else if !LLVMIsAConstant(user).is_null() && LLVMIsAGlobalValue(user).is_null() {
+                    is_used_by(user, function)
+                } else {
+                    true
+                };
+                if used {
+                    return true;
+                }
+                use_ref = LLVMGetNextUse(use_ref);
+            }
+            false
+        }
+        unsafe { is_used_by(self.v, self.v) }
+    }
+
+    /// remove the function with its body from the module, constants nothing uses may still
+    /// refer to it
+    pub fn delete(self) {
+        unsafe {
+            LLVMReplaceAllUsesWith(self.v, LLVMGetUndef(LLVMTypeOf(self.v)));
+            LLVMDeleteFunction(self.v)
+        }
+    }
+
+    pub fn count_params(&self) -> u32 {
+        unsafe { LLVMCountParams(self.v) }
+    }
+
+    pub fn get_param(&self, index: u32) -> BasicValue {
+        BasicValue::new(unsafe { LLVMGetParam(self.v, index) })
+    }
+
+    /// parameter types from the function type, so that they are known for function pointers
+    /// loaded from a vtable as well
+    pub fn param_types(&self) -> Vec<BasicType> {
+        unsafe {
+            let function_type = LLVMGetElementType(LLVMTypeOf(self.v));
+            let mut types = vec![std::ptr::null_mut(); LLVMCountParamTypes(function_type) as usize];
+            LLVMGetParamTypes(function_type, types.as_mut_ptr());
+            types.into_iter().map(BasicType::new).collect()
+        }
+    }
+
+    pub fn return_type(&self) -> BasicType {
+        // the type of a function value is a pointer to its function type
+        BasicType::new(unsafe { LLVMGetReturnType(LLVMGetElementType(LLVMTypeOf(self.v))) })
+    }
+}
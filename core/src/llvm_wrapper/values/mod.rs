@@ -1,16 +1,20 @@
+use crate::llvm_wrapper::types::BasicType;
+use crate::llvm_wrapper::values::pointer_value::PointerValue;
+use llvm_sys::core::{LLVMIsConstant, LLVMTypeOf};
 use llvm_sys::prelude::LLVMValueRef;
 
 pub mod function_value;
 pub mod int_value;
+pub mod pointer_value;
 pub mod void_value;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum ValueOrVoid {
     Void,
     Value(LLVMValueRef),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct BasicValue {
     v: ValueOrVoid,
 }
@@ -31,4 +35,20 @@ impl BasicValue {
             ValueOrVoid::Value(lvr) => Some(lvr),
         }
     }
+
+    /// `None` for the value of void expressions
+    pub fn get_type(&self) -> Option<BasicType> {
+        self.as_llvm_ref()
+            .map(|lvr| BasicType::new(unsafe { LLVMTypeOf(lvr) }))
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.as_llvm_ref()
+            .map(|lvr| unsafe { LLVMIsConstant(lvr) } == 1)
+            .unwrap_or(false)
+    }
+
+    pub fn into_pointer_value(self) -> PointerValue {
+        PointerValue::new(self.as_llvm_ref().expect("void value is not a pointer"))
+    }
 }
@@ -0,0 +1,33 @@
+use crate::llvm_wrapper::types::BasicType;
+use crate::llvm_wrapper::values::BasicValue;
+use llvm_sys::core::{LLVMConstPointerCast, LLVMGetElementType, LLVMTypeOf};
+use llvm_sys::prelude::LLVMValueRef;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PointerValue {
+    value: LLVMValueRef,
+}
+
+impl PointerValue {
+    pub fn new(value: LLVMValueRef) -> Self {
+        PointerValue { value }
+    }
+
+    pub fn as_llvm_ref(&self) -> LLVMValueRef {
+        self.value
+    }
+
+    pub fn into_basic_value(self) -> BasicValue {
+        BasicValue::new(self.value)
+    }
+
+    /// the type of the value the pointer points to
+    pub fn pointee_type(&self) -> BasicType {
+        BasicType::new(unsafe { LLVMGetElementType(LLVMTypeOf(self.value)) })
+    }
+
+    /// the constant pointer cast to another pointer type, used in global initializers
+    pub fn const_pointer_cast(&self, ty: &BasicType) -> PointerValue {
+        PointerValue::new(unsafe { LLVMConstPointerCast(self.value, ty.as_llvm_type_ref()) })
+    }
+}
@@ -0,0 +1,272 @@
+use crate::codegen::{
+    auto_deref, bind_pattern, enum_instance, build_function_call, expr_codegen_as, function_for_call, source_type,
+    struct_field_ptr, temporary_place, to_basic_type, variant_payload_type, ExprCodegen,
+    PlaceCodegen,
+};
+use crate::intrinsics::call_trait_method;
+use crate::llvm_wrapper::builder::TyphoonBuilder;
+use crate::llvm_wrapper::context::TyphoonContext;
+use crate::llvm_wrapper::module::TyphoonModule;
+use crate::llvm_wrapper::types::AddressSpace;
+use crate::llvm_wrapper::values::pointer_value::PointerValue;
+use crate::llvm_wrapper::values::BasicValue;
+use crate::scope::Scope;
+use crate::traits::{implements, type_name, ITER, ITERATOR, NEXT, OPTION, RANGE, RANGE_INCLUSIVE};
+use ast::{EnumDeclare, Expr, Pattern, Type};
+use llvm_sys::{LLVMIntPredicate, LLVMOpcode};
+
+/// `start..end` as a `Range` and `start..=end` as a `RangeInclusive` of the standard library
+pub(crate) fn range_codegen(
+    start: Expr,
+    end: Expr,
+    inclusive: bool,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    let environment = scope.environment();
+    let name = if inclusive { RANGE_INCLUSIVE } else { RANGE };
+    let struct_decl = environment
+        .get_struct(name)
+        .unwrap_or_else(|| panic!("struct {} is undefined", name));
+    let i64_type = context.i64_type().as_basic_type();
+    let start = expr_codegen_as(start, &i64_type, context, builder, module, scope);
+    let end = expr_codegen_as(end, &i64_type, context, builder, module, scope);
+    let ty = to_basic_type(&Type::named(name.to_string()), context, environment);
+    let range = builder.build_insert_value(
+        &ty.get_undef(),
+        &start,
+        struct_decl.field_index("start").unwrap(),
+        "range",
+    );
+    let range = builder.build_insert_value(
+        &range,
+        &end,
+        struct_decl.field_index("end").unwrap(),
+        "range",
+    );
+    match struct_decl.field_index("done") {
+        Some(index) => {
+            let zero = context.i64_type().const_int(0, false).into_basic_value();
+            builder.build_insert_value(&range, &zero, index, "range")
+        }
+        None => range,
+    }
+}
+
+/// `for pattern in iterable { body }`, a range literal bound to a variable is a counted
+/// loop and an array is iterated by its index, its elements are copied out of it unless it
+/// is iterated through a reference, any other value is moved into the loop when it is an
+/// `Iterator` and borrowed by its `iter` method otherwise
+pub(crate) fn for_codegen(
+    pattern: Pattern,
+    iterable: Expr,
+    body: Expr,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    let unit = context.void_type().const_value().into_basic_value();
+    let environment = scope.environment();
+    let (iterable, pattern) = match (iterable, pattern) {
+        (
+            Expr::Range {
+                start,
+                end,
+                inclusive,
+            },
+            pattern @ Pattern::Identifier(_),
+        ) => {
+            trace!("build counted loop");
+            let i64_type = context.i64_type().as_basic_type();
+            let start = expr_codegen_as(*start, &i64_type, context, builder, module, scope);
+            let end = expr_codegen_as(*end, &i64_type, context, builder, module, scope);
+            build_counted_loop(start, end, inclusive, context, builder, |index| {
+                let scope = Scope::new_with_upper(scope);
+                bind_pattern(pattern, *index, context, builder, module, &scope);
+                body.expr_codegen(context, builder, module, &scope);
+            });
+            return unit;
+        }
+        other => other,
+    };
+    let ptr = iterable.place_codegen(context, builder, module, scope);
+    let by_reference = ptr.pointee_type().into_pointer_type().is_some();
+    let target = auto_deref(builder, ptr);
+    if let Some(array_type) = target.pointee_type().into_array_type() {
+        trace!("build array loop");
+        let array = if by_reference {
+            target
+        } else {
+            temporary_place(
+                builder.build_load(&target, "array"),
+                context,
+                builder,
+                module,
+                scope,
+            )
+        };
+        let i64_type = context.i64_type();
+        let start = i64_type.const_int(0, false).into_basic_value();
+        let end = i64_type
+            .const_int(array_type.len() as u64, false)
+            .into_basic_value();
+        build_counted_loop(start, end, false, context, builder, |index| {
+            let zero = i64_type.const_int(0, false).into_basic_value();
+            let element = builder.build_in_bounds_gep(&array, &[zero, *index], "element_ptr");
+            let item = if by_reference {
+                element.into_basic_value()
+            } else {
+                builder.build_load(&element, "element")
+            };
+            let scope = Scope::new_with_upper(scope);
+            bind_pattern(pattern, item, context, builder, module, &scope);
+            body.expr_codegen(context, builder, module, &scope);
+        });
+        return unit;
+    }
+    trace!("build iterator loop");
+    let ty = source_type(target.pointee_type(), environment);
+    let iterator = if implements(&ty, ITERATOR, environment) {
+        builder.build_load(&target, "iterator")
+    } else {
+        let func_decl = type_name(&ty)
+            .and_then(|name| environment.get_method(name, ITER))
+            .filter(|func_decl| func_decl.is_method())
+            .unwrap_or_else(|| panic!("{} is not an iterator and has no {} method", ty, ITER));
+        let receiver = match &func_decl.args[0].1 {
+            Type::Reference { .. } => target.into_basic_value(),
+            _ => builder.build_load(&target, "self"),
+        };
+        let function = function_for_call(
+            &func_decl.name,
+            &[receiver],
+            None,
+            context,
+            builder,
+            module,
+            environment,
+        );
+        let iterator = build_function_call(
+            &func_decl.name,
+            &function,
+            vec![receiver],
+            context,
+            builder,
+            module,
+            environment,
+        );
+        let iterator_type = source_type(iterator.get_type().unwrap(), environment);
+        if !implements(&iterator_type, ITERATOR, environment) {
+            panic!(
+                "{} returned by {}::{} is not an iterator",
+                iterator_type, ty, ITER
+            );
+        }
+        iterator
+    };
+    let iterator_type = source_type(iterator.get_type().unwrap(), environment);
+    let iterator = temporary_place(iterator, context, builder, module, scope);
+
+    let function = builder.current_function();
+    let header = context.append_basic_block(function, "for_header");
+    let body_block = context.append_basic_block(function, "for_body");
+    let end_block = context.append_basic_block(function, "for_end");
+    builder.build_branch(&header);
+
+    builder.position_at_end(&header);
+    let item = call_trait_method(
+        &iterator_type,
+        (ITERATOR, NEXT),
+        vec![iterator.into_basic_value()],
+        context,
+        builder,
+        module,
+        environment,
+    );
+    let option = item_option(&item, scope);
+    let (tag, some) = option.variant("Some").unwrap();
+    let item = temporary_place(item, context, builder, module, scope);
+    let is_some = builder.build_int_compare(
+        LLVMIntPredicate::LLVMIntEQ,
+        &builder.build_load(&struct_field_ptr(context, builder, &item, 0), "tag"),
+        &context
+            .i32_type()
+            .const_int(tag as u64, false)
+            .into_basic_value(),
+        "is_some",
+    );
+    builder.build_conditional_branch(&is_some, &body_block, &end_block);
+
+    builder.position_at_end(&body_block);
+    let payload_type = variant_payload_type(some, context, environment);
+    let payload = builder.build_pointer_cast(
+        &struct_field_ptr(context, builder, &item, 1),
+        &payload_type.ptr_type(AddressSpace::Generic).as_basic_type(),
+        "payload",
+    );
+    let value = builder.build_load(&struct_field_ptr(context, builder, &payload, 0), "item");
+    let body_scope = Scope::new_with_upper(scope);
+    bind_pattern(pattern, value, context, builder, module, &body_scope);
+    body.expr_codegen(context, builder, module, &body_scope);
+    if !builder.is_terminated() {
+        builder.build_branch(&header);
+    }
+    builder.position_at_end(&end_block);
+    unit
+}
+
+/// the instance of `Option` the `next` method of an iterator returned `item` as
+fn item_option(item: &BasicValue, scope: &Scope) -> EnumDeclare {
+    item.get_type()
+        .and_then(|ty| enum_instance(&ty, OPTION, scope.environment()))
+        .unwrap_or_else(|| panic!("{} of an iterator does not return an {}", NEXT, OPTION))
+}
+
+/// run `body` with every index from `start` up to `end`, which is included when `inclusive`,
+/// the index is compared before it is incremented, so that it never overflows
+fn build_counted_loop(
+    start: BasicValue,
+    end: BasicValue,
+    inclusive: bool,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    body: impl FnOnce(&BasicValue),
+) {
+    let i64_type = context.i64_type();
+    let counter: PointerValue = builder.build_entry_alloca(&i64_type.as_basic_type(), "counter");
+    builder.build_store(&counter, &start);
+    let function = builder.current_function();
+    let header = context.append_basic_block(function, "for_header");
+    let body_block = context.append_basic_block(function, "for_body");
+    let end_block = context.append_basic_block(function, "for_end");
+    builder.build_branch(&header);
+
+    builder.position_at_end(&header);
+    let index = builder.build_load(&counter, "index");
+    let predicate = if inclusive {
+        LLVMIntPredicate::LLVMIntSLE
+    } else {
+        LLVMIntPredicate::LLVMIntSLT
+    };
+    let within = builder.build_int_compare(predicate, &index, &end, "within");
+    builder.build_conditional_branch(&within, &body_block, &end_block);
+
+    builder.position_at_end(&body_block);
+    body(&index);
+    if !builder.is_terminated() {
+        if inclusive {
+            let last = builder.build_int_compare(LLVMIntPredicate::LLVMIntEQ, &index, &end, "last");
+            let next_block = context.append_basic_block(function, "for_next");
+            builder.build_conditional_branch(&last, &end_block, &next_block);
+            builder.position_at_end(&next_block);
+        }
+        let one = i64_type.const_int(1, false).into_basic_value();
+        let next = builder.build_binop(LLVMOpcode::LLVMAdd, &index, &one, "next");
+        builder.build_store(&counter, &next);
+        builder.build_branch(&header);
+    }
+    builder.position_at_end(&end_block);
+}
@@ -0,0 +1,310 @@
+use ast::{FunctionDeclare, Identifier, Type};
+
+/// the symbol of a function, or of the instance of a generic function for `arguments`
+///
+/// `main` and `extern` functions keep their source name, so that the C runtime and C code
+/// find them, every other function is mangled
+pub(crate) fn function_symbol(func_decl: &FunctionDeclare, arguments: &[Type]) -> String {
+    if func_decl.is_extern || func_decl.name == "main" {
+        func_decl.name.clone()
+    } else {
+        mangle(&func_decl.name, arguments)
+    }
+}
+
+/// the symbol of closure number `index` of the function with the symbol `enclosing`
+pub(crate) fn closure_symbol(enclosing: &str, index: usize) -> String {
+    format!("{}C{}_", mangled(enclosing), index)
+}
+
+/// the symbol of the function taking an unused environment that calls the function with the
+/// symbol `function`, so that it can be used as a function value like a closure
+pub(crate) fn shim_symbol(function: &str) -> String {
+    format!("{}S", mangled(function))
+}
+
+/// `main` and `extern` functions are mangled as the item they are before a suffix is added
+fn mangled(symbol: &str) -> String {
+    if symbol.starts_with("_TN") {
+        symbol.to_string()
+    } else {
+        mangle(symbol, &[])
+    }
+}
+
+/// mangle the path of an item `geo::shapes::area` with the type arguments of an instance,
+/// the symbol only uses `[A-Za-z0-9_]`, so it is a valid C identifier and never clashes with
+/// the functions of libc
+///
+/// rule:
+///  - SYMBOL: _T NAME SUFFIX*
+///  - SUFFIX: C INDEX _ for closure number INDEX of the function, S for its shim
+///  - NAME: N SEGMENT+ (I TYPE+ E)? E
+///  - SEGMENT: LENGTH IDENTIFIER, the length is the number of bytes in decimal
+///  - TYPE:
+///    - a | s | i | x for i8, i16, i32 and i64, f | d for f32 and f64
+///    - NAME for structs and enums
+///    - R TYPE | Q TYPE | P TYPE for &TYPE, &mut TYPE and *TYPE
+///    - B TYPE for Box<TYPE>
+///    - A LENGTH _ TYPE for [TYPE; LENGTH]
+///    - V LENGTH _ TYPE for simd<TYPE, LENGTH>
+///    - T TYPE* E for tuples, `()` is `TE`
+///    - D NAME for dyn TRAIT, with the type arguments of a generic trait in NAME
+///    - F TYPE* E TYPE for fn(TYPE, ...) -> TYPE
+///
+/// `geo::shapes::area` is `_TN3geo6shapes4areaE`, `max<i32>` is `_TN3maxIiEE`,
+/// `Pair::swap<i32, &geo::Point>` is `_TN4Pair4swapIiRN3geo5PointEEE` and the first closure
+/// of `main` is `_TN4mainEC0_`
+pub fn mangle(name: &str, arguments: &[Type]) -> String {
+    let mut symbol = "_T".to_string();
+    mangle_name(&mut symbol, name, arguments);
+    symbol
+}
+
+/// the prefix of the symbols of the items of the module `path`, `_TN3geo` for `geo`
+pub(crate) fn module_prefix(path: &str) -> String {
+    let mut symbol = mangle(path, &[]);
+    symbol.pop();
+    symbol
+}
+
+fn mangle_name(symbol: &mut String, name: &str, arguments: &[Type]) {
+    symbol.push('N');
+    for segment in name.split("::") {
+        symbol.push_str(&format!("{}{}", segment.len(), segment));
+    }
+    if !arguments.is_empty() {
+        symbol.push('I');
+        arguments.iter().for_each(|it| mangle_type(symbol, it));
+        symbol.push('E');
+    }
+    symbol.push('E');
+}
+
+fn mangle_type(symbol: &mut String, ty: &Type) {
+    match ty {
+        Type::Named(name) => match name.as_str() {
+            "i8" => symbol.push('a'),
+            "i16" => symbol.push('s'),
+            "i32" => symbol.push('i'),
+            "i64" => symbol.push('x'),
+            "f32" => symbol.push('f'),
+            "f64" => symbol.push('d'),
+            name => mangle_name(symbol, name, &[]),
+        },
+        Type::Generic(name, arguments) => mangle_name(symbol, name, arguments),
+        Type::Reference { mutable: false, inner } => {
+            symbol.push('R');
+            mangle_type(symbol, inner);
+        }
+        Type::Reference { mutable: true, inner } => {
+            symbol.push('Q');
+            mangle_type(symbol, inner);
+        }
+        Type::Pointer(inner) => {
+            symbol.push('P');
+            mangle_type(symbol, inner);
+        }
+        Type::Boxed(inner) => {
+            symbol.push('B');
+            mangle_type(symbol, inner);
+        }
+        Type::Array(element, len) => {
+            symbol.push_str(&format!("A{}_", len));
+            mangle_type(symbol, element);
+        }
+        Type::Simd(element, lanes) => {
+            symbol.push_str(&format!("V{}_", lanes));
+            mangle_type(symbol, element);
+        }
+        Type::Tuple(elements) => {
+            symbol.push('T');
+            elements.iter().for_each(|it| mangle_type(symbol, it));
+            symbol.push('E');
+        }
+        Type::Dyn(trait_name, arguments) => {
+            symbol.push('D');
+            mangle_name(symbol, trait_name, arguments);
+        }
+        Type::Function(parameters, return_type) => {
+            symbol.push('F');
+            parameters.iter().for_each(|it| mangle_type(symbol, it));
+            symbol.push('E');
+            mangle_type(symbol, return_type);
+        }
+    }
+}
+
+/// the source name of a mangled symbol, `_TN3maxIiEE` is `max<i32>`, `None` when `symbol`
+/// is not a symbol mangled by typhoon
+pub fn demangle(symbol: &str) -> Option<String> {
+    let mut demangler = Demangler { input: symbol.as_bytes(), position: 0 };
+    let name = demangler.symbol()?;
+    (demangler.position == symbol.len()).then_some(name)
+}
+
+/// demangle every symbol found in `text`, such as the output of `nm` or a backtrace, and
+/// leave the rest of it as it is
+pub fn demangle_text(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("_TN") {
+        output.push_str(&rest[..start]);
+        let mut demangler = Demangler { input: &rest.as_bytes()[start..], position: 0 };
+        match demangler.symbol() {
+            Some(name) => {
+                output.push_str(&name);
+                rest = &rest[start + demangler.position..];
+            }
+            None => {
+                output.push_str("_TN");
+                rest = &rest[start + 3..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+struct Demangler<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Demangler<'a> {
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.input.get(self.position).copied()?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        (self.next()? == byte).then_some(())
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.position;
+        while self.peek().is_some_and(|it| it.is_ascii_digit()) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.position]).ok()?.parse().ok()
+    }
+
+    fn symbol(&mut self) -> Option<String> {
+        self.expect(b'_')?;
+        self.expect(b'T')?;
+        let mut name = self.name()?.to_string();
+        loop {
+            match self.peek() {
+                Some(b'C') => {
+                    self.position += 1;
+                    let index = self.number()?;
+                    self.expect(b'_')?;
+                    name.push_str(&format!("::{{closure#{}}}", index));
+                }
+                Some(b'S') => {
+                    self.position += 1;
+                    name.push_str("::{shim}");
+                }
+                _ => return Some(name),
+            }
+        }
+    }
+
+    /// a path with its type arguments, as the type it names
+    fn name(&mut self) -> Option<Type> {
+        self.expect(b'N')?;
+        let mut segments: Vec<Identifier> = vec![];
+        while self.peek()?.is_ascii_digit() {
+            let len = self.number()?;
+            let segment = self.input.get(self.position..self.position + len)?;
+            segments.push(String::from_utf8(segment.to_vec()).ok()?);
+            self.position += len;
+        }
+        if segments.is_empty() {
+            return None;
+        }
+        let mut arguments = vec![];
+        if self.peek()? == b'I' {
+            self.position += 1;
+            while self.peek()? != b'E' {
+                arguments.push(self.ty()?);
+            }
+            self.position += 1;
+        }
+        self.expect(b'E')?;
+        let name = segments.join("::");
+        Some(if arguments.is_empty() {
+            Type::named(name)
+        } else {
+            Type::generic(name, arguments)
+        })
+    }
+
+    fn ty(&mut self) -> Option<Type> {
+        let ty = match self.peek()? {
+            b'N' => return self.name(),
+            b'a' => Type::named("i8".to_string()),
+            b's' => Type::named("i16".to_string()),
+            b'i' => Type::named("i32".to_string()),
+            b'x' => Type::named("i64".to_string()),
+            b'f' => Type::named("f32".to_string()),
+            b'd' => Type::named("f64".to_string()),
+            b'R' | b'Q' => {
+                let mutable = self.next()? == b'Q';
+                return Some(Type::reference(self.ty()?, mutable));
+            }
+            b'P' => {
+                self.position += 1;
+                return Some(Type::pointer(self.ty()?));
+            }
+            b'B' => {
+                self.position += 1;
+                return Some(Type::boxed(self.ty()?));
+            }
+            b'A' | b'V' => {
+                let kind = self.next()?;
+                let len = self.number()? as u64;
+                self.expect(b'_')?;
+                let element = self.ty()?;
+                return Some(match kind {
+                    b'A' => Type::array(element, len),
+                    _ => Type::simd(element, len),
+                });
+            }
+            b'T' => {
+                self.position += 1;
+                let mut elements = vec![];
+                while self.peek()? != b'E' {
+                    elements.push(self.ty()?);
+                }
+                self.position += 1;
+                return Some(Type::tuple(elements));
+            }
+            b'F' => {
+                self.position += 1;
+                let mut parameters = vec![];
+                while self.peek()? != b'E' {
+                    parameters.push(self.ty()?);
+                }
+                self.position += 1;
+                return Some(Type::function(parameters, self.ty()?));
+            }
+            b'D' => {
+                self.position += 1;
+                return match self.name()? {
+                    Type::Named(trait_name) => Some(Type::Dyn(trait_name, vec![])),
+                    Type::Generic(trait_name, arguments) => Some(Type::Dyn(trait_name, arguments)),
+                    _ => None,
+                };
+            }
+            _ => return None,
+        };
+        self.position += 1;
+        Some(ty)
+    }
+}
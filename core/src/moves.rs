@@ -0,0 +1,173 @@
+use crate::cfg::{Access, ControlFlowGraph, Items, Place};
+use crate::traits::COPY;
+use ast::{FunctionDeclare, Identifier, ImplBlock, Location, Module, ModuleItem, Type};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+/// a value used after it was moved, on some path through the function
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveError {
+    pub function: Identifier,
+    /// the value as written, a variable or a field of one `p.x`
+    pub value: String,
+    /// type of the moved value, which is not `Copy`
+    pub ty: Type,
+    pub moved: Location,
+    pub used: Location,
+    /// whether the value is borrowed rather than used
+    pub borrowed: bool,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of moved value `{}` in {} at {}, moved at {} since {} is not Copy",
+            if self.borrowed { "borrow" } else { "use" },
+            self.value,
+            self.function,
+            self.used,
+            self.moved,
+            self.ty
+        )
+    }
+}
+
+/// places which may be moved, with the location of a move
+type Moved = HashMap<Place, Location>;
+
+/// checks that values are not used once they are moved, a value of a type which is not
+/// `Copy` is moved when it is passed, returned or stored by value, and can be used again
+/// once it is assigned
+pub struct MoveChecker {
+    items: Items,
+}
+
+impl MoveChecker {
+    /// an error when a type implementing `Copy` holds a value which is not `Copy`
+    pub fn new(module: &Module) -> Result<Self, String> {
+        let items = Items::new(module);
+        for item in module.items.iter() {
+            if let ModuleItem::Impl(impl_block) = item.as_ref() {
+                if impl_block.trait_name.as_deref() == Some(COPY) {
+                    check_copy_impl(impl_block, &items)?;
+                }
+            }
+        }
+        Ok(MoveChecker { items })
+    }
+
+    /// the uses of moved values in the functions of the module, generic functions are
+    /// checked once with type parameters which are not `Copy` unless bound by it
+    pub fn check_module(&self, module: &Module) -> Vec<MoveError> {
+        module
+            .items
+            .iter()
+            .flat_map(|item| match item.as_ref() {
+                ModuleItem::FunctionDeclare(function) => vec![function.clone()],
+                ModuleItem::Impl(impl_block) => impl_block.qualified_functions(),
+                _ => vec![],
+            })
+            .flat_map(|function| self.check_function(&function))
+            .collect()
+    }
+
+    /// the uses of moved values in `function` and in its closures, ordered by location
+    pub fn check_function(&self, function: &FunctionDeclare) -> Vec<MoveError> {
+        let graph = ControlFlowGraph::new(function, &self.items);
+        let mut errors = vec![];
+        self.check_graph(&graph, &mut errors);
+        errors.sort_by_key(|error| (error.used.line, error.used.column));
+        errors.dedup();
+        errors
+    }
+
+    fn check_graph(&self, graph: &ControlFlowGraph, errors: &mut Vec<MoveError>) {
+        // the places which may be moved at the start of each block, until nothing changes
+        let mut entries: Vec<Option<Moved>> = vec![None; graph.blocks.len()];
+        entries[0] = Some(Moved::new());
+        let mut pending = vec![0];
+        while let Some(block) = pending.pop() {
+            let mut moved = entries[block].clone().unwrap();
+            self.transfer(graph, block, &mut moved, &mut vec![]);
+            for successor in graph.blocks[block].successors.iter() {
+                let first_visit = entries[*successor].is_none();
+                let entry = entries[*successor].get_or_insert_with(Moved::new);
+                let before = entry.len();
+                for (place, location) in moved.iter() {
+                    entry.entry(place.clone()).or_insert(*location);
+                }
+                if (first_visit || entry.len() != before) && !pending.contains(successor) {
+                    pending.push(*successor);
+                }
+            }
+        }
+        for (block, entry) in entries.into_iter().enumerate() {
+            if let Some(mut moved) = entry {
+                self.transfer(graph, block, &mut moved, errors);
+            }
+        }
+        for closure in graph.closures.iter() {
+            self.check_graph(closure, errors);
+        }
+    }
+
+    /// apply the events of `block`, reporting the uses of moved places
+    fn transfer(&self, graph: &ControlFlowGraph, block: usize, moved: &mut Moved, errors: &mut Vec<MoveError>) {
+        for event in graph.blocks[block].events.iter() {
+            match event.access {
+                Access::Dead => continue,
+                // a write through a pointer or an index only uses the place
+                Access::Write if event.through.is_none() => {
+                    moved.retain(|place, _| !event.place.contains(place));
+                    continue;
+                }
+                _ => {}
+            }
+            let conflict = moved
+                .iter()
+                .filter(|(place, _)| place.overlaps(&event.place))
+                .min_by_key(|(_, location)| (location.line, location.column));
+            if let Some((place, location)) = conflict {
+                errors.push(MoveError {
+                    function: graph.function.clone(),
+                    value: graph.describe(&event.place),
+                    ty: graph.place_type(place, &self.items).unwrap_or_else(Type::void),
+                    moved: *location,
+                    used: event.location,
+                    borrowed: matches!(event.access, Access::Borrow { .. }),
+                });
+            }
+            if event.access == Access::Move {
+                moved.insert(event.place.clone(), event.location);
+            }
+        }
+    }
+}
+
+/// every field of a `Copy` struct, and of every variant of a `Copy` enum, is `Copy`, the
+/// type parameters of the `impl` block are taken as `Copy`
+fn check_copy_impl(impl_block: &ImplBlock, items: &Items) -> Result<(), String> {
+    let copy_parameters: HashSet<Identifier> = impl_block.generics.iter().cloned().collect();
+    let fields: Vec<(String, Type)> = match (items.fields(&impl_block.target()), items.get_enum(&impl_block.name)) {
+        (Some(fields), _) => fields,
+        (None, Some(enum_decl)) => enum_decl
+            .variants
+            .iter()
+            .flat_map(|variant| {
+                variant
+                    .field_types()
+                    .into_iter()
+                    .map(move |ty| (format!("of variant {}", variant.name), ty.clone()))
+            })
+            .collect(),
+        (None, None) => vec![],
+    };
+    match fields.iter().find(|(_, ty)| !items.is_copy(ty, &copy_parameters)) {
+        Some((field, ty)) => Err(format!(
+            "Copy cannot be implemented for {}, field {} of type {} is not Copy",
+            impl_block.name, field, ty
+        )),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,281 @@
+use crate::codegen::build_with_overflow;
+use crate::llvm_wrapper::builder::TyphoonBuilder;
+use crate::llvm_wrapper::context::TyphoonContext;
+use crate::llvm_wrapper::module::TyphoonModule;
+use crate::llvm_wrapper::types::{AddressSpace, BasicType};
+use crate::llvm_wrapper::values::BasicValue;
+use crate::scope::{Environment, Scope};
+use ast::{Expr, Location, Opcode};
+use llvm_sys::{LLVMIntPredicate, LLVMOpcode};
+
+/// the panic handler of the runtime, it prints the message it is passed together with the
+/// location and a backtrace and exits the program with a status of its own
+pub(crate) const PANIC: &str = "typhoon_panic";
+
+/// the panic handler of an index out of bounds, it is passed the index and the length
+pub(crate) const PANIC_BOUNDS_CHECK: &str = "typhoon_panic_bounds_check";
+
+/// the builtin `panic("message")`, it is called like a function which no module defines
+pub(crate) const PANIC_BUILTIN: &str = "panic";
+
+/// `panic("message")` stops the program with the message and the location of the call
+pub(crate) fn panic_codegen(
+    args: Vec<Expr>,
+    location: Location,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    scope: &Scope,
+) -> BasicValue {
+    let environment = scope.environment();
+    let message = match args.as_slice() {
+        [Expr::String(message)] => Some(message.clone()),
+        _ => None,
+    };
+    let message = message.unwrap_or_else(|| {
+        panic!(
+            "{} takes a string literal as its message at {}:{}",
+            PANIC_BUILTIN, &environment.source_name, location
+        )
+    });
+    build_panic(
+        &message,
+        Some(location),
+        context,
+        builder,
+        module,
+        environment,
+    );
+    // the code following the call is unreachable, but it still needs a block
+    let block = context.append_basic_block(builder.current_function(), "after_panic");
+    builder.position_at_end(&block);
+    context.void_type().const_value().into_basic_value()
+}
+
+/// stop the program with `message` raised at `location`, which ends the current block
+pub(crate) fn build_panic(
+    message: &str,
+    location: Option<Location>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) {
+    let message = builder.build_global_string_ptr(message, "panic_message");
+    build_handler_call(
+        PANIC,
+        vec![message.into_basic_value()],
+        location,
+        context,
+        builder,
+        module,
+        environment,
+    );
+}
+
+/// continue when `condition` holds and panic with `message` otherwise
+pub(crate) fn build_check(
+    condition: &BasicValue,
+    message: &str,
+    location: Option<Location>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) {
+    let function = builder.current_function();
+    let fail_block = context.append_basic_block(function, "check_failed");
+    let ok_block = context.append_basic_block(function, "check_passed");
+    builder.build_conditional_branch(condition, &ok_block, &fail_block);
+
+    builder.position_at_end(&fail_block);
+    build_panic(message, location, context, builder, module, environment);
+
+    builder.position_at_end(&ok_block);
+}
+
+/// panic with a message pointing at the indexing expression when `index` is not below
+/// `len`, the unsigned comparison catches negative indexes as well
+pub(crate) fn build_bounds_check(
+    index: &BasicValue,
+    len: &BasicValue,
+    location: Option<Location>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) {
+    let in_bounds =
+        builder.build_int_compare(LLVMIntPredicate::LLVMIntULT, index, len, "in_bounds");
+
+    let function = builder.current_function();
+    let fail_block = context.append_basic_block(function, "index_out_of_bounds");
+    let ok_block = context.append_basic_block(function, "index_in_bounds");
+    builder.build_conditional_branch(&in_bounds, &ok_block, &fail_block);
+
+    builder.position_at_end(&fail_block);
+    build_handler_call(
+        PANIC_BOUNDS_CHECK,
+        vec![*index, *len],
+        location,
+        context,
+        builder,
+        module,
+        environment,
+    );
+
+    builder.position_at_end(&ok_block);
+}
+
+/// `lhs / rhs` and `lhs % rhs` of integers panic when `rhs` is zero and when the result
+/// overflows, as the one of `i64::MIN / -1` does
+pub(crate) fn build_division_check(
+    opcode: Opcode,
+    (lhs, rhs): (&BasicValue, &BasicValue),
+    location: Location,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) {
+    let ty = rhs.get_type().unwrap();
+    let width = match ty.int_width() {
+        Some(width) => width,
+        None => return,
+    };
+    let (zero_message, overflow_message) = match opcode {
+        Opcode::Div => (
+            "attempt to divide by zero",
+            "attempt to divide with overflow",
+        ),
+        _ => (
+            "attempt to calculate the remainder with a divisor of zero",
+            "attempt to calculate the remainder with overflow",
+        ),
+    };
+    let not_zero = builder.build_int_compare(
+        LLVMIntPredicate::LLVMIntNE,
+        rhs,
+        &ty.const_zero(),
+        "not_zero",
+    );
+    let location = Some(location);
+    build_check(
+        &not_zero,
+        zero_message,
+        location,
+        context,
+        builder,
+        module,
+        environment,
+    );
+
+    let min = ty.const_int(1 << (width - 1), false);
+    let minus_one = ty.const_int(u64::MAX, true);
+    let is_min = builder.build_int_compare(LLVMIntPredicate::LLVMIntEQ, lhs, &min, "is_min");
+    let is_minus_one =
+        builder.build_int_compare(LLVMIntPredicate::LLVMIntEQ, rhs, &minus_one, "is_minus_one");
+    let overflows = builder.build_binop(LLVMOpcode::LLVMAnd, &is_min, &is_minus_one, "overflows");
+    let bool_type = context.int_type(1).as_basic_type();
+    let no_overflow = builder.build_int_compare(
+        LLVMIntPredicate::LLVMIntEQ,
+        &overflows,
+        &bool_type.const_zero(),
+        "no_overflow",
+    );
+    build_check(
+        &no_overflow,
+        overflow_message,
+        location,
+        context,
+        builder,
+        module,
+        environment,
+    );
+}
+
+/// `lhs + rhs`, `lhs - rhs` and `lhs * rhs` of integers in the checked overflow mode,
+/// panicking when the result overflows, `None` for other operations
+pub(crate) fn build_checked_arithmetic(
+    opcode: Opcode,
+    (lhs, rhs): (&BasicValue, &BasicValue),
+    location: Location,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) -> Option<BasicValue> {
+    let (value, overflows) = build_with_overflow(opcode, lhs, rhs, context, builder, module)?;
+    let bool_type = context.int_type(1).as_basic_type();
+    let no_overflow = builder.build_int_compare(
+        LLVMIntPredicate::LLVMIntEQ,
+        &overflows,
+        &bool_type.const_zero(),
+        "no_overflow",
+    );
+    let verb = match opcode {
+        Opcode::Add => "add",
+        Opcode::Sub => "subtract",
+        _ => "multiply",
+    };
+    let message = format!("attempt to {} with overflow", verb);
+    build_check(
+        &no_overflow,
+        &message,
+        Some(location),
+        context,
+        builder,
+        module,
+        environment,
+    );
+    Some(value)
+}
+
+/// call the panic handler `name` with `arguments` followed by a pointer to the constant
+/// `{ i8* file, i32 line, i32 column }` of `location`, null when the location is unknown
+fn build_handler_call(
+    name: &str,
+    mut arguments: Vec<BasicValue>,
+    location: Option<Location>,
+    context: &TyphoonContext,
+    builder: &TyphoonBuilder,
+    module: &TyphoonModule,
+    environment: &Environment,
+) {
+    let i8_pointer = context
+        .i8_type()
+        .as_basic_type()
+        .ptr_type(AddressSpace::Generic)
+        .as_basic_type();
+    let location = match location {
+        Some(location) => {
+            let i32_type = context.i32_type();
+            let value = context.const_struct(
+                &[
+                    builder
+                        .build_global_string_ptr(&environment.source_name, "panic_file")
+                        .into_basic_value(),
+                    i32_type.const_int(location.line as u64, false).into_basic_value(),
+                    i32_type.const_int(location.column as u64, false).into_basic_value(),
+                ],
+                false,
+            );
+            module
+                .add_global_constant("panic_location", &value)
+                .const_pointer_cast(&i8_pointer)
+                .into_basic_value()
+        }
+        None => i8_pointer.const_zero(),
+    };
+    arguments.push(location);
+    let parameters: Vec<BasicType> = arguments.iter().map(|it| it.get_type().unwrap()).collect();
+    let handler = module.get_or_add_function(
+        name,
+        context
+            .void_type()
+            .as_basic_type()
+            .fn_type(&parameters, false),
+    );
+    builder.build_call(&handler, &arguments);
+    builder.build_unreachable();
+}
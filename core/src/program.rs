@@ -1,7 +1,14 @@
+use crate::borrows::BorrowChecker;
+use crate::c_header::CHeader;
 use crate::codegen::Codegen;
-use crate::error::TyphoonError;
+use crate::error::{catch, TyphoonError};
+use crate::gc;
+use crate::moves::MoveChecker;
+use crate::panics;
 use crate::llvm_wrapper::context::TyphoonContext;
-use ast::Module;
+use crate::resolver::{resolve_with_prelude, SourceModule};
+use crate::scope::Environment;
+use ast::{Module, ModuleItem};
 use llvm_sys::core::LLVMPrintModuleToString;
 use llvm_sys::target::{
     LLVM_InitializeAllAsmParsers, LLVM_InitializeAllAsmPrinters, LLVM_InitializeAllTargetInfos,
@@ -12,8 +19,11 @@ use llvm_sys::target_machine::{
     LLVMGetDefaultTargetTriple, LLVMGetTargetFromTriple, LLVMGetTargetName, LLVMRelocMode,
     LLVMTargetMachineEmitToFile, LLVMTargetRef,
 };
-use parser::parser::parse_module;
+use parser::parser::{error_location, parse_module};
+use std::collections::HashMap;
+use std::os::unix::process::ExitStatusExt;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::SystemTime;
 use std::{
     ffi::{CStr, CString},
@@ -22,22 +32,207 @@ use std::{
     ptr,
 };
 
+/// kind of artifact produced by `typhoon build`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrateType {
+    /// executable linked with `main` as entry point
+    Bin,
+    /// `lib<name>.a` archive
+    StaticLib,
+    /// `lib<name>.so` shared object
+    CDylib,
+}
+
+impl FromStr for CrateType {
+    type Err = TyphoonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bin" => Ok(CrateType::Bin),
+            "staticlib" => Ok(CrateType::StaticLib),
+            "cdylib" => Ok(CrateType::CDylib),
+            other => Err(TyphoonError::CompileError(format!(
+                "unknown crate type {}, expected one of bin, staticlib, cdylib",
+                other
+            ))),
+        }
+    }
+}
+
+/// what `+`, `-` and `*` of integers do when the result does not fit their type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// panic with the location of the operation
+    Checked,
+    /// wrap around in two's complement
+    Wrapping,
+}
+
+impl FromStr for Overflow {
+    type Err = TyphoonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(Overflow::Checked),
+            "wrapping" => Ok(Overflow::Wrapping),
+            other => Err(TyphoonError::CompileError(format!(
+                "unknown overflow mode {}, expected one of checked, wrapping",
+                other
+            ))),
+        }
+    }
+}
+
+/// switches that change the generated code
+#[derive(Debug, Clone)]
+pub struct CompileOptions {
+    /// check array indexes at runtime and abort when they are out of bounds
+    pub bounds_checks: bool,
+    /// whether integer arithmetic panics or wraps around on overflow
+    pub overflow: Overflow,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        CompileOptions {
+            bounds_checks: true,
+            overflow: Overflow::Wrapping,
+        }
+    }
+}
+
 pub struct Program {
     pub timestamp: u64,
     pub filename: String,
     pub build_folder: PathBuf,
     pub token_tree: Box<Module>,
+    pub options: CompileOptions,
+    /// warnings of the last build, like unreachable match arms
+    pub warnings: Vec<String>,
+    /// run the built program with the statistics of its heap printed to stderr at exit
+    pub gc_stats: bool,
+}
+
+/// parse the source of the file `path`, the error names the position parsing failed at
+fn parse(content: &str, path: &Path) -> Result<Module, TyphoonError> {
+    parse_module(content).map_err(|error| {
+        let position = error_location(&error)
+            .map(|location| format!("{}:{}", path.display(), location))
+            .unwrap_or_else(|| path.display().to_string());
+        TyphoonError::ParserError(format!("{}: syntax error", position))
+    })
+}
+
+/// a value of a type which is not `Copy` cannot be used once it is moved, the error names
+/// the first such use
+fn check_moves(module: &Module, path: &Path) -> Result<(), TyphoonError> {
+    let checker = MoveChecker::new(module)
+        .map_err(|message| TyphoonError::MoveError(format!("{}: {}", path.display(), message)))?;
+    match checker.check_module(module).first() {
+        Some(error) => Err(TyphoonError::MoveError(format!("{}: {}", path.display(), error))),
+        None => Ok(()),
+    }
+}
+
+/// references do not outlive what they borrow and do not alias a mutable borrow, the error
+/// names the first violation
+fn check_borrows(module: &Module, path: &Path) -> Result<(), TyphoonError> {
+    match BorrowChecker::new(module).check_module(module).first() {
+        Some(error) => Err(TyphoonError::BorrowError(format!("{}: {}", path.display(), error))),
+        None => Ok(()),
+    }
+}
+
+/// parse a module and the files of its `mod` declarations, the module `foo` is in
+/// `directory/foo.ty` or `directory/foo/mod.ty` and its own submodules are in `directory/foo`
+fn load_module(content: &str, path: &Path, directory: &Path) -> Result<SourceModule, TyphoonError> {
+    let module = parse(content, path)?;
+    let mut submodules = HashMap::new();
+    for item in module.items.iter() {
+        if let ModuleItem::Mod(mod_decl) = item.as_ref() {
+            let name = &mod_decl.name;
+            let candidates = [
+                directory.join(format!("{}.ty", name)),
+                directory.join(name).join("mod.ty"),
+            ];
+            let path = match candidates.iter().filter(|it| it.is_file()).collect::<Vec<_>>()[..] {
+                [path] => path,
+                [] => {
+                    return Err(TyphoonError::ResolveError(format!(
+                        "file not found for module {}, expected {} or {}",
+                        name,
+                        candidates[0].display(),
+                        candidates[1].display()
+                    )))
+                }
+                _ => {
+                    return Err(TyphoonError::ResolveError(format!(
+                        "file for module {} found at both {} and {}",
+                        name,
+                        candidates[0].display(),
+                        candidates[1].display()
+                    )))
+                }
+            };
+            debug!("load module {} from {}", name, path.display());
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| TyphoonError::FileError(path.display().to_string(), e))?;
+            submodules.insert(name.clone(), load_module(&content, path, &directory.join(name))?);
+        }
+    }
+    Ok(SourceModule { module, submodules })
+}
+
+/// the standard library, written in typhoon and compiled with every program, its public
+/// items are found from every module which does not define an item of the same name
+pub const STD_SOURCE: &str = include_str!("../std/mod.ty");
+
+/// the standard library as the module `std`
+fn std_module() -> SourceModule {
+    SourceModule {
+        module: parse_module(STD_SOURCE).unwrap(),
+        submodules: HashMap::new(),
+    }
+}
+
+/// name of the archive of the runtime crate, programs using the heap are linked with it
+const RUNTIME_LIBRARY: &str = "libtyphoon_runtime.a";
+
+/// libraries the runtime depends on, as reported by `rustc --print native-static-libs`
+const RUNTIME_NATIVE_LIBRARIES: [&str; 3] = ["-lpthread", "-ldl", "-lm"];
+
+/// the runtime archive, given by `TYPHOON_RUNTIME` or found next to the compiler
+fn runtime_library() -> Result<PathBuf, TyphoonError> {
+    let path = match std::env::var_os("TYPHOON_RUNTIME") {
+        Some(path) => PathBuf::from(path),
+        None => std::env::current_exe()
+            .map_err(|e| TyphoonError::FileError("typhoon".to_string(), e))?
+            .with_file_name(RUNTIME_LIBRARY),
+    };
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(TyphoonError::CompileError(format!(
+            "runtime library not found at {}, build it with `cargo build -p runtime` or set TYPHOON_RUNTIME",
+            path.display()
+        )))
+    }
 }
 
 impl Program {
-    pub fn new(path: impl AsRef<Path>) -> Self {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, TyphoonError> {
         let path = path.as_ref();
-        let file_content = std::fs::read_to_string(path).unwrap();
+        let file_content = std::fs::read_to_string(path)
+            .map_err(|e| TyphoonError::FileError(path.display().to_string(), e))?;
         Program::new_with_string(path.to_path_buf(), &file_content)
     }
 
-    pub fn new_with_string(filename: PathBuf, content: &str) -> Program {
-        let module = parse_module(content).unwrap();
+    /// the files of the `mod` declarations are looked up next to `filename`
+    pub fn new_with_string(filename: PathBuf, content: &str) -> Result<Program, TyphoonError> {
+        let directory = filename.parent().map(Path::to_path_buf).unwrap_or_default();
+        let module = resolve_with_prelude(load_module(content, &filename, &directory)?, std_module())?;
+        check_moves(&module, &filename)?;
+        check_borrows(&module, &filename)?;
 
         let timestamp = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
@@ -53,13 +248,16 @@ impl Program {
             &filename
         ));
         std::fs::create_dir_all(&target_build_folder).expect("Cannot create build folder");
-        std::fs::write(target_build_folder.join("source.ty"), &content).expect("cannot output source code");
-        Program {
+        std::fs::write(target_build_folder.join("source.ty"), content).expect("cannot output source code");
+        Ok(Program {
             timestamp,
             filename,
             build_folder: target_build_folder,
             token_tree: Box::new(module),
-        }
+            options: CompileOptions::default(),
+            warnings: vec![],
+            gc_stats: false,
+        })
     }
 
     pub fn as_llir(self) -> String {
@@ -67,9 +265,123 @@ impl Program {
     }
 
     pub fn as_binary_output(
-        self,
+        &mut self,
         debug: bool,
     ) -> Result<(i32, String, String), TyphoonError> {
+        let build_folder = self.build_folder.clone();
+        let filename = self.filename.clone();
+        // `cc` links position independent executables, jump tables of `switch` need
+        // position independent code in them
+        let (o_file_, uses_runtime) = self.emit_object(LLVMRelocMode::LLVMRelocPIC, debug)?;
+
+        debug!("link object file as binary {}", &filename);
+        let execute_file_path = build_folder.join(&filename);
+        let mut command = std::process::Command::new("cc");
+        command.arg(o_file_);
+        if uses_runtime {
+            command.arg(runtime_library()?).args(RUNTIME_NATIVE_LIBRARIES);
+        }
+        let output = command
+            .arg("-o")
+            .arg(execute_file_path.to_str().unwrap())
+            .output()
+            .expect("error on executing linker cc");
+
+        if output.status.success() {
+            debug!("running binary file {}", &filename);
+            let mut command = std::process::Command::new(execute_file_path);
+            if self.gc_stats {
+                command.env(gc::STATS_VARIABLE, "1");
+            }
+            let output = command.output().expect("error on executing output file");
+
+            let stdout = String::from_utf8(output.stdout).unwrap();
+            let stderr = String::from_utf8(output.stderr).unwrap();
+
+            // a program killed by a signal, e.g. `abort()`, reports `128 + signal` like shells do
+            let exit_code = output
+                .status
+                .code()
+                .or_else(|| output.status.signal().map(|signal| 128 + signal))
+                .unwrap();
+            Ok((exit_code, stdout, stderr))
+        } else {
+            println!("cannot emit executing file");
+            let stdout = String::from_utf8(output.stdout).unwrap();
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            Err(TyphoonError::LinkError(output.status, stdout, stderr))
+        }
+    }
+
+    /// build the program as a C compatible library, the `<name>.h` header describing its
+    /// exported functions is written next to the library
+    ///
+    /// a shared object using the heap is linked with the runtime, for a static library the
+    /// runtime archive is copied next to it and has to be linked as well
+    ///
+    /// returns the path of the library
+    pub fn as_library(&mut self, crate_type: CrateType, debug: bool) -> Result<PathBuf, TyphoonError> {
+        let build_folder = self.build_folder.clone();
+        let filename = self.filename.clone();
+
+        let header = CHeader::new(&filename, &self.token_tree).generate()?;
+        let header_path = build_folder.join(format!("{}.h", &filename));
+        debug!("output header file {:?}", &header_path);
+        std::fs::write(&header_path, header).expect("cannot output header file");
+
+        let (reloc_mode, library_path, mut command) = match crate_type {
+            CrateType::StaticLib => {
+                let library_path = build_folder.join(format!("lib{}.a", &filename));
+                let mut command = std::process::Command::new("ar");
+                command.arg("crs").arg(&library_path);
+                // the archive is linked into position independent executables by default
+                (LLVMRelocMode::LLVMRelocPIC, library_path, command)
+            }
+            CrateType::CDylib => {
+                let library_path = build_folder.join(format!("lib{}.so", &filename));
+                let mut command = std::process::Command::new("cc");
+                command.arg("-shared").arg("-o").arg(&library_path);
+                (LLVMRelocMode::LLVMRelocPIC, library_path, command)
+            }
+            CrateType::Bin => {
+                return Err(TyphoonError::CompileError(
+                    "bin is not a library crate type".to_string(),
+                ))
+            }
+        };
+
+        let (o_file, uses_runtime) = self.emit_object(reloc_mode, debug)?;
+        command.arg(o_file);
+        if uses_runtime {
+            let runtime = runtime_library()?;
+            match crate_type {
+                CrateType::CDylib => {
+                    command.arg(runtime).args(RUNTIME_NATIVE_LIBRARIES);
+                }
+                _ => {
+                    let target = build_folder.join(RUNTIME_LIBRARY);
+                    std::fs::copy(&runtime, &target)
+                        .map_err(|e| TyphoonError::FileError(runtime.display().to_string(), e))?;
+                }
+            }
+        }
+        debug!("archive object file as library {:?}", &library_path);
+        let output = command
+            .output()
+            .expect("error on executing archiver");
+
+        if output.status.success() {
+            Ok(library_path)
+        } else {
+            let stdout = String::from_utf8(output.stdout).unwrap();
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            Err(TyphoonError::LinkError(output.status, stdout, stderr))
+        }
+    }
+
+    /// compile the module into `<build_folder>/<name>.o` and return its path, along with
+    /// whether it calls into the runtime
+    fn emit_object(&mut self, reloc_mode: LLVMRelocMode, debug: bool) -> Result<(String, bool), TyphoonError> {
         if debug {
             debug!("output ast file");
             std::fs::write(
@@ -80,7 +392,18 @@ impl Program {
         }
 
         let context = TyphoonContext::new();
-        let (module, builder) = self.token_tree.codegen(&context);
+        let environment = Environment::new(self.options.clone(), format!("{}.ty", &self.filename));
+        let codegen = catch(|| self.token_tree.clone().codegen(&context, &environment));
+        self.warnings = environment.warnings();
+        let (module, _builder) = codegen?;
+        let uses_runtime = [
+            gc::ALLOC,
+            gc::ALLOC_ARRAY,
+            panics::PANIC,
+            panics::PANIC_BOUNDS_CHECK,
+        ]
+            .iter()
+            .any(|name| module.get_function(name).is_some_and(|function| function.is_used()));
         unsafe {
             debug!("init target message");
 
@@ -90,11 +413,11 @@ impl Program {
             LLVM_InitializeAllTargetMCs();
             LLVM_InitializeAllAsmParsers();
             LLVM_InitializeAllAsmPrinters();
+            gc::link_builtin_strategies();
             let mut target: MaybeUninit<LLVMTargetRef> = std::mem::MaybeUninit::uninit();
             LLVMGetTargetFromTriple(triple, target.as_mut_ptr(), ptr::null_mut());
             let target = target.assume_init();
             let opt_level = LLVMCodeGenOptLevel::LLVMCodeGenLevelNone;
-            let reloc_mode = LLVMRelocMode::LLVMRelocDefault;
             let code_model = LLVMCodeModel::LLVMCodeModelDefault;
 
             let name = LLVMGetTargetName(target);
@@ -108,7 +431,7 @@ impl Program {
 
                 let x = CStr::from_ptr(string).to_str().unwrap();
                 let llir = x.to_string();
-                std::fs::write(self.build_folder.join("llir.ll"), format!("{}", llir))
+                std::fs::write(self.build_folder.join("llir.ll"), llir)
                     .expect("cannot output llir file");
             }
 
@@ -147,32 +470,7 @@ impl Program {
 
                 return Err(TyphoonError::CompileError(x.to_str().unwrap().to_string()));
             }
-
-            debug!("link object file as binary {}", &self.filename);
-            let execute_file_path = self.build_folder.join(&self.filename);
-            let output = std::process::Command::new("cc")
-                .arg(o_file_)
-                .arg("-o")
-                .arg(execute_file_path.to_str().unwrap())
-                .output()
-                .expect("error on executing linker cc");
-
-            if output.status.success() {
-                debug!("running binary file {}", &self.filename);
-                let output = std::process::Command::new(execute_file_path)
-                    .output()
-                    .expect("error on executing output file");
-
-                let stdout = String::from_utf8(output.stdout).unwrap();
-                let stderr = String::from_utf8(output.stderr).unwrap();
-
-                Ok((output.status.code().unwrap(), stdout, stderr))
-            } else {
-                println!("cannot emit executing file");
-                let stdout = String::from_utf8(output.stdout).unwrap();
-                let stderr = String::from_utf8(output.stderr).unwrap();
-                Err(TyphoonError::LinkError(output.status, stdout, stderr))
-            }
+            Ok((o_file_, uses_runtime))
         }
     }
 }
@@ -0,0 +1,854 @@
+use crate::codegen::simd_reduction;
+use crate::error::{catch, fail, TyphoonError};
+use crate::intrinsics::{is_intrinsic, INTRINSICS};
+use crate::panics::PANIC_BUILTIN;
+use crate::traits::builtin_traits;
+use ast::{
+    ConstDeclare, EnumDeclare, EnumVariant, Expr, FunctionDeclare, Identifier, ImplBlock, Location,
+    MatchArm, Module, ModuleItem, NewtypeDeclare, Pattern, Statement, StaticDeclare, StructDeclare,
+    TraitDeclare, TraitFunction, Type, TypeAlias, VariantFields, Visibility,
+};
+use std::collections::{HashMap, HashSet};
+
+/// a parsed source file together with the files of its `mod` declarations
+pub struct SourceModule {
+    pub module: Module,
+    pub submodules: HashMap<Identifier, SourceModule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemKind {
+    Module(usize),
+    Struct,
+    Enum,
+    Trait,
+    Function,
+    Const,
+    Static,
+    TypeAlias,
+    Newtype,
+}
+
+impl ItemKind {
+    /// items that are used as values in expressions
+    fn is_value(&self) -> bool {
+        matches!(
+            self,
+            ItemKind::Function | ItemKind::Const | ItemKind::Static | ItemKind::Newtype
+        )
+    }
+
+    /// items that are used as types, a newtype is both a type and its constructor
+    fn is_type(&self) -> bool {
+        matches!(
+            self,
+            ItemKind::Struct | ItemKind::Enum | ItemKind::TypeAlias | ItemKind::Newtype
+        )
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            ItemKind::Module(_) => "module",
+            ItemKind::Struct => "struct",
+            ItemKind::Enum => "enum",
+            ItemKind::Trait => "trait",
+            ItemKind::Function => "function",
+            ItemKind::Const => "constant",
+            ItemKind::Static => "static",
+            ItemKind::TypeAlias => "type alias",
+            ItemKind::Newtype => "newtype",
+        }
+    }
+}
+
+/// an item a name refers to, with the name it has in the flattened module
+#[derive(Debug, Clone)]
+struct Item {
+    kind: ItemKind,
+    name: Identifier,
+}
+
+#[derive(Debug)]
+struct ModuleScope {
+    path: Vec<Identifier>,
+    parent: Option<usize>,
+    items: HashMap<Identifier, (Item, Visibility)>,
+    /// `use` declarations, resolved from this module when they are looked up
+    imports: HashMap<Identifier, (Vec<Identifier>, Visibility)>,
+}
+
+/// names of every module of a program, the root module is the first one
+pub struct Resolver {
+    modules: Vec<ModuleScope>,
+    /// the standard library, its public items are found from every module which does not
+    /// define an item of the same name
+    prelude: Option<usize>,
+}
+
+/// the name of the module of the standard library
+pub const STD: &str = "std";
+
+/// `use` declarations following each other are given up on after this many steps
+const MAX_IMPORT_DEPTH: usize = 64;
+
+/// flatten the module tree into a single module, items of the root module keep their name,
+/// other items are named by their path `geo::Point` and every reference to an item is
+/// rewritten to that name
+pub fn resolve(root: SourceModule) -> Result<Module, TyphoonError> {
+    catch(|| resolve_modules(root, None))
+}
+
+/// flatten the module tree like `resolve`, together with the standard library `prelude`,
+/// whose items are named `std::Vec`
+pub fn resolve_with_prelude(root: SourceModule, prelude: SourceModule) -> Result<Module, TyphoonError> {
+    catch(|| resolve_modules(root, Some(prelude)))
+}
+
+/// stop resolving with `message`, a name which cannot be found or is not visible
+fn error(message: String) -> ! {
+    fail(TyphoonError::ResolveError(message))
+}
+
+fn resolve_modules(root: SourceModule, prelude: Option<SourceModule>) -> Module {
+    let mut resolver = Resolver {
+        modules: vec![],
+        prelude: None,
+    };
+    let mut sources = vec![];
+    resolver.collect(root, vec![], None, &mut sources);
+    if let Some(prelude) = prelude {
+        let index = resolver.collect(prelude, vec![STD.to_string()], None, &mut sources);
+        resolver.prelude = Some(index);
+    }
+    // submodules are collected before the module declaring them, keep the source order
+    sources.sort_by_key(|(module, _)| *module);
+    let mut items = vec![];
+    for (module, source) in sources {
+        for item in source.items {
+            if let Some(item) = resolver.item(module, *item) {
+                items.push(Box::new(item));
+            }
+        }
+    }
+    Module::new(items)
+}
+
+impl Resolver {
+    /// declare the items of a module and of its submodules
+    fn collect(
+        &mut self,
+        source: SourceModule,
+        path: Vec<Identifier>,
+        parent: Option<usize>,
+        sources: &mut Vec<(usize, Module)>,
+    ) -> usize {
+        let index = self.modules.len();
+        self.modules.push(ModuleScope {
+            path: path.clone(),
+            parent,
+            items: HashMap::new(),
+            imports: HashMap::new(),
+        });
+        let SourceModule {
+            module,
+            mut submodules,
+        } = source;
+        for item in module.items.iter() {
+            let (name, kind, visibility) = match item.as_ref() {
+                ModuleItem::Mod(mod_decl) => {
+                    let submodule = submodules.remove(&mod_decl.name).unwrap_or_else(|| {
+                        error(format!("module {} of {} is not loaded", mod_decl.name, self.module_name(index)))
+                    });
+                    let child_path = path.iter().cloned().chain([mod_decl.name.clone()]).collect();
+                    let child = self.collect(submodule, child_path, Some(index), sources);
+                    (&mod_decl.name, ItemKind::Module(child), mod_decl.visibility)
+                }
+                ModuleItem::StructDeclare(s) => (&s.name, ItemKind::Struct, s.visibility),
+                ModuleItem::EnumDeclare(e) => (&e.name, ItemKind::Enum, e.visibility),
+                ModuleItem::TraitDeclare(t) => (&t.name, ItemKind::Trait, t.visibility),
+                ModuleItem::FunctionDeclare(f) => (&f.name, ItemKind::Function, f.visibility),
+                ModuleItem::Const(c) => (&c.name, ItemKind::Const, c.visibility),
+                ModuleItem::Static(s) => (&s.name, ItemKind::Static, s.visibility),
+                ModuleItem::TypeAlias(a) => (&a.name, ItemKind::TypeAlias, a.visibility),
+                ModuleItem::Newtype(n) => (&n.name, ItemKind::Newtype, n.visibility),
+                ModuleItem::Use(use_decl) => {
+                    for (import, name) in use_decl.imports.iter() {
+                        self.declare_name(index, name);
+                        self.modules[index]
+                            .imports
+                            .insert(name.clone(), (import.clone(), use_decl.visibility));
+                    }
+                    continue;
+                }
+                ModuleItem::Impl(_) => continue,
+            };
+            self.declare_name(index, name);
+            let flat_name = match item.as_ref() {
+                // the symbol of an extern function is its name in the source
+                ModuleItem::FunctionDeclare(f) if f.is_extern => f.name.clone(),
+                _ => qualify(&path, name),
+            };
+            let item = Item {
+                kind,
+                name: flat_name,
+            };
+            self.modules[index]
+                .items
+                .insert(name.clone(), (item, visibility));
+        }
+        sources.push((index, module));
+        index
+    }
+
+    fn declare_name(&self, module: usize, name: &str) {
+        let scope = &self.modules[module];
+        if scope.items.contains_key(name) || scope.imports.contains_key(name) {
+            error(format!("{} is defined multiple times in module {}", name, self.module_name(module)));
+        }
+    }
+
+    fn module_name(&self, module: usize) -> String {
+        match self.modules[module].path.as_slice() {
+            [] => "crate".to_string(),
+            path => path.join("::"),
+        }
+    }
+
+    /// whether `from` is `module` or one of its submodules, which see its private items
+    fn is_within(&self, mut from: usize, module: usize) -> bool {
+        loop {
+            if from == module {
+                return true;
+            }
+            match self.modules[from].parent {
+                Some(parent) => from = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// the item `name` of `module` as seen from the module `from`
+    fn find(&self, module: usize, name: &str, from: usize, depth: usize) -> Option<Item> {
+        let scope = &self.modules[module];
+        let (item, visibility) = match scope.items.get(name) {
+            Some((item, visibility)) => (item.clone(), *visibility),
+            None => {
+                let (path, visibility) = scope.imports.get(name)?;
+                if depth > MAX_IMPORT_DEPTH {
+                    error(format!("cannot resolve the cyclic import of {}", name));
+                }
+                let item = self.resolve_from(module, path, depth + 1).unwrap_or_else(|| {
+                    error(format!("cannot find {} in module {}", path.join("::"), self.module_name(module)))
+                });
+                (item, *visibility)
+            }
+        };
+        if visibility == Visibility::Private && !self.is_within(from, module) {
+            error(format!("{} {} of module {} is private", item.kind.describe(), name, self.module_name(module)));
+        }
+        Some(item)
+    }
+
+    /// the item a path refers to from the module `from`, `None` when a path of a single
+    /// segment names nothing, a longer path has to name an item
+    fn resolve(&self, from: usize, segments: &[Identifier]) -> Option<Item> {
+        self.resolve_from(from, segments, 0)
+    }
+
+    fn resolve_from(&self, from: usize, segments: &[Identifier], depth: usize) -> Option<Item> {
+        let module_item = |index: usize| Item {
+            kind: ItemKind::Module(index),
+            name: self.modules[index].path.join("::"),
+        };
+        let parent = |index: usize| {
+            self.modules[index].parent.unwrap_or_else(|| {
+                error("there is no module above the crate root".to_string())
+            })
+        };
+        let (first, rest) = segments.split_first().unwrap();
+        let mut current = match first.as_str() {
+            "crate" => module_item(0),
+            "self" => module_item(from),
+            "super" => module_item(parent(from)),
+            name => match self.find(from, name, from, depth).or_else(|| self.prelude_item(name)) {
+                Some(item) => item,
+                None if rest.is_empty() => return None,
+                None => error(format!("cannot find {} in module {}", name, self.module_name(from))),
+            },
+        };
+        for segment in rest {
+            let module = match current.kind {
+                ItemKind::Module(module) => module,
+                kind => error(format!("{} {} is not a module", kind.describe(), current.name)),
+            };
+            current = match segment.as_str() {
+                "self" => module_item(module),
+                "super" => module_item(parent(module)),
+                name => self.find(module, name, from, depth).unwrap_or_else(|| {
+                    error(format!("cannot find {} in module {}", name, self.module_name(module)))
+                }),
+            };
+        }
+        Some(current)
+    }
+
+    /// the standard library itself or one of its public items, which every module sees
+    fn prelude_item(&self, name: &str) -> Option<Item> {
+        let prelude = self.prelude?;
+        if name == STD {
+            return Some(Item {
+                kind: ItemKind::Module(prelude),
+                name: STD.to_string(),
+            });
+        }
+        match self.modules[prelude].items.get(name) {
+            Some((item, Visibility::Public)) => Some(item.clone()),
+            _ => None,
+        }
+    }
+
+    /// whether `module` is the standard library, which calls the intrinsics
+    fn is_prelude(&self, module: usize) -> bool {
+        self.prelude == Some(module)
+    }
+
+    /// rewrite an item of `module`, `mod` and `use` declarations have done their part
+    fn item(&self, module: usize, item: ModuleItem) -> Option<ModuleItem> {
+        let names = |generics: &[Identifier], has_self: bool| Names {
+            resolver: self,
+            module,
+            generics: generics.iter().cloned().chain(has_self.then(|| "Self".to_string())).collect(),
+            locals: vec![],
+        };
+        let flat_name = |name: &str| self.modules[module].items[name].0.name.clone();
+        let item = match item {
+            ModuleItem::Mod(_) | ModuleItem::Use(_) => return None,
+            ModuleItem::StructDeclare(struct_decl) => {
+                let names = names(&struct_decl.generics, false);
+                ModuleItem::StructDeclare(StructDeclare {
+                    name: flat_name(&struct_decl.name),
+                    fields: struct_decl
+                        .fields
+                        .into_iter()
+                        .map(|(field, ty)| (field, names.ty(&ty)))
+                        .collect(),
+                    ..struct_decl
+                })
+            }
+            ModuleItem::EnumDeclare(enum_decl) => {
+                let mut names = names(&enum_decl.generics, false);
+                ModuleItem::EnumDeclare(EnumDeclare {
+                    name: flat_name(&enum_decl.name),
+                    variants: enum_decl
+                        .variants
+                        .into_iter()
+                        .map(|variant| EnumVariant {
+                            fields: names.variant_fields(variant.fields, |names, ty| names.ty(&ty)),
+                            ..variant
+                        })
+                        .collect(),
+                    ..enum_decl
+                })
+            }
+            ModuleItem::TraitDeclare(trait_decl) => ModuleItem::TraitDeclare(TraitDeclare {
+                name: flat_name(&trait_decl.name),
+                functions: trait_decl
+                    .functions
+                    .into_iter()
+                    .map(|function| {
+                        let generics: Vec<Identifier> =
+                            trait_decl.generics.iter().chain(&function.generics).cloned().collect();
+                        let mut names = names(&generics, true);
+                        let bounds = names.bounds(&function.bounds);
+                        let args = names.arguments(&function.args);
+                        let return_type = names.ty(&function.return_type);
+                        let default = function.default.map(|body| Box::new(names.expr(*body)));
+                        TraitFunction {
+                            bounds,
+                            args,
+                            return_type,
+                            default,
+                            ..function
+                        }
+                    })
+                    .collect(),
+                ..trait_decl
+            }),
+            ModuleItem::Const(const_decl) => {
+                let mut names = names(&[], false);
+                ModuleItem::Const(ConstDeclare {
+                    name: flat_name(&const_decl.name),
+                    ty: names.ty(&const_decl.ty),
+                    value: Box::new(names.expr(*const_decl.value)),
+                    ..const_decl
+                })
+            }
+            ModuleItem::Static(static_decl) => {
+                let mut names = names(&[], false);
+                ModuleItem::Static(StaticDeclare {
+                    name: flat_name(&static_decl.name),
+                    ty: names.ty(&static_decl.ty),
+                    value: Box::new(names.expr(*static_decl.value)),
+                    ..static_decl
+                })
+            }
+            ModuleItem::TypeAlias(alias) => ModuleItem::TypeAlias(TypeAlias {
+                name: flat_name(&alias.name),
+                ty: names(&[], false).ty(&alias.ty),
+                ..alias
+            }),
+            ModuleItem::Newtype(newtype) => ModuleItem::Newtype(NewtypeDeclare {
+                name: flat_name(&newtype.name),
+                inner: names(&[], false).ty(&newtype.inner),
+                ..newtype
+            }),
+            ModuleItem::FunctionDeclare(func_decl) => {
+                let name = flat_name(&func_decl.name);
+                let func_decl = names(&func_decl.generics, false).function(func_decl);
+                ModuleItem::FunctionDeclare(FunctionDeclare { name, ..func_decl })
+            }
+            ModuleItem::Impl(impl_block) => {
+                let names = names(&impl_block.generics, true);
+                let name = match names.type_name(&impl_block.name) {
+                    Some(Item { kind: ItemKind::Struct | ItemKind::Enum | ItemKind::Newtype, name }) => name,
+                    Some(item) => error(format!("{} {} has no impl blocks", item.kind.describe(), item.name)),
+                    None => impl_block.name.clone(),
+                };
+                let functions = impl_block
+                    .functions
+                    .into_iter()
+                    .map(|function| {
+                        let generics = impl_block.generics.iter().chain(&function.generics);
+                        let names = Names {
+                            generics: generics.cloned().chain(["Self".to_string()]).collect(),
+                            ..names.nested()
+                        };
+                        names.function(function)
+                    })
+                    .collect();
+                ModuleItem::Impl(ImplBlock {
+                    bounds: names.bounds(&impl_block.bounds),
+                    trait_name: impl_block.trait_name.as_ref().map(|it| names.trait_name(it)),
+                    trait_arguments: impl_block.trait_arguments.iter().map(|it| names.ty(it)).collect(),
+                    name,
+                    arguments: impl_block.arguments.iter().map(|it| names.ty(it)).collect(),
+                    functions,
+                    ..impl_block
+                })
+            }
+        };
+        Some(item)
+    }
+}
+
+fn qualify(path: &[Identifier], name: &str) -> Identifier {
+    path.iter()
+        .map(String::as_str)
+        .chain([name])
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn segments(path: &str) -> Vec<Identifier> {
+    path.split("::").map(str::to_string).collect()
+}
+
+/// names visible at some point of a module, type parameters and local variables shadow
+/// the items of the module
+struct Names<'a> {
+    resolver: &'a Resolver,
+    module: usize,
+    generics: HashSet<Identifier>,
+    locals: Vec<HashSet<Identifier>>,
+}
+
+impl<'a> Names<'a> {
+    fn nested(&self) -> Names<'a> {
+        Names {
+            resolver: self.resolver,
+            module: self.module,
+            generics: self.generics.clone(),
+            locals: self.locals.clone(),
+        }
+    }
+
+    fn module_name(&self) -> String {
+        self.resolver.module_name(self.module)
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        self.locals.iter().any(|scope| scope.contains(name))
+    }
+
+    fn bind(&mut self, name: &str) {
+        self.locals.last_mut().unwrap().insert(name.to_string());
+    }
+
+    fn bind_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Identifier(name) => self.bind(name),
+            Pattern::Tuple(elements) => elements.iter().for_each(|it| self.bind_pattern(it)),
+            Pattern::Struct(_, fields) => fields.iter().for_each(|(_, it)| self.bind_pattern(it)),
+            Pattern::Variant { fields, .. } => match fields {
+                VariantFields::Unit => {}
+                VariantFields::Tuple(elements) => elements.iter().for_each(|it| self.bind_pattern(it)),
+                VariantFields::Struct(fields) => fields.iter().for_each(|(_, it)| self.bind_pattern(it)),
+            },
+            Pattern::Wildcard | Pattern::Integer(_) | Pattern::Range(..) => {}
+        }
+    }
+
+    /// the item a type or trait name refers to, `None` for type parameters and names of
+    /// the built-in types and traits
+    fn type_name(&self, name: &str) -> Option<Item> {
+        if self.generics.contains(name) {
+            return None;
+        }
+        let builtin = matches!(name, "i8" | "i16" | "i32" | "i64" | "f32" | "f64")
+            || builtin_traits().iter().any(|trait_decl| trait_decl.name == name);
+        match self.resolver.resolve(self.module, &segments(name)) {
+            Some(item) => Some(item),
+            None if builtin => None,
+            None => error(format!("cannot find {} in module {}", name, self.module_name())),
+        }
+    }
+
+    fn trait_name(&self, name: &str) -> Identifier {
+        match self.type_name(name) {
+            Some(Item { kind: ItemKind::Trait, name }) => name,
+            Some(item) => error(format!("expected a trait, found {} {}", item.kind.describe(), name)),
+            None => name.to_string(),
+        }
+    }
+
+    fn ty(&self, ty: &Type) -> Type {
+        let type_name = |name: &str| match self.type_name(name) {
+            Some(item) if item.kind.is_type() => item.name,
+            Some(item) => error(format!("expected a type, found {} {}", item.kind.describe(), name)),
+            None => name.to_string(),
+        };
+        match ty {
+            Type::Named(name) => Type::named(type_name(name)),
+            Type::Generic(name, arguments) => {
+                Type::generic(type_name(name), arguments.iter().map(|it| self.ty(it)).collect())
+            }
+            Type::Dyn(trait_name, arguments) => Type::Dyn(
+                self.trait_name(trait_name),
+                arguments.iter().map(|it| self.ty(it)).collect(),
+            ),
+            Type::Reference { mutable, inner } => Type::reference(self.ty(inner), *mutable),
+            Type::Pointer(inner) => Type::pointer(self.ty(inner)),
+            Type::Boxed(inner) => Type::boxed(self.ty(inner)),
+            Type::Array(element, len) => Type::array(self.ty(element), *len),
+            Type::Simd(element, lanes) => Type::simd(self.ty(element), *lanes),
+            Type::Tuple(elements) => Type::tuple(elements.iter().map(|it| self.ty(it)).collect()),
+            Type::Function(parameters, return_type) => Type::function(
+                parameters.iter().map(|it| self.ty(it)).collect(),
+                self.ty(return_type),
+            ),
+        }
+    }
+
+    fn bounds(&self, bounds: &[(Identifier, Identifier)]) -> Vec<(Identifier, Identifier)> {
+        bounds
+            .iter()
+            .map(|(parameter, trait_name)| (parameter.clone(), self.trait_name(trait_name)))
+            .collect()
+    }
+
+    fn arguments(&self, args: &[(Identifier, Type)]) -> Vec<(Identifier, Type)> {
+        args.iter().map(|(name, ty)| (name.clone(), self.ty(ty))).collect()
+    }
+
+    fn variant_fields<T>(
+        &mut self,
+        fields: VariantFields<T>,
+        mut f: impl FnMut(&mut Self, T) -> T,
+    ) -> VariantFields<T> {
+        match fields {
+            VariantFields::Unit => VariantFields::Unit,
+            VariantFields::Tuple(items) => {
+                VariantFields::Tuple(items.into_iter().map(|it| f(self, it)).collect())
+            }
+            VariantFields::Struct(items) => VariantFields::Struct(
+                items.into_iter().map(|(name, it)| (name, f(self, it))).collect(),
+            ),
+        }
+    }
+
+    fn function(mut self, func_decl: FunctionDeclare) -> FunctionDeclare {
+        let bounds = self.bounds(&func_decl.bounds);
+        let args = self.arguments(&func_decl.args);
+        let return_type = self.ty(&func_decl.return_type);
+        self.locals.push(args.iter().map(|(name, _)| name.clone()).collect());
+        let stats = Box::new(self.expr(*func_decl.stats));
+        FunctionDeclare {
+            bounds,
+            args,
+            return_type,
+            stats,
+            ..func_decl
+        }
+    }
+
+    /// the function a callee without path refers to
+    fn function_name(&self, name: Identifier) -> Identifier {
+        match self.resolver.resolve(self.module, std::slice::from_ref(&name)) {
+            Some(Item { kind: ItemKind::Function | ItemKind::Newtype, name }) => name,
+            Some(item) => error(format!("expected a function, found {} {}", item.kind.describe(), name)),
+            // builtins are not items of any module
+            None if simd_reduction(&name).is_some() || name == PANIC_BUILTIN => name,
+            None => error(format!("cannot find function {} in module {}", name, self.module_name())),
+        }
+    }
+
+    /// `prefix::last` as a function, an enum variant or an associated function
+    fn path(&self, prefix: Identifier, last: Identifier) -> Expr {
+        // intrinsics are not items of any module, only the standard library calls them
+        if prefix == INTRINSICS && self.resolver.is_prelude(self.module) && is_intrinsic(&last) {
+            return Expr::Path(prefix, last);
+        }
+        match self.type_name(&prefix) {
+            Some(Item { kind: ItemKind::Module(module), .. }) => {
+                match self.resolver.find(module, &last, self.module, 0) {
+                    Some(item) if item.kind.is_value() => Expr::Identifier(item.name, Location::default()),
+                    Some(item) => error(format!("expected a value, found {} {}", item.kind.describe(), last)),
+                    None => error(format!("cannot find {} in module {}", last, self.resolver.module_name(module))),
+                }
+            }
+            Some(Item { kind: ItemKind::Struct | ItemKind::Enum | ItemKind::Newtype, name }) => {
+                Expr::Path(name, last)
+            }
+            Some(item) => error(format!("{} {} has no associated items", item.kind.describe(), prefix)),
+            None => Expr::Path(prefix, last),
+        }
+    }
+
+    /// the struct named `name` or the variant `name::variant` of a literal or a pattern,
+    /// `geo::Point` is parsed like a variant and turns out to be a struct of a module
+    fn struct_or_variant(&self, name: &str, variant: Option<Identifier>) -> (Identifier, Option<Identifier>) {
+        let item = match self.type_name(name) {
+            Some(item) => item,
+            None => return (name.to_string(), variant),
+        };
+        match (item.kind, variant) {
+            (ItemKind::Struct, None) => (item.name, None),
+            (ItemKind::Enum, Some(variant)) => (item.name, Some(variant)),
+            (ItemKind::Module(module), Some(variant)) => {
+                match self.resolver.find(module, &variant, self.module, 0) {
+                    Some(Item { kind: ItemKind::Struct, name }) => (name, None),
+                    Some(item) => error(format!("expected a struct, found {} {}", item.kind.describe(), variant)),
+                    None => error(format!("cannot find {} in module {}", variant, self.resolver.module_name(module))),
+                }
+            }
+            (kind, _) => error(format!("expected a struct, found {} {}", kind.describe(), name)),
+        }
+    }
+
+    fn pattern(&mut self, pattern: Pattern) -> Pattern {
+        match pattern {
+            Pattern::Tuple(elements) => {
+                Pattern::Tuple(elements.into_iter().map(|it| self.pattern(it)).collect())
+            }
+            Pattern::Struct(name, fields) => {
+                let (name, _) = self.struct_or_variant(&name, None);
+                let fields = fields.into_iter().map(|(field, it)| (field, self.pattern(it))).collect();
+                Pattern::Struct(name, fields)
+            }
+            Pattern::Variant {
+                enum_name,
+                variant,
+                fields,
+            } => {
+                let fields = self.variant_fields(fields, Self::pattern);
+                match (self.struct_or_variant(&enum_name, Some(variant)), fields) {
+                    ((name, None), VariantFields::Struct(fields)) => Pattern::Struct(name, fields),
+                    ((name, None), _) => error(format!("struct {} is matched without its fields", name)),
+                    ((enum_name, Some(variant)), fields) => Pattern::Variant {
+                        enum_name,
+                        variant,
+                        fields,
+                    },
+                }
+            }
+            pattern => pattern,
+        }
+    }
+
+    fn statement(&mut self, statement: Statement) -> Statement {
+        match statement {
+            Statement::Declare(name, ty, value) => {
+                let value = Box::new(self.expr(*value));
+                self.bind(&name);
+                Statement::Declare(name, self.ty(&ty), value)
+            }
+            Statement::Destructure(pattern, ty, value) => {
+                let value = Box::new(self.expr(*value));
+                let pattern = self.pattern(pattern);
+                self.bind_pattern(&pattern);
+                Statement::Destructure(pattern, ty.map(|it| self.ty(&it)), value)
+            }
+            Statement::Assignment(place, value) => {
+                Statement::Assignment(self.boxed(place), self.boxed(value))
+            }
+            Statement::Expr(expr) => Statement::Expr(self.boxed(expr)),
+            Statement::Return(expr, location) => Statement::Return(self.boxed(expr), location),
+        }
+    }
+
+    fn boxed(&mut self, mut expr: Box<Expr>) -> Box<Expr> {
+        *expr = self.expr(*expr);
+        expr
+    }
+
+    fn expr(&mut self, expr: Expr) -> Expr {
+        match expr {
+            Expr::Identifier(name, location) if self.is_local(&name) => Expr::Identifier(name, location),
+            // a function used as a value, a constant or a static, anything else is left to
+            // codegen to report
+            Expr::Identifier(name, location) => {
+                match self.resolver.resolve(self.module, std::slice::from_ref(&name)) {
+                    Some(item) if item.kind.is_value() => Expr::Identifier(item.name, location),
+                    _ => Expr::Identifier(name, location),
+                }
+            }
+            Expr::Call(callee, args) => {
+                let callee = match *callee {
+                    Expr::Identifier(name, location) if !self.is_local(&name) => {
+                        Expr::Identifier(self.function_name(name), location)
+                    }
+                    callee => self.expr(callee),
+                };
+                Expr::Call(Box::new(callee), args.into_iter().map(|it| self.boxed(it)).collect())
+            }
+            Expr::Path(prefix, last) => self.path(prefix, last),
+            Expr::StructLiteral {
+                name,
+                variant,
+                fields,
+            } => {
+                let (name, variant) = self.struct_or_variant(&name, variant);
+                let fields = fields.into_iter().map(|(field, it)| (field, self.boxed(it))).collect();
+                Expr::StructLiteral {
+                    name,
+                    variant,
+                    fields,
+                }
+            }
+            Expr::Block(statements, value) => {
+                self.locals.push(HashSet::new());
+                let statements = statements
+                    .into_iter()
+                    .map(|it| Box::new(self.statement(*it)))
+                    .collect();
+                let value = value.map(|it| self.boxed(it));
+                self.locals.pop();
+                Expr::Block(statements, value)
+            }
+            Expr::Closure {
+                params,
+                return_type,
+                body,
+            } => {
+                let params: Vec<(Identifier, Option<Type>)> = params
+                    .into_iter()
+                    .map(|(name, ty)| (name, ty.map(|it| self.ty(&it))))
+                    .collect();
+                self.locals.push(params.iter().map(|(name, _)| name.clone()).collect());
+                let body = self.boxed(body);
+                self.locals.pop();
+                Expr::Closure {
+                    params,
+                    return_type: return_type.map(|it| self.ty(&it)),
+                    body,
+                }
+            }
+            Expr::Match {
+                scrutinee,
+                arms,
+                location,
+            } => {
+                let scrutinee = self.boxed(scrutinee);
+                let arms = arms
+                    .into_iter()
+                    .map(|arm| {
+                        let pattern = self.pattern(arm.pattern);
+                        self.locals.push(HashSet::new());
+                        self.bind_pattern(&pattern);
+                        let body = self.expr(*arm.body);
+                        self.locals.pop();
+                        MatchArm::new(pattern, body, arm.location)
+                    })
+                    .collect();
+                Expr::Match {
+                    scrutinee,
+                    arms,
+                    location,
+                }
+            }
+            Expr::For {
+                pattern,
+                iterable,
+                body,
+            } => {
+                let iterable = self.boxed(iterable);
+                let pattern = self.pattern(pattern);
+                self.locals.push(HashSet::new());
+                self.bind_pattern(&pattern);
+                let body = self.boxed(body);
+                self.locals.pop();
+                Expr::For {
+                    pattern,
+                    iterable,
+                    body,
+                }
+            }
+            Expr::Field(expr, field) => Expr::Field(self.boxed(expr), field),
+            Expr::TupleField(expr, index) => Expr::TupleField(self.boxed(expr), index),
+            Expr::Tuple(elements) => Expr::Tuple(elements.into_iter().map(|it| self.boxed(it)).collect()),
+            Expr::BinOperation(opcode, lhs, rhs, location) => {
+                Expr::BinOperation(opcode, self.boxed(lhs), self.boxed(rhs), location)
+            }
+            Expr::Range { start, end, inclusive } => Expr::Range {
+                start: self.boxed(start),
+                end: self.boxed(end),
+                inclusive,
+            },
+            Expr::If {
+                condition,
+                then_body,
+                else_body,
+            } => Expr::If {
+                condition: self.boxed(condition),
+                then_body: self.boxed(then_body),
+                else_body: self.boxed(else_body),
+            },
+            Expr::Group(expr) => Expr::Group(self.boxed(expr)),
+            Expr::Reference { mutable, expr } => Expr::Reference {
+                mutable,
+                expr: self.boxed(expr),
+            },
+            Expr::Deref(expr, location) => Expr::Deref(self.boxed(expr), location),
+            Expr::Box(expr) => Expr::Box(self.boxed(expr)),
+            Expr::Array(elements) => Expr::Array(elements.into_iter().map(|it| self.boxed(it)).collect()),
+            Expr::ArrayRepeat(value, len) => Expr::ArrayRepeat(self.boxed(value), len),
+            Expr::Index {
+                expr,
+                index,
+                location,
+            } => Expr::Index {
+                expr: self.boxed(expr),
+                index: self.boxed(index),
+                location,
+            },
+            Expr::SimdVector(elements) => {
+                Expr::SimdVector(elements.into_iter().map(|it| self.boxed(it)).collect())
+            }
+            Expr::SimdSplat(value, lanes) => Expr::SimdSplat(self.boxed(value), lanes),
+            Expr::Cast(expr, ty) => Expr::Cast(self.boxed(expr), self.ty(&ty)),
+            Expr::Unsafe(block) => Expr::Unsafe(self.boxed(block)),
+            Expr::Try(expr, location) => Expr::Try(self.boxed(expr), location),
+            expr @ (Expr::Number(_) | Expr::Negative(_) | Expr::String(_)) => expr,
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use crate::consts::ConstValue;
+use crate::llvm_wrapper::types::BasicType;
+use crate::llvm_wrapper::values::function_value::FunctionValue;
+use crate::llvm_wrapper::values::pointer_value::PointerValue;
+use crate::program::CompileOptions;
+use ast::{
+    EnumDeclare, FunctionDeclare, Identifier, NewtypeDeclare, StructDeclare, TraitDeclare, Type,
+    TypeAlias,
+};
+use llvm_sys::prelude::LLVMTypeRef;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+/// module level information shared by every function, the root of all scopes
+pub struct Environment {
+    pub options: CompileOptions,
+    /// name of the source file, used in runtime error messages
+    pub source_name: String,
+    structs: RefCell<HashMap<Identifier, StructDeclare>>,
+    enums: RefCell<HashMap<Identifier, EnumDeclare>>,
+    aliases: RefCell<HashMap<Identifier, TypeAlias>>,
+    /// newtypes are declared as one field structs as well
+    newtypes: RefCell<HashMap<Identifier, NewtypeDeclare>>,
+    /// declared functions by their source name, their symbols are mangled
+    functions: RefCell<HashMap<Identifier, FunctionValue>>,
+    /// declared parameter types of the non generic functions, aliases are kept so that
+    /// diagnostics name the types as written
+    parameters: RefCell<HashMap<Identifier, Vec<Type>>>,
+    generic_functions: RefCell<HashMap<Identifier, FunctionDeclare>>,
+    /// functions of `impl` blocks by their qualified name `Type::function`
+    methods: RefCell<HashMap<Identifier, FunctionDeclare>>,
+    traits: RefCell<HashMap<Identifier, TraitDeclare>>,
+    /// `(type, trait)` of every `impl Trait for Type` block
+    trait_impls: RefCell<HashSet<(Identifier, Identifier)>>,
+    /// instances of generic functions, emitted once per list of type arguments
+    function_instances: RefCell<HashMap<(Identifier, Vec<Type>), FunctionValue>>,
+    /// generic struct and type arguments behind the llvm name of a struct instance
+    struct_instances: RefCell<HashMap<String, (Identifier, Vec<Type>)>>,
+    /// trait and type arguments behind the llvm name of a trait object type
+    trait_objects: RefCell<HashMap<String, (Identifier, Vec<Type>)>>,
+    /// number of closures generated so far in every function, by the symbol of the function
+    closures: RefCell<HashMap<String, usize>>,
+    /// values of the module level constants with their declared type
+    constants: RefCell<HashMap<Identifier, (Type, ConstValue)>>,
+    /// globals of the statics and whether they are mutable
+    statics: RefCell<HashMap<Identifier, (PointerValue, bool)>>,
+    /// constants describing the types allocated on the heap or held by roots to the gc
+    type_infos: RefCell<HashMap<LLVMTypeRef, PointerValue>>,
+    /// messages about code which compiles but is likely a mistake, like unreachable arms
+    warnings: RefCell<Vec<String>>,
+}
+
+impl Environment {
+    pub fn new(options: CompileOptions, source_name: impl Into<String>) -> Self {
+        Environment {
+            options,
+            source_name: source_name.into(),
+            structs: RefCell::new(HashMap::new()),
+            enums: RefCell::new(HashMap::new()),
+            aliases: RefCell::new(HashMap::new()),
+            newtypes: RefCell::new(HashMap::new()),
+            functions: RefCell::new(HashMap::new()),
+            parameters: RefCell::new(HashMap::new()),
+            generic_functions: RefCell::new(HashMap::new()),
+            methods: RefCell::new(HashMap::new()),
+            traits: RefCell::new(HashMap::new()),
+            trait_impls: RefCell::new(HashSet::new()),
+            function_instances: RefCell::new(HashMap::new()),
+            struct_instances: RefCell::new(HashMap::new()),
+            trait_objects: RefCell::new(HashMap::new()),
+            closures: RefCell::new(HashMap::new()),
+            constants: RefCell::new(HashMap::new()),
+            statics: RefCell::new(HashMap::new()),
+            type_infos: RefCell::new(HashMap::new()),
+            warnings: RefCell::new(vec![]),
+        }
+    }
+
+    pub fn warn(&self, message: String) {
+        self.warnings.borrow_mut().push(message);
+    }
+
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.borrow().clone()
+    }
+
+    pub fn declare_struct(&self, struct_decl: StructDeclare) {
+        self.structs
+            .borrow_mut()
+            .insert(struct_decl.name.clone(), struct_decl);
+    }
+
+    pub fn get_struct(&self, name: &str) -> Option<StructDeclare> {
+        self.structs.borrow().get(name).cloned()
+    }
+
+    pub fn declare_enum(&self, enum_decl: EnumDeclare) {
+        self.enums.borrow_mut().insert(enum_decl.name.clone(), enum_decl);
+    }
+
+    pub fn get_enum(&self, name: &str) -> Option<EnumDeclare> {
+        self.enums.borrow().get(name).cloned()
+    }
+
+    pub fn declare_alias(&self, alias: TypeAlias) {
+        self.aliases.borrow_mut().insert(alias.name.clone(), alias);
+    }
+
+    pub fn get_alias(&self, name: &str) -> Option<TypeAlias> {
+        self.aliases.borrow().get(name).cloned()
+    }
+
+    pub fn declare_newtype(&self, newtype: NewtypeDeclare) {
+        self.declare_struct(newtype.as_struct());
+        self.newtypes.borrow_mut().insert(newtype.name.clone(), newtype);
+    }
+
+    pub fn get_newtype(&self, name: &str) -> Option<NewtypeDeclare> {
+        self.newtypes.borrow().get(name).cloned()
+    }
+
+    pub fn add_function(&self, name: Identifier, function: FunctionValue) {
+        self.functions.borrow_mut().insert(name, function);
+    }
+
+    pub fn get_function(&self, name: &str) -> Option<FunctionValue> {
+        self.functions.borrow().get(name).cloned()
+    }
+
+    pub fn declare_parameters(&self, name: Identifier, parameters: Vec<Type>) {
+        self.parameters.borrow_mut().insert(name, parameters);
+    }
+
+    pub fn get_parameters(&self, name: &str) -> Option<Vec<Type>> {
+        self.parameters.borrow().get(name).cloned()
+    }
+
+    pub fn declare_generic_function(&self, func_decl: FunctionDeclare) {
+        self.generic_functions
+            .borrow_mut()
+            .insert(func_decl.name.clone(), func_decl);
+    }
+
+    pub fn get_generic_function(&self, name: &str) -> Option<FunctionDeclare> {
+        self.generic_functions.borrow().get(name).cloned()
+    }
+
+    pub fn declare_method(&self, func_decl: FunctionDeclare) {
+        self.methods.borrow_mut().insert(func_decl.name.clone(), func_decl);
+    }
+
+    /// function `name` of an `impl` block of the type named `type_name`
+    pub fn get_method(&self, type_name: &str, name: &str) -> Option<FunctionDeclare> {
+        self.methods
+            .borrow()
+            .get(&format!("{}::{}", type_name, name))
+            .cloned()
+    }
+
+    pub fn declare_trait(&self, trait_decl: TraitDeclare) {
+        let name = trait_decl.name.clone();
+        if self.traits.borrow_mut().insert(name.clone(), trait_decl).is_some() {
+            panic!("trait {} is already defined", name);
+        }
+    }
+
+    pub fn get_trait(&self, name: &str) -> Option<TraitDeclare> {
+        self.traits.borrow().get(name).cloned()
+    }
+
+    pub fn add_trait_impl(&self, type_name: Identifier, trait_name: Identifier) {
+        if !self.trait_impls.borrow_mut().insert((type_name.clone(), trait_name.clone())) {
+            panic!("conflicting implementations of trait {} for {}", trait_name, type_name);
+        }
+    }
+
+    pub fn has_trait_impl(&self, type_name: &str, trait_name: &str) -> bool {
+        self.trait_impls
+            .borrow()
+            .contains(&(type_name.to_string(), trait_name.to_string()))
+    }
+
+    pub fn add_function_instance(&self, name: Identifier, arguments: Vec<Type>, function: FunctionValue) {
+        self.function_instances
+            .borrow_mut()
+            .insert((name, arguments), function);
+    }
+
+    pub fn get_function_instance(&self, name: &str, arguments: &[Type]) -> Option<FunctionValue> {
+        self.function_instances
+            .borrow()
+            .get(&(name.to_string(), arguments.to_vec()))
+            .cloned()
+    }
+
+    pub fn add_struct_instance(&self, llvm_name: String, name: Identifier, arguments: Vec<Type>) {
+        self.struct_instances
+            .borrow_mut()
+            .insert(llvm_name, (name, arguments));
+    }
+
+    pub fn get_struct_instance(&self, llvm_name: &str) -> Option<(Identifier, Vec<Type>)> {
+        self.struct_instances.borrow().get(llvm_name).cloned()
+    }
+
+    pub fn add_trait_object(&self, llvm_name: String, trait_name: Identifier, arguments: Vec<Type>) {
+        self.trait_objects
+            .borrow_mut()
+            .insert(llvm_name, (trait_name, arguments));
+    }
+
+    pub fn get_trait_object(&self, llvm_name: &str) -> Option<(Identifier, Vec<Type>)> {
+        self.trait_objects.borrow().get(llvm_name).cloned()
+    }
+
+    /// index of the next closure of the function with the symbol `function`
+    pub fn next_closure(&self, function: &str) -> usize {
+        let mut closures = self.closures.borrow_mut();
+        let index = closures.entry(function.to_string()).or_insert(0);
+        *index += 1;
+        *index - 1
+    }
+
+    pub fn add_constant(&self, name: Identifier, ty: Type, value: ConstValue) {
+        self.constants.borrow_mut().insert(name, (ty, value));
+    }
+
+    pub fn get_constant(&self, name: &str) -> Option<(Type, ConstValue)> {
+        self.constants.borrow().get(name).cloned()
+    }
+
+    pub fn add_static(&self, name: Identifier, global: PointerValue, mutable: bool) {
+        self.statics.borrow_mut().insert(name, (global, mutable));
+    }
+
+    pub fn get_static(&self, name: &str) -> Option<(PointerValue, bool)> {
+        self.statics.borrow().get(name).cloned()
+    }
+
+    pub fn add_type_info(&self, ty: &BasicType, global: PointerValue) {
+        self.type_infos.borrow_mut().insert(ty.as_llvm_type_ref(), global);
+    }
+
+    pub fn get_type_info(&self, ty: &BasicType) -> Option<PointerValue> {
+        self.type_infos.borrow().get(&ty.as_llvm_type_ref()).cloned()
+    }
+}
+
+/// variables visible in a block, every variable lives in an alloca of the function
+pub struct Scope<'a> {
+    environment: &'a Environment,
+    upper: Option<&'a Scope<'a>>,
+    variables: RefCell<HashMap<Identifier, PointerValue>>,
+    /// concrete types of the type parameters of the function instance being generated
+    type_arguments: HashMap<Identifier, Type>,
+    /// inside of an `unsafe` block, where mutable statics may be used
+    unsafe_context: bool,
+}
+
+impl<'a> Scope<'a> {
+    pub fn new(environment: &'a Environment) -> Self {
+        Scope {
+            environment,
+            upper: None,
+            variables: RefCell::new(HashMap::new()),
+            type_arguments: HashMap::new(),
+            unsafe_context: false,
+        }
+    }
+
+    /// root scope of an instance of a generic function
+    pub fn new_generic(environment: &'a Environment, type_arguments: HashMap<Identifier, Type>) -> Self {
+        Scope {
+            type_arguments,
+            ..Scope::new(environment)
+        }
+    }
+
+    pub fn new_with_upper(upper: &'a Scope<'a>) -> Self {
+        Scope {
+            environment: upper.environment,
+            upper: Some(upper),
+            variables: RefCell::new(HashMap::new()),
+            type_arguments: upper.type_arguments.clone(),
+            unsafe_context: upper.unsafe_context,
+        }
+    }
+
+    /// scope of the body of an `unsafe` block
+    pub fn new_unsafe(upper: &'a Scope<'a>) -> Self {
+        Scope {
+            unsafe_context: true,
+            ..Scope::new_with_upper(upper)
+        }
+    }
+
+    /// root scope of the body of a closure, the variables of the enclosing function are not
+    /// visible, the captured ones are declared again from the environment of the closure
+    pub fn new_closure(enclosing: &Scope<'a>) -> Self {
+        Scope {
+            type_arguments: enclosing.type_arguments.clone(),
+            unsafe_context: enclosing.unsafe_context,
+            ..Scope::new(enclosing.environment)
+        }
+    }
+
+    pub fn is_unsafe(&self) -> bool {
+        self.unsafe_context
+    }
+
+    pub fn environment(&self) -> &'a Environment {
+        self.environment
+    }
+
+    /// the type with type parameters replaced by their concrete types
+    pub fn resolve_type(&self, ty: &Type) -> Type {
+        ty.substitute(&self.type_arguments)
+    }
+
+    pub fn declare(&self, name: Identifier, ptr: PointerValue) {
+        debug!("declare variable {}", &name);
+        self.variables.borrow_mut().insert(name, ptr);
+    }
+
+    pub fn get(&self, name: &str) -> Option<PointerValue> {
+        self.variables
+            .borrow()
+            .get(name)
+            .cloned()
+            .or_else(|| self.upper.and_then(|upper| upper.get(name)))
+    }
+}
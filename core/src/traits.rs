@@ -0,0 +1,244 @@
+use crate::codegen::type_error;
+use crate::scope::Environment;
+use ast::{FunctionDeclare, Identifier, ImplBlock, Opcode, TraitDeclare, TraitFunction, Type};
+use std::collections::HashMap;
+
+/// every operator with the trait overloading it and the function of the trait it calls,
+/// `a + b` is `Add::add(a, b)` for any type other than the built-in ones
+const OPERATORS: [(Opcode, &str, &str); 11] = [
+    (Opcode::Add, "Add", "add"),
+    (Opcode::Sub, "Sub", "sub"),
+    (Opcode::Mul, "Mul", "mul"),
+    (Opcode::Div, "Div", "div"),
+    (Opcode::Mod, "Rem", "rem"),
+    (Opcode::Pow, "Pow", "pow"),
+    (Opcode::Or, "BitOr", "bitor"),
+    (Opcode::And, "BitAnd", "bitand"),
+    (Opcode::Xor, "BitXor", "bitxor"),
+    (Opcode::LShift, "Shl", "shl"),
+    (Opcode::RShift, "Shr", "shr"),
+];
+
+/// trait and function an operator is overloaded with
+pub(crate) fn operator_trait(opcode: Opcode) -> (&'static str, &'static str) {
+    OPERATORS
+        .iter()
+        .find(|(it, _, _)| *it == opcode)
+        .map(|(_, trait_name, function)| (*trait_name, *function))
+        .unwrap()
+}
+
+/// the marker trait of types whose values are copied instead of moved, structs and enums
+/// opt in with `impl Copy for Type {}`
+pub(crate) const COPY: &str = "Copy";
+
+/// the traits of the standard library hash maps need of their keys, `fn hash(&self) -> i64`
+/// and `fn eq(&self, other: &Self) -> i64` returning one for equal values, integers and
+/// tuples and arrays of such values implement them and structs derive them with an empty
+/// `impl Hash for Point {}`
+pub(crate) const HASH: &str = "std::Hash";
+pub(crate) const EQ: &str = "std::Eq";
+
+/// the trait `for` loops iterate with, `fn next(&mut self) -> Option<Item>` of
+/// `Iterator<Item>` returns the items until it returns `None`, a value which is no iterator
+/// is iterated through the one its `fn iter(&self)` method returns
+pub(crate) const ITERATOR: &str = "std::Iterator";
+pub(crate) const NEXT: &str = "next";
+pub(crate) const ITER: &str = "iter";
+
+/// the enums `next` returns and `?` propagates and the structs `a..b` and `a..=b` build
+pub(crate) const OPTION: &str = "std::Option";
+pub(crate) const RESULT: &str = "std::Result";
+pub(crate) const RANGE: &str = "std::Range";
+pub(crate) const RANGE_INCLUSIVE: &str = "std::RangeInclusive";
+
+/// the methods `v[i]` calls on a type other than arrays and simd vectors, `fn index(&self,
+/// index: i64) -> &T` for values and `fn index_mut(&mut self, index: i64) -> &mut T` for
+/// places
+pub(crate) const INDEX: &str = "index";
+pub(crate) const INDEX_MUT: &str = "index_mut";
+
+/// the traits every module starts with, the operator traits, `fn add(self, rhs: Self) ->
+/// Self`, and `Copy`
+pub(crate) fn builtin_traits() -> Vec<TraitDeclare> {
+    let self_type = || Type::named("Self".to_string());
+    OPERATORS
+        .iter()
+        .map(|(_, trait_name, function)| {
+            let args = vec![("self".to_string(), self_type()), ("rhs".to_string(), self_type())];
+            let function = TraitFunction::new(function.to_string(), args, self_type(), None);
+            TraitDeclare::new(trait_name.to_string(), vec![function])
+        })
+        .chain(std::iter::once(TraitDeclare::new(COPY.to_string(), vec![])))
+        .collect()
+}
+
+/// the operand table of the built-in types, integers and simd vectors of them implement
+/// every operator trait apart from `Pow`, integers `Hash` and `Eq` as well, floats and
+/// simd vectors of them only the arithmetic ones, floats `Eq` as well, and every built-in
+/// type apart from boxes and `&mut` references is `Copy`
+pub(crate) fn builtin_implements(ty: &Type, trait_name: &str) -> bool {
+    let is_operator = OPERATORS.iter().any(|(_, it, _)| *it == trait_name);
+    let is_arithmetic = matches!(trait_name, "Add" | "Sub" | "Mul" | "Div" | "Rem");
+    match ty {
+        Type::Named(name) if matches!(name.as_str(), "i8" | "i16" | "i32" | "i64") => {
+            (is_operator && trait_name != "Pow") || matches!(trait_name, COPY | HASH | EQ)
+        }
+        Type::Named(name) if is_float(name) => is_arithmetic || matches!(trait_name, COPY | EQ),
+        Type::Simd(element, _) if matches!(&**element, Type::Named(name) if is_float(name)) => {
+            is_arithmetic || trait_name == COPY
+        }
+        Type::Simd(..) => (is_operator && trait_name != "Pow") || trait_name == COPY,
+        Type::Reference { mutable: false, .. } | Type::Pointer(_) | Type::Function(..) => {
+            trait_name == COPY
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn is_float(name: &str) -> bool {
+    matches!(name, "f32" | "f64")
+}
+
+/// name `impl` blocks refer to the type by
+pub(crate) fn type_name(ty: &Type) -> Option<&Identifier> {
+    match ty {
+        Type::Named(name) | Type::Generic(name, _) => Some(name),
+        _ => None,
+    }
+}
+
+/// whether `ty` satisfies the bound `T: trait_name`
+pub(crate) fn implements(ty: &Type, trait_name: &str, environment: &Environment) -> bool {
+    if environment.get_trait(trait_name).is_none() {
+        panic!("trait {} is undefined", trait_name);
+    }
+    match ty {
+        Type::Tuple(elements) if matches!(trait_name, COPY | HASH | EQ) => {
+            elements.iter().all(|it| implements(it, trait_name, environment))
+        }
+        Type::Array(element, _) if matches!(trait_name, COPY | HASH | EQ) => {
+            implements(element, trait_name, environment)
+        }
+        // values are hashed and compared through references and boxes
+        Type::Reference { inner, .. } | Type::Boxed(inner) if matches!(trait_name, HASH | EQ) => {
+            implements(inner, trait_name, environment)
+        }
+        _ => {
+            builtin_implements(ty, trait_name)
+                || type_name(ty).is_some_and(|name| environment.has_trait_impl(name, trait_name))
+        }
+    }
+}
+
+/// functions of an `impl` block as free functions, an `impl Trait for Type` block is checked
+/// against the trait and completed with the default functions it leaves out, in which the
+/// type parameters of a generic trait stand for the type arguments of the block
+pub(crate) fn impl_functions(impl_block: &ImplBlock, environment: &Environment) -> Vec<FunctionDeclare> {
+    let trait_name = match &impl_block.trait_name {
+        Some(trait_name) => trait_name,
+        None => return impl_block.qualified_functions(),
+    };
+    let trait_decl = environment
+        .get_trait(trait_name)
+        .unwrap_or_else(|| panic!("trait {} is undefined", trait_name));
+    if trait_decl.generics.len() != impl_block.trait_arguments.len() {
+        type_error(format!(
+            "trait {} takes {} type arguments but the implementation for {} supplies {}",
+            trait_name,
+            trait_decl.generics.len(),
+            impl_block.name,
+            impl_block.trait_arguments.len()
+        ));
+    }
+    let trait_arguments: Vec<(Identifier, Type)> = trait_decl
+        .generics
+        .iter()
+        .cloned()
+        .zip(impl_block.trait_arguments.iter().cloned())
+        .collect();
+    let bindings: HashMap<Identifier, Type> = trait_arguments.iter().cloned().collect();
+    for function in impl_block.functions.iter() {
+        let declared = trait_decl.function(&function.name).unwrap_or_else(|| {
+            panic!("{} is not a function of trait {}", function.name, trait_name)
+        });
+        if declared.args.len() != function.args.len() {
+            panic!(
+                "{} of trait {} takes {} arguments but the implementation for {} takes {}",
+                function.name,
+                trait_name,
+                declared.args.len(),
+                impl_block.name,
+                function.args.len()
+            );
+        }
+    }
+    let mut functions = impl_block.functions.clone();
+    for declared in trait_decl.functions.iter() {
+        if functions.iter().any(|function| function.name == declared.name) {
+            continue;
+        }
+        let default = declared.default_function().unwrap_or_else(|| {
+            panic!(
+                "{} of trait {} is not implemented for {}",
+                declared.name, trait_name, impl_block.name
+            )
+        });
+        functions.push(FunctionDeclare {
+            args: default
+                .args
+                .iter()
+                .map(|(name, ty)| (name.clone(), ty.substitute(&bindings)))
+                .collect(),
+            return_type: default.return_type.substitute(&bindings),
+            trait_arguments: trait_arguments.clone(),
+            ..default
+        });
+    }
+    ImplBlock {
+        functions,
+        ..impl_block.clone()
+    }
+    .qualified_functions()
+}
+
+/// whether `Self` appears anywhere in `ty`
+fn mentions_self(ty: &Type) -> bool {
+    match ty {
+        Type::Named(name) => name == "Self",
+        Type::Reference { inner, .. } | Type::Pointer(inner) | Type::Boxed(inner) => mentions_self(inner),
+        Type::Array(element, _) | Type::Simd(element, _) => mentions_self(element),
+        Type::Tuple(elements) | Type::Generic(_, elements) | Type::Dyn(_, elements) => {
+            elements.iter().any(mentions_self)
+        }
+        Type::Function(parameters, return_type) => {
+            parameters.iter().any(mentions_self) || mentions_self(return_type)
+        }
+    }
+}
+
+/// `dyn Trait` calls every function of the trait through a vtable with the data pointer as
+/// receiver, so each function needs a `&self` or `&mut self` receiver, no type parameters
+/// and no other mention of `Self`, whose size is unknown behind the trait object
+pub(crate) fn check_object_safety(trait_decl: &TraitDeclare) -> Result<(), String> {
+    for function in trait_decl.functions.iter() {
+        let reason = match function.args.first() {
+            Some((name, Type::Reference { .. })) if name == "self" => None,
+            Some((name, _)) if name == "self" => Some("takes self by value"),
+            _ => Some("has no self receiver"),
+        }
+        .or_else(|| (!function.generics.is_empty()).then_some("has type parameters"))
+        .or_else(|| {
+            let mut types = function.args.iter().skip(1).map(|(_, ty)| ty);
+            (types.any(mentions_self) || mentions_self(&function.return_type))
+                .then_some("refers to Self outside of its receiver")
+        });
+        if let Some(reason) = reason {
+            return Err(format!(
+                "trait {} cannot be made into an object, {} {}",
+                trait_decl.name, function.name, reason
+            ));
+        }
+    }
+    Ok(())
+}
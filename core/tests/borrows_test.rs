@@ -0,0 +1,260 @@
+use ast::Location;
+use core::borrows::{Action, BorrowChecker, BorrowError};
+use core::error::TyphoonError;
+use core::program::Program;
+use parser::parser::parse_module;
+use std::path::PathBuf;
+
+fn check(source: &str) -> Vec<BorrowError> {
+    let module = parse_module(source).unwrap();
+    BorrowChecker::new(&module).check_module(&module)
+}
+
+/// the errors as their messages
+fn messages(source: &str) -> Vec<String> {
+    check(source).iter().map(|error| error.to_string()).collect()
+}
+
+const POINT: &str = r#"
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Point {
+    fn get_x(&self) -> i32 {
+        self.x
+    }
+
+    fn set_x(&mut self, x: i32) -> i32 {
+        self.x = x;
+        x
+    }
+}
+
+fn length(p: &Point) -> i32 {
+    p.x + p.y
+}
+
+fn shift(p: &mut Point, by: i32) -> i32 {
+    p.x = p.x + by;
+    p.x
+}
+"#;
+
+#[test]
+fn mutable_borrows_do_not_alias_live_borrows() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+fn main() -> i32 {
+    let p: Point = Point { x: 1, y: 2 };
+    let r: &mut Point = &mut p;
+    let s: &Point = &p;
+    r.x = 3;
+    return s.x;
+}
+
+fn sequential() -> i32 {
+    let p: Point = Point { x: 1, y: 2 };
+    let r: &mut Point = &mut p;
+    r.x = 3;
+    let s: &Point = &p;
+    let a: i32 = p.x + s.x;
+    let t: &mut Point = &mut p;
+    return a + t.x;
+}
+"#
+    );
+    let errors = check(&source);
+    assert_eq!(
+        errors,
+        vec![BorrowError::Conflict {
+            function: "main".to_string(),
+            place: "p".to_string(),
+            action: Action::Borrow { mutable: false },
+            location: Location::new(30, 22),
+            mutable: true,
+            borrowed: Location::new(29, 30),
+            used: Location::new(31, 5),
+        }]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "cannot borrow `p` in main at 30:22 while it is borrowed as mutable, the borrow is created at 29:30 and used later at 31:5"
+    );
+}
+
+#[test]
+fn borrowed_values_are_not_assigned_moved_or_used_mutably() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+fn consume(p: Point) -> i32 {
+    p.x
+}
+
+fn main() -> i32 {
+    let p: Point = Point { x: 1, y: 2 };
+    let r: &Point = &p;
+    p.x = 5;
+    let a: i32 = consume(p) + r.x;
+    let q: Point = Point { x: 1, y: 2 };
+    let m: &mut Point = &mut q;
+    let b: i32 = q.y + m.y;
+    return a + b;
+}
+"#
+    );
+    assert_eq!(
+        messages(&source),
+        vec![
+            "cannot assign to `p.x` in main at 34:5 while it is borrowed, the borrow is created at 33:22 and used later at 35:31",
+            "cannot move out of `p` in main at 35:26 while it is borrowed, the borrow is created at 33:22 and used later at 35:31",
+            "cannot use `q.y` in main at 38:18 while it is borrowed as mutable, the borrow is created at 37:30 and used later at 38:24",
+        ]
+    );
+}
+
+#[test]
+fn arguments_of_a_call_are_borrowed_until_the_call() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+fn both(a: &mut Point, b: &Point) -> i32 {
+    a.x + b.x
+}
+
+fn main() -> i32 {
+    let p: Point = Point { x: 1, y: 2 };
+    let a: i32 = length(&p) + shift(&mut p, 1) + length(&p);
+    let b: i32 = p.set_x(p.get_x() + p.y);
+    return a + b + both(&mut p, &p);
+}
+"#
+    );
+    assert_eq!(
+        messages(&source),
+        vec![
+            "cannot borrow `p` in main at 35:34 while it is borrowed as mutable, the borrow is created at 35:30 and used later at 35:20",
+        ]
+    );
+}
+
+#[test]
+fn references_do_not_outlive_what_they_borrow() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+fn first(p: &Point) -> &i32 {
+    &p.x
+}
+
+fn dangling(p: Point) -> &i32 {
+    &p.x
+}
+
+fn scoped() -> i32 {
+    let p: Point = Point { x: 1, y: 2 };
+    let r: &i32 = &p.x;
+    let a: i32 = {
+        let q: Point = Point { x: 3, y: 4 };
+        r = &q.y;
+        *r
+    };
+    let b: i32 = {
+        let q: Point = Point { x: 3, y: 4 };
+        r = first(&q);
+        5
+    };
+    return a + b + *r;
+}
+"#
+    );
+    assert_eq!(
+        messages(&source),
+        vec![
+            "dangling returns a reference to its local `p`, which is borrowed at 32:6",
+            "`q` in scoped goes out of scope while it is borrowed, the borrow is created at 45:20 and used later at 48:21",
+        ]
+    );
+}
+
+#[test]
+fn references_returned_from_blocks_are_used_where_they_are_returned() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+fn tail() -> &i32 {
+    {
+        let q: Point = Point { x: 3, y: 4 };
+        &q.x
+    }
+}
+"#
+    );
+    assert_eq!(
+        messages(&source),
+        vec![
+            "`q` in tail goes out of scope while it is borrowed, the borrow is created at 30:10 and used later at 30:10",
+            "tail returns a reference to its local `q`, which is borrowed at 30:10",
+        ]
+    );
+}
+
+#[test]
+fn values_behind_shared_references_are_not_mutated() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+fn reset(p: &Point, q: &mut Point, n: &i32) -> i32 {
+    p.x = 0;
+    q.x = 0;
+    *n = 0;
+    let a: i32 = shift(&mut *p, 1) + shift(&mut *q, 1);
+    return a + p.set_x(1) + q.set_x(1) + p.get_x();
+}
+"#
+    );
+    assert_eq!(
+        messages(&source),
+        vec![
+            "cannot assign through the shared reference `p` in reset at 28:5",
+            "cannot assign through the shared reference `n` in reset at 30:6",
+            "cannot borrow as mutable through the shared reference `p` in reset at 31:30",
+            "cannot borrow as mutable through the shared reference `p` in reset at 32:16",
+        ]
+    );
+}
+
+#[test]
+fn program_reports_borrow_errors() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+fn main() -> i32 {
+    let p: Point = Point { x: 1, y: 2 };
+    let r: &mut Point = &mut p;
+    let s: &Point = &p;
+    r.x = 3;
+    return s.x;
+}
+"#
+    );
+    match Program::new_with_string(PathBuf::from("borrowed.ty"), &source) {
+        Err(TyphoonError::BorrowError(message)) => assert_eq!(
+            message,
+            "borrowed.ty: cannot borrow `p` in main at 30:22 while it is borrowed as mutable, \
+             the borrow is created at 29:30 and used later at 31:5"
+        ),
+        Err(error) => panic!("expected a borrow error, got {}", error),
+        Ok(_) => panic!("expected a borrow error"),
+    }
+}
@@ -0,0 +1,211 @@
+mod common;
+
+use core::c_header::CHeader;
+use core::program::CrateType;
+use parser::parser::parse_module;
+
+#[test]
+fn header_contains_exported_prototypes_and_struct_layouts() {
+    let module = parse_module(
+        r#"
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+        struct Line {
+            start: Point,
+            end: Point,
+        }
+        pub extern fn area(width: i32, height: i32) -> i32 {
+            return 1;
+        }
+        pub extern fn reset() -> () {
+        }
+        pub extern fn length(line: &Line) -> i32 {
+            return 1;
+        }
+        fn helper() -> i32 {
+            return 1;
+        }
+        "#,
+    )
+    .unwrap();
+
+    let header = CHeader::new("shapes", &module).generate().unwrap();
+
+    assert!(header.contains("#ifndef TYPHOON_SHAPES_H"));
+    assert!(header.contains("struct Point {\n    int32_t x;\n    int32_t y;\n};"));
+    assert!(header.contains("struct Line {\n    struct Point end;\n    struct Point start;\n};"));
+    assert!(header.find("struct Point {").unwrap() < header.find("struct Line {").unwrap());
+    assert!(header.contains("int32_t area(int32_t width, int32_t height);"));
+    assert!(header.contains("void reset(void);"));
+    assert!(header.contains("int32_t length(const struct Line * line);"));
+    assert!(!header.contains("helper"));
+}
+
+#[test]
+fn structs_outside_of_exported_signatures_are_left_out() {
+    let module = parse_module(
+        r#"
+        struct Point {
+            x: i32,
+        }
+        struct Cache {
+            entries: Vec<i32>,
+        }
+        pub extern fn norm(p: &Point) -> i32 {
+            return p.x;
+        }
+        "#,
+    )
+    .unwrap();
+
+    let header = CHeader::new("shapes", &module).generate().unwrap();
+
+    assert!(header.contains("struct Point {"));
+    assert!(!header.contains("Cache"));
+}
+
+#[test]
+fn struct_by_value_is_rejected_in_exported_signature() {
+    let module = parse_module(
+        r#"
+        struct Point {
+            x: i32,
+        }
+        pub extern fn origin(p: Point) -> i32 {
+            return 1;
+        }
+        "#,
+    )
+    .unwrap();
+
+    assert!(CHeader::new("shapes", &module).generate().is_err());
+}
+
+#[test]
+fn struct_by_reference_is_exported_as_pointer() {
+    let module = parse_module(
+        r#"
+        struct Point {
+            x: i32,
+        }
+        pub extern fn move_to(p: &mut Point, target: &Point) -> () {
+        }
+        "#,
+    )
+    .unwrap();
+
+    let header = CHeader::new("shapes", &module).generate().unwrap();
+
+    assert!(header.contains("void move_to(struct Point * p, const struct Point * target);"));
+}
+
+#[test]
+fn array_field_is_declared_with_its_length() {
+    let module = parse_module(
+        r#"
+        struct Matrix {
+            cells: [[i32; 3]; 2],
+        }
+        pub extern fn trace(matrix: &Matrix) -> i32 {
+            return 0;
+        }
+        "#,
+    )
+    .unwrap();
+
+    let header = CHeader::new("shapes", &module).generate().unwrap();
+
+    assert!(header.contains("    int32_t cells[2][3];"));
+}
+
+#[test]
+fn aliases_are_expanded_in_the_header() {
+    let module = parse_module(
+        r#"
+        type Meters = i64;
+        type Row = [Meters; 3];
+        struct Track {
+            length: Meters,
+            laps: Row,
+        }
+        pub extern fn advance(track: &Track, by: Meters) -> Meters {
+            return by;
+        }
+        "#,
+    )
+    .unwrap();
+
+    let header = CHeader::new("tracks", &module).generate().unwrap();
+
+    assert!(header.contains("struct Track {\n    int64_t laps[3];\n    int64_t length;\n};"));
+    assert!(header.contains("int64_t advance(const struct Track * track, int64_t by);"));
+}
+
+#[test]
+fn library_header_only_describes_the_program() {
+    let mut program = common::program(
+        "points",
+        r#"
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+        pub extern fn norm(p: &Point) -> i32 {
+            return p.x * p.x + p.y * p.y;
+        }
+        "#,
+    );
+    let build_folder = program.build_folder.clone();
+
+    let library = program.as_library(CrateType::StaticLib, false).unwrap();
+
+    assert!(library.is_file());
+    let header = std::fs::read_to_string(build_folder.join("points.h")).unwrap();
+    assert!(header.contains("struct Point {\n    int32_t x;\n    int32_t y;\n};"));
+    assert!(header.contains("int32_t norm(const struct Point * p);"));
+    assert!(!header.contains("std::"));
+}
+
+#[test]
+fn static_library_links_into_a_c_program() {
+    let mut program = common::program(
+        "grades",
+        r#"
+        struct Score {
+            points: i32,
+        }
+        pub extern fn grade(score: &Score) -> i32 {
+            return match score.points {
+                0 => 6,
+                1 => 5,
+                2 => 4,
+                3 => 3,
+                4 => 2,
+                _ => 1,
+            };
+        }
+        "#,
+    );
+    let build_folder = program.build_folder.clone();
+    let library = program.as_library(CrateType::StaticLib, false).unwrap();
+
+    let main = build_folder.join("main.c");
+    std::fs::write(
+        &main,
+        "#include \"grades.h\"\nint main(void) {\n    struct Score score = {3};\n    return grade(&score);\n}\n",
+    )
+    .unwrap();
+    let executable = build_folder.join("main");
+    let status = std::process::Command::new("cc")
+        .arg(&main)
+        .arg(&library)
+        .arg("-o")
+        .arg(&executable)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let status = std::process::Command::new(&executable).status().unwrap();
+    assert_eq!(status.code(), Some(3));
+}
@@ -103,3 +103,972 @@
 //     "#;
 //     run_test_with_expected("load_nested_struct_value_as_return_code", t, 4, "", "");
 // }
+
+mod common;
+
+use core::error::TyphoonError;
+
+fn run_test_with_expected(
+    name: &str,
+    program_text: &str,
+    exit_code: i32,
+    stdout: &str,
+    stderr: &str,
+) {
+    let (runtime_exit_code, runtime_stdout, runtime_stderr) = common::run(name, program_text);
+
+    assert_eq!(exit_code, runtime_exit_code);
+    assert_eq!(stdout, runtime_stdout);
+    assert_eq!(stderr, runtime_stderr);
+}
+
+#[test]
+fn unreachable_arms_are_reported_as_warnings() {
+    let t = r#"
+    fn main() -> i32 {
+        let x: i32 = 2;
+        return match x {
+            _ => 2,
+            1 => 1,
+        };
+    }
+    "#;
+    let mut program = common::program("unreachable_arms", t);
+    assert_eq!(program.as_binary_output(false).unwrap().0, 2);
+    assert_eq!(program.warnings, vec!["unreachable pattern `1` at unreachable_arms.ty:6:13"]);
+}
+
+#[test]
+fn non_exhaustive_matches_are_errors() {
+    let t = r#"
+    enum Shape {
+        Circle(i32),
+        Rect { w: i32, h: i32 },
+        Empty,
+    }
+
+    fn area(s: Shape) -> i32 {
+        return match s {
+            Shape::Circle(r) => 3 * r * r,
+            Shape::Rect { w, h } => w * h,
+        };
+    }
+
+    fn main() -> i32 {
+        return area(Shape::Empty);
+    }
+    "#;
+    match common::build_error("non_exhaustive", t) {
+        TyphoonError::MatchError(message) => assert_eq!(
+            message,
+            "non-exhaustive patterns at non_exhaustive.ty:9:16: `Shape::Empty` not covered"
+        ),
+        error => panic!("expected a match error, got {}", error),
+    }
+}
+
+#[test]
+fn gc_stats_are_printed_when_the_program_exits() {
+    let t = r#"
+    fn main() -> i64 {
+        let a: Box<i64> = box 3;
+        let b: Box<i64> = box 4;
+        let c: Box<(i64, i64)> = box (*a, *b);
+        c.0 + c.1
+    }
+    "#;
+    let mut program = common::program("gc_stats", t);
+    assert_eq!(program.as_binary_output(false).unwrap(), (7, "".to_string(), "".to_string()));
+    program.gc_stats = true;
+    let stats = "gc: 0 collections, 3 objects in 32 bytes on the heap, peak heap 32 bytes, 32 bytes allocated\n";
+    assert_eq!(program.as_binary_output(false).unwrap(), (7, "".to_string(), stats.to_string()));
+}
+
+#[test]
+fn boxed_values_survive_collections() {
+    let t = r#"
+    enum List {
+        Nil,
+        Cons(i64, Box<List>),
+    }
+
+    fn build(n: i64) -> List {
+        return match n {
+            0 => List::Nil,
+            _ => List::Cons(n, box build(n - 1)),
+        };
+    }
+
+    fn sum(list: &List) -> i64 {
+        return match list {
+            List::Nil => 0,
+            List::Cons(value, rest) => value + sum(rest),
+        };
+    }
+
+    fn garbage(n: i64) -> i64 {
+        let big: Box<[i64; 512]> = box [n; 512];
+        return match n {
+            0 => 0,
+            _ => garbage(n - 1) + 1,
+        };
+    }
+
+    fn main() -> i32 {
+        let list: List = build(20);
+        let churned: i64 = garbage(500) + garbage(500);
+        let cell: Box<i64> = box 5;
+        return (sum(&list) + churned - 1000 + *cell) as i32;
+    }
+    "#;
+    run_test_with_expected("boxed_values_survive_collections", t, 215, "", "");
+}
+
+#[test]
+fn hash_map_grows_removes_and_looks_up_entries() {
+    let t = r#"
+    fn lookup(map: &HashMap<i64, i64>, key: i64) -> i64 {
+        match map.get(&key) {
+            Option::Some(value) => *value,
+            Option::None => 0 - 1,
+        }
+    }
+
+    fn main() -> i64 {
+        let map: HashMap<i64, i64> = HashMap::new();
+        for i in 0..100000 {
+            map.insert(i, i * 2);
+        }
+        for i in 0..50000 {
+            map.remove(&(i * 2));
+        }
+        let missing: i64 = lookup(&map, 10);
+        let found: i64 = lookup(&map, 99999);
+        let absent: i64 = lookup(&map, 200000);
+        let zero: i64 = 0;
+        let total: i64 = map.fold(zero, |acc, key, value| acc + *value - *key);
+        let updated: i64 = match map.get_mut(&7) {
+            Option::Some(value) => {
+                *value = 1;
+                *value
+            }
+            Option::None => 0,
+        };
+        let empty: HashMap<i64, i64> = HashMap::new();
+        let strings: HashMap<String, i64> = HashMap::new();
+        strings.insert(String::from_int(5), 5);
+        let five: i64 = *strings.get(&String::from_int(5)).unwrap();
+        map.len() - 50000 + missing + found - 199998 + absent + total - 2500000000 + updated + five + lookup(&empty, 1) + 1
+    }
+    "#;
+    run_test_with_expected("hash_map_grows_removes_and_looks_up_entries", t, 4, "", "");
+}
+
+#[test]
+#[should_panic(expected = "cannot dereference `i64` at deref_of_integer.ty:4:9")]
+fn deref_of_integer_is_an_error() {
+    let t = r#"
+    fn main() -> i64 {
+        let x: i64 = 3;
+        *x
+    }
+    "#;
+    common::run("deref_of_integer", t);
+}
+
+#[test]
+fn simd_lanes_are_computed_element_wise() {
+    let t = r#"
+    fn dot(a: simd<i32, 4>, b: simd<i32, 4>) -> i32 {
+        return reduce_add(a * b);
+    }
+
+    fn main() -> i32 {
+        let a: simd<i32, 4> = simd[1, 2, 3, 4];
+        let b: simd<i32, 4> = simd[2; 4];
+        let x: i32 = 5;
+        let c: simd<i32, 4> = simd[x, x, 1, 0] + 1;
+        a[3] = 10;
+        let d: simd<i32, 4> = 3 * a - b;
+        let i: i32 = 1;
+        return dot(a, b) + c[0] + reduce_max(d) - d[i] + reduce_min(c);
+    }
+    "#;
+    run_test_with_expected("simd_lanes_are_computed_element_wise", t, 63, "", "");
+}
+
+#[test]
+fn simd_float_lanes_use_float_arithmetic() {
+    let t = r#"
+    fn scale(v: simd<f32, 4>, by: f32) -> simd<f32, 4> {
+        v * by
+    }
+
+    fn main() -> i64 {
+        let a: simd<f32, 4> = simd[1.5, 2.5, 3.5, 4.5];
+        let b: simd<f32, 4> = simd[0.5; 4];
+        let scaled: simd<f32, 4> = scale(a + b, 2.0);
+        let halves: simd<f32, 4> = scaled / 4 - b;
+        let total: f32 = reduce_add(scaled);
+        let wide: simd<f64, 2> = simd[1.25, 2.75];
+        let lanes: simd<i64, 2> = (wide * 2) as simd<i64, 2>;
+        let y: f64 = 1 + (10 as f64) / 4;
+        let least: f32 = reduce_min(halves);
+        total as i64 + reduce_add(lanes) + y.eq(&3.5) * 100 + (least * 10) as i64 + a[3] as i64
+    }
+    "#;
+    run_test_with_expected("simd_float_lanes_use_float_arithmetic", t, 144, "", "");
+}
+
+#[test]
+#[should_panic(expected = "cannot apply `>>` to simd<f32, 4>, it does not implement Shr")]
+fn shift_of_float_lanes_is_an_error() {
+    let t = r#"
+    fn main() -> i64 {
+        let a: simd<f32, 4> = simd[1.5; 4];
+        let b: simd<f32, 4> = a >> a;
+        0
+    }
+    "#;
+    common::run("shift_of_float_lanes", t);
+}
+
+#[test]
+fn tuples_are_returned_and_destructured() {
+    let t = r#"
+    fn divmod(a: i32, b: i32) -> (i32, i32) {
+        return (a / b, a - a / b * b);
+    }
+
+    fn bump(pair: &mut (i32, i8)) -> () {
+        pair.1 = pair.1 + 1;
+    }
+
+    fn main() -> i32 {
+        let (q, r) = divmod(17, 5);
+        let pair: (i32, i8) = (40, 1);
+        bump(&mut pair);
+        let nested: ((i32, i32), i8) = ((q, r), 2);
+        let ((a, _), c) = nested;
+        let one: (i32,) = (3,);
+        return second() + pair.0 + pair.1 + nested.0.1 - a - c + one.0 - r;
+    }
+
+    fn second() -> i32 {
+        return divmod(7, 2).1;
+    }
+    "#;
+    run_test_with_expected("tuples_are_returned_and_destructured", t, 41, "", "");
+}
+
+#[test]
+fn enum_values_are_matched_by_variant() {
+    let t = r#"
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    enum Shape {
+        Circle(i32),
+        Rect { w: i32, h: i32 },
+        Empty,
+    }
+
+    enum Tree {
+        Leaf(i32),
+        Pair(&Shape, i32),
+    }
+
+    fn area(s: &Shape) -> i32 {
+        return match s {
+            Shape::Circle(0) => 100,
+            Shape::Circle(r) => 3 * r * r,
+            Shape::Rect { w, h: 1 } => w,
+            Shape::Rect { w, h } => w * h,
+            Shape::Empty => 0,
+        };
+    }
+
+    fn classify(n: i32, p: Point) -> i32 {
+        return match (n, p) {
+            (0, _) => 1,
+            (1, Point { x: 0, .. }) => 2,
+            (_, Point { x, y }) => x + y,
+        };
+    }
+
+    fn main() -> i32 {
+        let c: Shape = Shape::Circle(3);
+        let r: Shape = Shape::Rect { w: 2, h: 5 };
+        let e: Shape = Shape::Empty;
+        let t: Tree = Tree::Pair(&r, 4);
+        let inner: i32 = match t {
+            Tree::Leaf(v) => v,
+            Tree::Pair(s, k) => area(s) + k,
+        };
+        let p: Point = Point { x: 3, y: 4 };
+        return area(&c) + area(&r) + area(&e) + classify(0, p) + classify(5, p) + inner;
+    }
+
+    impl Copy for Point {}
+    "#;
+    run_test_with_expected("enum_values_are_matched_by_variant", t, 59, "", "");
+}
+
+#[test]
+fn methods_are_called_on_values_references_and_types() {
+    let t = r#"
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    impl Point {
+        fn new(x: i32, y: i32) -> Self {
+            return Self { x: x, y: y };
+        }
+
+        fn origin() -> Point {
+            return Point::new(0, 0);
+        }
+
+        fn len(self) -> i32 {
+            return self.x + self.y;
+        }
+
+        fn scaled(&self, factor: i32) -> Point {
+            return Self::new(self.x * factor, self.y * factor);
+        }
+
+        fn shift(&mut self, by: i32) -> () {
+            self.x = self.x + by;
+            return ();
+        }
+    }
+
+    struct Pair<A, B> {
+        first: A,
+        second: B,
+    }
+
+    impl<A, B> Pair<A, B> {
+        fn new(first: A, second: B) -> Self {
+            return Pair { first: first, second: second };
+        }
+
+        fn swap(self) -> Pair<B, A> {
+            return Pair { first: self.second, second: self.first };
+        }
+
+        fn first(&self) -> A {
+            return self.first;
+        }
+    }
+
+    enum Shape {
+        Square(i32),
+        Empty,
+    }
+
+    impl Shape {
+        fn area(&self) -> i32 {
+            return match self {
+                Shape::Square(side) => side * side,
+                Shape::Empty => 0,
+            };
+        }
+    }
+
+    fn main() -> i32 {
+        let p: Point = Point::new(1, 2);
+        p.shift(10);
+        let q: &Point = &p;
+        let small: i8 = 4;
+        let pair: Pair<i8, i32> = Pair::new(small, 100);
+        let s: Shape = Shape::Square(3);
+        return q.scaled(2).len() + p.len() + Point::origin().len() + pair.swap().first() + pair.first() + s.area();
+    }
+
+    impl<A, B> Copy for Pair<A, B> {}
+    "#;
+    run_test_with_expected("methods_are_called_on_values_references_and_types", t, 152, "", "");
+}
+
+#[test]
+fn traits_dispatch_statically_through_bounds() {
+    let t = r#"
+    struct V2 {
+        x: i32,
+        y: i32,
+    }
+
+    impl Add for V2 {
+        fn add(self, rhs: Self) -> Self {
+            return V2 { x: self.x + rhs.x, y: self.y + rhs.y };
+        }
+    }
+
+    impl Mul for V2 {
+        fn mul(self, rhs: V2) -> V2 {
+            return V2 { x: self.x * rhs.x, y: self.y * rhs.y };
+        }
+    }
+
+    trait Area {
+        fn area(&self) -> i32;
+        fn double_area(&self) -> i32 {
+            return self.area() * 2;
+        }
+    }
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    impl Zero for i32 {
+        fn zero() -> i32 {
+            return 0;
+        }
+    }
+
+    impl Zero for V2 {
+        fn zero() -> V2 {
+            return V2 { x: 0, y: 0 };
+        }
+    }
+
+    impl Area for V2 {
+        fn area(&self) -> i32 {
+            return self.x * self.y;
+        }
+    }
+
+    trait Describe {
+        fn code(self) -> i32;
+    }
+
+    impl Describe for i8 {
+        fn code(self) -> i32 {
+            return 8;
+        }
+    }
+
+    fn sum3<T: Add + Zero>(a: T, b: T, c: T) -> T {
+        return T::zero() + a + b + c;
+    }
+
+    fn total_area<T: Area>(shape: &T) -> i32 {
+        return shape.double_area();
+    }
+
+    fn main() -> i32 {
+        let a: V2 = V2 { x: 1, y: 2 };
+        let b: V2 = V2 { x: 3, y: 4 };
+        let c: V2 = sum3(a, b, a * b);
+        let n: i32 = sum3(1, 2, 3);
+        let small: i8 = 1;
+        return c.x + c.y + n + total_area(&c) + small.code();
+    }
+
+    impl Copy for V2 {}
+    "#;
+    run_test_with_expected("traits_dispatch_statically_through_bounds", t, 231, "", "");
+}
+
+#[test]
+fn trait_objects_dispatch_through_vtables() {
+    let t = r#"
+    struct Square {
+        side: i32,
+    }
+
+    struct Rect {
+        w: i32,
+        h: i32,
+    }
+
+    struct Pair<T> {
+        a: T,
+        b: T,
+    }
+
+    trait Shape {
+        fn area(&self) -> i32;
+        fn scaled_area(&self, factor: i32) -> i32 {
+            return self.area() * factor;
+        }
+    }
+
+    impl Shape for Square {
+        fn area(&self) -> i32 {
+            return self.side * self.side;
+        }
+    }
+
+    impl Shape for Rect {
+        fn area(&self) -> i32 {
+            return self.w * self.h;
+        }
+        fn scaled_area(&self, factor: i32) -> i32 {
+            return 1000;
+        }
+    }
+
+    impl<T> Shape for Pair<T> {
+        fn area(&self) -> i32 {
+            return self.a * self.b;
+        }
+    }
+
+    fn total(shapes: &[&dyn Shape; 3]) -> i32 {
+        return shapes[0].area() + shapes[1].area() + shapes[2].area();
+    }
+
+    fn scaled(shape: &dyn Shape) -> i32 {
+        return shape.scaled_area(2);
+    }
+
+    fn main() -> i32 {
+        let s: Square = Square { side: 3 };
+        let r: Rect = Rect { w: 2, h: 5 };
+        let p: Pair<i8> = Pair { a: 2, b: 4 };
+        let shapes: [&dyn Shape; 3] = [&s, &r, &p];
+        let one: &dyn Shape = &s;
+        return total(&shapes) + scaled(&s) + scaled(&r) - 1000 + one.area();
+    }
+    "#;
+    run_test_with_expected("trait_objects_dispatch_through_vtables", t, 54, "", "");
+}
+
+#[test]
+fn closures_capture_their_environment() {
+    let t = r#"
+    fn double(x: i32) -> i32 {
+        x * 2
+    }
+
+    fn apply(f: fn(i32) -> i32, x: i32) -> i32 {
+        f(x)
+    }
+
+    fn map<T, U>(a: [T; 3], f: fn(T) -> U) -> [U; 3] {
+        [f(a[0]), f(a[1]), f(a[2])]
+    }
+
+    fn adder(n: i32) -> fn(i32) -> i32 {
+        |x| x + n
+    }
+
+    fn id<T>(x: T) -> T {
+        x
+    }
+
+    fn main() -> i32 {
+        let captured: i32 = 10;
+        let f: fn(i32) -> i32 = |x| x + captured;
+        let g: fn(i32) -> i32 = double;
+        let a: [i32; 3] = [1, 2, 3];
+        let b: [i32; 3] = map(a, |x| x * 3);
+        let c: [i32; 3] = map(a, double);
+        let add5: fn(i32) -> i32 = adder(5);
+        let h: fn(i32) -> i32 = id;
+        let twice: fn(i32) -> i32 = |x: i32| -> i32 { f(f(x)) };
+        let unit: fn() -> i32 = || 7;
+        return f(1) + g(2) + apply(|y| y - 1, 5) + b[2] + c[1] + add5(1) + (adder(2))(3) + h(4) + twice(0) + unit();
+    }
+    "#;
+    run_test_with_expected("closures_capture_their_environment", t, 74, "", "");
+}
+
+#[test]
+fn for_loops_iterate_ranges_arrays_vecs_and_iterators() {
+    let t = r#"
+    struct Countdown {
+        n: i64,
+    }
+
+    impl Iterator<i64> for Countdown {
+        fn next(&mut self) -> Option<i64> {
+            match self.n {
+                0 => Option::None,
+                _ => {
+                    self.n = self.n - 1;
+                    Option::Some(self.n + 1)
+                }
+            }
+        }
+    }
+
+    fn main() -> i64 {
+        let total: i64 = 0;
+        for i in 0..5 {
+            total = total + i;
+        }
+        for i in 1..=3 {
+            total = total + i * 100;
+        }
+        let arr: [i64; 3] = [1, 2, 3];
+        for x in arr {
+            total = total + x * 1000;
+        }
+        for x in &arr {
+            total = total + *x * 10000;
+        }
+        let v: Vec<i64> = Vec::new();
+        v.push(7);
+        v.push(8);
+        for x in v {
+            total = total + *x;
+        }
+        for x in &v {
+            total = total + *x;
+        }
+        let r: Range = 2..4;
+        for i in r {
+            total = total + i;
+        }
+        for i in Countdown { n: 3 } {
+            total = total + i;
+        }
+        total - 66600
+    }
+    "#;
+    run_test_with_expected("for_loops_iterate_ranges_arrays_vecs_and_iterators", t, 51, "", "");
+}
+
+#[test]
+fn question_marks_return_none_and_err_early() {
+    let t = r#"
+    fn half(x: i64) -> Option<i64> {
+        match x % 2 {
+            0 => Option::Some(x / 2),
+            _ => Option::None,
+        }
+    }
+
+    fn quarter(x: i64) -> Option<i64> {
+        let h: i64 = half(x)?;
+        half(h)
+    }
+
+    fn parse(x: i64) -> Result<i64, i32> {
+        match x >> 63 {
+            0 => Result::Ok(x * 10),
+            _ => Result::Err(7),
+        }
+    }
+
+    fn total(a: i64, b: i64) -> Result<i64, i32> {
+        let x: i64 = parse(a)?;
+        let y: i64 = parse(b)?;
+        Result::Ok(x + y)
+    }
+
+    fn code(r: Result<i64, i32>) -> i64 {
+        match r {
+            Result::Ok(v) => v,
+            Result::Err(e) => e as i64,
+        }
+    }
+
+    fn main() -> i64 {
+        let a: i64 = quarter(12).unwrap_or(100);
+        let b: i64 = quarter(6).unwrap_or(100);
+        let c: i64 = code(total(1, 2));
+        let d: i64 = code(total(1, -2));
+        a + b + c + d
+    }
+    "#;
+    run_test_with_expected("question_marks_return_none_and_err_early", t, 140, "", "");
+}
+
+#[test]
+fn main_returning_a_result_exits_with_one_on_err() {
+    let ok = r#"
+    fn check(x: i64) -> Result<i64, i32> {
+        match x {
+            0 => Result::Err(3),
+            _ => Result::Ok(x),
+        }
+    }
+
+    fn main() -> Result<i64, i32> {
+        let x: i64 = check(4)?;
+        check(x - 4)
+    }
+    "#;
+    run_test_with_expected("main_returning_err", ok, 1, "", "");
+    let ok = ok.replace("check(x - 4)", "check(x)");
+    run_test_with_expected("main_returning_ok", &ok, 0, "", "");
+}
+
+/// the message of the type error building the program stops with
+fn type_error(name: &str, source: &str) -> String {
+    match common::build_error(name, source) {
+        TyphoonError::TypeError(message) => message,
+        error => panic!("expected a type error, got {}", error),
+    }
+}
+
+#[test]
+fn question_marks_are_only_used_where_they_can_return() {
+    let not_an_option = r#"
+    fn main() -> Option<i64> {
+        let x: i64 = 3;
+        Option::Some(x?)
+    }
+    "#;
+    assert_eq!(
+        type_error("try_integer", not_an_option),
+        "the `?` operator can only be applied to an Option or a Result, found i64 at try_integer.ty:4:23"
+    );
+    let wrong_return = r#"
+    fn main() -> i64 {
+        let x: Option<i64> = Option::Some(3);
+        x?
+    }
+    "#;
+    assert_eq!(
+        type_error("try_in_integer_function", wrong_return),
+        "the `?` operator can only be used in a function returning std::Option, not i64 at try_in_integer_function.ty:4:10"
+    );
+    let other_kind = r#"
+    fn first() -> Result<i64, i32> {
+        let x: Option<i64> = Option::None;
+        Result::Ok(x?)
+    }
+
+    fn main() -> i64 {
+        0
+    }
+    "#;
+    assert_eq!(
+        type_error("try_option_in_result", other_kind),
+        "the `?` operator can only be used in a function returning std::Option, not std::Result<i64, i32> at try_option_in_result.ty:4:21"
+    );
+    let other_error = r#"
+    fn first(r: Result<i64, i8>) -> Result<i64, i32> {
+        Result::Ok(r?)
+    }
+
+    fn main() -> i64 {
+        0
+    }
+    "#;
+    assert_eq!(
+        type_error("try_other_error", other_error),
+        "mismatched error types: `?` returns i8 from a function returning i32 at try_other_error.ty:3:21"
+    );
+}
+
+#[test]
+fn constants_are_folded_and_statics_are_globals() {
+    let t = r#"
+    const BASE: i32 = 40;
+    const MAX: i32 = BASE + OFFSET * 2;
+    const OFFSET: i32 = 1;
+    const SMALL: i8 = 300 as i8;
+    const TABLE: [i32; 3] = [1, MAX / 2, 3];
+    const PAIR: (i32, i8) = (7, SMALL);
+    static LIMIT: i32 = MAX - 2;
+    static mut COUNTER: i32 = 0;
+
+    fn bump() -> () {
+        unsafe {
+            COUNTER = COUNTER + 1;
+        };
+        return ();
+    }
+
+    fn main() -> i32 {
+        bump();
+        bump();
+        let p: &i32 = &MAX;
+        let c: i32 = unsafe { COUNTER };
+        return MAX + TABLE[1] + PAIR.0 + (SMALL as i32) + LIMIT - *p + c;
+    }
+    "#;
+    run_test_with_expected("constants_are_folded_and_statics_are_globals", t, 114, "", "");
+}
+
+#[test]
+fn constant_cycles_and_invalid_initializers_are_errors() {
+    let cycle = r#"
+    const A: i32 = B + 1;
+    const B: i32 = A;
+
+    fn main() -> i32 {
+        return A;
+    }
+    "#;
+    match common::build_error("const_cycle", cycle) {
+        TyphoonError::ConstError(message) => assert_eq!(
+            message,
+            "cycle detected when evaluating constant A: A -> B -> A"
+        ),
+        error => panic!("expected a const error, got {}", error),
+    }
+    let initializer = r#"
+    static LIMIT: i32 = 1 / 0;
+
+    fn main() -> i32 {
+        return LIMIT;
+    }
+    "#;
+    match common::build_error("static_initializer", initializer) {
+        TyphoonError::ConstError(message) => assert_eq!(
+            message,
+            "initializer of static LIMIT: attempt to divide by zero in a constant"
+        ),
+        error => panic!("expected a const error, got {}", error),
+    }
+}
+
+#[test]
+fn mutable_statics_are_only_used_in_unsafe_blocks() {
+    let read = r#"
+    static mut COUNTER: i32 = 0;
+
+    fn main() -> i32 {
+        return COUNTER;
+    }
+    "#;
+    match common::build_error("static_read", read) {
+        TyphoonError::UnsafeError(message) => assert_eq!(
+            message,
+            "use of mutable static COUNTER is unsafe and requires an unsafe block at static_read.ty:5:16"
+        ),
+        error => panic!("expected an unsafe error, got {}", error),
+    }
+    let write = r#"
+    static mut COUNTER: i32 = 0;
+
+    fn main() -> i32 {
+        COUNTER = 3;
+        return 0;
+    }
+    "#;
+    match common::build_error("static_write", write) {
+        TyphoonError::UnsafeError(message) => assert_eq!(
+            message,
+            "use of mutable static COUNTER is unsafe and requires an unsafe block at static_write.ty:5:9"
+        ),
+        error => panic!("expected an unsafe error, got {}", error),
+    }
+}
+
+#[test]
+fn cyclic_type_aliases_are_errors() {
+    let t = r#"
+    type Pair = (Inner, i32);
+    type Inner = [Pair; 2];
+
+    fn main() -> i32 {
+        return 0;
+    }
+    "#;
+    assert_eq!(type_error("alias_cycle", t), "cycle detected when expanding type alias Pair: Pair -> Inner -> Pair");
+}
+
+#[test]
+fn trait_objects_of_traits_which_are_not_object_safe_are_errors() {
+    let t = r#"
+    trait Double {
+        fn double(self) -> i32;
+    }
+
+    struct Meters {
+        value: i32,
+    }
+
+    impl Double for Meters {
+        fn double(self) -> i32 {
+            self.value * 2
+        }
+    }
+
+    fn main() -> i32 {
+        let m: Meters = Meters { value: 3 };
+        let d: &dyn Double = &m;
+        return 0;
+    }
+    "#;
+    assert_eq!(type_error("not_object_safe", t), "trait Double cannot be made into an object, double takes self by value");
+}
+
+#[test]
+fn comparisons_and_generic_trait_objects_iterate_ranges_at_the_bounds() {
+    let t = r#"
+    fn first(it: &mut dyn Iterator<i64>) -> i64 {
+        match it.next() {
+            Option::Some(x) => x,
+            Option::None => 0,
+        }
+    }
+
+    fn main() -> i64 {
+        let total: i64 = 0;
+        let near_max: i64 = 9223372036854775807;
+        let near_min: i64 = -9223372036854775807 - 1;
+        for i in near_max - 2..near_max {
+            total = total + 1;
+        }
+        for i in near_min..near_min + 3 {
+            total = total + 10;
+        }
+        for i in near_max - 1..=near_max {
+            total = total + 100;
+        }
+        for i in 5..3 {
+            total = total + 1000;
+        }
+        let r: Range = 2..4;
+        total = total + first(&mut r) * 10000 + first(&mut r) * 10000;
+        total = total + (3 < 4) + (4 <= 4) * 2 + (5 > 6) * 4 + (1 == 1) * 8 + (1 != 1) * 16 + (2.5 >= 2.0) * 32;
+        total - 50200
+    }
+    "#;
+    run_test_with_expected("comparisons_and_generic_trait_objects", t, 75, "", "");
+}
+
+#[test]
+fn generic_traits_are_implemented_and_used_with_their_type_arguments() {
+    let missing_arguments = r#"
+    struct Meters {
+        value: i64,
+    }
+
+    impl Iterator for Meters {
+        fn next(&mut self) -> Option<i64> {
+            Option::None
+        }
+    }
+
+    fn main() -> i64 {
+        return 0;
+    }
+    "#;
+    assert_eq!(
+        type_error("iterator_arguments", missing_arguments),
+        "trait std::Iterator takes 1 type arguments but the implementation for Meters supplies 0"
+    );
+    let other_item = r#"
+    fn main() -> i64 {
+        let r: Range = 0..3;
+        let it: &mut dyn Iterator<i32> = &mut r;
+        return 0;
+    }
+    "#;
+    assert_eq!(
+        type_error("iterator_item", other_item),
+        "std::Range implements std::Iterator with other types than dyn std::Iterator<i32>, so it cannot be used as one"
+    );
+    let tuples = r#"
+    fn main() -> i64 {
+        let a: (i64, i64) = (1, 2);
+        a == a
+    }
+    "#;
+    assert_eq!(type_error("compare_tuples", tuples), "cannot apply `==` to (i64, i64) and (i64, i64) at compare_tuples.ty:4:11");
+}
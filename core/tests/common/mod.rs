@@ -0,0 +1,47 @@
+#![allow(dead_code)]
+
+use core::error::TyphoonError;
+use core::program::Program;
+use std::path::PathBuf;
+
+/// point `TYPHOON_RUNTIME` at the archive cargo built for the dev-dependency on the runtime,
+/// it is next to the test executable under a name with a hash
+pub fn find_runtime() {
+    if std::env::var_os("TYPHOON_RUNTIME").is_some() {
+        return;
+    }
+    let deps = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let archive = std::fs::read_dir(&deps)
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with("libtyphoon_runtime-") && name.ends_with(".a")
+        })
+        .max_by_key(|entry| entry.metadata().and_then(|it| it.modified()).ok())
+        .expect("the runtime archive should be built for the tests");
+    std::env::set_var("TYPHOON_RUNTIME", archive.path());
+}
+
+/// the program `<name>.ty` with the source `source`, ready to be built
+pub fn program(name: &str, source: &str) -> Program {
+    find_runtime();
+    Program::new_with_string(PathBuf::from(format!("{}.ty", name)), source).unwrap()
+}
+
+/// build and run the program, returning its exit code, stdout and stderr
+pub fn run(name: &str, source: &str) -> (i32, String, String) {
+    program(name, source).as_binary_output(false).unwrap()
+}
+
+/// the error building the program stops with
+pub fn build_error(name: &str, source: &str) -> TyphoonError {
+    match program(name, source).as_binary_output(false) {
+        Err(error) => error,
+        Ok(_) => panic!("{} is expected not to compile", name),
+    }
+}
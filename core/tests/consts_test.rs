@@ -0,0 +1,100 @@
+use ast::{Expr, Location, Number, Opcode, Type};
+use core::consts::{ConstEvaluator, ConstValue};
+use parser::parser::parse_module;
+
+fn evaluator(source: &str) -> ConstEvaluator {
+    ConstEvaluator::new(&parse_module(source).unwrap())
+}
+
+fn number(n: i64) -> Expr {
+    Expr::Number(Number::Integer64(n))
+}
+
+#[test]
+fn constants_fold_arithmetic_and_other_constants() {
+    let mut evaluator = evaluator(
+        r#"
+        const MAX: i32 = BASE * 2 + (7 - 4) / 2;
+        const BASE: i32 = 100;
+        "#,
+    );
+    assert_eq!(evaluator.constant("MAX"), Ok(Some(ConstValue::Integer(201))));
+    assert_eq!(evaluator.constant("MIN"), Ok(None));
+}
+
+#[test]
+fn casts_and_declared_types_truncate() {
+    let mut evaluator = evaluator(
+        r#"
+        const BYTE: i8 = 300 as i8;
+        const WIDE: i64 = BYTE as i64 * 2;
+        const STORED: i8 = 200;
+        const DIVIDED: i8 = 200 / 2;
+        "#,
+    );
+    assert_eq!(evaluator.constant("BYTE"), Ok(Some(ConstValue::Integer(44))));
+    assert_eq!(evaluator.constant("WIDE"), Ok(Some(ConstValue::Integer(88))));
+    assert_eq!(evaluator.constant("STORED"), Ok(Some(ConstValue::Integer(-56))));
+    // the division happens on the i64 literals, only the result is truncated
+    assert_eq!(evaluator.constant("DIVIDED"), Ok(Some(ConstValue::Integer(100))));
+}
+
+#[test]
+fn arrays_and_tuples_are_folded_element_by_element() {
+    let mut evaluator = evaluator(
+        r#"
+        const TABLE: [i8; 3] = [1, 2, 256 + 3];
+        const ZEROS: [i32; 2] = [0; 2];
+        const PAIR: (i32, i8) = (TABLE[2] as i32, 1);
+        "#,
+    );
+    let integers = |values: &[i64]| values.iter().map(|it| ConstValue::Integer(*it)).collect();
+    assert_eq!(evaluator.constant("TABLE"), Ok(Some(ConstValue::Array(integers(&[1, 2, 3])))));
+    assert_eq!(evaluator.constant("ZEROS"), Ok(Some(ConstValue::Array(integers(&[0, 0])))));
+    assert_eq!(evaluator.constant("PAIR"), Ok(Some(ConstValue::Tuple(integers(&[3, 1])))));
+}
+
+#[test]
+fn cycles_between_constants_are_rejected() {
+    let mut evaluator = evaluator(
+        r#"
+        const A: i32 = B + 1;
+        const B: i32 = C;
+        const C: i32 = A;
+        const D: i32 = D;
+        "#,
+    );
+    assert_eq!(
+        evaluator.constant("A"),
+        Err("cycle detected when evaluating constant A: A -> B -> C -> A".to_string())
+    );
+    assert_eq!(
+        evaluator.constant("D"),
+        Err("cycle detected when evaluating constant D: D -> D".to_string())
+    );
+}
+
+#[test]
+fn invalid_constants_are_errors() {
+    let mut evaluator = evaluator(
+        r#"
+        const ZERO: i32 = 0;
+        const DIV: i32 = 1 / ZERO;
+        const OUT: i32 = [1, 2][2];
+        const PAIR: (i32, i32) = [1, 2];
+        "#,
+    );
+    assert!(evaluator.constant("DIV").unwrap_err().contains("divide by zero"));
+    assert!(evaluator.constant("OUT").unwrap_err().contains("index out of bounds"));
+    assert!(evaluator.constant("PAIR").unwrap_err().contains("mismatched types"));
+    let byte = Expr::Cast(Box::new(number(1)), Type::named("i8".to_string()));
+    let shift = Expr::BinOperation(Opcode::LShift, Box::new(byte), Box::new(number(8)), Location::default());
+    assert!(evaluator.evaluate(&shift, &Type::named("i8".to_string())).unwrap_err().contains("shift"));
+    let call = Expr::Call(Box::new(Expr::Identifier("f".to_string(), Location::default())), vec![]);
+    assert!(evaluator.evaluate(&call, &Type::named("i32".to_string())).is_err());
+    let mask = Expr::BinOperation(Opcode::Or, Box::new(number(16)), Box::new(number(3)), Location::default());
+    assert_eq!(
+        evaluator.evaluate(&mask, &Type::named("i8".to_string())),
+        Ok(ConstValue::Integer(19))
+    );
+}
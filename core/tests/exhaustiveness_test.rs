@@ -0,0 +1,144 @@
+use ast::{Module, Pattern, Type};
+use core::exhaustiveness::{check_match, MatchCheck};
+use parser::parser::parse_module;
+
+fn shapes() -> Module {
+    parse_module(
+        r#"
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+        enum Shape {
+            Circle(i32),
+            Rect { w: i32, h: i32 },
+            Empty,
+        }
+        struct Pair<A, B> {
+            first: A,
+            second: B,
+        }
+        enum Tree {
+            Leaf(Shape),
+            Node(&Tree, &Tree),
+        }
+        "#,
+    )
+    .unwrap()
+}
+
+/// patterns of the arms of `match value { ... }`
+fn arms(source: &str) -> Vec<Pattern> {
+    let module = parse_module(&format!(
+        "fn main() -> () {{ return match value {{ {} }}; }}",
+        source
+    ))
+    .unwrap();
+    match &*module.items[0] {
+        ast::ModuleItem::FunctionDeclare(f) => match &*f.stats {
+            ast::Expr::Block(statements, _) => match &*statements[0] {
+                ast::Statement::Return(expr, _) => match &**expr {
+                    ast::Expr::Match { arms, .. } => {
+                        arms.iter().map(|arm| arm.pattern.clone()).collect()
+                    }
+                    _ => panic!("Expected match expression"),
+                },
+                _ => panic!("Expected return statement"),
+            },
+            _ => panic!("Expected block"),
+        },
+        _ => panic!("Expected function declaration"),
+    }
+}
+
+fn check_arms(ty: &str, source: &str) -> MatchCheck {
+    let patterns = arms(source);
+    let patterns: Vec<&Pattern> = patterns.iter().collect();
+    check_match(&shapes(), &Type::named(ty.to_string()), &patterns).unwrap()
+}
+
+#[test]
+fn missing_variant_is_reported() {
+    let check = check_arms(
+        "Shape",
+        "Shape::Circle(_) => 1, Shape::Rect { w, .. } => w,",
+    );
+
+    assert!(!check.is_exhaustive());
+    assert_eq!(check.missing_message(), "`Shape::Empty` not covered");
+    assert!(check.unreachable.is_empty());
+}
+
+#[test]
+fn all_variants_are_exhaustive() {
+    let check = check_arms(
+        "Shape",
+        "Shape::Circle(0) => 1, Shape::Circle(_) => 2, Shape::Rect { .. } => 3, Shape::Empty => 4,",
+    );
+
+    assert!(check.is_exhaustive());
+    assert!(check.unreachable.is_empty());
+}
+
+#[test]
+fn missing_integers_are_reported_as_ranges() {
+    let check = check_arms("i8", "0..=10 => 1, 11 => 2, 13..=127 => 3,");
+
+    assert_eq!(check.missing_message(), "`-128..=-1` and `12` not covered");
+    assert!(check_arms("i8", "-128..=-1 => 1, 0 => 2, 1..=127 => 3,").is_exhaustive());
+}
+
+#[test]
+fn nested_patterns_are_checked_field_by_field() {
+    let check = check_arms(
+        "Tree",
+        "Tree::Leaf(Shape::Circle(_)) => 1, Tree::Leaf(Shape::Rect { w: 0, h }) => h, \
+         Tree::Node(_, _) => 2, Tree::Leaf(Shape::Rect { .. }) => 3,",
+    );
+
+    assert_eq!(check.missing_message(), "`Tree::Leaf(Shape::Empty)` not covered");
+
+    let check = check_arms("Point", "Point { x: 0, y } => y, Point { x: 1..=9, .. } => 1,");
+    assert_eq!(
+        check.missing_message(),
+        "`Point { x: -2147483648..=-1, y: _ }` and `Point { x: 10..=2147483647, y: _ }` not covered"
+    );
+}
+
+#[test]
+fn arms_covered_by_earlier_arms_are_unreachable() {
+    let check = check_arms(
+        "Shape",
+        "Shape::Circle(1..=5) => 1, Shape::Circle(3) => 2, _ => 3, Shape::Empty => 4,",
+    );
+
+    assert!(check.is_exhaustive());
+    assert_eq!(check.unreachable, vec![1, 3]);
+}
+
+#[test]
+fn pattern_of_another_type_is_rejected() {
+    let patterns = arms("Shape::Empty => 1, _ => 2,");
+    let patterns: Vec<&Pattern> = patterns.iter().collect();
+
+    assert!(check_match(&shapes(), &Type::named("Point".to_string()), &patterns).is_err());
+}
+
+#[test]
+fn generic_struct_fields_use_the_type_arguments() {
+    let patterns = arms(
+        "Pair { first: Shape::Empty, second: 0..=127 } => 1, \
+         Pair { first: Shape::Circle(_), .. } => 2, Pair { first: Shape::Rect { .. }, .. } => 3,",
+    );
+    let patterns: Vec<&Pattern> = patterns.iter().collect();
+    let ty = Type::generic(
+        "Pair".to_string(),
+        vec![Type::named("Shape".to_string()), Type::named("i8".to_string())],
+    );
+    let check = check_match(&shapes(), &ty, &patterns).unwrap();
+
+    assert_eq!(
+        check.missing_message(),
+        "`Pair { first: Shape::Empty, second: -128..=-1 }` not covered"
+    );
+}
@@ -0,0 +1,64 @@
+use ast::Type;
+use core::mangle::{demangle, demangle_text, mangle};
+
+fn named(name: &str) -> Type {
+    Type::named(name.to_string())
+}
+
+#[test]
+fn paths_are_length_prefixed() {
+    assert_eq!(mangle("len", &[]), "_TN3lenE");
+    assert_eq!(mangle("geo::shapes::area", &[]), "_TN3geo6shapes4areaE");
+    assert_eq!(demangle("_TN3geo6shapes4areaE").unwrap(), "geo::shapes::area");
+}
+
+#[test]
+fn instances_of_the_same_function_get_different_symbols() {
+    let i32_instance = mangle("max", &[named("i32")]);
+    let i8_instance = mangle("max", &[named("i8")]);
+    assert_eq!(i32_instance, "_TN3maxIiEE");
+    assert_ne!(i32_instance, i8_instance);
+    assert_ne!(mangle("geo::len", &[]), mangle("len", &[]));
+}
+
+#[test]
+fn type_arguments_are_demangled_as_written() {
+    let arguments = vec![
+        Type::reference(named("geo::Point"), false),
+        Type::reference(Type::Dyn("Shape".to_string(), vec![]), true),
+        Type::pointer(Type::array(named("i64"), 4)),
+        Type::simd(named("i16"), 8),
+        Type::simd(named("f32"), 4),
+        Type::tuple(vec![named("i8"), named("f64"), Type::void()]),
+        Type::generic("Pair".to_string(), vec![named("i32"), named("i8")]),
+        Type::boxed(named("geo::Point")),
+    ];
+    let symbol = mangle("Pair::swap", &arguments);
+    assert!(symbol.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    assert_eq!(
+        demangle(&symbol).unwrap(),
+        "Pair::swap<&geo::Point, &mut dyn Shape, *[i64; 4], simd<i16, 8>, simd<f32, 4>, (i8, f64, ()), Pair<i32, i8>, Box<geo::Point>>"
+    );
+}
+
+#[test]
+fn other_symbols_are_left_alone() {
+    assert_eq!(demangle("main"), None);
+    assert_eq!(demangle("_TN3max"), None);
+    assert_eq!(demangle("_TN3maxEtrailing"), None);
+    assert_eq!(
+        demangle_text("0000 t _TN3maxIiEE\n0010 T main\n0020 t _TNbroken\n"),
+        "0000 t max<i32>\n0010 T main\n0020 t _TNbroken\n"
+    );
+}
+
+#[test]
+fn closures_and_shims_are_demangled_with_their_function() {
+    let function_type = Type::function(vec![named("i32"), named("i8")], Type::void());
+    let symbol = mangle("map", &[function_type]);
+    assert_eq!(demangle(&symbol).unwrap(), "map<fn(i32, i8) -> ()>");
+    assert_eq!(demangle("_TN4mainEC0_").unwrap(), "main::{closure#0}");
+    assert_eq!(demangle("_TN5adderEC1_C0_").unwrap(), "adder::{closure#1}::{closure#0}");
+    assert_eq!(demangle("_TN6doubleES").unwrap(), "double::{shim}");
+    assert_eq!(demangle("_TN4mainEC0"), None);
+}
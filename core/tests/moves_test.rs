@@ -0,0 +1,289 @@
+use ast::Location;
+use core::error::TyphoonError;
+use core::moves::{MoveChecker, MoveError};
+use core::program::Program;
+use parser::parser::parse_module;
+use std::path::PathBuf;
+
+fn check(source: &str) -> Result<Vec<MoveError>, String> {
+    let module = parse_module(source).unwrap();
+    let checker = MoveChecker::new(&module)?;
+    Ok(checker.check_module(&module))
+}
+
+/// the moved values with the lines they are moved and used at
+fn moves(source: &str) -> Vec<(String, u32, u32)> {
+    check(source)
+        .unwrap()
+        .into_iter()
+        .map(|error| (error.value, error.moved.line, error.used.line))
+        .collect()
+}
+
+const POINT: &str = r#"
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Line {
+    from: Point,
+    to: Point,
+}
+
+fn consume(p: Point) -> i32 {
+    p.x
+}
+"#;
+
+#[test]
+fn values_passed_by_value_cannot_be_used_again() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+fn main() -> i32 {
+    let p: Point = Point { x: 1, y: 2 };
+    let n: i32 = 3;
+    let a: i32 = consume(p) + n;
+    let b: i32 = consume(p) + n;
+    return a + b;
+}
+"#
+    );
+    let errors = check(&source).unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0],
+        MoveError {
+            function: "main".to_string(),
+            value: "p".to_string(),
+            ty: ast::Type::named("Point".to_string()),
+            moved: Location::new(19, 26),
+            used: Location::new(20, 26),
+            borrowed: false,
+        }
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "use of moved value `p` in main at 20:26, moved at 19:26 since Point is not Copy"
+    );
+}
+
+#[test]
+fn copy_types_references_and_assignments_keep_values_usable() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+struct Pixel {
+    x: i8,
+    y: i8,
+}
+
+impl Copy for Pixel {}
+
+fn length(p: &Point) -> i32 {
+    p.x
+}
+
+fn shade(p: Pixel) -> i8 {
+    p.x
+}
+
+fn main() -> i32 {
+    let p: Point = Point { x: 1, y: 2 };
+    let q: Pixel = Pixel { x: 1, y: 2 };
+    let r: &Point = &p;
+    let a: i32 = length(r) + length(r) + length(&p) + p.x + p.y;
+    let b: i8 = shade(q) + shade(q);
+    let c: i32 = consume(p);
+    p = Point { x: 3, y: 4 };
+    return a + c + consume(p);
+}
+"#
+    );
+    assert_eq!(moves(&source), vec![]);
+}
+
+#[test]
+fn moves_in_branches_and_fields() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+fn pick(n: i32, p: Point, q: Point) -> i32 {
+    let a: i32 = match n {
+        0 => consume(p),
+        _ => consume(q),
+    };
+    return a + consume(q);
+}
+
+fn parts(line: Line) -> i32 {
+    let a: i32 = consume(line.from) + line.to.x;
+    let b: i32 = consume(line.to);
+    return a + b + consume(line.from);
+}
+
+fn whole(line: Line) -> Line {
+    let a: i32 = consume(line.to);
+    return line;
+}
+"#
+    );
+    assert_eq!(
+        moves(&source),
+        vec![
+            ("q".to_string(), 19, 21),
+            ("line.from".to_string(), 25, 27),
+            ("line".to_string(), 31, 32),
+        ]
+    );
+}
+
+#[test]
+fn receivers_and_closures_move_like_parameters() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+impl Point {
+    fn into_x(self) -> i32 {
+        self.x
+    }
+
+    fn get_x(&self) -> i32 {
+        self.x
+    }
+}
+
+fn main() -> i32 {
+    let p: Point = Point { x: 1, y: 2 };
+    let a: i32 = p.get_x() + p.into_x();
+    let b: &Point = &p;
+    let q: Point = Point { x: 1, y: 2 };
+    let f: fn() -> i32 = || consume(q);
+    return a + f() + q.get_x();
+}
+"#
+    );
+    let errors = check(&source).unwrap();
+    let found: Vec<(String, bool, u32)> = errors
+        .iter()
+        .map(|error| (error.value.clone(), error.borrowed, error.moved.line))
+        .collect();
+    assert_eq!(found, vec![("p".to_string(), true, 28), ("q".to_string(), true, 31)]);
+}
+
+#[test]
+fn loops_move_values_again_on_every_iteration() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+fn main() -> i32 {
+    let p: Point = Point { x: 1, y: 2 };
+    let total: i32 = 0;
+    for i in 0..3 {
+        total = total + consume(p);
+    }
+    for q in [Point { x: 1, y: 2 }] {
+        total = total + consume(q);
+    }
+    return total;
+}
+"#
+    );
+    assert_eq!(moves(&source), vec![("p".to_string(), 20, 20)]);
+}
+
+#[test]
+fn the_try_operator_moves_its_operand() {
+    let source = r#"
+enum Result<T, E> {
+    Ok(T),
+    Err(E),
+}
+
+fn parse(x: i64) -> Result<i64, i32> {
+    Result::Ok(x)
+}
+
+fn twice(x: i64) -> Result<i64, i32> {
+    let r: Result<i64, i32> = parse(x);
+    let value: i64 = r?;
+    let again: i64 = r?;
+    Result::Ok(value + again)
+}
+"#;
+    assert_eq!(moves(source), vec![("r".to_string(), 13, 14)]);
+}
+
+#[test]
+fn type_parameters_are_copy_only_when_bound() {
+    let source = r#"
+fn twice<T>(x: T) -> (T, T) {
+    (x, x)
+}
+
+fn copied<T: Copy>(x: T) -> (T, T) {
+    (x, x)
+}
+"#;
+    assert_eq!(moves(source), vec![("x".to_string(), 3, 3)]);
+}
+
+#[test]
+fn copy_needs_every_field_to_be_copy() {
+    let source = r#"
+struct Node {
+    value: i64,
+    next: Box<Node>,
+}
+
+impl Copy for Node {}
+"#;
+    assert_eq!(
+        check(source).err(),
+        Some("Copy cannot be implemented for Node, field next of type Box<Node> is not Copy".to_string())
+    );
+    let source = r#"
+enum List {
+    Nil,
+    Cons(i64, Box<List>),
+}
+
+impl Copy for List {}
+"#;
+    assert_eq!(
+        check(source).err(),
+        Some(
+            "Copy cannot be implemented for List, field of variant Cons of type Box<List> is not Copy"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn program_reports_uses_of_moved_values() {
+    let source = format!(
+        "{}{}",
+        POINT,
+        r#"
+fn main() -> i32 {
+    let p: Point = Point { x: 1, y: 2 };
+    let a: i32 = consume(p);
+    return a + p.x;
+}
+"#
+    );
+    match Program::new_with_string(PathBuf::from("moved.ty"), &source) {
+        Err(TyphoonError::MoveError(message)) => assert_eq!(
+            message,
+            "moved.ty: use of moved value `p.x` in main at 19:16, moved at 18:26 since Point is not Copy"
+        ),
+        Err(error) => panic!("expected a move error, got {}", error),
+        Ok(_) => panic!("expected a move error"),
+    }
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use core::program::Overflow;
+use typhoon_runtime::PANIC_EXIT_CODE;
+
+fn run(name: &str, source: &str, overflow: Overflow) -> (i32, String, String) {
+    let mut program = common::program(name, source);
+    program.options.overflow = overflow;
+    program.as_binary_output(false).unwrap()
+}
+
+#[test]
+fn panic_reports_its_message_and_location() {
+    let (code, stdout, stderr) = run(
+        "explicit",
+        r#"
+fn check(x: i64) -> i64 {
+    match x {
+        0 => panic("x must not be zero"),
+        _ => x,
+    }
+}
+
+fn main() -> i64 {
+    println(&"checking");
+    check(0)
+}
+"#,
+        Overflow::Wrapping,
+    );
+    assert_eq!(code, PANIC_EXIT_CODE);
+    assert_eq!(stdout, "checking\n");
+    assert!(
+        stderr.starts_with("panicked at explicit.ty:4:14:\nx must not be zero\nstack backtrace:\n")
+    );
+}
+
+#[test]
+fn division_by_zero_panics_with_a_backtrace() {
+    let (code, _, stderr) = run(
+        "divide",
+        r#"
+fn divide(a: i64, b: i64) -> i64 {
+    a / b
+}
+
+fn main() -> i64 {
+    divide(1, 0) + divide(2, 1)
+}
+"#,
+        Overflow::Wrapping,
+    );
+    assert_eq!(code, PANIC_EXIT_CODE);
+    assert!(stderr.starts_with("panicked at divide.ty:3:7:\nattempt to divide by zero\n"));
+    // the function which panicked is named by its mangled symbol
+    assert!(stderr.contains("_TN6divideE"));
+}
+
+#[test]
+fn index_out_of_bounds_panics() {
+    let (code, _, stderr) = run(
+        "bounds",
+        r#"
+fn main() -> i32 {
+    let xs: [i32; 4] = [1, 2, 3, 4];
+    let i: i32 = 7;
+    xs[i]
+}
+"#,
+        Overflow::Wrapping,
+    );
+    assert_eq!(code, PANIC_EXIT_CODE);
+    assert!(stderr.starts_with(
+        "panicked at bounds.ty:5:5:\nindex out of bounds: the len is 4 but the index is 7\n"
+    ));
+}
+
+#[test]
+fn overflow_panics_only_when_checked() {
+    let source = r#"
+fn main() -> i64 {
+    let max: i8 = 127;
+    let wrapped: i8 = max + 1;
+    wrapped as i64 + 130
+}
+"#;
+    assert_eq!(run("wrapping", source, Overflow::Wrapping).0, 2);
+    let (code, _, stderr) = run("checked", source, Overflow::Checked);
+    assert_eq!(code, PANIC_EXIT_CODE);
+    assert!(stderr.starts_with("panicked at checked.ty:4:27:\nattempt to add with overflow\n"));
+}
+
+#[test]
+fn integer_methods_choose_their_overflow_behaviour() {
+    let source = r#"
+fn unwrap_or(value: Option<i8>, default: i8) -> i8 {
+    match value {
+        Option::Some(x) => x,
+        Option::None => default,
+    }
+}
+
+fn main() -> i64 {
+    let max: i8 = 127;
+    let min: i8 = -128;
+    let wrapped: i8 = max.wrapping_add(1);
+    let saturated: i8 = max.saturating_add(100);
+    let low: i8 = min.saturating_sub(1);
+    let product: i8 = min.saturating_mul(3);
+    let negative: i8 = max.saturating_mul(-2);
+    let none: i8 = unwrap_or(max.checked_add(1), 7);
+    let some: i8 = unwrap_or(max.checked_sub(27), 7);
+    wrapped as i64 + saturated as i64 + low as i64 + product as i64 + negative as i64 + none as i64 + some as i64
+}
+"#;
+    // the methods behave the same whatever the mode of the operators is
+    for overflow in [Overflow::Wrapping, Overflow::Checked] {
+        let (code, _, stderr) = run("methods", source, overflow);
+        assert_eq!(
+            code,
+            (-128 + 127 - 128 - 128 - 128 + 7 + 100) & 0xff,
+            "{}",
+            stderr
+        );
+    }
+}
@@ -0,0 +1,374 @@
+use ast::{Expr, Module, ModuleItem, Statement, Type};
+use core::error::TyphoonError;
+use core::program::{Program, STD_SOURCE};
+use core::resolver::{resolve, resolve_with_prelude, SourceModule};
+use parser::parser::parse_module;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// a module tree of `(path, source)` files, the root has the empty path
+fn resolve_files(files: &[(&str, &str)]) -> Result<Module, TyphoonError> {
+    fn load(files: &[(&str, &str)], path: &str) -> SourceModule {
+        let source = files.iter().find(|(it, _)| *it == path).unwrap().1;
+        let module = parse_module(source).unwrap();
+        let submodules = module
+            .items
+            .iter()
+            .filter_map(|item| match item.as_ref() {
+                ModuleItem::Mod(mod_decl) => Some(mod_decl.name.clone()),
+                _ => None,
+            })
+            .map(|name| {
+                let child = match path {
+                    "" => name.clone(),
+                    path => format!("{}::{}", path, name),
+                };
+                (name, load(files, &child))
+            })
+            .collect::<HashMap<_, _>>();
+        SourceModule { module, submodules }
+    }
+    resolve(load(files, ""))
+}
+
+fn program(files: &[(&str, &str)]) -> Module {
+    resolve_files(files).unwrap()
+}
+
+/// the message of the error resolving `files` stops with
+fn resolve_error(files: &[(&str, &str)]) -> String {
+    match resolve_files(files) {
+        Err(TyphoonError::ResolveError(message)) => message,
+        Err(error) => panic!("expected a resolve error, got {}", error),
+        Ok(_) => panic!("expected a resolve error"),
+    }
+}
+
+fn geo() -> Vec<(&'static str, &'static str)> {
+    vec![
+        (
+            "geo",
+            r#"
+            pub mod shapes;
+            pub struct Point {
+                x: i32,
+                y: i32,
+            }
+            fn origin() -> Point {
+                Point { x: 0, y: 0 }
+            }
+            "#,
+        ),
+        (
+            "geo::shapes",
+            r#"
+            use super::Point;
+            pub fn left(p: Point) -> Point {
+                super::origin()
+            }
+            "#,
+        ),
+    ]
+}
+
+fn function<'a>(module: &'a Module, name: &str) -> &'a ast::FunctionDeclare {
+    module
+        .items
+        .iter()
+        .find_map(|item| match item.as_ref() {
+            ModuleItem::FunctionDeclare(f) if f.name == name => Some(f),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("function {} is not found", name))
+}
+
+/// the value of `return VALUE;` as first statement of the function
+fn returned<'a>(module: &'a Module, name: &str) -> &'a Expr {
+    match &*function(module, name).stats {
+        Expr::Block(statements, _) => match &*statements[0] {
+            Statement::Return(expr, _) => expr,
+            _ => panic!("Expected return statement"),
+        },
+        _ => panic!("Expected block"),
+    }
+}
+
+#[test]
+fn items_of_submodules_are_named_by_their_path() {
+    let mut files = geo();
+    files.push(("", "mod geo; fn main() -> i32 { return 0; }"));
+    let module = program(&files);
+
+    let left = function(&module, "geo::shapes::left");
+    assert_eq!(left.args[0].1, Type::named("geo::Point".to_string()));
+    assert_eq!(left.return_type, Type::named("geo::Point".to_string()));
+    assert!(module.items.iter().all(|item| !matches!(**item, ModuleItem::Mod(_) | ModuleItem::Use(_))));
+    assert!(module.items.iter().any(|item| matches!(&**item,
+        ModuleItem::StructDeclare(s) if s.name == "geo::Point")));
+    function(&module, "main");
+}
+
+#[test]
+fn paths_and_imports_refer_to_the_qualified_names() {
+    let mut files = geo();
+    files.push((
+        "",
+        r#"
+        mod geo;
+        use geo::shapes::left as go_left;
+        fn main() -> i32 {
+            return go_left(geo::Point { x: 1, y: 2 });
+        }
+        fn other() -> i32 {
+            return crate::geo::shapes::left(main());
+        }
+        "#,
+    ));
+    let module = program(&files);
+
+    match returned(&module, "main") {
+        Expr::Call(callee, args) => {
+            assert!(matches!(&**callee, Expr::Identifier(name, _) if name == "geo::shapes::left"));
+            assert!(matches!(&*args[0],
+                Expr::StructLiteral { name, variant: None, .. } if name == "geo::Point"));
+        }
+        _ => panic!("Expected call"),
+    }
+    match returned(&module, "other") {
+        Expr::Call(callee, args) => {
+            assert!(matches!(&**callee, Expr::Identifier(name, _) if name == "geo::shapes::left"));
+            assert!(matches!(&*args[0],
+                Expr::Call(callee, _) if matches!(&**callee, Expr::Identifier(name, _) if name == "main")));
+        }
+        _ => panic!("Expected call"),
+    }
+    match &*function(&module, "geo::shapes::left").stats {
+        Expr::Block(_, Some(value)) => assert!(matches!(&**value,
+            Expr::Call(callee, _) if matches!(&**callee, Expr::Identifier(name, _) if name == "geo::origin"))),
+        _ => panic!("Expected block with a value"),
+    }
+}
+
+#[test]
+fn private_items_are_not_visible_outside_their_module() {
+    let mut files = geo();
+    files.push(("", "mod geo; fn main() -> i32 { return geo::origin().x; }"));
+    assert_eq!(resolve_error(&files), "function origin of module geo is private");
+}
+
+#[test]
+fn unknown_items_are_reported_with_their_module() {
+    let mut files = geo();
+    files.push(("", "mod geo; fn main(l: geo::Line) -> i32 { return 0; }"));
+    assert_eq!(resolve_error(&files), "cannot find Line in module geo");
+}
+
+#[test]
+fn aliases_and_newtypes_are_types_and_newtypes_constructors() {
+    let module = program(&[
+        (
+            "",
+            r#"
+            mod units;
+            use units::{Meters, UserId};
+            fn main(m: Meters) -> UserId {
+                return units::UserId(1);
+            }
+            "#,
+        ),
+        (
+            "units",
+            r#"
+            pub type Meters = (i64, Length);
+            type Length = i64;
+            pub newtype UserId(Meters);
+            "#,
+        ),
+    ]);
+
+    let main = function(&module, "main");
+    assert_eq!(main.args[0].1, Type::named("units::Meters".to_string()));
+    assert_eq!(main.return_type, Type::named("units::UserId".to_string()));
+    assert!(matches!(returned(&module, "main"),
+        Expr::Call(callee, _) if matches!(&**callee, Expr::Identifier(name, _) if name == "units::UserId")));
+    assert!(module.items.iter().any(|item| matches!(&**item,
+        ModuleItem::TypeAlias(a) if a.ty == Type::tuple(vec![
+            Type::named("i64".to_string()),
+            Type::named("units::Length".to_string()),
+        ]))));
+    assert!(module.items.iter().any(|item| matches!(&**item,
+        ModuleItem::Newtype(n) if n.inner == Type::named("units::Meters".to_string()))));
+}
+
+/// resolve the root module together with the standard library
+fn resolve_with_std(source: &str) -> Result<Module, TyphoonError> {
+    let module = parse_module(source).unwrap();
+    let prelude = parse_module(STD_SOURCE).unwrap();
+    resolve_with_prelude(
+        SourceModule { module, submodules: HashMap::new() },
+        SourceModule { module: prelude, submodules: HashMap::new() },
+    )
+}
+
+fn program_with_prelude(source: &str) -> Module {
+    resolve_with_std(source).unwrap()
+}
+
+#[test]
+fn the_whole_standard_library_parses() {
+    let module = parse_module(STD_SOURCE).unwrap();
+    let names = module
+        .items
+        .iter()
+        .filter_map(|item| match item.as_ref() {
+            ModuleItem::StructDeclare(s) => Some(s.name.clone()),
+            ModuleItem::EnumDeclare(e) => Some(e.name.clone()),
+            ModuleItem::TraitDeclare(t) => Some(t.name.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["Hash", "Eq", "Option", "Result", "Iterator", "Range", "RangeInclusive", "Vec", "Iter", "String", "HashMap"]
+    );
+}
+
+#[test]
+fn unknown_names_fall_back_to_the_standard_library() {
+    let module = program_with_prelude(
+        r#"
+        fn main() -> i64 {
+            let v: Vec<i64> = Vec::new();
+            println(&"hello");
+            std::String::from_int(v.len()).len()
+        }
+        "#,
+    );
+
+    let main = function(&module, "main");
+    match &*main.stats {
+        Expr::Block(statements, _) => match statements[0].as_ref() {
+            Statement::Declare(_, ty, value) => {
+                assert_eq!(*ty, Type::generic("std::Vec".to_string(), vec![Type::named("i64".to_string())]));
+                assert!(matches!(&**value,
+                    Expr::Call(callee, _) if matches!(&**callee, Expr::Path(ty, name) if ty == "std::Vec" && name == "new")));
+            }
+            _ => panic!("Expected declaration"),
+        },
+        _ => panic!("Expected block"),
+    }
+    assert!(module.items.iter().any(|item| matches!(&**item,
+        ModuleItem::FunctionDeclare(f) if f.name == "std::println")));
+}
+
+#[test]
+fn items_of_the_program_shadow_the_standard_library() {
+    let module = program_with_prelude(
+        r#"
+        struct String {
+            len: i64,
+        }
+        fn main(s: String) -> i64 {
+            s.len
+        }
+        "#,
+    );
+
+    assert_eq!(function(&module, "main").args[0].1, Type::named("String".to_string()));
+}
+
+#[test]
+fn only_the_standard_library_calls_intrinsics() {
+    let result = resolve_with_std(
+        r#"
+        fn main() -> i64 {
+            intrinsics::array_alloc(1)
+        }
+        "#,
+    );
+    match result {
+        Err(TyphoonError::ResolveError(message)) => assert_eq!(message, "cannot find intrinsics in module crate"),
+        _ => panic!("expected a resolve error"),
+    }
+}
+
+#[test]
+fn structs_derive_the_traits_of_the_standard_library() {
+    let module = program_with_prelude(
+        r#"
+        struct Point {
+            x: i64,
+            y: i64,
+        }
+        impl Hash for Point {}
+        impl std::Eq for Point {}
+        fn main() -> i64 {
+            let points: HashMap<Point, i64> = HashMap::new();
+            points.len()
+        }
+        "#,
+    );
+
+    let traits = module
+        .items
+        .iter()
+        .filter_map(|item| match item.as_ref() {
+            ModuleItem::Impl(impl_block) if impl_block.name == "Point" => impl_block.trait_name.clone(),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(traits, vec!["std::Hash", "std::Eq"]);
+}
+
+#[test]
+fn program_reports_where_parsing_failed() {
+    let source = r#"
+fn first() -> i64 {
+    1
+}
+
+fn second() -> i64 {
+    let x: i64 = ;
+    x
+}
+"#;
+    match Program::new_with_string(PathBuf::from("broken.ty"), source) {
+        Err(TyphoonError::ParserError(message)) => assert_eq!(message, "broken.ty:6:1: syntax error"),
+        Err(error) => panic!("expected a parser error, got {}", error),
+        Ok(_) => panic!("expected a parser error"),
+    }
+}
+
+#[test]
+fn program_reports_missing_module_files() {
+    match Program::new_with_string(PathBuf::from("missing.ty"), "mod nowhere;\nfn main() -> i32 { 0 }") {
+        Err(TyphoonError::ResolveError(message)) => assert_eq!(
+            message,
+            "file not found for module nowhere, expected nowhere.ty or nowhere/mod.ty"
+        ),
+        Err(error) => panic!("expected a resolve error, got {}", error),
+        Ok(_) => panic!("expected a resolve error"),
+    }
+}
+
+#[test]
+fn program_reports_module_files_found_twice() {
+    let directory = std::env::temp_dir().join("typhoon_module_found_twice");
+    std::fs::create_dir_all(directory.join("twice")).unwrap();
+    std::fs::write(directory.join("twice.ty"), "pub fn one() -> i32 { 1 }").unwrap();
+    std::fs::write(directory.join("twice").join("mod.ty"), "pub fn one() -> i32 { 1 }").unwrap();
+    let main = directory.join("main.ty");
+    std::fs::write(&main, "mod twice;\nfn main() -> i32 { twice::one() }").unwrap();
+    match Program::new(&main) {
+        Err(TyphoonError::ResolveError(message)) => assert_eq!(
+            message,
+            format!(
+                "file for module twice found at both {} and {}",
+                directory.join("twice.ty").display(),
+                directory.join("twice").join("mod.ty").display()
+            )
+        ),
+        Err(error) => panic!("expected a resolve error, got {}", error),
+        Ok(_) => panic!("expected a resolve error"),
+    }
+}
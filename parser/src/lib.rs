@@ -8,7 +8,7 @@ pub mod parser;
 
 #[cfg(test)]
 mod test {
-    use ast::{ModuleItem, Type};
+    use ast::{Expr, Location, ModuleItem, Number, Opcode, Pattern, Statement, Type, VariantFields, Visibility};
 
     use crate::parser::parse_module;
 
@@ -67,6 +67,878 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_pub_extern_function() {
+        let result = parse_module(r#"
+            pub extern fn add(a: i32, b: i32) -> i32 {
+                return a + b;
+            }
+            fn helper() -> i32 {
+                return 1;
+            }
+        "#).unwrap();
+
+        assert_eq!(result.items.len(), 2);
+        match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => {
+                assert_eq!(f.name, "add");
+                assert_eq!(f.visibility, Visibility::Public);
+                assert!(f.is_extern);
+                assert!(f.is_exported());
+            },
+            _ => panic!("Expected function declaration")
+        }
+        match &*result.items[1] {
+            ModuleItem::FunctionDeclare(f) => {
+                assert_eq!(f.visibility, Visibility::Private);
+                assert!(!f.is_exported());
+            },
+            _ => panic!("Expected function declaration")
+        }
+    }
+
+    #[test]
+    fn test_reference_and_pointer_types() {
+        let result = parse_module(r#"
+            fn swap(a: &mut i32, b: &i32, raw: *i8) -> () {
+                *a = *b;
+            }
+        "#).unwrap();
+
+        match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => {
+                assert_eq!(f.args[0].1, Type::reference(Type::named("i32".to_string()), true));
+                assert_eq!(f.args[1].1, Type::reference(Type::named("i32".to_string()), false));
+                assert_eq!(f.args[2].1, Type::pointer(Type::named("i8".to_string())));
+            },
+            _ => panic!("Expected function declaration")
+        }
+    }
+
+    #[test]
+    fn test_reference_and_deref_expression() {
+        let result = parse_module(r#"
+            fn main() -> i32 {
+                let a: i32 = 1;
+                let b: &mut i32 = &mut a;
+                *b = *b + 1;
+                return *&a;
+            }
+        "#).unwrap();
+
+        let body = match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => &f.stats,
+            _ => panic!("Expected function declaration")
+        };
+        let statements = match &**body {
+            Expr::Block(statements, None) => statements,
+            _ => panic!("Expected block without tail expression")
+        };
+        assert_eq!(statements.len(), 4);
+        match &*statements[1] {
+            Statement::Declare(_, _, expr) => {
+                assert!(matches!(&**expr, Expr::Reference { mutable: true, .. }));
+            },
+            _ => panic!("Expected let statement")
+        }
+        match &*statements[2] {
+            Statement::Assignment(place, value) => {
+                assert!(matches!(&**place, Expr::Deref(..)));
+                assert!(matches!(&**value, Expr::BinOperation(Opcode::Add, _, _, _)));
+            },
+            _ => panic!("Expected assignment statement")
+        }
+        match &*statements[3] {
+            Statement::Return(expr, _) => match &**expr {
+                Expr::Deref(inner, _) => assert!(matches!(&**inner, Expr::Reference { mutable: false, .. })),
+                _ => panic!("Expected dereference")
+            },
+            _ => panic!("Expected return statement")
+        }
+    }
+
+    #[test]
+    fn test_array_type_literal_and_index() {
+        let result = parse_module(r#"
+            fn main() -> i32 {
+                let xs: [[i32; 2]; 3] = [[1, 2], [3, 4], [0; 2],];
+                xs[1][0] = 5;
+                return xs[2][1];
+            }
+        "#).unwrap();
+
+        let body = match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => &f.stats,
+            _ => panic!("Expected function declaration")
+        };
+        let statements = match &**body {
+            Expr::Block(statements, None) => statements,
+            _ => panic!("Expected block without tail expression")
+        };
+        match &*statements[0] {
+            Statement::Declare(_, ty, expr) => {
+                assert_eq!(ty, &Type::array(Type::array(Type::named("i32".to_string()), 2), 3));
+                match &**expr {
+                    Expr::Array(items) => {
+                        assert_eq!(items.len(), 3);
+                        assert!(matches!(&*items[2], Expr::ArrayRepeat(_, 2)));
+                    },
+                    _ => panic!("Expected array literal")
+                }
+            },
+            _ => panic!("Expected let statement")
+        }
+        match &*statements[1] {
+            Statement::Assignment(place, _) => match &**place {
+                Expr::Index { expr, location, .. } => {
+                    assert!(matches!(&**expr, Expr::Index { .. }));
+                    assert_eq!(location, &Location::new(4, 17));
+                },
+                _ => panic!("Expected index expression")
+            },
+            _ => panic!("Expected assignment statement")
+        }
+        // a length which overflows a u64 is a parse error rather than a panic
+        let error = parse_module(r#"
+            fn main() -> i32 {
+                let xs: [i64; 99999999999999999999999] = [0; 99999999999999999999999];
+                return 0;
+            }
+        "#).unwrap_err();
+        assert_eq!(crate::parser::error_location(&error), Some(Location::new(3, 21)));
+    }
+
+    #[test]
+    fn test_simd_type_and_literals() {
+        let result = parse_module(r#"
+            fn main() -> i32 {
+                let a: simd<i32, 4> = simd[1, 2, 3, 4];
+                let b: simd<i32, 4> = simd [0; 4];
+                return reduce_add(a + b);
+            }
+        "#).unwrap();
+
+        let body = match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => &f.stats,
+            _ => panic!("Expected function declaration")
+        };
+        let statements = match &**body {
+            Expr::Block(statements, None) => statements,
+            _ => panic!("Expected block without tail expression")
+        };
+        match &*statements[0] {
+            Statement::Declare(_, ty, expr) => {
+                assert_eq!(ty, &Type::simd(Type::named("i32".to_string()), 4));
+                assert!(matches!(&**expr, Expr::SimdVector(items) if items.len() == 4));
+            },
+            _ => panic!("Expected let statement")
+        }
+        match &*statements[1] {
+            Statement::Declare(_, _, expr) => assert!(matches!(&**expr, Expr::SimdSplat(_, 4))),
+            _ => panic!("Expected let statement")
+        }
+    }
+
+    #[test]
+    fn test_float_lanes_and_literals() {
+        let result = parse_module(r#"
+            fn main() -> f32 {
+                let a: simd<f32, 2> = simd[1.5, -0.25];
+                for i in 0..2 {
+                    a[i] = 2.0;
+                }
+                return reduce_add(a) * 1_000.5;
+            }
+        "#).unwrap();
+
+        let body = match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => &f.stats,
+            _ => panic!("Expected function declaration")
+        };
+        let statements = match &**body {
+            Expr::Block(statements, None) => statements,
+            _ => panic!("Expected block without tail expression")
+        };
+        match &*statements[0] {
+            Statement::Declare(_, ty, expr) => {
+                assert_eq!(ty, &Type::simd(Type::named("f32".to_string()), 2));
+                match &**expr {
+                    Expr::SimdVector(items) => {
+                        assert!(matches!(&*items[0], Expr::Number(Number::Float(n)) if *n == 1.5));
+                        assert!(matches!(&*items[1], Expr::Number(Number::Float(n)) if *n == -0.25));
+                    }
+                    _ => panic!("Expected simd vector")
+                }
+            },
+            _ => panic!("Expected let statement")
+        }
+        // the range keeps its integers
+        match &*statements[1] {
+            Statement::Expr(expr) => match &**expr {
+                Expr::For { iterable, .. } => assert!(matches!(&**iterable,
+                    Expr::Range { start, .. } if matches!(&**start, Expr::Number(Number::Integer64(0))))),
+                _ => panic!("Expected for loop")
+            },
+            _ => panic!("Expected expression statement")
+        }
+        match &*statements[2] {
+            Statement::Return(expr, _) => assert!(matches!(&**expr,
+                Expr::BinOperation(Opcode::Mul, _, rhs, _) if matches!(&**rhs, Expr::Number(Number::Float(n)) if *n == 1000.5))),
+            _ => panic!("Expected return statement")
+        }
+    }
+
+    #[test]
+    fn test_tuple_type_literal_and_destructure() {
+        let result = parse_module(r#"
+            fn pair() -> (i32, (i8,)) {
+                return (1, (2,));
+            }
+            fn main() -> () {
+                let ((a, _), b): ((i32, i32), i8) = ((1, 2), 3);
+                return pair().1.0;
+            }
+        "#).unwrap();
+
+        match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => assert_eq!(
+                f.return_type,
+                Type::tuple(vec![Type::named("i32".to_string()), Type::tuple(vec![Type::named("i8".to_string())])])
+            ),
+            _ => panic!("Expected function declaration")
+        };
+        let (return_type, body) = match &*result.items[1] {
+            ModuleItem::FunctionDeclare(f) => (&f.return_type, &f.stats),
+            _ => panic!("Expected function declaration")
+        };
+        assert!(return_type.is_void());
+        let statements = match &**body {
+            Expr::Block(statements, None) => statements,
+            _ => panic!("Expected block without tail expression")
+        };
+        match &*statements[0] {
+            Statement::Destructure(pattern, ty, expr) => {
+                assert_eq!(pattern, &Pattern::Tuple(vec![
+                    Pattern::Tuple(vec![Pattern::Identifier("a".to_string()), Pattern::Wildcard]),
+                    Pattern::Identifier("b".to_string()),
+                ]));
+                assert!(ty.is_some());
+                assert!(matches!(&**expr, Expr::Tuple(items) if items.len() == 2));
+            },
+            _ => panic!("Expected destructuring let")
+        }
+        match &*statements[1] {
+            Statement::Return(expr, _) => match &**expr {
+                Expr::TupleField(inner, 0) => assert!(matches!(&**inner, Expr::TupleField(_, 1))),
+                _ => panic!("Expected tuple field access")
+            },
+            _ => panic!("Expected return statement")
+        }
+    }
+
+    #[test]
+    fn test_enum_and_match() {
+        let result = parse_module(r#"
+            enum Shape {
+                Circle(i32),
+                Rect { w: i32, h: i32 },
+                Empty,
+            }
+            fn area(s: Shape) -> i32 {
+                return match s {
+                    Shape::Circle(0) => 1,
+                    Shape::Rect { w, h: 1 } => w,
+                    Shape::Empty => Shape::Rect { w: 1, h: 2 },
+                    _ => Shape::Circle(3),
+                };
+            }
+        "#).unwrap();
+
+        let shape = match &*result.items[0] {
+            ModuleItem::EnumDeclare(e) => e,
+            _ => panic!("Expected enum declaration")
+        };
+        assert_eq!(shape.name, "Shape");
+        assert_eq!(shape.variants[0].fields, VariantFields::Tuple(vec![Type::named("i32".to_string())]));
+        assert!(matches!(&shape.variants[1].fields, VariantFields::Struct(fields) if fields.len() == 2));
+        assert_eq!(shape.variants[2].fields, VariantFields::Unit);
+
+        let body = match &*result.items[1] {
+            ModuleItem::FunctionDeclare(f) => &f.stats,
+            _ => panic!("Expected function declaration")
+        };
+        let arms = match &**body {
+            Expr::Block(statements, None) => match &*statements[0] {
+                Statement::Return(expr, _) => match &**expr {
+                    Expr::Match { scrutinee, arms, .. } => {
+                        assert!(matches!(&**scrutinee, Expr::Identifier(name, _) if name == "s"));
+                        arms
+                    },
+                    _ => panic!("Expected match expression")
+                },
+                _ => panic!("Expected return statement")
+            },
+            _ => panic!("Expected block without tail expression")
+        };
+        assert_eq!(arms.len(), 4);
+        assert_eq!(arms[0].pattern, Pattern::Variant {
+            enum_name: "Shape".to_string(),
+            variant: "Circle".to_string(),
+            fields: VariantFields::Tuple(vec![Pattern::Integer(0)]),
+        });
+        assert_eq!(arms[1].pattern, Pattern::Variant {
+            enum_name: "Shape".to_string(),
+            variant: "Rect".to_string(),
+            fields: VariantFields::Struct(vec![
+                ("w".to_string(), Pattern::Identifier("w".to_string())),
+                ("h".to_string(), Pattern::Integer(1)),
+            ]),
+        });
+        assert!(matches!(&*arms[2].body, Expr::StructLiteral { variant: Some(_), .. }));
+        assert_eq!(arms[3].pattern, Pattern::Wildcard);
+        assert!(matches!(&*arms[3].body, Expr::Call(_, _)));
+    }
+
+    #[test]
+    fn test_range_pattern_and_match_locations() {
+        let result = parse_module(r#"
+            fn sign(n: i32) -> i32 {
+                return match n {
+                    -5..=-1 => 0,
+                    0 => 1,
+                    1 ..= 5 => 2,
+                };
+            }
+        "#).unwrap();
+
+        let body = match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => &f.stats,
+            _ => panic!("Expected function declaration")
+        };
+        let statements = match &**body {
+            Expr::Block(statements, None) => statements,
+            _ => panic!("Expected block without tail expression")
+        };
+        match &*statements[0] {
+            Statement::Return(expr, _) => match &**expr {
+                Expr::Match { arms, location, .. } => {
+                    assert_eq!(location, &Location::new(3, 24));
+                    assert_eq!(arms[0].pattern, Pattern::Range(-5, -1));
+                    assert_eq!(arms[1].pattern, Pattern::Integer(0));
+                    assert_eq!(arms[2].pattern, Pattern::Range(1, 5));
+                    assert_eq!(arms[2].location, Location::new(6, 21));
+                },
+                _ => panic!("Expected match expression")
+            },
+            _ => panic!("Expected return statement")
+        }
+    }
+
+    #[test]
+    fn test_generic_function_and_struct() {
+        let result = parse_module(r#"
+            struct Pair<A, B> {
+                first: A,
+                second: B,
+            }
+            fn swap<A, B>(pair: Pair<A, B>) -> Pair<B, A> {
+                return Pair { first: pair.second, second: pair.first };
+            }
+            fn main() -> i32 {
+                let pair: Pair<i32, Pair<i8, i8>> = make();
+                return 0;
+            }
+        "#).unwrap();
+
+        match &*result.items[0] {
+            ModuleItem::StructDeclare(s) => {
+                assert_eq!(s.generics, vec!["A".to_string(), "B".to_string()]);
+                assert_eq!(s.fields["first"], Type::named("A".to_string()));
+            },
+            _ => panic!("Expected struct declaration")
+        }
+        let pair = |first: &str, second: &str| Type::generic(
+            "Pair".to_string(),
+            vec![Type::named(first.to_string()), Type::named(second.to_string())],
+        );
+        match &*result.items[1] {
+            ModuleItem::FunctionDeclare(f) => {
+                assert_eq!(f.generics, vec!["A".to_string(), "B".to_string()]);
+                assert_eq!(f.args[0].1, pair("A", "B"));
+                assert_eq!(f.return_type, pair("B", "A"));
+            },
+            _ => panic!("Expected function declaration")
+        }
+        match &*result.items[2] {
+            ModuleItem::FunctionDeclare(f) => {
+                assert!(!f.is_generic());
+                match &*f.stats {
+                    Expr::Block(statements, None) => match &*statements[0] {
+                        Statement::Declare(_, ty, _) => assert_eq!(ty.to_string(), "Pair<i32, Pair<i8, i8>>"),
+                        _ => panic!("Expected declare statement")
+                    },
+                    _ => panic!("Expected block without tail expression")
+                }
+            },
+            _ => panic!("Expected function declaration")
+        }
+    }
+
+    #[test]
+    fn test_impl_block_and_method_calls() {
+        let result = parse_module(r#"
+            impl<T> Wrapper<T> {
+                fn new(inner: T) -> Self {
+                    return Wrapper { inner: inner };
+                }
+                fn get(&self) -> T {
+                    return self.inner;
+                }
+                fn set(&mut self, inner: T) -> () {
+                    self.inner = inner;
+                    return ();
+                }
+                fn into_inner(self) -> T {
+                    return Self::new(self.inner).get();
+                }
+            }
+        "#).unwrap();
+
+        let impl_block = match &*result.items[0] {
+            ModuleItem::Impl(impl_block) => impl_block,
+            _ => panic!("Expected impl block")
+        };
+        assert_eq!(impl_block.generics, vec!["T".to_string()]);
+        assert_eq!(impl_block.target().to_string(), "Wrapper<T>");
+        let receivers: Vec<Option<String>> = impl_block.functions
+            .iter()
+            .map(|f| if f.is_method() { Some(f.args[0].1.to_string()) } else { None })
+            .collect();
+        assert_eq!(receivers, vec![
+            None,
+            Some("&Self".to_string()),
+            Some("&mut Self".to_string()),
+            Some("Self".to_string()),
+        ]);
+        assert_eq!(impl_block.functions[2].args[1].0, "inner");
+
+        let functions = impl_block.qualified_functions();
+        assert_eq!(functions[1].name, "Wrapper::get");
+        assert_eq!(functions[1].args[0].1.to_string(), "&Wrapper<T>");
+        assert_eq!(functions[0].return_type.to_string(), "Wrapper<T>");
+
+        // `Self::new(..).get()` is a call of the field `get` of the result of a call
+        match &*impl_block.functions[3].stats {
+            Expr::Block(statements, None) => match &*statements[0] {
+                Statement::Return(expr, _) => match &**expr {
+                    Expr::Call(callee, args) => {
+                        assert!(args.is_empty());
+                        match &**callee {
+                            Expr::Field(receiver, method) => {
+                                assert_eq!(method, "get");
+                                assert!(matches!(&**receiver, Expr::Call(path, _) if matches!(&**path, Expr::Path(..))));
+                            },
+                            _ => panic!("Expected field")
+                        }
+                    },
+                    _ => panic!("Expected call")
+                },
+                _ => panic!("Expected return statement")
+            },
+            _ => panic!("Expected block without tail expression")
+        }
+    }
+
+    #[test]
+    fn test_trait_impl_and_bounds() {
+        let result = parse_module(r#"
+            trait Area {
+                fn area(&self) -> i32;
+                fn double_area(&self) -> i32 {
+                    return self.area() * 2;
+                }
+            }
+            impl<T: Area> Area for Wrapper<T> {
+                fn area(&self) -> i32 {
+                    return self.inner.area();
+                }
+            }
+            fn total<T: Area + Add, U>(shape: &T, other: U) -> i32 {
+                return shape.area();
+            }
+        "#).unwrap();
+
+        match &*result.items[0] {
+            ModuleItem::TraitDeclare(t) => {
+                assert_eq!(t.name, "Area");
+                assert_eq!(t.functions.len(), 2);
+                assert!(t.functions[0].default.is_none());
+                assert_eq!(t.functions[0].args[0].1.to_string(), "&Self");
+                assert!(t.function("double_area").unwrap().default.is_some());
+            },
+            _ => panic!("Expected trait declaration")
+        }
+        match &*result.items[1] {
+            ModuleItem::Impl(impl_block) => {
+                assert_eq!(impl_block.trait_name, Some("Area".to_string()));
+                assert_eq!(impl_block.target().to_string(), "Wrapper<T>");
+                assert_eq!(impl_block.bounds, vec![("T".to_string(), "Area".to_string())]);
+            },
+            _ => panic!("Expected impl block")
+        }
+        match &*result.items[2] {
+            ModuleItem::FunctionDeclare(f) => {
+                assert_eq!(f.generics, vec!["T".to_string(), "U".to_string()]);
+                assert_eq!(f.bounds, vec![
+                    ("T".to_string(), "Area".to_string()),
+                    ("T".to_string(), "Add".to_string()),
+                ]);
+            },
+            _ => panic!("Expected function declaration")
+        }
+    }
+
+    #[test]
+    fn test_trait_object_types() {
+        let result = parse_module(r#"
+            fn draw(shape: &dyn Shape, shapes: [&mut dyn Shape; 2], raw: *dyn Shape, dynamo: Dynamo) -> () {
+                return ();
+            }
+        "#).unwrap();
+
+        match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => {
+                let shape = || Type::Dyn("Shape".to_string(), vec![]);
+                assert_eq!(f.args[0].1, Type::reference(shape(), false));
+                assert_eq!(f.args[1].1, Type::array(Type::reference(shape(), true), 2));
+                assert_eq!(f.args[2].1, Type::pointer(shape()));
+                assert_eq!(f.args[3].1, Type::named("Dynamo".to_string()));
+                assert_eq!(f.args[0].1.to_string(), "&dyn Shape");
+            },
+            _ => panic!("Expected function declaration")
+        }
+    }
+
+    #[test]
+    fn test_modules_and_use() {
+        let result = parse_module(r#"
+            pub mod geo;
+            use geo::shapes::area as shape_area;
+            pub use geo::{Point, shapes::{self}};
+            pub struct Origin {
+                at: geo::Point,
+            }
+            fn main() -> i32 {
+                let s: geo::Shape = geo::Shape::Circle(1);
+                let p: geo::Point = geo::Point { x: 1, y: 2 };
+                return geo::shapes::area(&s) + crate::geo::Point::new(1, 2).x;
+            }
+        "#).unwrap();
+
+        match &*result.items[0] {
+            ModuleItem::Mod(m) => {
+                assert_eq!(m.name, "geo");
+                assert_eq!(m.visibility, Visibility::Public);
+            },
+            _ => panic!("Expected mod declaration")
+        }
+        let path = |segments: &[&str]| segments.iter().map(|it| it.to_string()).collect::<Vec<_>>();
+        match &*result.items[1] {
+            ModuleItem::Use(u) => {
+                assert_eq!(u.visibility, Visibility::Private);
+                assert_eq!(u.imports, vec![(path(&["geo", "shapes", "area"]), "shape_area".to_string())]);
+            },
+            _ => panic!("Expected use declaration")
+        }
+        match &*result.items[2] {
+            ModuleItem::Use(u) => {
+                assert_eq!(u.visibility, Visibility::Public);
+                assert_eq!(u.imports, vec![
+                    (path(&["geo", "Point"]), "Point".to_string()),
+                    (path(&["geo", "shapes", "self"]), "shapes".to_string()),
+                ]);
+            },
+            _ => panic!("Expected use declaration")
+        }
+        match &*result.items[3] {
+            ModuleItem::StructDeclare(s) => {
+                assert_eq!(s.visibility, Visibility::Public);
+                assert_eq!(s.fields["at"], Type::named("geo::Point".to_string()));
+            },
+            _ => panic!("Expected struct declaration")
+        }
+        let statements = match &*result.items[4] {
+            ModuleItem::FunctionDeclare(f) => match &*f.stats {
+                Expr::Block(statements, _) => statements.clone(),
+                _ => panic!("Expected block")
+            },
+            _ => panic!("Expected function declaration")
+        };
+        match &*statements[0] {
+            Statement::Declare(_, ty, value) => {
+                assert_eq!(ty, &Type::named("geo::Shape".to_string()));
+                assert!(matches!(&**value, Expr::Call(callee, _)
+                    if matches!(&**callee, Expr::Path(a, b) if a == "geo::Shape" && b == "Circle")));
+            },
+            _ => panic!("Expected let statement")
+        }
+        // `geo::Point { .. }` is read like a variant, the resolver tells them apart
+        match &*statements[1] {
+            Statement::Declare(_, _, value) => assert!(matches!(&**value,
+                Expr::StructLiteral { name, variant: Some(variant), .. } if name == "geo" && variant == "Point")),
+            _ => panic!("Expected let statement")
+        }
+        match &*statements[2] {
+            Statement::Return(value, _) => match &**value {
+                Expr::BinOperation(Opcode::Add, lhs, rhs, _) => {
+                    assert!(matches!(&**lhs, Expr::Call(callee, _)
+                        if matches!(&**callee, Expr::Path(a, b) if a == "geo::shapes" && b == "area")));
+                    assert!(matches!(&**rhs, Expr::Field(call, _)
+                        if matches!(&**call, Expr::Call(callee, _)
+                            if matches!(&**callee, Expr::Path(a, b) if a == "crate::geo::Point" && b == "new"))));
+                },
+                _ => panic!("Expected addition")
+            },
+            _ => panic!("Expected return statement")
+        }
+    }
+
+    #[test]
+    fn test_closures_and_function_types() {
+        let result = parse_module(r#"
+            fn apply(f: fn(i32, i8) -> i32, g: fn()) -> fn(i32) -> i32 {
+                let n: i32 = 1;
+                let h: fn(i32) -> i32 = |x| x + n;
+                let k: fn(i32, i8) -> i32 = |a: i32, b| -> i32 { a };
+                return || 1;
+            }
+        "#).unwrap();
+
+        let i32_type = || Type::named("i32".to_string());
+        match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => {
+                assert_eq!(f.args[0].1, Type::function(vec![i32_type(), Type::named("i8".to_string())], i32_type()));
+                assert_eq!(f.args[1].1, Type::function(vec![], Type::void()));
+                assert_eq!(f.return_type, Type::function(vec![i32_type()], i32_type()));
+                assert_eq!(f.args[0].1.to_string(), "fn(i32, i8) -> i32");
+                let statements = match &*f.stats {
+                    Expr::Block(statements, _) => statements,
+                    _ => panic!("Expected block")
+                };
+                match &*statements[1] {
+                    Statement::Declare(_, _, value) => match &**value {
+                        Expr::Closure { params, return_type: None, body } => {
+                            assert_eq!(params, &vec![("x".to_string(), None)]);
+                            assert!(matches!(&**body, Expr::BinOperation(Opcode::Add, _, _, _)));
+                        },
+                        _ => panic!("Expected closure")
+                    },
+                    _ => panic!("Expected let statement")
+                }
+                match &*statements[2] {
+                    Statement::Declare(_, _, value) => match &**value {
+                        Expr::Closure { params, return_type: Some(ty), body } => {
+                            assert_eq!(params, &vec![("a".to_string(), Some(i32_type())), ("b".to_string(), None)]);
+                            assert_eq!(ty, &i32_type());
+                            assert!(matches!(&**body, Expr::Block(..)));
+                        },
+                        _ => panic!("Expected closure")
+                    },
+                    _ => panic!("Expected let statement")
+                }
+                match &*statements[3] {
+                    Statement::Return(value, _) => assert!(matches!(&**value,
+                        Expr::Closure { params, return_type: None, .. } if params.is_empty())),
+                    _ => panic!("Expected return statement")
+                }
+            },
+            _ => panic!("Expected function declaration")
+        }
+    }
+
+    #[test]
+    fn test_constants_statics_and_casts() {
+        let result = parse_module(r#"
+            pub const MAX: i32 = 100 as i32 * 2;
+            static LIMIT: i8 = 3;
+            pub static mut COUNTER: i64 = 0;
+            fn main() -> i32 {
+                return unsafe { COUNTER as i32 };
+            }
+        "#).unwrap();
+
+        match &*result.items[0] {
+            ModuleItem::Const(c) => {
+                assert_eq!(c.visibility, Visibility::Public);
+                assert_eq!(c.name, "MAX");
+                assert_eq!(c.ty, Type::named("i32".to_string()));
+                // `as` binds tighter than `*`
+                match &*c.value {
+                    Expr::BinOperation(Opcode::Mul, lhs, _, _) => assert!(matches!(&**lhs, Expr::Cast(..))),
+                    _ => panic!("Expected multiplication")
+                }
+            },
+            _ => panic!("Expected constant")
+        }
+        match (&*result.items[1], &*result.items[2]) {
+            (ModuleItem::Static(limit), ModuleItem::Static(counter)) => {
+                assert!(!limit.mutable);
+                assert_eq!(limit.visibility, Visibility::Private);
+                assert!(counter.mutable);
+                assert_eq!(counter.ty, Type::named("i64".to_string()));
+            },
+            _ => panic!("Expected statics")
+        }
+        match &*result.items[3] {
+            ModuleItem::FunctionDeclare(f) => match &*f.stats {
+                Expr::Block(statements, _) => match &*statements[0] {
+                    Statement::Return(value, _) => match &**value {
+                        Expr::Unsafe(body) => assert!(matches!(&**body, Expr::Block(_, Some(_)))),
+                        _ => panic!("Expected unsafe block")
+                    },
+                    _ => panic!("Expected return statement")
+                },
+                _ => panic!("Expected block")
+            },
+            _ => panic!("Expected function declaration")
+        }
+    }
+
+    #[test]
+    fn test_casts_after_calls_and_parentheses() {
+        let result = parse_module(r#"
+            fn main() -> i8 {
+                let x: i64 = f(1) as i64;
+                (a + b) as i8
+            }
+            fn after() -> () {
+            }
+        "#).unwrap();
+
+        assert_eq!(result.items.len(), 2);
+        match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => match &*f.stats {
+                Expr::Block(statements, Some(tail)) => {
+                    match &*statements[0] {
+                        Statement::Declare(_, _, value) => match &**value {
+                            Expr::Cast(inner, ty) => {
+                                assert!(matches!(&**inner, Expr::Call(..)));
+                                assert_eq!(*ty, Type::named("i64".to_string()));
+                            }
+                            _ => panic!("Expected cast of the call")
+                        },
+                        _ => panic!("Expected let statement")
+                    }
+                    match &**tail {
+                        Expr::Cast(inner, ty) => {
+                            assert!(matches!(&**inner, Expr::BinOperation(Opcode::Add, ..)));
+                            assert_eq!(*ty, Type::named("i8".to_string()));
+                        }
+                        _ => panic!("Expected cast of the parenthesized expression")
+                    }
+                }
+                _ => panic!("Expected block with a tail expression")
+            },
+            _ => panic!("Expected function declaration")
+        }
+    }
+
+    #[test]
+    fn test_input_left_after_the_items_is_an_error() {
+        let error = parse_module(r#"
+            fn main() -> i32 {
+                return 1;
+            }
+            fn broken( -> i32 {
+                return 2;
+            }
+        "#).unwrap_err();
+
+        assert_eq!(crate::parser::error_location(&error), Some(Location::new(5, 13)));
+        assert!(parse_module("fn main() -> i32 {\n    return 1;\n}\n\n").is_ok());
+    }
+
+    #[test]
+    fn test_type_aliases_and_newtypes() {
+        let result = parse_module(r#"
+            pub type Meters = i64;
+            type Pair = (Meters, &Meters);
+            newtype UserId(i64);
+            pub newtype Path( [i8; 4] );
+        "#).unwrap();
+
+        let meters = || Type::named("Meters".to_string());
+        match (&*result.items[0], &*result.items[1]) {
+            (ModuleItem::TypeAlias(a), ModuleItem::TypeAlias(b)) => {
+                assert_eq!(a.visibility, Visibility::Public);
+                assert_eq!(a.name, "Meters");
+                assert_eq!(a.ty, Type::named("i64".to_string()));
+                assert_eq!(b.visibility, Visibility::Private);
+                assert_eq!(b.ty, Type::tuple(vec![meters(), Type::reference(meters(), false)]));
+            },
+            _ => panic!("Expected type aliases")
+        }
+        match (&*result.items[2], &*result.items[3]) {
+            (ModuleItem::Newtype(id), ModuleItem::Newtype(path)) => {
+                assert_eq!(id.name, "UserId");
+                assert_eq!(id.inner, Type::named("i64".to_string()));
+                assert_eq!(id.as_struct().fields.keys().collect::<Vec<_>>(), vec!["0"]);
+                assert_eq!(path.visibility, Visibility::Public);
+                assert_eq!(path.inner, Type::array(Type::named("i8".to_string()), 4));
+            },
+            _ => panic!("Expected newtypes")
+        }
+    }
+
+    #[test]
+    fn test_box_expressions_and_types() {
+        let result = parse_module(r#"
+            struct Node {
+                value: i32,
+                next: Box<Node>,
+            }
+            fn main() -> i32 {
+                let boxes: Box<Box<i32>> = box box 1;
+                let pair: Box<(i32, i8)> = box (1, 2);
+                return **boxes;
+            }
+        "#).unwrap();
+
+        match &*result.items[0] {
+            ModuleItem::StructDeclare(s) => {
+                assert_eq!(s.fields["next"], Type::boxed(Type::named("Node".to_string())));
+            },
+            _ => panic!("Expected struct")
+        }
+        match &*result.items[1] {
+            ModuleItem::FunctionDeclare(f) => match &*f.stats {
+                Expr::Block(statements, _) => {
+                    match (&*statements[0], &*statements[1]) {
+                        (Statement::Declare(_, boxes, value), Statement::Declare(_, pair, tuple)) => {
+                            let i32_type = Type::named("i32".to_string());
+                            assert_eq!(*boxes, Type::boxed(Type::boxed(i32_type.clone())));
+                            match &**value {
+                                Expr::Box(inner) => assert!(matches!(&**inner, Expr::Box(_))),
+                                _ => panic!("Expected box expression")
+                            }
+                            assert_eq!(
+                                *pair,
+                                Type::boxed(Type::tuple(vec![i32_type, Type::named("i8".to_string())]))
+                            );
+                            match &**tuple {
+                                Expr::Box(inner) => assert!(matches!(&**inner, Expr::Tuple(_))),
+                                _ => panic!("Expected box expression")
+                            }
+                        },
+                        _ => panic!("Expected let statements")
+                    }
+                },
+                _ => panic!("Expected function body")
+            },
+            _ => panic!("Expected function declaration")
+        }
+    }
+
     #[test]
     fn test() {
         let result = parse_module(r#"
@@ -85,7 +957,7 @@ mod test {
                     {c}
             }
         "#).unwrap();
-        let x = &result.items[0];
+        assert_eq!(result.items.len(), 3);
         dbg!(result);
     }
     #[test]
@@ -94,7 +966,182 @@ mod test {
             fn main() -> () {
                 print("hello world");
             }
-        "#);
+        "#).unwrap();
         dbg!(result);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_remainder_and_shifts() {
+        let result = parse_module(r#"
+            const SIGN: i64 = 1 << 62 + 1 >> 1;
+            const DIGIT: i64 = 48 - 1234 % 10;
+        "#).unwrap();
+
+        match (&*result.items[0], &*result.items[1]) {
+            (ModuleItem::Const(sign), ModuleItem::Const(digit)) => {
+                // shifts bind looser than sums and associate to the left
+                match &*sign.value {
+                    Expr::BinOperation(Opcode::RShift, lhs, _, _) => {
+                        assert!(matches!(&**lhs, Expr::BinOperation(Opcode::LShift, _, rhs, _)
+                            if matches!(&**rhs, Expr::BinOperation(Opcode::Add, _, _, _))))
+                    }
+                    _ => panic!("Expected right shift")
+                }
+                match &*digit.value {
+                    Expr::BinOperation(Opcode::Sub, _, rhs, _) => {
+                        // the location of an operation is the one of its operator
+                        assert!(matches!(&**rhs, Expr::BinOperation(Opcode::Mod, _, _, location)
+                            if *location == Location::new(3, 42)))
+                    }
+                    _ => panic!("Expected subtraction")
+                }
+            },
+            _ => panic!("Expected constants")
+        }
+    }
+
+    #[test]
+    fn test_for_loops_and_ranges() {
+        let result = parse_module(r#"
+            enum Option<T> {
+                None,
+                Some(T),
+            }
+            fn main() -> i64 {
+                for i in 0..n + 1 {
+                    total = total + i;
+                }
+                for (a, b) in pairs {}
+                0..=9
+            }
+        "#).unwrap();
+
+        match &*result.items[0] {
+            ModuleItem::EnumDeclare(e) => assert_eq!(e.generics, vec!["T".to_string()]),
+            _ => panic!("Expected enum declaration")
+        }
+        let (statements, value) = match &*result.items[1] {
+            ModuleItem::FunctionDeclare(f) => match &*f.stats {
+                Expr::Block(statements, Some(value)) => (statements, value),
+                _ => panic!("Expected block with a value")
+            },
+            _ => panic!("Expected function declaration")
+        };
+        assert_eq!(statements.len(), 2);
+        match &*statements[0] {
+            Statement::Expr(expr) => match &**expr {
+                Expr::For { pattern, iterable, body } => {
+                    assert_eq!(pattern, &Pattern::Identifier("i".to_string()));
+                    // the bounds of a range bind tighter than the range
+                    assert!(matches!(&**iterable, Expr::Range { end, inclusive: false, .. }
+                        if matches!(&**end, Expr::BinOperation(Opcode::Add, _, _, _))));
+                    assert!(matches!(&**body, Expr::Block(statements, None) if statements.len() == 1));
+                },
+                _ => panic!("Expected for loop")
+            },
+            _ => panic!("Expected expression statement")
+        }
+        match &*statements[1] {
+            Statement::Expr(expr) => assert!(matches!(&**expr, Expr::For { pattern: Pattern::Tuple(_), .. })),
+            _ => panic!("Expected expression statement")
+        }
+        assert!(matches!(&**value, Expr::Range { inclusive: true, .. }));
+    }
+
+    #[test]
+    fn test_try_operator() {
+        let result = parse_module(r#"
+            fn total(a: i64) -> Result<i64, i32> {
+                let x: i64 = parse(a)?.value;
+                Result::Ok(lines(a)?.count()? + x)
+            }
+        "#).unwrap();
+
+        let (statements, value) = match &*result.items[0] {
+            ModuleItem::FunctionDeclare(f) => match &*f.stats {
+                Expr::Block(statements, Some(value)) => (statements, value),
+                _ => panic!("Expected block with a value")
+            },
+            _ => panic!("Expected function declaration")
+        };
+        match &*statements[0] {
+            // `?` binds tighter than a member access which follows it
+            Statement::Declare(_, _, expr) => match &**expr {
+                Expr::Field(target, field) => {
+                    assert_eq!(field, "value");
+                    assert!(matches!(&**target, Expr::Try(call, location)
+                        if matches!(&**call, Expr::Call(..)) && location.line == 3));
+                },
+                _ => panic!("Expected field access")
+            },
+            _ => panic!("Expected declaration")
+        }
+        match &**value {
+            Expr::Call(_, params) => assert!(matches!(&*params[0], Expr::BinOperation(Opcode::Add, left, _, _)
+                if matches!(&**left, Expr::Try(..)))),
+            _ => panic!("Expected call")
+        }
+    }
+
+    #[test]
+    fn test_generic_traits_and_comparisons() {
+        let result = parse_module(r#"
+            trait Iterator<Item> {
+                fn next(&mut self) -> Option<Item>;
+            }
+            impl<T> Iterator<&T> for Iter<T> {
+                fn next(&mut self) -> Option<&T> {
+                    return Option::None;
+                }
+            }
+            fn more(it: &mut dyn Iterator<i64>, a: i64, b: i64) -> i64 {
+                return a + 1 < b << 2;
+            }
+        "#).unwrap();
+
+        match &*result.items[0] {
+            ModuleItem::TraitDeclare(t) => assert_eq!(t.generics, vec!["Item".to_string()]),
+            _ => panic!("Expected trait declaration")
+        }
+        match &*result.items[1] {
+            ModuleItem::Impl(impl_block) => {
+                assert_eq!(impl_block.trait_name, Some("Iterator".to_string()));
+                assert_eq!(impl_block.trait_arguments[0].to_string(), "&T");
+            },
+            _ => panic!("Expected impl block")
+        }
+        match &*result.items[2] {
+            ModuleItem::FunctionDeclare(f) => {
+                assert_eq!(f.args[0].1.to_string(), "&mut dyn Iterator<i64>");
+                // comparisons bind looser than sums and shifts
+                match &*f.stats {
+                    Expr::Block(statements, None) => assert!(matches!(&*statements[0], Statement::Return(value, _)
+                        if matches!(&**value, Expr::BinOperation(Opcode::Lt, lhs, rhs, _)
+                            if matches!(&**lhs, Expr::BinOperation(Opcode::Add, ..))
+                                && matches!(&**rhs, Expr::BinOperation(Opcode::LShift, ..))))),
+                    _ => panic!("Expected block")
+                }
+            },
+            _ => panic!("Expected function declaration")
+        }
+    }
+
+    #[test]
+    fn test_integer_suffixes() {
+        let result = parse_module(r#"
+            const SMALL: i8 = -128i8;
+            const LARGE: i64 = 9_223_372_036_854_775_807i64;
+        "#).unwrap();
+
+        match (&*result.items[0], &*result.items[1]) {
+            (ModuleItem::Const(small), ModuleItem::Const(large)) => {
+                assert!(matches!(&*small.value, Expr::Number(Number::Integer8(-128))));
+                assert!(matches!(&*large.value, Expr::Number(Number::Integer64(i64::MAX))));
+            },
+            _ => panic!("Expected constants")
+        }
+        // a literal which does not fit in its type is an error rather than a panic
+        assert!(parse_module("const BIG: i8 = 128i8;").is_err());
+        assert!(parse_module("const HUGE: i64 = 99999999999999999999;").is_err());
+    }
+}
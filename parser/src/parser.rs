@@ -2,10 +2,10 @@ use ast::*;
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_while1},
-    character::complete::{alpha1, alphanumeric1, char, digit1, multispace0, one_of},
-    combinator::{map, opt, recognize},
+    character::complete::{alpha1, alphanumeric1, char, digit1, multispace0, multispace1, one_of},
+    combinator::{all_consuming, map, not, opt, recognize, verify},
     error::ParseError,
-    multi::{many0, many1, separated_list0},
+    multi::{many0, many1, separated_list0, separated_list1},
     sequence::{delimited, pair, preceded, terminated, tuple},
     IResult, InputLength, Parser,
 };
@@ -16,9 +16,9 @@ type Span<'a> = LocatedSpan<&'a str>;
 /// Parses a comma-separated list with optional trailing comma
 /// Example: "a, b, c," or "a, b, c" both parse to vec!["a", "b", "c"]
 fn separated_list0_trailing<I, O, O2, E, F, G, G2>(
-    mut sep: G,
-    mut f: F,
-    mut trailing: G2,
+    sep: G,
+    f: F,
+    trailing: G2,
 ) -> impl FnMut(I) -> IResult<I, Vec<O>, E>
 where
     I: Clone + InputLength,
@@ -37,7 +37,7 @@ fn identifier(input: Span) -> IResult<Span, String> {
     let (input, rest) = many0(alt((alphanumeric1, tag("_"))))(input)?;
     let mut ident = first.to_string();
     for r in rest {
-        ident.push_str(&*r);
+        ident.push_str(&r);
     }
     Ok((input, ident))
 }
@@ -45,33 +45,164 @@ fn identifier(input: Span) -> IResult<Span, String> {
 /// Type parser
 /// rule:
 ///  - NAMED: [a-zA-Z_][a-zA-Z0-9_]*
-///  - UNIT: ()
+///  - TUPLE: () | (TYPE,) | (TYPE, TYPE, ...)
+///  - REFERENCE: &TYPE | &mut TYPE
+///  - POINTER: *TYPE
+///  - BOX: Box<TYPE>
+///  - ARRAY: [TYPE; [0-9]+]
+///  - GENERIC: NAME<TYPE, ...>
+///  - TRAIT OBJECT: dyn NAME
+///  - FUNCTION: fn(TYPE, ...) (-> TYPE)?, returning `()` without return type
+///
+/// where NAME is an identifier with an optional module path `geo::Point`
 fn ttype(input: Span) -> IResult<Span, Type> {
     alt((
-        map(identifier, |i| Type::named(i)),
-        map(delimited(char('('), multispace0, char(')')), |_| {
-            Type::void()
+        map(
+            delimited(
+                pair(char('['), multispace0),
+                tuple((ttype, delimited(multispace0, char(';'), multispace0), length)),
+                pair(multispace0, char(']')),
+            ),
+            |(element, _, len)| Type::array(element, len),
+        ),
+        map(
+            preceded(tuple((char('&'), multispace0, tag("mut"), multispace1)), ttype),
+            |t| Type::reference(t, true),
+        ),
+        map(preceded(pair(char('&'), multispace0), ttype), |t| {
+            Type::reference(t, false)
         }),
+        map(preceded(pair(char('*'), multispace0), ttype), Type::pointer),
+        map(
+            delimited(
+                tuple((tag("simd"), multispace0, char('<'), multispace0)),
+                tuple((ttype, delimited(multispace0, char(','), multispace0), length)),
+                pair(multispace0, char('>')),
+            ),
+            |(element, _, lanes)| Type::simd(element, lanes),
+        ),
+        map(
+            delimited(
+                tuple((tag("Box"), multispace0, char('<'), multispace0)),
+                ttype,
+                pair(multispace0, char('>')),
+            ),
+            Type::boxed,
+        ),
+        map(
+            preceded(
+                pair(tag("dyn"), multispace1),
+                pair(qualified_name, opt(preceded(multispace0, angle_list(ttype)))),
+            ),
+            |(trait_name, arguments)| Type::Dyn(trait_name, arguments.unwrap_or_default()),
+        ),
+        map(
+            preceded(
+                pair(tag("fn"), multispace0),
+                pair(
+                    parenthesized_list(ttype),
+                    opt(preceded(delimited(multispace0, tag("->"), multispace0), ttype)),
+                ),
+            ),
+            |((parameters, _), return_type)| {
+                Type::function(parameters, return_type.unwrap_or_else(Type::void))
+            },
+        ),
+        map(
+            tuple((qualified_name, opt(preceded(multispace0, angle_list(ttype))))),
+            |(name, arguments)| match arguments {
+                Some(arguments) => Type::generic(name, arguments),
+                None => Type::named(name),
+            },
+        ),
+        tuple_type,
     ))(input)
 }
 
-// Number parser
-// todo: Parses numeric literals with optional type suffixes:
-// - Signed integers: i8, i16, i32 (default)
-// - Unsigned integers: u8, u16, u32
-// Examples:
-//   42i8    -> Number::Integer8(42)
-//   123u16  -> Number::UnSignInteger16(123)
-//   456     -> Number::Integer32(456)
-// Underscores are allowed between digits for readability
+/// Tuple type parser
+/// rule: () | (TYPE,) | (TYPE, TYPE, ...), a single type without comma is just parenthesized
+fn tuple_type(input: Span) -> IResult<Span, Type> {
+    map(
+        parenthesized_list(ttype),
+        |(mut elements, trailing)| match (elements.len(), trailing) {
+            (1, false) => elements.remove(0),
+            _ => Type::tuple(elements),
+        },
+    )(input)
+}
+
+/// `( ITEM, ITEM, ... )`, also reports whether the list ends with a comma to tell the one
+/// element tuple `(a,)` from the parenthesized `(a)`
+fn parenthesized_list<'a, O, F>(item: F) -> impl FnMut(Span<'a>) -> IResult<Span<'a>, (Vec<O>, bool)>
+where
+    F: Parser<Span<'a>, O, nom::error::Error<Span<'a>>>,
+{
+    map(
+        delimited(
+            pair(char('('), multispace0),
+            pair(
+                separated_list0(delimited(multispace0, char(','), multispace0), item),
+                opt(preceded(multispace0, char(','))),
+            ),
+            pair(multispace0, char(')')),
+        ),
+        |(items, trailing)| (items, trailing.is_some()),
+    )
+}
+
+/// Number parser, an integer literal with an optional type suffix, `i64` without one
+/// rule: -?[0-9][0-9_]*(i8|i16|i32|i64)?
+/// Examples:
+///   42i8    -> Number::Integer8(42)
+///   456     -> Number::Integer64(456)
+/// a literal which does not fit in its type is no number
 fn number(input: Span) -> IResult<Span, Number> {
-    let (input, neg) = opt(char('-'))(input)?;
-    let (input, num) = recognize(pair(digit1, many0(alt((digit1, tag("_"))))))(input)?;
-    let parsed = num.replace("_", "").parse::<i64>().unwrap();
-    Ok((
-        input,
-        Number::Integer64(if neg.is_some() { -parsed } else { parsed }),
-    ))
+    let (rest, (neg, num, suffix)) = tuple((
+        opt(char('-')),
+        recognize(pair(digit1, many0(alt((digit1, tag("_")))))),
+        opt(alt((tag("i8"), tag("i16"), tag("i32"), tag("i64")))),
+    ))(input)?;
+    let digits = format!("{}{}", if neg.is_some() { "-" } else { "" }, num.replace("_", ""));
+    let parsed = match suffix.map(|it| *it.fragment()) {
+        Some("i8") => digits.parse().map(Number::Integer8),
+        Some("i16") => digits.parse().map(Number::Integer16),
+        Some("i32") => digits.parse().map(Number::Integer32),
+        _ => digits.parse().map(Number::Integer64),
+    };
+    match parsed {
+        Ok(number) => Ok((rest, number)),
+        Err(_) => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        ))),
+    }
+}
+
+/// Float parser, digits on both sides of the point so that `0..10` stays a range
+/// rule: -?[0-9][0-9_]*.[0-9][0-9_]*
+fn float(input: Span) -> IResult<Span, Number> {
+    let digits = || pair(digit1, many0(alt((digit1, tag("_")))));
+    let (input, num) = recognize(tuple((opt(char('-')), digits(), char('.'), digits())))(input)?;
+    Ok((input, Number::Float(num.replace("_", "").parse::<f64>().unwrap())))
+}
+
+/// current position in the source
+fn location(input: Span) -> IResult<Span, Location> {
+    let (input, pos) = position(input)?;
+    Ok((input, Location::new(pos.location_line(), pos.get_utf8_column())))
+}
+
+/// Length parser for arrays, a length which does not fit in a `u64` is no length
+/// rule: [0-9][0-9_]*
+fn length(input: Span) -> IResult<Span, u64> {
+    let (rest, num) = recognize(pair(digit1, many0(alt((digit1, tag("_"))))))(input)?;
+    match num.replace("_", "").parse::<u64>() {
+        Ok(len) => Ok((rest, len)),
+        Err(_) => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        ))),
+    }
 }
 
 // String parser
@@ -100,31 +231,268 @@ fn string_literal(input: Span) -> IResult<Span, String> {
 // Expression parsers
 fn atom(input: Span) -> IResult<Span, Expr> {
     alt((
-        map(identifier, |i| Expr::Identifier(i)),
-        map(number, |n| Expr::Number(n)),
-        map(string_literal, |s| Expr::String(s)),
-        delimited(char('('), expression, char(')')),
+        match_expression,
+        unsafe_expression,
+        closure_expression,
+        simd_expression,
+        struct_literal,
+        map(path, |(enum_name, variant)| Expr::Path(enum_name, variant)),
+        map(pair(location, identifier), |(location, i)| Expr::Identifier(i, location)),
+        map(alt((float, number)), Expr::Number),
+        map(string_literal, Expr::String),
+        tuple_expression,
+        array_expression,
         block_expression,
     ))(input)
 }
 
-fn field_access(input: Span) -> IResult<Span, Expr> {
-    let (input, first) = atom(input)?;
-    let (input, rest) = many0(preceded(
+/// Unsafe block parser
+/// rule: unsafe BLOCK
+fn unsafe_expression(input: Span) -> IResult<Span, Expr> {
+    map(preceded(pair(tag("unsafe"), multispace0), block_expression), |block| {
+        Expr::Unsafe(Box::new(block))
+    })(input)
+}
+
+/// Closure parser, a block body is needed to give the return type
+/// rule: |PARAMETER, ...| EXPR | |PARAMETER, ...| -> TYPE BLOCK, PARAMETER: IDENTIFIER (: TYPE)?
+fn closure_expression(input: Span) -> IResult<Span, Expr> {
+    let parameter = pair(
+        identifier,
+        opt(preceded(delimited(multispace0, char(':'), multispace0), ttype)),
+    );
+    map(
+        tuple((
+            alt((
+                map(tag("||"), |_| vec![]),
+                delimited(
+                    pair(char('|'), multispace0),
+                    separated_list0_trailing(
+                        delimited(multispace0, char(','), multispace0),
+                        parameter,
+                        delimited(multispace0, char(','), multispace0),
+                    ),
+                    pair(multispace0, char('|')),
+                ),
+            )),
+            multispace0,
+            alt((
+                map(
+                    pair(
+                        preceded(pair(tag("->"), multispace0), ttype),
+                        preceded(multispace0, block_expression),
+                    ),
+                    |(return_type, body)| (Some(return_type), body),
+                ),
+                map(expression, |body| (None, body)),
+            )),
+        )),
+        |(params, _, (return_type, body))| Expr::Closure {
+            params,
+            return_type,
+            body: Box::new(body),
+        },
+    )(input)
+}
+
+/// Array parser
+/// rule: [EXPR; LENGTH] | [EXPR, EXPR, ...]
+fn array_expression(input: Span) -> IResult<Span, Expr> {
+    delimited(
+        pair(char('['), multispace0),
+        alt((
+            map(
+                tuple((expression, delimited(multispace0, char(';'), multispace0), length)),
+                |(value, _, len)| Expr::ArrayRepeat(Box::new(value), len),
+            ),
+            map(
+                separated_list0_trailing(
+                    delimited(multispace0, char(','), multispace0),
+                    expression,
+                    delimited(multispace0, char(','), multispace0),
+                ),
+                |items| Expr::Array(items.into_iter().map(Box::new).collect()),
+            ),
+        )),
+        pair(multispace0, char(']')),
+    )(input)
+}
+
+/// Simd vector parser
+/// rule: simd [EXPR; LANES] | simd [EXPR, EXPR, ...]
+fn simd_expression(input: Span) -> IResult<Span, Expr> {
+    map(
+        preceded(pair(tag("simd"), multispace0), array_expression),
+        |array| match array {
+            Expr::ArrayRepeat(value, lanes) => Expr::SimdSplat(value, lanes),
+            Expr::Array(items) => Expr::SimdVector(items),
+            _ => unreachable!(),
+        },
+    )(input)
+}
+
+/// Tuple parser
+/// rule: () | (EXPR,) | (EXPR, EXPR, ...), a single expression without comma is a group
+fn tuple_expression(input: Span) -> IResult<Span, Expr> {
+    map(
+        parenthesized_list(expression),
+        |(mut items, trailing)| match (items.len(), trailing) {
+            (1, false) => items.remove(0),
+            _ => Expr::Tuple(items.into_iter().map(Box::new).collect()),
+        },
+    )(input)
+}
+
+enum Member {
+    Named(Identifier),
+    Position(u32),
+}
+
+/// `::` between the segments of a path
+fn path_separator(input: Span) -> IResult<Span, Span> {
+    delimited(multispace0, tag("::"), multispace0)(input)
+}
+
+/// Path parser, the last segment is split from the segments before it
+/// rule: IDENTIFIER (::IDENTIFIER)+
+fn path(input: Span) -> IResult<Span, (Identifier, Identifier)> {
+    map(
+        pair(identifier, many1(preceded(path_separator, identifier))),
+        |(first, mut rest)| {
+            let last = rest.pop().unwrap();
+            let prefix: Vec<Identifier> = std::iter::once(first).chain(rest).collect();
+            (prefix.join("::"), last)
+        },
+    )(input)
+}
+
+/// name of an item with an optional module path
+/// rule: IDENTIFIER (::IDENTIFIER)*
+fn qualified_name(input: Span) -> IResult<Span, Identifier> {
+    map(separated_list1(path_separator, identifier), |segments| segments.join("::"))(input)
+}
+
+/// a name starting with an uppercase letter, so that `match x {` is not mistaken for a
+/// struct literal
+fn type_name(input: Span) -> IResult<Span, Identifier> {
+    let (rest, name) = identifier(input)?;
+    if name.starts_with(|c: char| c.is_ascii_uppercase()) {
+        Ok((rest, name))
+    } else {
+        Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        )))
+    }
+}
+
+/// `{ NAME: ITEM, ... }` with an optional trailing comma
+fn named_fields<'a, O, F>(item: F) -> impl FnMut(Span<'a>) -> IResult<Span<'a>, Vec<(Identifier, O)>>
+where
+    F: Parser<Span<'a>, O, nom::error::Error<Span<'a>>>,
+{
+    delimited(
+        pair(char('{'), multispace0),
+        separated_list0_trailing(
+            delimited(multispace0, char(','), multispace0),
+            tuple((identifier, preceded(delimited(multispace0, char(':'), multispace0), item))),
+            delimited(multispace0, char(','), multispace0),
+        ),
+        pair(multispace0, char('}')),
+    )
+}
+
+/// Struct literal parser
+/// rule: TYPE_NAME { FIELD: EXPR, ... } | IDENTIFIER::IDENTIFIER { FIELD: EXPR, ... }
+fn struct_literal(input: Span) -> IResult<Span, Expr> {
+    map(
+        tuple((
+            alt((
+                map(path, |(name, variant)| (name, Some(variant))),
+                map(type_name, |name| (name, None)),
+            )),
+            preceded(multispace0, named_fields(expression)),
+        )),
+        |((name, variant), fields)| Expr::StructLiteral {
+            name,
+            variant,
+            fields: fields
+                .into_iter()
+                .map(|(field, value)| (field, Box::new(value)))
+                .collect(),
+        },
+    )(input)
+}
+
+/// Match parser
+/// rule: match EXPR { PATTERN => EXPR, ... }, the comma is optional after a block
+fn match_expression(input: Span) -> IResult<Span, Expr> {
+    map(
+        tuple((
+            location,
+            terminated(tag("match"), multispace1),
+            expression,
+            delimited(
+                pair(multispace0, char('{')),
+                many0(preceded(
+                    multispace0,
+                    terminated(
+                        tuple((
+                            location,
+                            pattern,
+                            preceded(delimited(multispace0, tag("=>"), multispace0), expression),
+                        )),
+                        opt(preceded(multispace0, char(','))),
+                    ),
+                )),
+                pair(multispace0, char('}')),
+            ),
+        )),
+        |(location, _, scrutinee, arms)| Expr::Match {
+            scrutinee: Box::new(scrutinee),
+            arms: arms
+                .into_iter()
+                .map(|(location, pattern, body)| MatchArm::new(pattern, body, location))
+                .collect(),
+            location,
+        },
+    )(input)
+}
+
+/// `.IDENTIFIER` or `.[0-9]+`
+fn member(input: Span) -> IResult<Span, Member> {
+    preceded(
         delimited(multispace0, char('.'), multispace0),
-        atom,
-    ))(input)?;
+        alt((
+            map(identifier, Member::Named),
+            map(digit1, |position: Span| {
+                Member::Position(position.parse::<u32>().unwrap())
+            }),
+        )),
+    )(input)
+}
 
-    Ok((
-        input,
-        rest.into_iter().fold(first, |acc, expr| {
-            Expr::Field(Box::new(acc), Box::new(expr))
-        }),
-    ))
+fn member_access(expr: Expr, member: Member) -> Expr {
+    match member {
+        Member::Named(name) => Expr::Field(Box::new(expr), name),
+        Member::Position(position) => Expr::TupleField(Box::new(expr), position),
+    }
+}
+
+/// Field access parser
+/// rule: ATOM (.IDENTIFIER | .[0-9]+)*
+fn field_access(input: Span) -> IResult<Span, Expr> {
+    let (input, first) = atom(input)?;
+    let (input, rest) = many0(member)(input)?;
+    Ok((input, rest.into_iter().fold(first, member_access)))
 }
 
 fn call_parameters(input: Span) -> IResult<Span, Vec<Expr>> {
-    separated_list0(delimited(multispace0, char(','), multispace0), expression)(input)
+    separated_list0_trailing(
+        delimited(multispace0, char(','), multispace0),
+        expression,
+        delimited(multispace0, char(','), multispace0),
+    )(input)
 }
 
 fn call(input: Span) -> IResult<Span, Expr> {
@@ -144,17 +512,112 @@ fn call(input: Span) -> IResult<Span, Expr> {
     ))(input)
 }
 
-fn multiple(input: Span) -> IResult<Span, Expr> {
+enum Postfix {
+    Index(Expr),
+    Member(Member),
+    Call(Vec<Expr>),
+    Try(Location),
+}
+
+/// Index parser, members of the indexed element or of a call result are accessed and
+/// called here too, so that method calls chain as in `a.b().c()`
+/// rule: CALL ([EXPR] | .IDENTIFIER | .[0-9]+ | (EXPR, ...) | ?)*
+fn index(input: Span) -> IResult<Span, Expr> {
+    let (input, location) = location(input)?;
     let (input, first) = call(input)?;
+    let (input, rest) = many0(alt((
+        map(
+            preceded(
+                multispace0,
+                delimited(
+                    pair(char('['), multispace0),
+                    expression,
+                    pair(multispace0, char(']')),
+                ),
+            ),
+            Postfix::Index,
+        ),
+        map(member, Postfix::Member),
+        map(
+            delimited(
+                delimited(multispace0, char('('), multispace0),
+                call_parameters,
+                pair(multispace0, char(')')),
+            ),
+            Postfix::Call,
+        ),
+        map(preceded(multispace0, terminated(self::location, char('?'))), Postfix::Try),
+    )))(input)?;
+
+    Ok((
+        input,
+        rest.into_iter().fold(first, |acc, postfix| match postfix {
+            Postfix::Index(index) => Expr::Index {
+                expr: Box::new(acc),
+                index: Box::new(index),
+                location,
+            },
+            Postfix::Member(member) => member_access(acc, member),
+            Postfix::Call(params) => {
+                Expr::Call(Box::new(acc), params.into_iter().map(Box::new).collect())
+            }
+            Postfix::Try(location) => Expr::Try(Box::new(acc), location),
+        }),
+    ))
+}
+
+/// Unary parser
+/// rule: &mut UNARY | &UNARY | *UNARY | box UNARY | INDEX
+fn unary(input: Span) -> IResult<Span, Expr> {
+    alt((
+        map(
+            preceded(tuple((char('&'), multispace0, tag("mut"), multispace1)), unary),
+            |expr| Expr::Reference {
+                mutable: true,
+                expr: Box::new(expr),
+            },
+        ),
+        map(preceded(pair(char('&'), multispace0), unary), |expr| {
+            Expr::Reference {
+                mutable: false,
+                expr: Box::new(expr),
+            }
+        }),
+        map(pair(terminated(location, pair(char('*'), multispace0)), unary), |(location, expr)| {
+            Expr::Deref(Box::new(expr), location)
+        }),
+        map(preceded(pair(tag("box"), multispace1), unary), |expr| {
+            Expr::Box(Box::new(expr))
+        }),
+        index,
+    ))(input)
+}
+
+/// Cast parser
+/// rule: UNARY (as TYPE)*
+fn cast(input: Span) -> IResult<Span, Expr> {
+    let (input, first) = unary(input)?;
+    // calls and parenthesized expressions consume the whitespace following them
+    let (input, types) = many0(preceded(delimited(multispace0, tag("as"), multispace1), ttype))(input)?;
+    Ok((
+        input,
+        types
+            .into_iter()
+            .fold(first, |acc, ty| Expr::Cast(Box::new(acc), ty)),
+    ))
+}
+
+fn multiple(input: Span) -> IResult<Span, Expr> {
+    let (input, first) = cast(input)?;
     let (input, rest) = many0(tuple((
-        delimited(multispace0, alt((tag("*"), tag("/"))), multispace0),
-        call,
+        delimited(multispace0, pair(location, alt((tag("*"), tag("/"), tag("%")))), multispace0),
+        cast,
     )))(input)?;
 
     Ok((
         input,
-        rest.into_iter().fold(first, |acc, (op, expr)| {
-            Expr::BinOperation(Opcode::from(*op), Box::new(acc), Box::new(expr))
+        rest.into_iter().fold(first, |acc, ((location, op), expr)| {
+            Expr::BinOperation(Opcode::from(*op), Box::new(acc), Box::new(expr), location)
         }),
     ))
 }
@@ -162,20 +625,75 @@ fn multiple(input: Span) -> IResult<Span, Expr> {
 fn sum(input: Span) -> IResult<Span, Expr> {
     let (input, first) = multiple(input)?;
     let (input, rest) = many0(tuple((
-        delimited(multispace0, alt((tag("+"), tag("-"))), multispace0),
+        delimited(multispace0, pair(location, alt((tag("+"), tag("-")))), multispace0),
         multiple,
     )))(input)?;
 
     Ok((
         input,
-        rest.into_iter().fold(first, |acc, (op, expr)| {
-            Expr::BinOperation(Opcode::from(*op), Box::new(acc), Box::new(expr))
+        rest.into_iter().fold(first, |acc, ((location, op), expr)| {
+            Expr::BinOperation(Opcode::from(*op), Box::new(acc), Box::new(expr), location)
         }),
     ))
 }
 
+fn shift(input: Span) -> IResult<Span, Expr> {
+    let (input, first) = sum(input)?;
+    let (input, rest) = many0(tuple((
+        delimited(multispace0, pair(location, alt((tag("<<"), tag(">>")))), multispace0),
+        sum,
+    )))(input)?;
+
+    Ok((
+        input,
+        rest.into_iter().fold(first, |acc, ((location, op), expr)| {
+            Expr::BinOperation(Opcode::from(*op), Box::new(acc), Box::new(expr), location)
+        }),
+    ))
+}
+
+/// Comparison parser, comparisons do not chain
+/// rule: EXPR (== | != | <= | >= | < | >) EXPR
+fn comparison(input: Span) -> IResult<Span, Expr> {
+    let (input, first) = shift(input)?;
+    let operator = alt((tag("=="), tag("!="), tag("<="), tag(">="), tag("<"), tag(">")));
+    let (input, rest) = opt(pair(delimited(multispace0, pair(location, operator), multispace0), shift))(input)?;
+
+    Ok((
+        input,
+        match rest {
+            Some(((location, op), expr)) => {
+                Expr::BinOperation(Opcode::from(*op), Box::new(first), Box::new(expr), location)
+            }
+            None => first,
+        },
+    ))
+}
+
+/// Range parser, the bounds are evaluated once
+/// rule: EXPR..EXPR | EXPR..=EXPR
+fn range(input: Span) -> IResult<Span, Expr> {
+    map(
+        pair(
+            comparison,
+            opt(pair(
+                delimited(multispace0, alt((tag("..="), tag(".."))), multispace0),
+                comparison,
+            )),
+        ),
+        |(start, end)| match end {
+            Some((op, end)) => Expr::Range {
+                start: Box::new(start),
+                end: Box::new(end),
+                inclusive: *op == "..=",
+            },
+            None => start,
+        },
+    )(input)
+}
+
 fn expression(input: Span) -> IResult<Span, Expr> {
-    alt((sum, call))(input)
+    alt((range, call))(input)
 }
 
 // Statement parsers
@@ -194,15 +712,125 @@ fn let_statement(input: Span) -> IResult<Span, Statement> {
     )(input)
 }
 
+/// Pattern parser
+/// rule: _ | INTEGER | INTEGER..=INTEGER | PATH | PATH(PATTERN, ...) | PATH { FIELD: PATTERN, ... }
+///     | IDENTIFIER { FIELD: PATTERN, ... } | IDENTIFIER | (PATTERN, PATTERN, ...)
+fn pattern(input: Span) -> IResult<Span, Pattern> {
+    alt((
+        map(terminated(char('_'), not(alt((alphanumeric1, tag("_"))))), |_| {
+            Pattern::Wildcard
+        }),
+        map(
+            tuple((number, opt(preceded(delimited(multispace0, tag("..="), multispace0), number)))),
+            |(start, end)| match (start, end) {
+                (Number::Integer64(start), None) => Pattern::Integer(start),
+                (Number::Integer64(start), Some(Number::Integer64(end))) => Pattern::Range(start, end),
+                _ => unreachable!(),
+            },
+        ),
+        map(
+            tuple((path, opt(preceded(multispace0, variant_fields(pattern, field_patterns))))),
+            |((enum_name, variant), fields)| Pattern::Variant {
+                enum_name,
+                variant,
+                fields: fields.unwrap_or(VariantFields::Unit),
+            },
+        ),
+        map(
+            tuple((identifier, preceded(multispace0, field_patterns))),
+            |(name, fields)| Pattern::Struct(name, fields),
+        ),
+        map(identifier, Pattern::Identifier),
+        tuple_pattern,
+    ))(input)
+}
+
+/// `{ FIELD: PATTERN, FIELD, .. }`, a field without pattern binds a variable of its name
+fn field_patterns(input: Span) -> IResult<Span, Vec<(Identifier, Pattern)>> {
+    delimited(
+        pair(char('{'), multispace0),
+        terminated(
+            separated_list0_trailing(
+                delimited(multispace0, char(','), multispace0),
+                map(
+                    tuple((
+                        identifier,
+                        opt(preceded(delimited(multispace0, char(':'), multispace0), pattern)),
+                    )),
+                    |(name, pattern)| {
+                        let pattern = pattern.unwrap_or_else(|| Pattern::Identifier(name.clone()));
+                        (name, pattern)
+                    },
+                ),
+                delimited(multispace0, char(','), multispace0),
+            ),
+            opt(tag("..")),
+        ),
+        pair(multispace0, char('}')),
+    )(input)
+}
+
+/// `(ITEM, ...)` or `{ FIELD: ITEM, ... }` after the name of a variant
+fn variant_fields<'a, O, F, G>(
+    item: F,
+    named: G,
+) -> impl FnMut(Span<'a>) -> IResult<Span<'a>, VariantFields<O>>
+where
+    F: Parser<Span<'a>, O, nom::error::Error<Span<'a>>>,
+    G: Parser<Span<'a>, Vec<(Identifier, O)>, nom::error::Error<Span<'a>>>,
+{
+    alt((
+        map(parenthesized_list(item), |(items, _)| VariantFields::Tuple(items)),
+        map(named, VariantFields::Struct),
+    ))
+}
+
+fn tuple_pattern(input: Span) -> IResult<Span, Pattern> {
+    map(
+        parenthesized_list(pattern),
+        |(mut elements, trailing)| match (elements.len(), trailing) {
+            (1, false) => elements.remove(0),
+            _ => Pattern::Tuple(elements),
+        },
+    )(input)
+}
+
+/// rule: let TUPLE_PATTERN (: TYPE)? = EXPR ;
+fn destructure_statement(input: Span) -> IResult<Span, Statement> {
+    map(
+        tuple((
+            preceded(tag("let"), multispace0),
+            tuple_pattern,
+            opt(preceded(delimited(multispace0, char(':'), multispace0), ttype)),
+            delimited(multispace0, char('='), multispace0),
+            expression,
+            delimited(multispace0, char(';'), multispace0),
+        )),
+        |(_, pattern, typ, _, expr, _)| Statement::Destructure(pattern, typ, Box::new(expr)),
+    )(input)
+}
+
 fn return_statement(input: Span) -> IResult<Span, Statement> {
     map(
         tuple((
-            tag("return"),
+            terminated(location, tag("return")),
             multispace0,
             expression,
             delimited(multispace0, char(';'), multispace0),
         )),
-        |(_, _, expr, _)| Statement::Return(Box::new(expr)),
+        |(location, _, expr, _)| Statement::Return(Box::new(expr), location),
+    )(input)
+}
+
+fn assignment_statement(input: Span) -> IResult<Span, Statement> {
+    map(
+        tuple((
+            expression,
+            delimited(multispace0, terminated(char('='), not(char('='))), multispace0),
+            expression,
+            delimited(multispace0, char(';'), multispace0),
+        )),
+        |(place, _, expr, _)| Statement::Assignment(Box::new(place), Box::new(expr)),
     )(input)
 }
 
@@ -213,8 +841,38 @@ fn expression_statement(input: Span) -> IResult<Span, Statement> {
     )(input)
 }
 
+/// For loop parser, the semicolon after the body is optional
+/// rule: for PATTERN in EXPR BLOCK
+fn for_statement(input: Span) -> IResult<Span, Statement> {
+    map(
+        tuple((
+            terminated(tag("for"), multispace1),
+            pattern,
+            delimited(multispace1, tag("in"), multispace1),
+            expression,
+            preceded(multispace0, block_expression),
+            opt(preceded(multispace0, char(';'))),
+            multispace0,
+        )),
+        |(_, pattern, _, iterable, body, _, _)| {
+            Statement::Expr(Box::new(Expr::For {
+                pattern,
+                iterable: Box::new(iterable),
+                body: Box::new(body),
+            }))
+        },
+    )(input)
+}
+
 fn statement(input: Span) -> IResult<Span, Statement> {
-    alt((let_statement, return_statement, expression_statement))(input)
+    alt((
+        for_statement,
+        let_statement,
+        destructure_statement,
+        return_statement,
+        assignment_statement,
+        expression_statement,
+    ))(input)
 }
 
 fn block_expression(input: Span) -> IResult<Span, Expr> {
@@ -228,7 +886,7 @@ fn block_expression(input: Span) -> IResult<Span, Expr> {
             preceded(multispace0, char('}')),
         ),
         |(statements, expr)| {
-            let mut statements: Vec<Box<Statement>> =
+            let statements: Vec<Box<Statement>> =
                 statements.into_iter().map(Box::new).collect();
             Expr::Block(statements, expr.map(Box::new))
         },
@@ -246,28 +904,128 @@ fn function_parameter(input: Span) -> IResult<Span, (String, Type)> {
     ))(input)
 }
 
+/// Receiver parser, the `self` parameter of a method
+/// rule: self | &self | &mut self
+fn receiver(input: Span) -> IResult<Span, (String, Type)> {
+    let self_type = || Type::named("Self".to_string());
+    let keyword = || verify(identifier, |name: &str| name == "self");
+    alt((
+        map(
+            tuple((
+                terminated(char('&'), multispace0),
+                opt(terminated(tag("mut"), multispace1)),
+                keyword(),
+            )),
+            move |(_, mutable, name)| (name, Type::reference(self_type(), mutable.is_some())),
+        ),
+        map(keyword(), move |name| (name, self_type())),
+    ))(input)
+}
+
 fn function_parameters(input: Span) -> IResult<Span, Vec<(String, Type)>> {
-    separated_list0(
-        delimited(multispace0, char(','), multispace0),
-        function_parameter,
-    )(input)
+    let (input, receiver) = opt(receiver)(input)?;
+    let separator = || delimited(multispace0, char(','), multispace0);
+    match receiver {
+        Some(receiver) => map(many0(preceded(separator(), function_parameter)), |params| {
+            std::iter::once(receiver.clone()).chain(params).collect()
+        })(input),
+        None => separated_list0(separator(), function_parameter)(input),
+    }
 }
 
-fn function_declare(input: Span) -> IResult<Span, FunctionDeclare> {
+/// Visibility parser
+/// rule: `pub` followed by whitespace, private when absent
+fn visibility(input: Span) -> IResult<Span, Visibility> {
+    map(opt(terminated(tag("pub"), multispace1)), |v| match v {
+        Some(_) => Visibility::Public,
+        None => Visibility::Private,
+    })(input)
+}
+
+/// `<ITEM, ITEM, ...>`, used for type parameters and type arguments
+fn angle_list<'a, O, F>(item: F) -> impl FnMut(Span<'a>) -> IResult<Span<'a>, Vec<O>>
+where
+    F: Parser<Span<'a>, O, nom::error::Error<Span<'a>>>,
+{
+    delimited(
+        pair(char('<'), multispace0),
+        separated_list0_trailing(
+            delimited(multispace0, char(','), multispace0),
+            item,
+            delimited(multispace0, char(','), multispace0),
+        ),
+        pair(multispace0, char('>')),
+    )
+}
+
+/// `<T, U>` after the name of a generic function or struct
+fn type_parameters(input: Span) -> IResult<Span, Vec<Identifier>> {
+    map(opt(preceded(multispace0, angle_list(identifier))), Option::unwrap_or_default)(input)
+}
+
+/// the names of type parameters and their `(parameter, trait)` bounds
+type TypeParameters = (Vec<Identifier>, Vec<(Identifier, Identifier)>);
+
+/// `<T: Trait + Trait, U>`, type parameters with their trait bounds
+/// rule: <IDENTIFIER (: IDENTIFIER (+ IDENTIFIER)*)?, ...>
+fn bounded_type_parameters(input: Span) -> IResult<Span, TypeParameters> {
+    let bound = pair(
+        identifier,
+        opt(preceded(
+            delimited(multispace0, char(':'), multispace0),
+            separated_list0(delimited(multispace0, char('+'), multispace0), qualified_name),
+        )),
+    );
+    map(opt(preceded(multispace0, angle_list(bound))), |parameters| {
+        let mut generics = vec![];
+        let mut bounds = vec![];
+        for (name, traits) in parameters.unwrap_or_default() {
+            for trait_name in traits.unwrap_or_default() {
+                bounds.push((name.clone(), trait_name));
+            }
+            generics.push(name);
+        }
+        (generics, bounds)
+    })(input)
+}
+
+/// the name, the type parameters, the parameters and the return type of a function
+type FunctionSignature = (Identifier, TypeParameters, Vec<(String, Type)>, Type);
+
+/// `fn NAME<T: Trait, ...>(PARAMETERS) -> TYPE`
+fn function_signature(input: Span) -> IResult<Span, FunctionSignature> {
     map(
         tuple((
             tag("fn"),
             multispace0,
             identifier,
+            bounded_type_parameters,
             delimited(multispace0, char('('), multispace0),
             function_parameters,
             delimited(multispace0, char(')'), multispace0),
             delimited(multispace0, tag("->"), multispace0),
             ttype,
+        )),
+        |(_, _, name, generics, _, params, _, _, return_type)| (name, generics, params, return_type),
+    )(input)
+}
+
+fn function_declare(input: Span) -> IResult<Span, FunctionDeclare> {
+    map(
+        tuple((
+            visibility,
+            opt(terminated(tag("extern"), multispace1)),
+            function_signature,
             preceded(multispace0, block_expression),
         )),
-        |(_, _, name, _, params, _, _, return_type, body)| {
-            FunctionDeclare::new(name, params, return_type, Box::new(body))
+        |(visibility, is_extern, (name, (generics, bounds), params, return_type), body)| {
+            FunctionDeclare {
+                visibility,
+                is_extern: is_extern.is_some(),
+                generics,
+                bounds,
+                ..FunctionDeclare::new(name, params, return_type, Box::new(body))
+            }
         },
     )(input)
 }
@@ -293,28 +1051,323 @@ fn struct_items(input: Span) -> IResult<Span, Vec<(String, Type)>> {
 fn struct_define(input: Span) -> IResult<Span, StructDeclare> {
     map(
         tuple((
+            visibility,
             tag("struct"),
             multispace0,
             identifier,
+            type_parameters,
             delimited(
                 delimited(multispace0, char('{'), multispace0),
                 struct_items,
                 delimited(multispace0, char('}'), multispace0),
             ),
         )),
-        |(_, _, name, items)| StructDeclare::new(name, items),
+        |(visibility, _, _, name, generics, items)| StructDeclare {
+            visibility,
+            generics,
+            ..StructDeclare::new(name, items)
+        },
+    )(input)
+}
+
+/// Enum parser
+/// rule: enum IDENTIFIER <T, ...>? { VARIANT, VARIANT(TYPE, ...), VARIANT { FIELD: TYPE, ... }, ... }
+fn enum_define(input: Span) -> IResult<Span, EnumDeclare> {
+    map(
+        tuple((
+            visibility,
+            terminated(tag("enum"), multispace1),
+            identifier,
+            type_parameters,
+            delimited(
+                delimited(multispace0, char('{'), multispace0),
+                separated_list0_trailing(
+                    delimited(multispace0, char(','), multispace0),
+                    map(
+                        tuple((
+                            identifier,
+                            opt(preceded(multispace0, variant_fields(ttype, named_fields(ttype)))),
+                        )),
+                        |(name, fields)| EnumVariant::new(name, fields.unwrap_or(VariantFields::Unit)),
+                    ),
+                    delimited(multispace0, char(','), multispace0),
+                ),
+                delimited(multispace0, char('}'), multispace0),
+            ),
+        )),
+        |(visibility, _, name, generics, variants)| EnumDeclare {
+            visibility,
+            generics,
+            ..EnumDeclare::new(name, variants)
+        },
+    )(input)
+}
+
+/// Impl block parser
+/// rule: impl<T: Trait, ...> (NAME<TYPE, ...> for)? NAME<TYPE, ...> { FUNCTION* }
+fn impl_block(input: Span) -> IResult<Span, ImplBlock> {
+    map(
+        tuple((
+            tag("impl"),
+            bounded_type_parameters,
+            opt(delimited(
+                multispace0,
+                pair(qualified_name, opt(preceded(multispace0, angle_list(ttype)))),
+                delimited(multispace1, tag("for"), multispace1),
+            )),
+            preceded(multispace0, qualified_name),
+            opt(preceded(multispace0, angle_list(ttype))),
+            delimited(
+                delimited(multispace0, char('{'), multispace0),
+                many0(delimited(multispace0, function_declare, multispace0)),
+                char('}'),
+            ),
+        )),
+        |(_, (generics, bounds), trait_name, name, arguments, functions)| ImplBlock {
+            generics,
+            bounds,
+            trait_name: trait_name.as_ref().map(|(trait_name, _)| trait_name.clone()),
+            trait_arguments: trait_name.and_then(|(_, arguments)| arguments).unwrap_or_default(),
+            name,
+            arguments: arguments.unwrap_or_default(),
+            functions,
+        },
+    )(input)
+}
+
+/// Trait parser, functions without a body end with `;`
+/// rule: trait IDENTIFIER <T, ...>? { (SIGNATURE ; | SIGNATURE BLOCK)* }
+fn trait_define(input: Span) -> IResult<Span, TraitDeclare> {
+    map(
+        tuple((
+            visibility,
+            terminated(tag("trait"), multispace1),
+            identifier,
+            type_parameters,
+            delimited(
+                delimited(multispace0, char('{'), multispace0),
+                many0(delimited(
+                    multispace0,
+                    pair(
+                        function_signature,
+                        preceded(
+                            multispace0,
+                            alt((map(char(';'), |_| None), map(block_expression, Some))),
+                        ),
+                    ),
+                    multispace0,
+                )),
+                char('}'),
+            ),
+        )),
+        |(visibility, _, name, generics, functions)| {
+            let functions = functions
+                .into_iter()
+                .map(|((name, (generics, bounds), params, return_type), body)| TraitFunction {
+                    generics,
+                    bounds,
+                    ..TraitFunction::new(name, params, return_type, body.map(Box::new))
+                })
+                .collect();
+            TraitDeclare {
+                visibility,
+                generics,
+                ..TraitDeclare::new(name, functions)
+            }
+        },
+    )(input)
+}
+
+/// Module parser, the items of the module are in the file `NAME.ty` or `NAME/mod.ty`
+/// rule: mod IDENTIFIER ;
+fn mod_declare(input: Span) -> IResult<Span, ModDeclare> {
+    map(
+        tuple((
+            visibility,
+            terminated(tag("mod"), multispace1),
+            identifier,
+            preceded(multispace0, char(';')),
+        )),
+        |(visibility, _, name, _)| ModDeclare { visibility, name },
+    )(input)
+}
+
+/// imported paths with the names they are imported as when renamed
+type UsePaths = Vec<(Vec<Identifier>, Option<Identifier>)>;
+
+/// the paths of a `use` declaration with the names they are imported as when renamed
+/// rule: PATH (as IDENTIFIER)? | PATH::{ USE_TREE, ... }
+fn use_tree(input: Span) -> IResult<Span, UsePaths> {
+    let group = preceded(
+        path_separator,
+        delimited(
+            pair(char('{'), multispace0),
+            separated_list0_trailing(
+                delimited(multispace0, char(','), multispace0),
+                use_tree,
+                delimited(multispace0, char(','), multispace0),
+            ),
+            pair(multispace0, char('}')),
+        ),
+    );
+    let alias = opt(preceded(delimited(multispace1, tag("as"), multispace1), identifier));
+    alt((
+        map(pair(separated_list1(path_separator, identifier), group), |(prefix, trees)| {
+            trees
+                .into_iter()
+                .flatten()
+                .map(|(path, alias)| (prefix.iter().cloned().chain(path).collect(), alias))
+                .collect()
+        }),
+        map(pair(separated_list1(path_separator, identifier), alias), |import| vec![import]),
+    ))(input)
+}
+
+/// Use parser, an import without `as` is named by the last segment of its path
+/// rule: use USE_TREE ;
+fn use_declare(input: Span) -> IResult<Span, UseDeclare> {
+    map(
+        tuple((
+            visibility,
+            terminated(tag("use"), multispace1),
+            use_tree,
+            preceded(multispace0, char(';')),
+        )),
+        |(visibility, _, imports, _)| {
+            let imports = imports
+                .into_iter()
+                .map(|(path, alias)| {
+                    // `use geo::{self}` imports the module `geo` itself
+                    let name = alias.unwrap_or_else(|| match path.as_slice() {
+                        [.., module, last] if last == "self" => module.clone(),
+                        [.., last] => last.clone(),
+                        [] => unreachable!(),
+                    });
+                    (path, name)
+                })
+                .collect();
+            UseDeclare { visibility, imports }
+        },
+    )(input)
+}
+
+/// Constant parser
+/// rule: const IDENTIFIER : TYPE = EXPR ;
+fn const_declare(input: Span) -> IResult<Span, ConstDeclare> {
+    map(
+        tuple((
+            visibility,
+            terminated(tag("const"), multispace1),
+            identifier,
+            delimited(multispace0, char(':'), multispace0),
+            ttype,
+            delimited(multispace0, char('='), multispace0),
+            expression,
+            preceded(multispace0, char(';')),
+        )),
+        |(visibility, _, name, _, ty, _, value, _)| ConstDeclare {
+            visibility,
+            name,
+            ty,
+            value: Box::new(value),
+        },
+    )(input)
+}
+
+/// Static parser
+/// rule: static (mut)? IDENTIFIER : TYPE = EXPR ;
+fn static_declare(input: Span) -> IResult<Span, StaticDeclare> {
+    map(
+        tuple((
+            visibility,
+            terminated(tag("static"), multispace1),
+            opt(terminated(tag("mut"), multispace1)),
+            identifier,
+            delimited(multispace0, char(':'), multispace0),
+            ttype,
+            delimited(multispace0, char('='), multispace0),
+            expression,
+            preceded(multispace0, char(';')),
+        )),
+        |(visibility, _, mutable, name, _, ty, _, value, _)| StaticDeclare {
+            visibility,
+            mutable: mutable.is_some(),
+            name,
+            ty,
+            value: Box::new(value),
+        },
+    )(input)
+}
+
+/// Type alias parser
+/// rule: type IDENTIFIER = TYPE ;
+fn type_alias(input: Span) -> IResult<Span, TypeAlias> {
+    map(
+        tuple((
+            visibility,
+            terminated(tag("type"), multispace1),
+            identifier,
+            delimited(multispace0, char('='), multispace0),
+            ttype,
+            preceded(multispace0, char(';')),
+        )),
+        |(visibility, _, name, _, ty, _)| TypeAlias { visibility, name, ty },
+    )(input)
+}
+
+/// Newtype parser
+/// rule: newtype IDENTIFIER ( TYPE ) ;
+fn newtype_declare(input: Span) -> IResult<Span, NewtypeDeclare> {
+    map(
+        tuple((
+            visibility,
+            terminated(tag("newtype"), multispace1),
+            identifier,
+            delimited(pair(multispace0, char('(')), delimited(multispace0, ttype, multispace0), char(')')),
+            preceded(multispace0, char(';')),
+        )),
+        |(visibility, _, name, inner, _)| NewtypeDeclare {
+            visibility,
+            name,
+            inner,
+        },
     )(input)
 }
 
 fn module_item(input: Span) -> IResult<Span, ModuleItem> {
     alt((
-        map(struct_define, |s| ModuleItem::StructDeclare(s)),
-        map(function_declare, |f| ModuleItem::FunctionDeclare(f)),
+        map(mod_declare, ModuleItem::Mod),
+        map(use_declare, ModuleItem::Use),
+        map(const_declare, ModuleItem::Const),
+        map(static_declare, ModuleItem::Static),
+        map(type_alias, ModuleItem::TypeAlias),
+        map(newtype_declare, ModuleItem::Newtype),
+        map(struct_define, ModuleItem::StructDeclare),
+        map(enum_define, ModuleItem::EnumDeclare),
+        map(impl_block, ModuleItem::Impl),
+        map(trait_define, ModuleItem::TraitDeclare),
+        map(function_declare, ModuleItem::FunctionDeclare),
     ))(input)
 }
 
-pub fn parse_module(input: &str) -> Result<Module, nom::Err<nom::error::Error<Span>>> {
+/// a module is a sequence of items, input left after the last one is an error
+pub fn parse_module(input: &str) -> Result<Module, nom::Err<nom::error::Error<Span<'_>>>> {
     let input = Span::new(input);
-    let (_, items) = many1(preceded(multispace0, module_item))(input)?;
+    let (_, items) = all_consuming(terminated(
+        many1(preceded(multispace0, module_item)),
+        multispace0,
+    ))(input)?;
     Ok(Module::new(items.into_iter().map(Box::new).collect()))
 }
+
+/// where the error of `parse_module` occurred, the start of the first item which cannot be
+/// parsed when the items before it were
+pub fn error_location(error: &nom::Err<nom::error::Error<Span>>) -> Option<Location> {
+    match error {
+        nom::Err::Error(error) | nom::Err::Failure(error) => Some(Location::new(
+            error.input.location_line(),
+            error.input.get_utf8_column(),
+        )),
+        nom::Err::Incomplete(_) => None,
+    }
+}
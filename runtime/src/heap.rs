@@ -0,0 +1,191 @@
+use std::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// the first collection happens once the heap holds this many bytes
+const INITIAL_THRESHOLD: u64 = 1 << 20;
+
+/// layout of the values of a type, the compiler emits one as a constant for every type
+/// allocated with `box` or held by a root
+///
+/// the `count` offsets of the heap pointers in a value follow the header
+#[repr(C)]
+pub struct TypeInfo {
+    pub size: u64,
+    pub align: u64,
+    pub count: u64,
+    offsets: [u64; 0],
+}
+
+impl TypeInfo {
+    /// offsets of the heap pointers in a value of this type
+    pub fn offsets(&self) -> &[u64] {
+        unsafe { std::slice::from_raw_parts(self.offsets.as_ptr(), self.count as usize) }
+    }
+}
+
+struct Object {
+    layout: Layout,
+    info: *const TypeInfo,
+    /// number of values of the type in the object, one unless it is an array
+    count: u64,
+    marked: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub collections: u64,
+    /// objects on the heap and the bytes they take
+    pub objects: u64,
+    pub bytes: u64,
+    /// the most bytes the heap held at once
+    pub peak_bytes: u64,
+    /// bytes allocated since the start of the program
+    pub allocated_bytes: u64,
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "gc: {} collections, {} objects in {} bytes on the heap, peak heap {} bytes, {} bytes allocated",
+            self.collections, self.objects, self.bytes, self.peak_bytes, self.allocated_bytes
+        )
+    }
+}
+
+/// a mark and sweep heap, objects never move
+///
+/// objects are kept by their address, so that a word is only followed when it points into
+/// an object of the heap, whether at its start or at one of its fields
+pub struct Heap {
+    objects: BTreeMap<usize, Object>,
+    threshold: u64,
+    stats: Stats,
+}
+
+// the type infos are constants of the program
+unsafe impl Send for Heap {}
+
+impl Heap {
+    pub const fn new() -> Self {
+        Heap {
+            objects: BTreeMap::new(),
+            threshold: INITIAL_THRESHOLD,
+            stats: Stats {
+                collections: 0,
+                objects: 0,
+                bytes: 0,
+                peak_bytes: 0,
+                allocated_bytes: 0,
+            },
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// whether the heap should be collected before allocating `size` more bytes, the
+    /// threshold doubles the bytes left by the last collection
+    pub fn should_collect(&self, size: u64) -> bool {
+        self.stats.bytes + size > self.threshold
+    }
+
+    /// a zeroed value of the type described by `info`
+    ///
+    /// # Safety
+    /// `info` points to a type info which lives as long as the heap
+    pub unsafe fn allocate(&mut self, info: *const TypeInfo) -> *mut u8 {
+        self.allocate_array(info, 1)
+    }
+
+    /// `count` zeroed values of the type described by `info` next to each other
+    ///
+    /// # Safety
+    /// `info` points to a type info which lives as long as the heap
+    pub unsafe fn allocate_array(&mut self, info: *const TypeInfo, count: u64) -> *mut u8 {
+        let info_ref = &*info;
+        // values of size zero get a byte of their own, so that their addresses differ
+        let size = info_ref.size.checked_mul(count).expect("array is too large");
+        let layout = Layout::from_size_align(size.max(1) as usize, info_ref.align as usize)
+            .expect("invalid type info");
+        let ptr = alloc_zeroed(layout);
+        if ptr.is_null() {
+            handle_alloc_error(layout);
+        }
+        self.objects.insert(
+            ptr as usize,
+            Object {
+                layout,
+                info,
+                count,
+                marked: false,
+            },
+        );
+        let size = layout.size() as u64;
+        self.stats.objects += 1;
+        self.stats.bytes += size;
+        self.stats.allocated_bytes += size;
+        self.stats.peak_bytes = self.stats.peak_bytes.max(self.stats.bytes);
+        ptr
+    }
+
+    /// the start of the object `address` points into
+    pub fn find(&self, address: usize) -> Option<usize> {
+        let (start, object) = self.objects.range(..=address).next_back()?;
+        if address < start + object.layout.size() {
+            Some(*start)
+        } else {
+            None
+        }
+    }
+
+    /// free every object which is not reachable from the words in `roots`
+    ///
+    /// # Safety
+    /// the objects hold the values described by their type infos
+    pub unsafe fn collect(&mut self, roots: impl IntoIterator<Item = usize>) {
+        let mut pending: Vec<usize> = roots.into_iter().filter_map(|it| self.find(it)).collect();
+        while let Some(start) = pending.pop() {
+            let object = self.objects.get_mut(&start).unwrap();
+            if object.marked {
+                continue;
+            }
+            object.marked = true;
+            let info = &*object.info;
+            for index in 0..object.count {
+                let base = start + (index * info.size) as usize;
+                for offset in info.offsets() {
+                    let word = *((base + *offset as usize) as *const usize);
+                    if let Some(found) = self.find(word) {
+                        pending.push(found);
+                    }
+                }
+            }
+        }
+
+        let mut garbage = vec![];
+        self.objects.retain(|start, object| {
+            let marked = object.marked;
+            object.marked = false;
+            if !marked {
+                garbage.push((*start, object.layout));
+            }
+            marked
+        });
+        for (start, layout) in garbage {
+            dealloc(start as *mut u8, layout);
+            self.stats.objects -= 1;
+            self.stats.bytes -= layout.size() as u64;
+        }
+        self.stats.collections += 1;
+        self.threshold = INITIAL_THRESHOLD.max(self.stats.bytes * 2);
+    }
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Heap::new()
+    }
+}
@@ -0,0 +1,71 @@
+//! runtime linked into typhoon programs which allocate on the heap or can panic
+//!
+//! values created with `box` live on a mark and sweep heap, the roots are found exactly
+//! through the shadow stack llvm maintains for functions holding heap pointers
+
+mod heap;
+mod panic;
+mod shadow_stack;
+
+pub use heap::{Heap, Stats, TypeInfo};
+pub use panic::{Location, PANIC_EXIT_CODE};
+pub use shadow_stack::{llvm_gc_root_chain, roots, FrameMap, StackEntry};
+
+use std::sync::{Mutex, OnceLock};
+
+static HEAP: Mutex<Heap> = Mutex::new(Heap::new());
+
+/// collect before every allocation when `TYPHOON_GC_STRESS` is set, which finds values
+/// that are used without being rooted
+fn stress() -> bool {
+    static STRESS: OnceLock<bool> = OnceLock::new();
+    *STRESS.get_or_init(|| std::env::var_os("TYPHOON_GC_STRESS").is_some())
+}
+
+/// print the statistics of the heap to stderr when the program exits if `TYPHOON_GC_STATS`
+/// is set, `typhoon build --gc-stats` sets it for the program it runs
+extern "C" fn register_stats() {
+    extern "C" {
+        fn atexit(function: extern "C" fn()) -> i32;
+    }
+    if std::env::var_os("TYPHOON_GC_STATS").is_some() {
+        unsafe { atexit(print_stats) };
+    }
+}
+
+/// the variable is read when the program starts, before its `main`, rather than on the
+/// first allocation
+#[used]
+#[link_section = ".init_array"]
+static REGISTER_STATS: extern "C" fn() = register_stats;
+
+/// a zeroed value of the type described by `info`
+///
+/// # Safety
+/// `info` is a type info emitted by the compiler and the shadow stack is consistent
+#[no_mangle]
+pub unsafe extern "C" fn typhoon_gc_alloc(info: *const TypeInfo) -> *mut u8 {
+    let mut heap = HEAP.lock().unwrap();
+    if stress() || heap.should_collect((*info).size) {
+        heap.collect(roots(llvm_gc_root_chain));
+    }
+    heap.allocate(info)
+}
+
+/// `count` zeroed values of the type described by `info`, the backing store of growable
+/// collections
+///
+/// # Safety
+/// `info` is a type info emitted by the compiler and the shadow stack is consistent
+#[no_mangle]
+pub unsafe extern "C" fn typhoon_gc_alloc_array(info: *const TypeInfo, count: u64) -> *mut u8 {
+    let mut heap = HEAP.lock().unwrap();
+    if stress() || heap.should_collect((*info).size.saturating_mul(count)) {
+        heap.collect(roots(llvm_gc_root_chain));
+    }
+    heap.allocate_array(info, count)
+}
+
+extern "C" fn print_stats() {
+    eprintln!("{}", HEAP.lock().unwrap().stats());
+}
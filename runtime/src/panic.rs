@@ -0,0 +1,65 @@
+use std::backtrace::Backtrace;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+/// status a program exits with when it panics, apart from the ones of `abort` and signals
+pub const PANIC_EXIT_CODE: i32 = 101;
+
+/// where a panic is raised in the source of the program, emitted by the compiler as a
+/// constant next to the call of the handler
+#[repr(C)]
+pub struct Location {
+    pub file: *const c_char,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// report `message`, raised by `panic` or by a failed check the compiler inserted, and exit
+///
+/// # Safety
+/// `message` is a nul terminated string and `location` a location whose file is one, or
+/// null when the location is unknown
+#[no_mangle]
+pub unsafe extern "C" fn typhoon_panic(message: *const c_char, location: *const Location) -> ! {
+    let message = CStr::from_ptr(message).to_string_lossy();
+    panic_at(&message, location)
+}
+
+/// report an index which is not below the length of the indexed array and exit
+///
+/// # Safety
+/// `location` is a location whose file is a nul terminated string, or null when the
+/// location is unknown
+#[no_mangle]
+pub unsafe extern "C" fn typhoon_panic_bounds_check(
+    index: i64,
+    len: i64,
+    location: *const Location,
+) -> ! {
+    let message = format!(
+        "index out of bounds: the len is {} but the index is {}",
+        len, index
+    );
+    panic_at(&message, location)
+}
+
+/// print the message, the location and the backtrace to stderr, the backtrace names the
+/// mangled symbols of the program, `typhoon demangle` reads them back
+unsafe fn panic_at(message: &str, location: *const Location) -> ! {
+    let location = match location.as_ref() {
+        Some(location) => format!(
+            " at {}:{}:{}",
+            CStr::from_ptr(location.file).to_string_lossy(),
+            location.line,
+            location.column
+        ),
+        None => String::new(),
+    };
+    eprintln!(
+        "panicked{}:\n{}\nstack backtrace:\n{}",
+        location,
+        message,
+        Backtrace::force_capture()
+    );
+    std::process::exit(PANIC_EXIT_CODE)
+}
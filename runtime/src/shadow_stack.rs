@@ -0,0 +1,62 @@
+use crate::heap::TypeInfo;
+use std::mem::size_of;
+use std::ptr;
+
+/// the roots of a function, emitted by llvm for every function with the `shadow-stack` gc
+///
+/// codegen gives every root the type info of its value as metadata, so that `meta` has an
+/// entry for each of the `num_roots` roots
+#[repr(C)]
+pub struct FrameMap {
+    pub num_roots: i32,
+    pub num_meta: i32,
+    meta: [*const TypeInfo; 0],
+}
+
+impl FrameMap {
+    /// type info of root number `index`
+    ///
+    /// # Safety
+    /// `index` is less than `num_meta`
+    pub unsafe fn meta(&self, index: usize) -> &TypeInfo {
+        &**self.meta.as_ptr().add(index)
+    }
+}
+
+/// header of the frame of a running function, the roots follow it laid out like the
+/// fields of a C struct
+#[repr(C)]
+pub struct StackEntry {
+    pub next: *mut StackEntry,
+    pub map: *const FrameMap,
+}
+
+/// the innermost frame, maintained by the code llvm emits for the `shadow-stack` gc
+#[allow(non_upper_case_globals)]
+#[no_mangle]
+pub static mut llvm_gc_root_chain: *mut StackEntry = ptr::null_mut();
+
+/// the heap pointers held by the roots of `entry` and of the frames below it
+///
+/// # Safety
+/// `entry` is null or a frame built by llvm, with type infos for all of its roots
+pub unsafe fn roots(mut entry: *const StackEntry) -> Vec<usize> {
+    let mut pointers = vec![];
+    while !entry.is_null() {
+        let map = &*(*entry).map;
+        assert_eq!(map.num_meta, map.num_roots, "root without type info");
+        let mut offset = size_of::<StackEntry>();
+        for index in 0..map.num_roots as usize {
+            let info = map.meta(index);
+            let align = info.align as usize;
+            offset = offset.div_ceil(align) * align;
+            let root = entry as usize + offset;
+            for field in info.offsets() {
+                pointers.push(*((root + *field as usize) as *const usize));
+            }
+            offset += info.size as usize;
+        }
+        entry = (*entry).next;
+    }
+    pointers
+}
@@ -0,0 +1,162 @@
+use std::mem::size_of;
+use typhoon_runtime::{roots, FrameMap, Heap, StackEntry, TypeInfo};
+
+/// a type info with `N` pointer offsets, laid out like the ones the compiler emits
+#[repr(C)]
+struct Info<const N: usize> {
+    size: u64,
+    align: u64,
+    count: u64,
+    offsets: [u64; N],
+}
+
+impl<const N: usize> Info<N> {
+    fn new(size: u64, offsets: [u64; N]) -> Self {
+        Info {
+            size,
+            align: 8,
+            count: N as u64,
+            offsets,
+        }
+    }
+
+    fn as_ptr(&self) -> *const TypeInfo {
+        self as *const Self as *const TypeInfo
+    }
+}
+
+/// `struct Node { value: i64, next: Box<Node> }`
+#[repr(C)]
+struct Node {
+    value: i64,
+    next: *mut Node,
+}
+
+unsafe fn node(heap: &mut Heap, info: &Info<1>, value: i64, next: *mut Node) -> *mut Node {
+    let node = heap.allocate(info.as_ptr()) as *mut Node;
+    (*node).value = value;
+    (*node).next = next;
+    node
+}
+
+#[test]
+fn unreachable_objects_are_freed() {
+    let info = Info::new(16, [8]);
+    let mut heap = Heap::new();
+    unsafe {
+        let tail = node(&mut heap, &info, 1, std::ptr::null_mut());
+        let head = node(&mut heap, &info, 2, tail);
+        let garbage = node(&mut heap, &info, 3, head);
+        // the garbage node points at the list, which does not keep it alive
+        let _ = garbage;
+        heap.collect(vec![head as usize]);
+        let stats = heap.stats();
+        assert_eq!(stats.collections, 1);
+        assert_eq!(stats.objects, 2);
+        assert_eq!(stats.bytes, 32);
+        assert_eq!(stats.peak_bytes, 48);
+        assert_eq!((*(*head).next).value, 1);
+
+        heap.collect(vec![]);
+        assert_eq!(heap.stats().objects, 0);
+        assert_eq!(heap.stats().allocated_bytes, 48);
+    }
+}
+
+#[test]
+fn cycles_and_interior_pointers() {
+    let info = Info::new(16, [8]);
+    let mut heap = Heap::new();
+    unsafe {
+        let a = node(&mut heap, &info, 1, std::ptr::null_mut());
+        let b = node(&mut heap, &info, 2, a);
+        (*a).next = b;
+        // a pointer to the field of an object keeps the whole object alive
+        let field = &mut (*b).next as *mut *mut Node as usize;
+        assert_eq!(heap.find(field), Some(b as usize));
+        heap.collect(vec![field]);
+        assert_eq!(heap.stats().objects, 2);
+        heap.collect(vec![0, 8]);
+        assert_eq!(heap.stats().objects, 0);
+    }
+}
+
+#[test]
+fn words_that_are_not_pointers_are_not_followed() {
+    let leaf = Info::new(8, []);
+    let mut heap = Heap::new();
+    unsafe {
+        let kept = heap.allocate(leaf.as_ptr());
+        let lost = heap.allocate(leaf.as_ptr());
+        // the leaf holds the address of the other object, but has no pointer field
+        *(kept as *mut usize) = lost as usize;
+        heap.collect(vec![kept as usize]);
+        assert_eq!(heap.stats().objects, 1);
+        assert_eq!(heap.find(lost as usize), None);
+    }
+}
+
+#[test]
+fn every_element_of_an_array_is_traced() {
+    let info = Info::new(16, [8]);
+    let mut heap = Heap::new();
+    unsafe {
+        let nodes = heap.allocate_array(info.as_ptr(), 3) as *mut Node;
+        for index in 0..3 {
+            let next = node(&mut heap, &info, index as i64, std::ptr::null_mut());
+            *nodes.add(index) = Node { value: 0, next };
+        }
+        assert_eq!(heap.stats().bytes, 48 + 3 * 16);
+        // the last element is reached through a pointer into the array
+        heap.collect(vec![nodes.add(2) as usize]);
+        assert_eq!(heap.stats().objects, 4);
+        assert_eq!((*(*nodes.add(2)).next).value, 2);
+        heap.collect(vec![]);
+        assert_eq!(heap.stats().objects, 0);
+    }
+}
+
+/// the frame llvm builds for a function with two roots, `Box<Node>` and `(i32, Box<Node>)`
+#[repr(C)]
+struct Frame {
+    entry: StackEntry,
+    node: *mut Node,
+    pair: (i32, *mut Node),
+}
+
+#[repr(C)]
+struct Map {
+    num_roots: i32,
+    num_meta: i32,
+    meta: [*const TypeInfo; 2],
+}
+
+#[test]
+fn roots_are_read_from_the_shadow_stack() {
+    let pointer = Info::new(8, [0]);
+    let pair = Info::new(16, [8]);
+    let map = Map {
+        num_roots: 2,
+        num_meta: 2,
+        meta: [pointer.as_ptr(), pair.as_ptr()],
+    };
+    let outer = Frame {
+        entry: StackEntry {
+            next: std::ptr::null_mut(),
+            map: &map as *const Map as *const FrameMap,
+        },
+        node: 16 as *mut Node,
+        pair: (7, 24 as *mut Node),
+    };
+    let inner = Frame {
+        entry: StackEntry {
+            next: &outer as *const Frame as *mut StackEntry,
+            map: &map as *const Map as *const FrameMap,
+        },
+        node: 32 as *mut Node,
+        pair: (7, 40 as *mut Node),
+    };
+    assert_eq!(size_of::<Frame>(), 40);
+    let found = unsafe { roots(&inner.entry) };
+    assert_eq!(found, vec![32, 40, 16, 24]);
+}
@@ -1,6 +1,8 @@
 use core::{error::TyphoonError};
+use core::mangle::{demangle, demangle_text};
+use std::io::Read;
 use structopt::StructOpt;
-use core::program::Program;
+use core::program::{CrateType, Overflow, Program};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "typhoon")]
@@ -8,11 +10,25 @@ enum Opts {
     Build {
         #[structopt(name = "FILE")]
         filename: String,
+        /// write the ast next to the binary, integer arithmetic is checked for overflow
+        /// unless `--overflow` says otherwise
         #[structopt(short, long)]
-        debug: bool
+        debug: bool,
+        /// checked or wrapping integer arithmetic, checked in debug builds by default
+        #[structopt(long)]
+        overflow: Option<Overflow>,
+        /// bin, staticlib or cdylib
+        #[structopt(long = "crate-type", default_value = "bin")]
+        crate_type: CrateType,
+        /// do not check array indexes at runtime
+        #[structopt(long = "no-bounds-checks")]
+        no_bounds_checks: bool,
+        /// print the statistics of the garbage collected heap when the program exits
+        #[structopt(long = "gc-stats")]
+        gc_stats: bool,
     },
 
-    LLIR {
+    Llir {
         #[structopt(name = "FILE")]
         filename: String,
     },
@@ -24,6 +40,27 @@ enum Opts {
         #[structopt(name = "FILE")]
         filename: String,
     },
+    /// print the source names of mangled symbols, the symbols in stdin are demangled when
+    /// none is given, e.g. `nm a.out | typhoon demangle`
+    Demangle {
+        #[structopt(name = "SYMBOL")]
+        symbols: Vec<String>,
+    },
+}
+
+/// the overflow mode given on the command line, debug builds check by default
+fn overflow_mode(overflow: Option<Overflow>, debug: bool) -> Overflow {
+    match (overflow, debug) {
+        (Some(overflow), _) => overflow,
+        (None, true) => Overflow::Checked,
+        (None, false) => Overflow::Wrapping,
+    }
+}
+
+fn print_warnings(program: &Program) {
+    for warning in program.warnings.iter() {
+        eprintln!("warning: {}", warning);
+    }
 }
 
 fn main() -> Result<(), TyphoonError> {
@@ -31,15 +68,44 @@ fn main() -> Result<(), TyphoonError> {
     let opt: Opts = Opts::from_args();
 
     match opt {
-        Opts::Build {filename, debug} => {
-            let program = Program::new(filename);
+        Opts::Build {filename, debug, crate_type: CrateType::Bin, no_bounds_checks, gc_stats, overflow} => {
+            let mut program = Program::new(filename)?;
+            program.options.bounds_checks = !no_bounds_checks;
+            program.gc_stats = gc_stats;
+            program.options.overflow = overflow_mode(overflow, debug);
             let result = program.as_binary_output(debug);
+            print_warnings(&program);
             match result {
                 Ok(ret) => {dbg!(ret);}
                 Err(e) => {eprintln!("got error: {}", e);}
             };
         }
-        _ => unimplemented!()
+        Opts::Build {filename, debug, crate_type, no_bounds_checks, overflow, ..} => {
+            let mut program = Program::new(filename)?;
+            program.options.bounds_checks = !no_bounds_checks;
+            program.options.overflow = overflow_mode(overflow, debug);
+            let result = program.as_library(crate_type, debug);
+            print_warnings(&program);
+            match result {
+                Ok(path) => println!("library is generated at {}", path.display()),
+                Err(e) => eprintln!("got error: {}", e),
+            };
+        }
+        Opts::Demangle { symbols } if symbols.is_empty() => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| TyphoonError::FileError("stdin".to_string(), e))?;
+            print!("{}", demangle_text(&input));
+        }
+        Opts::Demangle { symbols } => {
+            for symbol in symbols {
+                println!("{}", demangle(&symbol).unwrap_or(symbol));
+            }
+        }
+        Opts::Llir { filename } | Opts::Run { filename } | Opts::Ast { filename } => {
+            unimplemented!("cannot handle {} yet", filename)
+        }
     }
 
     //